@@ -3,6 +3,30 @@ use std::env;
 use std::fs;
 use std::path::Path;
 
+// Vendored copy of https://data.iana.org/rdap/dns.json used for offline builds and as the fetch fallback
+const VENDORED_RDAP_BOOTSTRAP: &str = "data/rdap-dns.json";
+
+// Vendored IANA root zone snapshot: each TLD's port-43 server and sponsoring organisation.
+// Crawling IANA here would make the generated maps depend on its rate limiting and
+// network timing; `cargo run --example refresh_iana_whois` updates the snapshot instead.
+const VENDORED_IANA_WHOIS: &str = "data/iana-whois.json";
+
+// Vendored Public Suffix List; its ICANN section lists the multi-label suffixes ("com.tr")
+const VENDORED_PUBLIC_SUFFIX_LIST: &str = "data/public_suffix_list.dat";
+const PSL_ICANN_END_MARKER: &str = "// ===END ICANN DOMAINS===";
+
+// Second-level suffixes run by a registry other than their TLD's
+const SECOND_LEVEL_REGISTRIES: &[(&str, &str)] = &[
+    ("ac.uk", "whois.ja.net"),
+    ("gov.uk", "whois.ja.net"),
+];
+
+#[derive(serde::Deserialize)]
+struct IanaTld {
+    whois: Option<String>,
+    organisation: Option<String>,
+}
+
 #[derive(serde::Deserialize)]
 struct RdapBootstrap {
     services: Vec<RdapBootstrapEntry>,
@@ -19,19 +43,34 @@ struct RdapBootstrapEntry {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", VENDORED_RDAP_BOOTSTRAP);
+    println!("cargo:rerun-if-changed={}", VENDORED_IANA_WHOIS);
+    println!("cargo:rerun-if-changed={}", VENDORED_PUBLIC_SUFFIX_LIST);
+    println!("cargo:rerun-if-env-changed=WHOIS_OFFLINE_BUILD");
     
     let out_dir = env::var("OUT_DIR")?;
     let dest_path = Path::new(&out_dir).join("rdap_mappings.rs");
+
+    // Air-gapped CI and reproducible builds: never touch the network
+    let offline = env::var_os("CARGO_FEATURE_OFFLINE_BUILD").is_some()
+        || env::var("WHOIS_OFFLINE_BUILD").is_ok_and(|v| !v.is_empty() && v != "0" && v != "false");
     
-    // Try to fetch latest IANA data, fallback to minimal set if it fails
-    let mappings = match fetch_iana_mappings().await {
-        Ok(mappings) => {
-            println!("cargo:warning=✅ Fetched {} RDAP mappings from IANA", mappings.len());
-            mappings
-        }
-        Err(e) => {
-            println!("cargo:warning=⚠️ Failed to fetch IANA data ({}), using minimal fallback", e);
-            get_minimal_fallback_mappings()
+    // Try to fetch latest IANA data, fallback to the vendored snapshot if it fails
+    let mappings = if offline {
+        vendored_mappings()?
+    } else {
+        match fetch_iana_mappings().await {
+            Ok(mappings) => {
+                println!("cargo:warning=✅ Fetched {} RDAP mappings from IANA", mappings.len());
+                mappings
+            }
+            Err(e) => {
+                println!("cargo:warning=⚠️ Failed to fetch IANA data ({}), using vendored snapshot", e);
+                vendored_mappings().unwrap_or_else(|e| {
+                    println!("cargo:warning=⚠️ Vendored snapshot unreadable ({}), using minimal fallback", e);
+                    get_minimal_fallback_mappings()
+                })
+            }
         }
     };
     
@@ -50,9 +89,110 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     code.push_str("});\n");
     
     fs::write(dest_path, code)?;
+
+    // Port-43 servers and registry operators for every TLD in the root zone, always
+    // from the vendored snapshot so every build of a commit ships the same maps; the
+    // hand-maintained map still takes precedence
+    let (whois_mappings, operators) = vendored_whois_mappings()?;
+
+    let mut code = String::new();
+    code.push_str("// Auto-generated whois (port 43) TLD mappings from the IANA root zone database\n");
+    code.push_str("// DO NOT EDIT - This file is generated at build time\n\n");
+    code.push_str("pub static GENERATED_WHOIS_SERVERS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {\n");
+    // Built from an array so an empty snapshot doesn't trip unused-mut lints
+    code.push_str("    HashMap::from([\n");
+
+    let mut sorted: Vec<_> = whois_mappings.iter().collect();
+    sorted.sort();
+    for (tld, server) in sorted {
+        code.push_str(&format!("        (\"{}\", \"{}\"),\n", tld, server));
+    }
+
+    code.push_str("    ])\n");
+    code.push_str("});\n\n");
+
+    // Multi-label public suffixes, so "com.tr" needs neither a hand-kept entry nor discovery;
+    // derived from the snapshot's TLD servers, never from anything fetched
+    let suffix_mappings = suffix_mappings(&whois_mappings).unwrap_or_else(|e| {
+        println!("cargo:warning=⚠️ Public suffix list unreadable ({}), skipping suffix mappings", e);
+        HashMap::new()
+    });
+
+    code.push_str("pub static GENERATED_SUFFIX_SERVERS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {\n");
+    code.push_str("    HashMap::from([\n");
+
+    let mut sorted: Vec<_> = suffix_mappings.iter().collect();
+    sorted.sort();
+    for (suffix, server) in sorted {
+        code.push_str(&format!("        (\"{}\", \"{}\"),\n", suffix, server));
+    }
+
+    code.push_str("    ])\n");
+    code.push_str("});\n\n");
+
+    code.push_str("pub static GENERATED_TLD_OPERATORS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {\n");
+    code.push_str("    HashMap::from([\n");
+
+    let mut sorted: Vec<_> = operators.iter().collect();
+    sorted.sort();
+    for (tld, operator) in sorted {
+        code.push_str(&format!("        (\"{}\", {:?}),\n", tld, operator));
+    }
+
+    code.push_str("    ])\n");
+    code.push_str("});\n");
+
+    fs::write(Path::new(&out_dir).join("whois_mappings.rs"), code)?;
     Ok(())
 }
 
+/// Whois server for every multi-label ICANN suffix whose TLD has one in the
+/// vendored snapshot, so the table is the same for every build
+///
+/// Suffixes use their TLD's server unless `SECOND_LEVEL_REGISTRIES` names
+/// another. Wildcard, exception and non-ASCII rules are skipped.
+fn suffix_mappings(whois_mappings: &HashMap<String, String>) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let psl = fs::read_to_string(VENDORED_PUBLIC_SUFFIX_LIST)?;
+    let icann = psl.split_once(PSL_ICANN_END_MARKER).map_or(psl.as_str(), |(icann, _)| icann);
+
+    let mut mappings = HashMap::new();
+    for rule in icann.lines().map(str::trim) {
+        if rule.is_empty() || rule.starts_with("//") || rule.starts_with(['*', '!']) || !rule.is_ascii() {
+            continue;
+        }
+        let Some((_, tld)) = rule.rsplit_once('.') else {
+            continue;
+        };
+        if let Some(server) = whois_mappings.get(tld) {
+            mappings.insert(rule.to_lowercase(), server.clone());
+        }
+    }
+    for (suffix, server) in SECOND_LEVEL_REGISTRIES {
+        mappings.insert(suffix.to_string(), server.to_string());
+    }
+    Ok(mappings)
+}
+
+// TLD -> whois server and TLD -> sponsoring organisation
+type IanaMappings = (HashMap<String, String>, HashMap<String, String>);
+
+fn vendored_whois_mappings() -> Result<IanaMappings, Box<dyn std::error::Error>> {
+    let data = fs::read_to_string(VENDORED_IANA_WHOIS)?;
+    let tlds: HashMap<String, IanaTld> = serde_json::from_str(&data)?;
+
+    let mut mappings = HashMap::new();
+    let mut operators = HashMap::new();
+    for (tld, record) in tlds {
+        if let Some(operator) = record.organisation {
+            operators.insert(tld.clone(), operator);
+        }
+        if let Some(server) = record.whois {
+            mappings.insert(tld, server);
+        }
+    }
+    Ok((mappings, operators))
+}
+
 async fn fetch_iana_mappings() -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
@@ -68,6 +208,16 @@ async fn fetch_iana_mappings() -> Result<HashMap<String, String>, Box<dyn std::e
     }
     
     let bootstrap: RdapBootstrap = response.json().await?;
+    Ok(bootstrap_mappings(bootstrap))
+}
+
+fn vendored_mappings() -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let data = fs::read_to_string(VENDORED_RDAP_BOOTSTRAP)?;
+    let bootstrap: RdapBootstrap = serde_json::from_str(&data)?;
+    Ok(bootstrap_mappings(bootstrap))
+}
+
+fn bootstrap_mappings(bootstrap: RdapBootstrap) -> HashMap<String, String> {
     let mut mappings = HashMap::new();
     
     // Extract mappings, including ALL TLDs for cybersecurity analysis
@@ -80,7 +230,7 @@ async fn fetch_iana_mappings() -> Result<HashMap<String, String>, Box<dyn std::e
         }
     }
     
-    Ok(mappings)
+    mappings
 }
 
 fn get_minimal_fallback_mappings() -> HashMap<String, String> {
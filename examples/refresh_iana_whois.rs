@@ -0,0 +1,126 @@
+//! Refresh the vendored IANA root zone snapshot (data/iana-whois.json)
+//!
+//! Asks whois.iana.org for every TLD in the root zone and records its port-43
+//! server and sponsoring organisation. build.rs generates the whois, operator
+//! and multi-label suffix maps from the snapshot, so builds never depend on
+//! IANA being reachable. A TLD that can't be fetched after a few attempts
+//! fails the run and leaves the snapshot untouched, rather than writing a
+//! partial one.
+//!
+//! ```sh
+//! cargo run --example refresh_iana_whois [-- path/to/iana-whois.json]
+//! ```
+
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Semaphore,
+    task::JoinSet,
+};
+
+const DEFAULT_OUTPUT: &str = "data/iana-whois.json";
+const IANA_TLD_LIST_URL: &str = "https://data.iana.org/TLD/tlds-alpha-by-domain.txt";
+const IANA_WHOIS_SERVER: &str = "whois.iana.org:43";
+// Gentle enough that IANA's rate limiting doesn't drop queries
+const CONCURRENCY: usize = 4;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(15);
+const ATTEMPTS: u32 = 4;
+
+#[derive(Serialize)]
+struct IanaTld {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    whois: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    organisation: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_OUTPUT.to_string());
+
+    let tlds: Vec<String> = reqwest::get(IANA_TLD_LIST_URL)
+        .await?
+        .error_for_status()?
+        .text()
+        .await?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect();
+    eprintln!("Querying {} TLDs at {}", tlds.len(), IANA_WHOIS_SERVER);
+
+    let semaphore = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for tld in tlds {
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore is never closed");
+            let record = fetch_with_retries(&tld).await;
+            (tld, record)
+        });
+    }
+
+    let mut snapshot = BTreeMap::new();
+    let mut failed = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        match result? {
+            (tld, Ok(record)) => {
+                snapshot.insert(tld, record);
+            }
+            (tld, Err(e)) => failed.push(format!("{} ({})", tld, e)),
+        }
+    }
+    if !failed.is_empty() {
+        failed.sort();
+        return Err(format!("{} TLDs could not be fetched, snapshot not written: {}", failed.len(), failed.join(", ")).into());
+    }
+
+    let mut json = serde_json::to_string_pretty(&snapshot)?;
+    json.push('\n');
+    std::fs::write(&output, json)?;
+    eprintln!("Wrote {} TLDs to {}", snapshot.len(), output);
+    Ok(())
+}
+
+async fn fetch_with_retries(tld: &str) -> Result<IanaTld, String> {
+    let mut last_error = String::new();
+    for attempt in 0..ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_secs(2u64 << attempt)).await;
+        }
+        match tokio::time::timeout(QUERY_TIMEOUT, query_iana_whois(tld)).await {
+            Ok(Ok(record)) => return Ok(record),
+            Ok(Err(e)) => last_error = e.to_string(),
+            Err(_) => last_error = "timed out".to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Ask IANA for a TLD's record and return its `whois:` server and sponsoring organisation
+async fn query_iana_whois(tld: &str) -> std::io::Result<IanaTld> {
+    let mut stream = TcpStream::connect(IANA_WHOIS_SERVER).await?;
+    stream.write_all(format!("{}\r\n", tld).as_bytes()).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    if !response.lines().any(|line| line.trim_start().to_lowercase().starts_with("domain:")) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "no delegation record in the answer"));
+    }
+
+    let field = |name: &str| {
+        response.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim();
+            (key.trim().eq_ignore_ascii_case(name) && !value.is_empty()).then(|| value.to_string())
+        })
+    };
+    // The first `organisation:` is the sponsor; admin and tech contacts follow it
+    Ok(IanaTld {
+        whois: field("whois").map(|server| server.to_lowercase()),
+        organisation: field("organisation"),
+    })
+}
@@ -0,0 +1,62 @@
+/*
+ * C API of the whois-service library (Cargo feature `ffi`).
+ *
+ * Build the shared library with
+ *
+ *   cargo rustc --release --lib --no-default-features --features ffi,whois-tcp --crate-type cdylib
+ *
+ * Every function returns a NUL-terminated JSON string that must be released
+ * with whois_string_free(): the lookup response on success, or
+ * {"error": "...", "code": "...", "status": N} on failure. `code` is a
+ * stable machine-readable identifier such as "INVALID_DOMAIN" or
+ * "UPSTREAM_TIMEOUT" (the same codes the HTTP server returns), finer than
+ * `status`. When `status` isn't NULL it receives one of the WHOIS_* codes
+ * below. Calls may block for the whole lookup and are safe from several
+ * threads.
+ */
+
+#ifndef WHOIS_SERVICE_H
+#define WHOIS_SERVICE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum WhoisStatus {
+    WHOIS_OK = 0,
+    WHOIS_INVALID_ARGUMENT = 1, /* NULL, non-UTF-8 or malformed argument */
+    WHOIS_INVALID_DOMAIN = 2,
+    WHOIS_NOT_FOUND = 3,
+    WHOIS_UNSUPPORTED_TLD = 4,
+    WHOIS_TIMEOUT = 5,
+    WHOIS_RATE_LIMITED = 6,
+    WHOIS_NETWORK = 7,          /* connection, HTTP or I/O failure */
+    WHOIS_INIT = 8,             /* the client could not be created */
+    WHOIS_INTERNAL = 9
+} WhoisStatus;
+
+/* Domain lookup, using the cache */
+char *whois_lookup_json(const char *domain, WhoisStatus *status);
+
+/* Domain lookup, bypassing the cache */
+char *whois_lookup_fresh_json(const char *domain, WhoisStatus *status);
+
+/* "available", "registered" or "unknown" */
+char *whois_availability_json(const char *domain, WhoisStatus *status);
+
+/* Netblock holding an IPv4 or IPv6 address */
+char *whois_lookup_ip_json(const char *ip, WhoisStatus *status);
+
+/* Autonomous system number */
+char *whois_lookup_asn_json(uint32_t asn, WhoisStatus *status);
+
+/* Release a string returned above; NULL is ignored */
+void whois_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WHOIS_SERVICE_H */
@@ -0,0 +1,113 @@
+//! Operator endpoints under `/admin`
+//!
+//! Mounted only when `admin_token` is configured; every request must carry
+//! `Authorization: Bearer <admin_token>`.
+//!
+//! - `GET /admin/cache/stats`: hit/miss counters and backend usage
+//! - `DELETE /admin/cache/:domain`: purge one domain (e.g. a poisoned entry);
+//!   a subdomain purges the registrable domain it is cached under
+//! - `DELETE /admin/cache`: purge everything
+//! - `GET /admin/upstreams`: success rate, latency and degraded state per whois server
+
+use crate::{AppState, ValidatedDomain};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use whois_service::{cache::CacheStats, errors::WhoisError, ErrorBody, ServerHealth};
+
+/// Admin routes behind bearer-token auth, or `None` when no token is configured
+pub fn router(admin_token: Option<&str>) -> Option<Router<AppState>> {
+    let token: Arc<str> = Arc::from(admin_token?);
+    Some(
+        Router::new()
+            .route("/admin/cache", delete(clear_cache))
+            .route("/admin/cache/stats", get(cache_stats))
+            .route("/admin/cache/:domain", delete(purge_domain))
+            .route("/admin/upstreams", get(upstreams))
+            .route_layer(axum::middleware::from_fn_with_state(token, require_token)),
+    )
+}
+
+async fn require_token(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => next.run(request).await,
+        _ => {
+            crate::metrics::increment_errors("admin_unauthorized");
+            let body = Json(ErrorBody::new("UNAUTHORIZED", "Unauthorized", 401));
+            (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response()
+        }
+    }
+}
+
+// Comparison time doesn't depend on where the first mismatch is
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn cache_stats(State(state): State<AppState>) -> Json<CacheStats> {
+    Json(state.cache_service.stats().await)
+}
+
+async fn upstreams(State(state): State<AppState>) -> Json<Vec<ServerHealth>> {
+    Json(state.client.server_health())
+}
+
+async fn purge_domain(State(state): State<AppState>, Path(domain): Path<String>) -> Result<Response, WhoisError> {
+    // Entries are keyed like lookups: "mail.example.com" is cached as "example.com"
+    let domain = crate::lookup_domain(&state.config, ValidatedDomain::validate_domain(domain)?.0);
+    let removed = state.cache_service.remove(&domain).await.map_err(WhoisError::CacheError)?;
+    info!(domain, removed, "Admin cache purge");
+    Ok(Json(json!({ "domain": domain, "removed": removed })).into_response())
+}
+
+async fn clear_cache(State(state): State<AppState>) -> Result<Response, WhoisError> {
+    let removed = state.cache_service.clear().await.map_err(WhoisError::CacheError)?;
+    info!(removed, "Admin cache cleared");
+    Ok(Json(json!({ "removed": removed })).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+    use whois_service::config::Config;
+
+    async fn status(app: &Router, authorization: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri("/admin/cache/stats");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_the_bearer_token() {
+        let state = AppState::for_tests(Config::builder().build().unwrap()).await;
+        let app = router(Some("s3cret-token")).unwrap().with_state(state);
+
+        assert_eq!(status(&app, None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app, Some("Bearer wrong-token")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app, Some("Bearer s3cret-toke")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app, Some("Basic czNjcmV0LXRva2Vu")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app, Some("s3cret-token")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app, Some("Bearer s3cret-token")).await, StatusCode::OK);
+
+        // Without a token there is no admin API at all
+        assert!(router(None).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Blocking API (feature `blocking`)
+//!
+//! `blocking::WhoisClient` wraps the async client together with a Tokio
+//! runtime it owns, so synchronous programs and build scripts can do lookups
+//! without setting up an executor. Background work (TLD and bootstrap
+//! refreshes, coalesced queries) runs on that runtime's worker threads between
+//! calls.
+//!
+//! Like `reqwest::blocking`, it must not be used from within an async
+//! runtime: calls would block that runtime's thread, and creating or dropping
+//! the client there panics.
+//!
+//! ```rust,no_run
+//! use whois_service::blocking::WhoisClient;
+//!
+//! fn main() -> Result<(), whois_service::WhoisError> {
+//!     let client = WhoisClient::new()?;
+//!     let response = client.lookup("google.com")?;
+//!     println!("{:?}", response.parsed_data.and_then(|p| p.registrar));
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    AsnWhoisResponse, CacheStats, Config, ConfigBuilder, DomainAvailability, IpWhoisResponse, LookupOptions,
+    WhoisError, WhoisResponse,
+};
+#[cfg(feature = "whois-tcp")]
+use crate::ServerHealth;
+use futures::StreamExt;
+use std::{future::Future, net::IpAddr, sync::Arc};
+use tokio::runtime::Runtime;
+
+// Enough for background refreshes plus the I/O of concurrent lookups
+const WORKER_THREADS: usize = 2;
+
+/// Synchronous whois client; cheap to clone, clones share the runtime and cache
+#[derive(Clone)]
+pub struct WhoisClient {
+    inner: crate::WhoisClient,
+    runtime: Arc<Runtime>,
+}
+
+impl WhoisClient {
+    /// Client with the default configuration and caching
+    pub fn new() -> Result<Self, WhoisError> {
+        Self::start(crate::WhoisClient::new())
+    }
+
+    /// Client without caching
+    pub fn new_without_cache() -> Result<Self, WhoisError> {
+        Self::start(crate::WhoisClient::new_without_cache())
+    }
+
+    /// Client with a custom configuration and caching
+    pub fn new_with_config(config: Arc<Config>) -> Result<Self, WhoisError> {
+        Self::start(crate::WhoisClient::new_with_config(config))
+    }
+
+    /// Client configured from a `Config::builder()`
+    pub fn with_config_builder(config: ConfigBuilder, cache: bool) -> Result<Self, WhoisError> {
+        let builder = crate::WhoisClient::builder().config(config);
+        Self::start(if cache { builder } else { builder.without_cache() }.build())
+    }
+
+    fn start(client: impl Future<Output = Result<crate::WhoisClient, WhoisError>>) -> Result<Self, WhoisError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(WORKER_THREADS)
+            .thread_name("whois-blocking")
+            .enable_all()
+            .build()?;
+        // Built inside the runtime so its background tasks are spawned there
+        let inner = runtime.block_on(client)?;
+        Ok(Self { inner, runtime: Arc::new(runtime) })
+    }
+
+    /// The async client this wraps, for APIs not mirrored here
+    pub fn async_client(&self) -> &crate::WhoisClient {
+        &self.inner
+    }
+
+    /// Run a future on this client's runtime, e.g. one built from `async_client()`
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Look up a domain, using the cache if available
+    pub fn lookup(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        self.block_on(self.inner.lookup(domain))
+    }
+
+    /// Look up a domain, bypassing the cache
+    pub fn lookup_fresh(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        self.block_on(self.inner.lookup_fresh(domain))
+    }
+
+    /// Look up a domain with per-call options
+    pub fn lookup_opts(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResponse, WhoisError> {
+        self.block_on(self.inner.lookup_opts(domain, options))
+    }
+
+    /// Look up many domains concurrently (`concurrent_whois_queries` at a time), in completion order
+    pub fn lookup_many<I>(&self, domains: I) -> Vec<(String, Result<WhoisResponse, WhoisError>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let domains: Vec<String> = domains.into_iter().map(Into::into).collect();
+        self.block_on(self.inner.lookup_stream(futures::stream::iter(domains)).collect())
+    }
+
+    /// Check whether a domain is registered
+    pub fn is_available(&self, domain: &str) -> Result<DomainAvailability, WhoisError> {
+        self.block_on(self.inner.is_available(domain))
+    }
+
+    /// Look up the netblock holding an IP address
+    pub fn lookup_ip(&self, ip: &IpAddr) -> Result<IpWhoisResponse, WhoisError> {
+        self.block_on(self.inner.lookup_ip(ip))
+    }
+
+    /// Look up an autonomous system number
+    pub fn lookup_asn(&self, asn: u32) -> Result<AsnWhoisResponse, WhoisError> {
+        self.block_on(self.inner.lookup_asn(asn))
+    }
+
+    /// Cache statistics (`None` without cache)
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.block_on(self.inner.cache_stats())
+    }
+
+    /// Health of the whois servers queried so far
+    #[cfg(feature = "whois-tcp")]
+    pub fn server_health(&self) -> Vec<ServerHealth> {
+        self.inner.server_health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_lookup_without_runtime() {
+        let client = WhoisClient::new_without_cache().unwrap();
+        assert!(matches!(client.lookup("invalid"), Err(WhoisError::InvalidDomain(_))));
+
+        let results = client.lookup_many(["", "also-invalid"]);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, result)| matches!(result, Err(WhoisError::InvalidDomain(_)))));
+    }
+}
@@ -1,73 +1,135 @@
-use std::sync::Arc;
-use tokio::sync::Mutex;
+//! Reusable network read buffers
+//!
+//! Idle buffers sit in a bounded lock-free queue (crossbeam `ArrayQueue`), so
+//! taking or returning one never waits on or loses to a contended lock. A
+//! buffer is only discarded when the pool already holds `capacity` idle ones.
+
+use crossbeam_queue::ArrayQueue;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
 use tracing::debug;
 
-// Buffer pool type
-pub type BufferPool = Arc<Mutex<Vec<Vec<u8>>>>;
+/// Buffer pool utilization counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BufferPoolStats {
+    /// Maximum idle buffers kept
+    pub capacity: usize,
+    /// Buffers waiting in the pool
+    pub idle: usize,
+    /// Buffers currently checked out
+    pub in_use: usize,
+    /// Checkouts served from the pool
+    pub hits: u64,
+    /// Checkouts that had to allocate
+    pub misses: u64,
+    /// Buffers dropped on return because the pool was full
+    pub discarded: u64,
+}
 
-// RAII Buffer Pool - automatically returns buffer to pool on drop
-pub struct PooledBuffer {
-    buffer: Vec<u8>,
-    pool: BufferPool,
+pub struct BufferPool {
+    buffers: ArrayQueue<Vec<u8>>,
     buffer_size: usize,
-    max_pool_size: usize,
+    in_use: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    discarded: AtomicU64,
 }
 
-impl PooledBuffer {
-    pub fn new(pool: BufferPool, buffer_size: usize, max_pool_size: usize) -> Self {
-        let buffer = match pool.try_lock() {
-            Ok(mut p) => {
-                if let Some(mut buf) = p.pop() {
-                    // Ensure buffer is the right size
-                    if buf.len() != buffer_size {
-                        buf.resize(buffer_size, 0);
-                    } else {
-                        buf.clear();
-                        buf.resize(buffer_size, 0);
-                    }
-                    debug!("Buffer retrieved from pool (remaining: {})", p.len());
-                    buf
-                } else {
-                    debug!("Buffer pool empty, creating new buffer");
-                    vec![0; buffer_size]
-                }
-            },
-            Err(_) => {
-                debug!("Buffer pool locked, creating new buffer to avoid deadlock");
-                vec![0; buffer_size]
+impl BufferPool {
+    pub fn new(capacity: usize, buffer_size: usize) -> Self {
+        Self {
+            buffers: ArrayQueue::new(capacity.max(1)),
+            buffer_size,
+            in_use: AtomicUsize::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    /// Check out a zeroed buffer, allocating when the pool is empty
+    pub fn get(self: &Arc<Self>) -> PooledBuffer {
+        let buffer = match self.buffers.pop() {
+            Some(mut buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer.clear();
+                buffer.resize(self.buffer_size, 0);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                debug!("Buffer pool empty, creating new buffer");
+                vec![0; self.buffer_size]
             }
         };
-        
-        Self { 
-            buffer, 
-            pool, 
-            buffer_size,
-            max_pool_size,
+        self.in_use.fetch_add(1, Ordering::Relaxed);
+
+        PooledBuffer {
+            buffer,
+            pool: self.clone(),
         }
     }
-    
-    pub fn as_mut(&mut self) -> &mut [u8] {
+
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            capacity: self.buffers.capacity(),
+            idle: self.buffers.len(),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// RAII buffer - automatically returns to the pool on drop
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: Arc<BufferPool>,
+}
+
+impl PooledBuffer {
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
         &mut self.buffer
     }
+
+    #[deprecated(note = "renamed to `as_mut_slice`")]
+    #[allow(clippy::should_implement_trait)]
+    pub fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
 }
 
 impl Drop for PooledBuffer {
     fn drop(&mut self) {
-        match self.pool.try_lock() {
-            Ok(mut pool) => {
-                if pool.len() < self.max_pool_size {
-                    // Reset buffer to correct size and clear it
-                    self.buffer.clear();
-                    self.buffer.resize(self.buffer_size, 0);
-                    pool.push(std::mem::take(&mut self.buffer));
-                    debug!("Buffer returned to pool (size: {})", pool.len());
-                } else {
-                    debug!("Buffer pool full, dropping buffer");
-                }
-            },
-            Err(_) => {
-                debug!("Buffer pool locked, dropping buffer to avoid deadlock");
-            }
+        self.pool.in_use.fetch_sub(1, Ordering::Relaxed);
+        if self.pool.buffers.push(std::mem::take(&mut self.buffer)).is_err() {
+            self.pool.discarded.fetch_add(1, Ordering::Relaxed);
+            debug!("Buffer pool full, dropping buffer");
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_reused_and_counted() {
+        let pool = Arc::new(BufferPool::new(1, 512));
+
+        let first = pool.get();
+        let second = pool.get();
+        assert_eq!(pool.stats().in_use, 2);
+        drop(first);
+        drop(second); // pool already holds one idle buffer
+
+        let mut reused = pool.get();
+        assert_eq!(reused.as_mut_slice().len(), 512);
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses, stats.discarded), (1, 2, 1));
+        assert_eq!((stats.idle, stats.in_use), (0, 1));
+    }
+}
@@ -1,48 +1,473 @@
-use crate::{config::Config, WhoisResponse};
-use moka::future::Cache;
-use std::{sync::Arc, time::Duration};
+use crate::{config::Config, telemetry, WhoisResponse};
+use async_trait::async_trait;
+use moka::{future::Cache, notification::RemovalCause};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
 use tracing::debug;
 
+/// Cache hit/miss counters and backend usage
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries currently held, including expired entries retained for stale fallback
+    pub entries: u64,
+    /// Approximate memory (or disk) footprint in bytes
+    pub estimated_size_bytes: u64,
+    /// Entries removed for capacity or expiry
+    pub evictions: u64,
+}
+
+/// One line of a JSONL cache export
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedEntry {
+    domain: String,
+    ttl_remaining_seconds: u64,
+    response: WhoisResponse,
+}
+
+/// Point-in-time usage reported by a cache backend
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheUsage {
+    pub entries: u64,
+    pub estimated_size_bytes: u64,
+    pub evictions: u64,
+}
+
+/// A cached response and when it was stored
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CacheEntry {
+    pub stored_at: u64, // Unix timestamp (seconds)
+    pub response: WhoisResponse,
+}
+
+impl CacheEntry {
+    pub fn age_seconds(&self) -> u64 {
+        unix_now().saturating_sub(self.stored_at)
+    }
+
+    /// Rough in-memory size: struct plus the large string payloads
+    fn estimated_size(&self, key: &str) -> u64 {
+        let response = &self.response;
+        (std::mem::size_of::<Self>()
+            + key.len()
+            + response.domain.len()
+            + response.whois_server.len()
+            + response.raw_data.len()
+            + response.referral_chain.iter().map(|hop| hop.server.len() + hop.raw_data.len()).sum::<usize>()) as u64
+    }
+}
+
+/// Storage backend for cached whois responses
+///
+/// Keys are already normalized by `CacheService`. Backends retain entries for the
+/// retention period they were built with; freshness is decided by `CacheService`.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>, String>;
+    async fn set(&self, key: &str, entry: &CacheEntry) -> Result<(), String>;
+    async fn usage(&self) -> CacheUsage;
+    /// Snapshot of all retained entries
+    async fn entries(&self) -> Result<Vec<(String, CacheEntry)>, String>;
+
+    /// Drop one entry, returning whether it was present
+    async fn remove(&self, _key: &str) -> Result<bool, String> {
+        Err("cache backend does not support removal".to_string())
+    }
+
+    /// Drop every entry, returning how many were held
+    async fn clear(&self) -> Result<u64, String> {
+        Err("cache backend does not support clearing".to_string())
+    }
+}
+
+/// In-memory cache with TTL and LRU-style eviction (default backend)
+pub struct MemoryCache {
+    cache: Cache<String, CacheEntry>,
+    evictions: Arc<AtomicU64>,
+}
+
+impl MemoryCache {
+    pub fn new(max_entries: u64, retention: Duration) -> Self {
+        let evictions = Arc::new(AtomicU64::new(0));
+        let eviction_counter = evictions.clone();
+
+        let cache = Cache::builder()
+            .max_capacity(max_entries)
+            .time_to_live(retention)
+            .eviction_listener(move |_key, _value, cause: RemovalCause| {
+                if cause.was_evicted() {
+                    eviction_counter.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build();
+
+        Self { cache, evictions }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        Ok(self.cache.get(key).await)
+    }
+
+    async fn set(&self, key: &str, entry: &CacheEntry) -> Result<(), String> {
+        self.cache.insert(key.to_string(), entry.clone()).await;
+        Ok(())
+    }
+
+    async fn usage(&self) -> CacheUsage {
+        // Flush pending evictions so counts are current
+        self.cache.run_pending_tasks().await;
+
+        CacheUsage {
+            entries: self.cache.entry_count(),
+            estimated_size_bytes: self.cache.iter().map(|(key, entry)| entry.estimated_size(&key)).sum(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CacheEntry)>, String> {
+        Ok(self.cache.iter().map(|(key, entry)| (key.to_string(), entry)).collect())
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, String> {
+        Ok(self.cache.remove(key).await.is_some())
+    }
+
+    async fn clear(&self) -> Result<u64, String> {
+        self.cache.run_pending_tasks().await;
+        let entries = self.cache.entry_count();
+        self.cache.invalidate_all();
+        self.cache.run_pending_tasks().await;
+        Ok(entries)
+    }
+}
+
+/// Backend that stores nothing, for running with caching turned off
+pub struct NullCache;
+
+#[async_trait]
+impl CacheBackend for NullCache {
+    async fn get(&self, _key: &str) -> Result<Option<CacheEntry>, String> {
+        Ok(None)
+    }
+
+    async fn set(&self, _key: &str, _entry: &CacheEntry) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn usage(&self) -> CacheUsage {
+        CacheUsage::default()
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CacheEntry)>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn remove(&self, _key: &str) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    async fn clear(&self) -> Result<u64, String> {
+        Ok(0)
+    }
+}
+
+/// On-disk cache backed by sled, so results survive process restarts
+#[cfg(feature = "disk-cache")]
+pub struct DiskCache {
+    db: sled::Db,
+    retention: Duration,
+    evictions: AtomicU64,
+}
+
+#[cfg(feature = "disk-cache")]
+impl DiskCache {
+    /// Open (or create) the cache database at `path`, dropping expired entries
+    pub fn open(path: &str, retention: Duration) -> Result<Self, String> {
+        let db = sled::open(path).map_err(|e| format!("Failed to open disk cache at {}: {}", path, e))?;
+        let cache = Self {
+            db,
+            retention,
+            evictions: AtomicU64::new(0),
+        };
+        let purged = cache.purge_expired();
+        tracing::info!("Disk cache opened at {} ({} entries, {} expired removed)", path, cache.db.len(), purged);
+        Ok(cache)
+    }
+
+    fn purge_expired(&self) -> usize {
+        let expired: Vec<_> = self.db
+            .iter()
+            .filter_map(Result::ok)
+            .filter(|(_, value)| self.decode(value).is_none())
+            .map(|(key, _)| key)
+            .collect();
+
+        for key in &expired {
+            let _ = self.db.remove(key);
+        }
+        self.evictions.fetch_add(expired.len() as u64, Ordering::Relaxed);
+        expired.len()
+    }
+
+    /// Decode an entry, treating unreadable (e.g. older format) and expired entries as absent
+    fn decode(&self, value: &[u8]) -> Option<CacheEntry> {
+        serde_json::from_slice::<CacheEntry>(value)
+            .ok()
+            .filter(|entry| entry.age_seconds() < self.retention.as_secs())
+    }
+}
+
+#[cfg(feature = "disk-cache")]
+#[async_trait]
+impl CacheBackend for DiskCache {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        let Some(value) = self.db.get(key).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+
+        match self.decode(&value) {
+            Some(entry) => Ok(Some(entry)),
+            None => {
+                self.db.remove(key).map_err(|e| e.to_string())?;
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn set(&self, key: &str, entry: &CacheEntry) -> Result<(), String> {
+        let value = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+        self.db.insert(key, value).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn usage(&self) -> CacheUsage {
+        CacheUsage {
+            entries: self.db.len() as u64,
+            estimated_size_bytes: self.db.size_on_disk().unwrap_or_default(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CacheEntry)>, String> {
+        let mut entries = Vec::new();
+        for item in self.db.iter() {
+            let (key, value) = item.map_err(|e| e.to_string())?;
+            if let Some(entry) = self.decode(&value) {
+                entries.push((String::from_utf8_lossy(&key).into_owned(), entry));
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, String> {
+        Ok(self.db.remove(key).map_err(|e| e.to_string())?.is_some())
+    }
+
+    async fn clear(&self) -> Result<u64, String> {
+        let entries = self.db.len() as u64;
+        self.db.clear().map_err(|e| e.to_string())?;
+        Ok(entries)
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 pub struct CacheService {
-    cache: Cache<String, WhoisResponse>,
+    backend: Box<dyn CacheBackend>,
+    ttl_seconds: u64,
+    hits: AtomicU64,
+    misses: AtomicU64,
 }
 
 impl CacheService {
+    /// Create the cache configured by `cache_path`: on disk if set, in memory otherwise
+    ///
+    /// Entries are kept for `cache_ttl_seconds + cache_stale_seconds` so expired
+    /// data can still be served by `get_stale` when upstream lookups fail.
     pub fn new(config: Arc<Config>) -> Result<Self, String> {
-        let cache = Cache::builder()
-            .max_capacity(config.cache_max_entries)
-            .time_to_live(Duration::from_secs(config.cache_ttl_seconds))
-            .build();
+        let retention = Duration::from_secs(config.cache_ttl_seconds + config.cache_stale_seconds);
+
+        let backend: Box<dyn CacheBackend> = match config.cache_path {
+            #[cfg(feature = "disk-cache")]
+            Some(ref path) => Box::new(DiskCache::open(path, retention)?),
+            #[cfg(not(feature = "disk-cache"))]
+            Some(_) => return Err("cache_path is set but the disk-cache feature is not enabled".to_string()),
+            None => Box::new(MemoryCache::new(config.cache_max_entries, retention)),
+        };
 
-        Ok(Self { cache })
+        Ok(Self::with_backend(backend, config.cache_ttl_seconds))
     }
 
+    /// Create a cache service on top of a custom backend
+    pub fn with_backend(backend: Box<dyn CacheBackend>, ttl_seconds: u64) -> Self {
+        Self {
+            backend,
+            ttl_seconds,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get a cached response that is still within the TTL
     pub async fn get(&self, domain: &str) -> Result<Option<WhoisResponse>, String> {
         let key = self.normalize_domain(domain);
-        
-        match self.cache.get(&key).await {
-            Some(mut response) => {
+
+        match self.backend.get(&key).await? {
+            Some(entry) if entry.age_seconds() < self.ttl_seconds => {
                 debug!("Cache hit for domain: {}", domain);
-                response.cached = true;
-                Ok(Some(response))
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                telemetry::record_cache_lookup(true);
+                Ok(Some(Self::cached_response(entry, false)))
             },
-            None => {
+            _ => {
                 debug!("Cache miss for domain: {}", domain);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                telemetry::record_cache_lookup(false);
                 Ok(None)
             }
         }
     }
 
+    /// Get a cached response regardless of TTL, marked as degraded if it has expired
+    ///
+    /// Meant as a fallback when upstream lookups fail.
+    pub async fn get_stale(&self, domain: &str) -> Result<Option<WhoisResponse>, String> {
+        let key = self.normalize_domain(domain);
+
+        Ok(self.backend.get(&key).await?.map(|entry| {
+            let expired = entry.age_seconds() >= self.ttl_seconds;
+            Self::cached_response(entry, expired)
+        }))
+    }
+
     pub async fn set(&self, domain: &str, response: &WhoisResponse) -> Result<(), String> {
         let key = self.normalize_domain(domain);
-        self.cache.insert(key, response.clone()).await;
+        let entry = CacheEntry {
+            stored_at: unix_now(),
+            response: response.clone(),
+        };
+        self.backend.set(&key, &entry).await?;
         debug!("Cached response for domain: {}", domain);
         Ok(())
     }
 
+    /// Drop the cached response for a domain, returning whether one was cached
+    pub async fn remove(&self, domain: &str) -> Result<bool, String> {
+        let key = self.normalize_domain(domain);
+        let removed = self.backend.remove(&key).await?;
+        debug!("Removed cache entry for domain: {} (present: {})", domain, removed);
+        Ok(removed)
+    }
+
+    /// Drop every cached response, returning how many were held
+    pub async fn clear(&self) -> Result<u64, String> {
+        let removed = self.backend.clear().await?;
+        tracing::info!("Cleared {} cache entries", removed);
+        Ok(removed)
+    }
+
+    /// Hit/miss counters since creation plus current backend usage
+    pub async fn stats(&self) -> CacheStats {
+        let usage = self.backend.usage().await;
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: usage.entries,
+            estimated_size_bytes: usage.estimated_size_bytes,
+            evictions: usage.evictions,
+        }
+    }
+
+    /// Write unexpired entries as JSONL (`domain`, `ttl_remaining_seconds`, `response`)
+    ///
+    /// Returns the number of entries written.
+    pub async fn export<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<usize, String> {
+        let mut exported = 0;
+        for (domain, entry) in self.backend.entries().await? {
+            let ttl_remaining_seconds = self.ttl_seconds.saturating_sub(entry.age_seconds());
+            if ttl_remaining_seconds == 0 {
+                continue;
+            }
+
+            let line = serde_json::to_string(&ExportedEntry {
+                domain,
+                ttl_remaining_seconds,
+                response: entry.response,
+            })
+            .map_err(|e| e.to_string())?;
+
+            writer.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+            writer.write_all(b"\n").await.map_err(|e| e.to_string())?;
+            exported += 1;
+        }
+        writer.flush().await.map_err(|e| e.to_string())?;
+        Ok(exported)
+    }
+
+    /// Load entries written by `export`, keeping their remaining TTL
+    ///
+    /// Returns the number of entries imported; expired lines are skipped.
+    pub async fn import<R: AsyncBufRead + Unpin>(&self, reader: R) -> Result<usize, String> {
+        let mut lines = reader.lines();
+        let mut imported = 0;
+        let mut line_number = 0;
+
+        while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let exported: ExportedEntry = serde_json::from_str(&line)
+                .map_err(|e| format!("Invalid cache export line {}: {}", line_number, e))?;
+            if exported.ttl_remaining_seconds == 0 {
+                continue;
+            }
+
+            // Backdate the entry so it expires when it would have in the exporting process
+            let age = self.ttl_seconds.saturating_sub(exported.ttl_remaining_seconds);
+            let entry = CacheEntry {
+                stored_at: unix_now().saturating_sub(age),
+                response: exported.response,
+            };
+            self.backend.set(&self.normalize_domain(&exported.domain), &entry).await?;
+            imported += 1;
+        }
+
+        debug!("Imported {} cache entries", imported);
+        Ok(imported)
+    }
+
+    fn cached_response(entry: CacheEntry, degraded: bool) -> WhoisResponse {
+        let data_age_seconds = entry.age_seconds();
+        let mut response = entry.response;
+        response.cached = true;
+        response.degraded = degraded;
+        response.data_age_seconds = Some(data_age_seconds);
+        response
+    }
+
     fn normalize_domain(&self, domain: &str) -> String {
         let normalized = domain.trim().to_lowercase();
-        
+
         // Remove trailing dot if present (common in DNS contexts)
         if normalized.ends_with('.') {
             normalized[..normalized.len() - 1].to_string()
@@ -50,4 +475,95 @@ impl CacheService {
             normalized
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(domain: &str) -> WhoisResponse {
+        WhoisResponse {
+            whois_server: "WHOIS: whois.example".to_string(),
+            query_time_ms: 12,
+            ..crate::test_response(domain)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_served_only_as_stale() {
+        let backend = MemoryCache::new(10, Duration::from_secs(60));
+        let entry = CacheEntry {
+            stored_at: unix_now() - 30,
+            response: response("example.com"),
+        };
+        backend.set("example.com", &entry).await.unwrap();
+
+        let cache = CacheService::with_backend(Box::new(backend), 10);
+        assert!(cache.get("example.com").await.unwrap().is_none());
+
+        let stale = cache.get_stale("Example.com.").await.unwrap().unwrap();
+        assert!(stale.cached && stale.degraded);
+        assert!(stale.data_age_seconds.unwrap() >= 30);
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 1, 1));
+        assert!(stats.estimated_size_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        let source = CacheService::with_backend(Box::new(MemoryCache::new(10, Duration::from_secs(60))), 60);
+        source.set("example.com", &response("example.com")).await.unwrap();
+
+        let mut exported = Vec::new();
+        assert_eq!(source.export(&mut exported).await.unwrap(), 1);
+
+        let target = CacheService::with_backend(Box::new(MemoryCache::new(10, Duration::from_secs(60))), 60);
+        assert_eq!(target.import(exported.as_slice()).await.unwrap(), 1);
+
+        let imported = target.get("example.com").await.unwrap().unwrap();
+        assert_eq!(imported.raw_data, "Domain Name: EXAMPLE.COM");
+        assert!(target.import(&b"not json\n"[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_clear() {
+        let cache = CacheService::with_backend(Box::new(MemoryCache::new(10, Duration::from_secs(60))), 60);
+        cache.set("example.com", &response("example.com")).await.unwrap();
+        cache.set("example.org", &response("example.org")).await.unwrap();
+
+        assert!(cache.remove("Example.COM.").await.unwrap());
+        assert!(!cache.remove("example.com").await.unwrap());
+        assert!(cache.get("example.com").await.unwrap().is_none());
+
+        assert_eq!(cache.clear().await.unwrap(), 1);
+        assert!(cache.get_stale("example.org").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "disk-cache")]
+    #[tokio::test]
+    async fn test_disk_cache_roundtrip_and_expiry() {
+        let path = std::env::temp_dir().join(format!("whois-disk-cache-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let cache = CacheService::with_backend(Box::new(DiskCache::open(path, Duration::from_secs(60)).unwrap()), 60);
+        cache.set("Example.COM.", &response("example.com")).await.unwrap();
+        let cached = cache.get("example.com").await.unwrap().unwrap();
+        assert!(cached.cached && !cached.degraded);
+        assert_eq!(cached.raw_data, "Domain Name: EXAMPLE.COM");
+        drop(cache);
+
+        // Entries past the retention period are dropped as soon as they're read
+        let expired = DiskCache::open(&format!("{}-expired", path), Duration::ZERO).unwrap();
+        let entry = CacheEntry {
+            stored_at: unix_now(),
+            response: response("example.com"),
+        };
+        expired.set("example.com", &entry).await.unwrap();
+        assert!(expired.get("example.com").await.unwrap().is_none());
+        drop(expired);
+
+        let _ = std::fs::remove_dir_all(path);
+        let _ = std::fs::remove_dir_all(format!("{}-expired", path));
+    }
+}
@@ -1,70 +1,631 @@
+use config::Source;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+// std's Instant panics on wasm32-unknown-unknown
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// IANA's RDAP bootstrap registry for domain names
+pub const DEFAULT_RDAP_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/dns.json";
+
+/// IANA's root whois server, used to discover TLD and RIR referrals
+pub const DEFAULT_ROOT_WHOIS_SERVER: &str = "whois.iana.org";
+
+/// Config files `Config::load()` picks up, first match wins
+pub const CONFIG_SEARCH_PATHS: &[&str] = &["whois.toml", "/etc/whois-service/config.toml"];
+
+/// Upper bound for whois and discovery timeouts
+pub const MAX_TIMEOUT_SECONDS: u64 = 300;
+
+/// Smallest network read buffer `validate` accepts
+pub const MIN_BUFFER_SIZE: usize = 512;
+
+/// Nested file sections, flattened to "<section>_<key>"
+const CONFIG_SECTIONS: &[&str] = &["cache", "rdap", "whois"];
+
+/// Nested file keys whose flat name isn't simply "<section>_<key>"
+const SECTION_KEY_ALIASES: &[(&str, &str)] = &[
+    ("whois.root_servers", "root_whois_servers"),
+    ("whois.max_referrals", "max_referrals"),
+    ("whois.discovery_timeout_seconds", "discovery_timeout_seconds"),
+    ("whois.concurrent_queries", "concurrent_whois_queries"),
+    ("whois.server_overrides", "tld_server_overrides"),
+    ("whois.tld_mappings_path", "tld_mappings_path"),
+    ("whois.address_family", "address_family"),
+    ("whois.tld_timeouts", "tld_timeouts"),
+    ("whois.tld_max_referrals", "tld_max_referrals"),
+    ("whois.pool_size", "whois_pool_size"),
+    ("whois.referral_deny_private_ips", "referral_deny_private_ips"),
+    ("whois.referral_allowlist", "referral_allowlist"),
+    ("whois.referral_denylist", "referral_denylist"),
+];
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub port: u16,
+    pub bind_address: String, // IP ("::1") or socket address ("[::1]:3000") for the HTTP server
     pub whois_timeout_seconds: u64,
     pub max_response_size: usize,
     pub cache_ttl_seconds: u64,
     pub cache_max_entries: u64,
+    pub cache_stale_seconds: u64, // How long past the TTL stale entries may be served on upstream failure
     pub start_time: Instant,
     pub max_referrals: usize,
     pub discovery_timeout_seconds: u64,
     pub concurrent_whois_queries: usize,
     pub buffer_pool_size: usize,    // Max buffers in pool
     pub buffer_size: usize,         // Size of each buffer
+    pub proxy_url: Option<String>,       // socks5://, socks5h:// or http:// proxy for all traffic
+    pub whois_proxy_url: Option<String>, // Override for port-43 whois connections
+    pub rdap_proxy_url: Option<String>,  // Override for RDAP HTTP requests
+    pub address_family: AddressFamilyPreference, // IPv4/IPv6 preference for whois connections
+    pub protocol_preference: ProtocolPreference, // RDAP/WHOIS ordering for domain lookups
+    pub whois_default_qps: f64,                  // Per-server query rate for unlisted hosts (0 = unlimited)
+    pub whois_rate_limits: HashMap<String, f64>, // Per-server query rate overrides
+    pub cache_path: Option<String>,              // On-disk cache location (requires disk-cache feature)
+    pub rdap_max_related_hops: usize,            // Registrar RDAP links to follow from a registry response (0 = off)
+    pub rdap_bootstrap_ttl_seconds: u64,         // Re-fetch IANA bootstrap data after this long (0 = never)
+    pub rdap_max_retries: u32,                   // Retries of an RDAP query answered with 429 (0 = surface RateLimited at once)
+    pub rdap_max_retry_wait_seconds: u64,        // Longest Retry-After honoured by waiting; longer ones fail with RateLimited
+    pub whois_tld_refresh_seconds: u64,          // Re-query IANA for whois servers this often (0 = never)
+    pub tld_server_overrides: HashMap<String, String>, // TLD -> whois server, ahead of hardcoded and discovered
+    pub tld_mappings_path: Option<String>,       // JSON file persisting discovered TLD -> whois server mappings
+    pub rdap_bootstrap_urls: Vec<String>,        // RDAP dns.json sources (mirrors) tried in order
+    pub root_whois_servers: Vec<String>,         // Root whois servers (host or host:port) tried in order during discovery
+    pub tld_timeouts: HashMap<String, u64>,      // Per-TLD whois_timeout_seconds for slow registries
+    pub tld_max_referrals: HashMap<String, usize>, // Per-TLD max_referrals
+    pub dns_servers: Vec<SocketAddr>,            // Upstream DNS servers for whois hosts (dns-resolver feature; empty = system)
+    pub dns_cache_ttl_seconds: u64,              // Cache resolved whois host addresses this long (0 = off)
+    pub whois_pool_size: usize,                  // Idle persistent connections kept per keep-alive server (0 = off)
+    pub whois_keepalive_servers: Vec<String>,    // Extra servers supporting RIPE-style "-k" persistent connections
+    pub fixture_mode: FixtureMode,               // Record upstream responses to, or replay them from, fixture_dir
+    pub fixture_dir: Option<String>,             // Directory holding recorded upstream responses
+    pub client_rate_limit_qps: f64,              // HTTP requests per second per client IP or API key (0 = unlimited)
+    pub client_rate_limit_burst: u32,            // Requests a client may send back to back before being limited
+    pub client_api_keys: Vec<String>,            // X-API-Key values limited per key; other requests are limited per IP
+    pub trust_forwarded_for: bool,               // Key clients by X-Forwarded-For (only behind a trusted proxy)
+    pub trusted_proxy_hops: u32,                 // Proxies in front of the server; the client is this many X-Forwarded-For hops from the right
+    pub admin_token: Option<String>,             // Bearer token for /admin endpoints (unset = admin API disabled)
+    pub tls_cert_path: Option<String>,           // PEM certificate chain; with tls_key_path the server speaks HTTPS (tls feature)
+    pub tls_key_path: Option<String>,            // PEM private key matching tls_cert_path
+    pub tls_reload_seconds: u64,                 // Check the certificate files for changes this often (0 = never)
+    pub whois_proxy_port: u16,                   // Also answer classic port-43 whois queries on this port (0 = off)
+    pub webhook_url: Option<String>,             // Default webhook for job/watch events (webhooks feature; unset = none)
+    pub webhook_secret: Option<String>,          // HMAC-SHA256 key for the X-Whois-Signature header
+    pub webhook_max_retries: u32,                // Redeliveries after a failed webhook POST (exponential backoff)
+    pub history_path: Option<String>,            // On-disk lookup history location (history feature; unset = in memory)
+    pub history_max_entries: usize,              // Lookup results kept per domain in the history (0 = unlimited)
+    pub history_max_domains: usize,              // Domains kept by the in-memory history, least recently recorded dropped first (0 = unlimited)
+    pub collapse_subdomains: bool,               // Look up and cache "mail.example.com" as "example.com"
+    pub provider_priority: Vec<String>,          // Commercial provider names in the order they are tried (see provider.rs)
+    pub whoisxmlapi_api_key: Option<String>,     // Enables the WhoisXML API provider tier
+    pub server_health_failure_threshold: u32,    // Consecutive failures before a whois server is avoided (0 = never)
+    pub server_health_cooldown_seconds: u64,     // How long a degraded server is avoided before it is probed again
+    pub adaptive_timeout_factor: f64,            // Per-server timeout = p99 latency x factor, capped at the configured timeout (0 = off)
+    pub adaptive_timeout_min_seconds: u64,       // Floor for adaptive timeouts
+    pub hedge_percentile: f64,                   // WhoisFirst: also ask RDAP once whois is slower than this latency percentile (0 = off)
+    pub referral_deny_private_ips: bool,         // Don't follow referrals to loopback, private or link-local addresses
+    pub referral_allowlist: Vec<String>,         // Only follow referrals to these hosts and their subdomains (empty = any)
+    pub referral_denylist: Vec<String>,          // Never follow referrals to these hosts and their subdomains
+}
+
+/// Which protocols a domain lookup tries, and in what order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolPreference {
+    /// Try RDAP, fall back to WHOIS on failure
+    #[default]
+    RdapFirst,
+    /// Try WHOIS, fall back to RDAP on failure
+    WhoisFirst,
+    /// Only query RDAP
+    RdapOnly,
+    /// Only query WHOIS
+    WhoisOnly,
+}
+
+/// Whether upstream responses are recorded to or replayed from `fixture_dir`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureMode {
+    /// Query upstream servers normally
+    #[default]
+    Off,
+    /// Query upstream servers and save each raw response
+    Record,
+    /// Answer from saved responses only; missing fixtures fail without network access
+    Replay,
+}
+
+/// Address family preference for direct whois TCP connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFamilyPreference {
+    /// Keep resolver order, interleaving address families (RFC 8305)
+    #[default]
+    Auto,
+    /// Try IPv4 addresses first, falling back to IPv6
+    PreferIpv4,
+    /// Try IPv6 addresses first, falling back to IPv4
+    PreferIpv6,
+    /// Only connect over IPv4
+    Ipv4Only,
+    /// Only connect over IPv6
+    Ipv6Only,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ConfigData {
     pub port: u16,
+    pub bind_address: String,
     pub whois_timeout_seconds: u64,
     pub max_response_size: usize,
     pub cache_ttl_seconds: u64,
     pub cache_max_entries: u64,
+    pub cache_stale_seconds: u64,
     pub max_referrals: usize,
     pub discovery_timeout_seconds: u64,
     pub concurrent_whois_queries: usize,
     pub buffer_pool_size: usize,
     pub buffer_size: usize,
+    pub proxy_url: Option<String>,
+    pub whois_proxy_url: Option<String>,
+    pub rdap_proxy_url: Option<String>,
+    pub address_family: AddressFamilyPreference,
+    pub protocol_preference: ProtocolPreference,
+    pub whois_default_qps: f64,
+    pub whois_rate_limits: Option<String>,
+    pub cache_path: Option<String>,
+    pub rdap_max_related_hops: usize,
+    pub rdap_bootstrap_ttl_seconds: u64,
+    pub rdap_max_retries: u32,
+    pub rdap_max_retry_wait_seconds: u64,
+    pub whois_tld_refresh_seconds: u64,
+    pub tld_server_overrides: Option<String>,
+    pub tld_mappings_path: Option<String>,
+    pub rdap_bootstrap_urls: Option<String>,
+    pub root_whois_servers: Option<String>,
+    pub tld_timeouts: Option<String>,
+    pub tld_max_referrals: Option<String>,
+    pub dns_servers: Option<String>,
+    pub dns_cache_ttl_seconds: u64,
+    pub whois_pool_size: usize,
+    pub whois_keepalive_servers: Option<String>,
+    pub fixture_mode: FixtureMode,
+    pub fixture_dir: Option<String>,
+    pub client_rate_limit_qps: f64,
+    pub client_rate_limit_burst: u32,
+    pub client_api_keys: Option<String>,
+    pub trust_forwarded_for: bool,
+    pub trusted_proxy_hops: u32,
+    pub admin_token: Option<String>,
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    pub tls_reload_seconds: u64,
+    pub whois_proxy_port: u16,
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub webhook_max_retries: u32,
+    pub history_path: Option<String>,
+    pub history_max_entries: usize,
+    pub history_max_domains: usize,
+    pub collapse_subdomains: bool,
+    pub provider_priority: Option<String>,
+    pub whoisxmlapi_api_key: Option<String>,
+    pub server_health_failure_threshold: u32,
+    pub server_health_cooldown_seconds: u64,
+    pub adaptive_timeout_factor: f64,
+    pub adaptive_timeout_min_seconds: u64,
+    pub hedge_percentile: f64,
+    pub referral_deny_private_ips: bool,
+    pub referral_allowlist: Option<String>,
+    pub referral_denylist: Option<String>,
 }
 
 impl Config {
+    /// Load configuration from the first file in `CONFIG_SEARCH_PATHS` (if any) and environment variables
     pub fn load() -> Result<Self, config::ConfigError> {
+        let path = CONFIG_SEARCH_PATHS.iter().map(Path::new).find(|path| path.is_file());
+        Self::load_from(path)
+    }
+
+    /// Load configuration from a TOML/YAML/JSON file; environment variables still take precedence
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, config::ConfigError> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(config::ConfigError::Message(format!("Config file not found: {}", path.display())));
+        }
+        Self::load_from(Some(path))
+    }
+
+    /// Start a fluent builder seeded from files, environment variables and system defaults
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Reject settings that would stall or break lookups
+    ///
+    /// Bounds: timeouts 1..=300s, `concurrent_whois_queries` >= 1 (0 deadlocks the
+    /// query semaphores), `buffer_size` >= 512, `buffer_pool_size` >= 1,
+    /// `max_response_size` >= `buffer_size`, `cache_max_entries` >= 1, rates finite
+    /// and non-negative, proxy/bootstrap/webhook URLs must parse, and fixture modes need a
+    /// `fixture_dir`.
+    pub fn validate(&self) -> Result<(), config::ConfigError> {
+        let invalid = |message: String| Err(config::ConfigError::Message(message));
+
+        let http_addr = self.bind_addr()?;
+        if self.whois_proxy_port != 0 && self.whois_proxy_port == http_addr.port() {
+            return invalid(format!("whois_proxy_port {} is already used by the HTTP server", self.whois_proxy_port));
+        }
+
+        for (name, value) in [
+            ("whois_timeout_seconds", self.whois_timeout_seconds),
+            ("discovery_timeout_seconds", self.discovery_timeout_seconds),
+        ] {
+            if !(1..=MAX_TIMEOUT_SECONDS).contains(&value) {
+                return invalid(format!("{} must be between 1 and {}, got {}", name, MAX_TIMEOUT_SECONDS, value));
+            }
+        }
+        if let Some((tld, timeout)) = self.tld_timeouts.iter().find(|(_, t)| !(1..=MAX_TIMEOUT_SECONDS).contains(*t)) {
+            return invalid(format!("Timeout for .{} must be between 1 and {} seconds, got {}", tld, MAX_TIMEOUT_SECONDS, timeout));
+        }
+        if self.concurrent_whois_queries == 0 {
+            return invalid("concurrent_whois_queries must be at least 1 (0 blocks every lookup)".to_string());
+        }
+        if self.buffer_size < MIN_BUFFER_SIZE {
+            return invalid(format!("buffer_size must be at least {} bytes, got {}", MIN_BUFFER_SIZE, self.buffer_size));
+        }
+        if self.trust_forwarded_for && self.trusted_proxy_hops == 0 {
+            return invalid("trusted_proxy_hops must be at least 1 when trust_forwarded_for is set".to_string());
+        }
+        if self.buffer_pool_size == 0 {
+            return invalid("buffer_pool_size must be at least 1".to_string());
+        }
+        if self.max_response_size < self.buffer_size {
+            return invalid(format!(
+                "max_response_size ({}) must not be smaller than buffer_size ({})",
+                self.max_response_size, self.buffer_size
+            ));
+        }
+        if self.cache_max_entries == 0 {
+            return invalid("cache_max_entries must be at least 1".to_string());
+        }
+        if !self.whois_default_qps.is_finite() || self.whois_default_qps < 0.0 {
+            return invalid(format!("whois_default_qps must be 0 (unlimited) or positive, got {}", self.whois_default_qps));
+        }
+        if !self.client_rate_limit_qps.is_finite() || self.client_rate_limit_qps < 0.0 {
+            return invalid(format!("client_rate_limit_qps must be 0 (unlimited) or positive, got {}", self.client_rate_limit_qps));
+        }
+        if !self.adaptive_timeout_factor.is_finite() || self.adaptive_timeout_factor < 0.0 {
+            return invalid(format!("adaptive_timeout_factor must be 0 (off) or positive, got {}", self.adaptive_timeout_factor));
+        }
+        if !(0.0..1.0).contains(&self.hedge_percentile) {
+            return invalid(format!("hedge_percentile must be 0 (off) or between 0 and 1 (e.g. 0.95), got {}", self.hedge_percentile));
+        }
+        if let Some((server, qps)) = self.whois_rate_limits.iter().find(|(_, qps)| !qps.is_finite() || **qps < 0.0) {
+            return invalid(format!("Whois rate limit for {} must be 0 (unlimited) or positive, got {}", server, qps));
+        }
+        for (name, value) in [
+            ("proxy_url", &self.proxy_url),
+            ("whois_proxy_url", &self.whois_proxy_url),
+            ("rdap_proxy_url", &self.rdap_proxy_url),
+        ] {
+            if let Some(Err(e)) = value.as_deref().map(url::Url::parse) {
+                return invalid(format!("{} is not a valid URL: {}", name, e));
+            }
+        }
+        if let Some(webhook_url) = &self.webhook_url {
+            match url::Url::parse(webhook_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => return invalid(format!("webhook_url '{}' must be an http(s) URL", webhook_url)),
+            }
+        }
+        for bootstrap_url in &self.rdap_bootstrap_urls {
+            match url::Url::parse(bootstrap_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => return invalid(format!("RDAP bootstrap URL '{}' must be an http(s) URL", bootstrap_url)),
+            }
+        }
+        if self.root_whois_servers.iter().any(|server| server.trim().is_empty()) {
+            return invalid("root_whois_servers must not contain empty entries".to_string());
+        }
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return invalid("tls_cert_path and tls_key_path must be set together".to_string());
+        }
+        if self.fixture_mode != FixtureMode::Off && self.fixture_dir.is_none() {
+            return invalid("fixture_dir is required when fixture_mode is record or replay".to_string());
+        }
+        Ok(())
+    }
+
+    fn load_from(path: Option<&Path>) -> Result<Self, config::ConfigError> {
         // Get system information for intelligent defaults
         let system_info = Self::detect_system_capabilities();
         
         let mut settings = config::Config::builder()
             .set_default("port", Self::get_default_port())?
+            .set_default("bind_address", "0.0.0.0")?
             .set_default("whois_timeout_seconds", system_info.default_timeout)?
             .set_default("max_response_size", system_info.max_response_size as i64)?
             .set_default("cache_ttl_seconds", system_info.cache_ttl)?
             .set_default("cache_max_entries", system_info.cache_max_entries)?
+            .set_default("cache_stale_seconds", 86400)?
             .set_default("max_referrals", system_info.max_referrals as i64)?
             .set_default("discovery_timeout_seconds", system_info.discovery_timeout)?
             .set_default("concurrent_whois_queries", system_info.concurrent_whois_queries as i64)?
             .set_default("buffer_pool_size", system_info.buffer_pool_size as i64)?
-            .set_default("buffer_size", system_info.buffer_size as i64)?;
+            .set_default("buffer_size", system_info.buffer_size as i64)?
+            .set_default("address_family", "auto")?
+            .set_default("protocol_preference", "rdap_first")?
+            .set_default("whois_default_qps", 0.0)?
+            .set_default("rdap_max_related_hops", 1)?
+            .set_default("rdap_bootstrap_ttl_seconds", 86400)?
+            .set_default("rdap_max_retries", 1)?
+            .set_default("rdap_max_retry_wait_seconds", 5)?
+            .set_default("whois_tld_refresh_seconds", 604800)?
+            .set_default("dns_cache_ttl_seconds", 60)?
+            .set_default("whois_pool_size", 4)?
+            .set_default("fixture_mode", "off")?
+            .set_default("client_rate_limit_qps", 0.0)?
+            .set_default("client_rate_limit_burst", 20)?
+            .set_default("trust_forwarded_for", false)?
+            .set_default("trusted_proxy_hops", 1)?
+            .set_default("tls_reload_seconds", 300)?
+            .set_default("whois_proxy_port", 0)?
+            .set_default("webhook_max_retries", 3)?
+            .set_default("history_max_entries", 100)?
+            .set_default("history_max_domains", 10_000)?
+            .set_default("collapse_subdomains", true)?
+            .set_default("server_health_failure_threshold", 3)?
+            .set_default("server_health_cooldown_seconds", 60)?
+            .set_default("adaptive_timeout_factor", 3.0)?
+            .set_default("adaptive_timeout_min_seconds", 2)?
+            .set_default("hedge_percentile", 0.0)?
+            .set_default("referral_deny_private_ips", false)?;
 
-        // Override with environment variables if present
+        // File values override defaults, environment variables override both
+        if let Some(path) = path {
+            settings = Self::apply_file(settings, path)?;
+        }
         settings = Self::apply_env_overrides(settings)?;
 
         let config_data: ConfigData = settings.build()?.try_deserialize()?;
+        let whois_rate_limits = Self::parse_rate_limits(config_data.whois_rate_limits.as_deref())?;
+        let tld_server_overrides = Self::parse_server_overrides(config_data.tld_server_overrides.as_deref())?;
+        let tld_timeouts = Self::parse_tld_values(config_data.tld_timeouts.as_deref(), "timeout")?;
+        let tld_max_referrals = Self::parse_tld_values(config_data.tld_max_referrals.as_deref(), "max referrals")?;
+        let dns_servers = Self::parse_dns_servers(config_data.dns_servers.as_deref())?;
         
-        Ok(Config {
+        let config = Config {
             port: config_data.port,
+            bind_address: config_data.bind_address,
             whois_timeout_seconds: config_data.whois_timeout_seconds,
             max_response_size: config_data.max_response_size,
             cache_ttl_seconds: config_data.cache_ttl_seconds,
             cache_max_entries: config_data.cache_max_entries,
+            cache_stale_seconds: config_data.cache_stale_seconds,
             max_referrals: config_data.max_referrals,
             discovery_timeout_seconds: config_data.discovery_timeout_seconds,
             concurrent_whois_queries: config_data.concurrent_whois_queries,
             buffer_pool_size: config_data.buffer_pool_size,
             buffer_size: config_data.buffer_size,
+            proxy_url: config_data.proxy_url,
+            whois_proxy_url: config_data.whois_proxy_url,
+            rdap_proxy_url: config_data.rdap_proxy_url,
+            address_family: config_data.address_family,
+            protocol_preference: config_data.protocol_preference,
+            whois_default_qps: config_data.whois_default_qps,
+            whois_rate_limits,
+            cache_path: config_data.cache_path,
+            rdap_max_related_hops: config_data.rdap_max_related_hops,
+            rdap_bootstrap_ttl_seconds: config_data.rdap_bootstrap_ttl_seconds,
+            rdap_max_retries: config_data.rdap_max_retries,
+            rdap_max_retry_wait_seconds: config_data.rdap_max_retry_wait_seconds,
+            whois_tld_refresh_seconds: config_data.whois_tld_refresh_seconds,
+            tld_server_overrides,
+            tld_mappings_path: config_data.tld_mappings_path,
+            rdap_bootstrap_urls: Self::parse_list(config_data.rdap_bootstrap_urls.as_deref(), DEFAULT_RDAP_BOOTSTRAP_URL),
+            root_whois_servers: Self::parse_list(config_data.root_whois_servers.as_deref(), DEFAULT_ROOT_WHOIS_SERVER),
+            tld_timeouts,
+            tld_max_referrals,
+            dns_servers,
+            dns_cache_ttl_seconds: config_data.dns_cache_ttl_seconds,
+            whois_pool_size: config_data.whois_pool_size,
+            whois_keepalive_servers: Self::split_list(config_data.whois_keepalive_servers.as_deref()),
+            fixture_mode: config_data.fixture_mode,
+            fixture_dir: config_data.fixture_dir,
+            client_rate_limit_qps: config_data.client_rate_limit_qps,
+            client_rate_limit_burst: config_data.client_rate_limit_burst,
+            client_api_keys: Self::split_list(config_data.client_api_keys.as_deref()),
+            trust_forwarded_for: config_data.trust_forwarded_for,
+            trusted_proxy_hops: config_data.trusted_proxy_hops,
+            admin_token: config_data.admin_token.filter(|token| !token.is_empty()),
+            tls_cert_path: config_data.tls_cert_path,
+            tls_key_path: config_data.tls_key_path,
+            tls_reload_seconds: config_data.tls_reload_seconds,
+            whois_proxy_port: config_data.whois_proxy_port,
+            webhook_url: config_data.webhook_url.filter(|url| !url.is_empty()),
+            webhook_secret: config_data.webhook_secret.filter(|secret| !secret.is_empty()),
+            webhook_max_retries: config_data.webhook_max_retries,
+            history_path: config_data.history_path.filter(|path| !path.is_empty()),
+            history_max_entries: config_data.history_max_entries,
+            history_max_domains: config_data.history_max_domains,
+            collapse_subdomains: config_data.collapse_subdomains,
+            provider_priority: Self::split_list(config_data.provider_priority.as_deref()),
+            whoisxmlapi_api_key: config_data.whoisxmlapi_api_key.filter(|key| !key.is_empty()),
+            server_health_failure_threshold: config_data.server_health_failure_threshold,
+            server_health_cooldown_seconds: config_data.server_health_cooldown_seconds,
+            adaptive_timeout_factor: config_data.adaptive_timeout_factor,
+            adaptive_timeout_min_seconds: config_data.adaptive_timeout_min_seconds,
+            hedge_percentile: config_data.hedge_percentile,
+            referral_deny_private_ips: config_data.referral_deny_private_ips,
+            referral_allowlist: Self::split_list(config_data.referral_allowlist.as_deref()),
+            referral_denylist: Self::split_list(config_data.referral_denylist.as_deref()),
             start_time: Instant::now(),
-        })
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Socket address for the HTTP server
+    ///
+    /// A bare IP (`127.0.0.1`, `::1`, `[::1]`) uses `port`; a full socket address
+    /// (`[::1]:3000`) carries its own port.
+    pub fn bind_addr(&self) -> Result<SocketAddr, config::ConfigError> {
+        let address = self.bind_address.trim();
+        if let Ok(addr) = address.parse::<SocketAddr>() {
+            return Ok(addr);
+        }
+        address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| SocketAddr::new(ip, self.port))
+            .map_err(|_| config::ConfigError::Message(format!(
+                "Invalid bind_address '{}', expected an IP or socket address such as 127.0.0.1 or [::1]:3000",
+                self.bind_address
+            )))
+    }
+
+    /// Whois timeout in seconds for a TLD, honouring per-TLD overrides ("com.br" before "br")
+    pub fn whois_timeout_for(&self, tld: &str) -> u64 {
+        Self::tld_value(&self.tld_timeouts, tld).unwrap_or(self.whois_timeout_seconds)
+    }
+
+    /// Referral depth for a TLD, honouring per-TLD overrides ("com.br" before "br")
+    pub fn max_referrals_for(&self, tld: &str) -> usize {
+        Self::tld_value(&self.tld_max_referrals, tld).unwrap_or(self.max_referrals)
+    }
+
+    fn tld_value<T: Copy>(values: &HashMap<String, T>, tld: &str) -> Option<T> {
+        let mut suffix = tld.trim_start_matches('.');
+        loop {
+            if let Some(value) = values.get(suffix) {
+                return Some(*value);
+            }
+            suffix = suffix.split_once('.')?.1;
+        }
+    }
+
+    /// Proxy for port-43 whois connections (protocol override, then global proxy)
+    pub fn whois_proxy(&self) -> Option<&str> {
+        self.whois_proxy_url.as_deref().or(self.proxy_url.as_deref())
+    }
+
+    /// Proxy for RDAP HTTP requests (protocol override, then global proxy)
+    pub fn rdap_proxy(&self) -> Option<&str> {
+        self.rdap_proxy_url.as_deref().or(self.proxy_url.as_deref())
+    }
+
+    /// Parse "host=qps,host=qps" rate limit overrides
+    fn parse_rate_limits(value: Option<&str>) -> Result<HashMap<String, f64>, config::ConfigError> {
+        let mut limits = HashMap::new();
+        for entry in value.unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let parsed = entry
+                .split_once('=')
+                .and_then(|(host, qps)| Some((host.trim().to_lowercase(), qps.trim().parse::<f64>().ok()?)));
+            match parsed {
+                Some((host, qps)) if !host.is_empty() && qps >= 0.0 => {
+                    limits.insert(host, qps);
+                }
+                _ => {
+                    return Err(config::ConfigError::Message(format!(
+                        "Invalid whois rate limit '{}', expected host=qps",
+                        entry
+                    )))
+                }
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Parse "tld=value,tld=value" per-TLD overrides
+    fn parse_tld_values<T: std::str::FromStr>(value: Option<&str>, what: &str) -> Result<HashMap<String, T>, config::ConfigError> {
+        let mut values = HashMap::new();
+        for entry in value.unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let parsed = entry.split_once('=').and_then(|(tld, value)| {
+                let tld = tld.trim().trim_start_matches('.').to_lowercase();
+                Some((tld, value.trim().parse::<T>().ok()?))
+            });
+            match parsed {
+                Some((tld, value)) if !tld.is_empty() => {
+                    values.insert(tld, value);
+                }
+                _ => {
+                    return Err(config::ConfigError::Message(format!(
+                        "Invalid per-TLD {} '{}', expected tld=value",
+                        what, entry
+                    )))
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Parse "ip,ip:port,[ipv6]:port" DNS servers (port 53 when omitted)
+    fn parse_dns_servers(value: Option<&str>) -> Result<Vec<SocketAddr>, config::ConfigError> {
+        value
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse::<SocketAddr>()
+                    .or_else(|_| entry.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                    .map_err(|_| config::ConfigError::Message(format!("Invalid DNS server '{}', expected an IP or IP:port", entry)))
+            })
+            .collect()
+    }
+
+    /// Parse a comma-separated list, falling back to a single default entry
+    fn parse_list(value: Option<&str>, default: &str) -> Vec<String> {
+        let urls = Self::split_list(value);
+        if urls.is_empty() {
+            vec![default.to_string()]
+        } else {
+            urls
+        }
+    }
+
+    /// Split a comma-separated list, dropping empty entries
+    fn split_list(value: Option<&str>) -> Vec<String> {
+        value
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Parse "tld=server,tld=server" whois server overrides
+    fn parse_server_overrides(value: Option<&str>) -> Result<HashMap<String, String>, config::ConfigError> {
+        let mut overrides = HashMap::new();
+        for entry in value.unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((tld, server)) if !tld.trim().trim_start_matches('.').is_empty() && !server.trim().is_empty() => {
+                    overrides.insert(
+                        tld.trim().trim_start_matches('.').to_lowercase(),
+                        server.trim().to_lowercase(),
+                    );
+                }
+                _ => {
+                    return Err(config::ConfigError::Message(format!(
+                        "Invalid TLD server override '{}', expected tld=server",
+                        entry
+                    )))
+                }
+            }
+        }
+        Ok(overrides)
     }
 
     fn detect_system_capabilities() -> SystemCapabilities {
@@ -135,7 +696,7 @@ impl Config {
     fn calculate_max_response_size(available_memory: u64) -> usize {
         // Use 0.1% of available memory, capped between 1MB and 10MB
         let calculated = (available_memory as f64 * 0.001) as usize;
-        calculated.max(1024 * 1024).min(10 * 1024 * 1024)
+        calculated.clamp(1024 * 1024, 10 * 1024 * 1024)
     }
 
     fn calculate_cache_size(available_memory: u64) -> u64 {
@@ -181,10 +742,58 @@ impl Config {
             .unwrap_or(3000)
     }
 
+    fn apply_file(mut settings: config::ConfigBuilder<config::builder::DefaultState>, path: &Path) -> Result<config::ConfigBuilder<config::builder::DefaultState>, config::ConfigError> {
+        for (key, value) in config::File::from(PathBuf::from(path)).collect()? {
+            match value.kind {
+                config::ValueKind::Table(section) if CONFIG_SECTIONS.contains(&key.as_str()) => {
+                    for (name, value) in section {
+                        settings = settings.set_override(Self::section_key(&key, &name), Self::flatten_value(value))?;
+                    }
+                }
+                kind => {
+                    settings = settings.set_override(key, Self::flatten_value(config::Value::new(None, kind)))?;
+                }
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Flat config key for a nested "[section] name" file entry
+    fn section_key(section: &str, name: &str) -> String {
+        let nested = format!("{}.{}", section, name);
+        SECTION_KEY_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == nested)
+            .map(|(_, key)| key.to_string())
+            .unwrap_or_else(|| format!("{}_{}", section, name))
+    }
+
+    /// Lists and tables become the comma-separated strings the environment variables use
+    fn flatten_value(value: config::Value) -> config::Value {
+        match value.kind {
+            config::ValueKind::Array(items) => items
+                .into_iter()
+                .filter_map(|item| item.into_string().ok())
+                .collect::<Vec<_>>()
+                .join(",")
+                .into(),
+            config::ValueKind::Table(table) => table
+                .into_iter()
+                .filter_map(|(name, item)| Some(format!("{}={}", name, item.into_string().ok()?)))
+                .collect::<Vec<_>>()
+                .join(",")
+                .into(),
+            kind => config::Value::new(None, kind),
+        }
+    }
+
     fn apply_env_overrides(mut settings: config::ConfigBuilder<config::builder::DefaultState>) -> Result<config::ConfigBuilder<config::builder::DefaultState>, config::ConfigError> {
         // Apply all possible environment variable overrides
         let env_mappings = [
             ("PORT", "port"),
+            ("BIND_ADDRESS", "bind_address"),
+            ("BIND_ADDR", "bind_address"),
             ("WHOIS_TIMEOUT_SECONDS", "whois_timeout_seconds"),
             ("WHOIS_TIMEOUT", "whois_timeout_seconds"),
             ("MAX_RESPONSE_SIZE", "max_response_size"),
@@ -192,12 +801,71 @@ impl Config {
             ("CACHE_TTL", "cache_ttl_seconds"),
             ("CACHE_MAX_ENTRIES", "cache_max_entries"),
             ("CACHE_SIZE", "cache_max_entries"),
+            ("CACHE_PATH", "cache_path"),
+            ("CACHE_STALE_SECONDS", "cache_stale_seconds"),
             ("MAX_REFERRALS", "max_referrals"),
             ("DISCOVERY_TIMEOUT_SECONDS", "discovery_timeout_seconds"),
             ("DISCOVERY_TIMEOUT", "discovery_timeout_seconds"),
             ("CONCURRENT_WHOIS_QUERIES", "concurrent_whois_queries"),
             ("BUFFER_POOL_SIZE", "buffer_pool_size"),
             ("BUFFER_SIZE", "buffer_size"),
+            ("PROXY_URL", "proxy_url"),
+            ("WHOIS_PROXY_URL", "whois_proxy_url"),
+            ("RDAP_PROXY_URL", "rdap_proxy_url"),
+            ("ADDRESS_FAMILY", "address_family"),
+            ("WHOIS_ADDRESS_FAMILY", "address_family"),
+            ("PROTOCOL_PREFERENCE", "protocol_preference"),
+            ("LOOKUP_PROTOCOL", "protocol_preference"),
+            ("WHOIS_DEFAULT_QPS", "whois_default_qps"),
+            ("WHOIS_RATE_LIMITS", "whois_rate_limits"),
+            ("RDAP_MAX_RELATED_HOPS", "rdap_max_related_hops"),
+            ("RDAP_BOOTSTRAP_TTL_SECONDS", "rdap_bootstrap_ttl_seconds"),
+            ("RDAP_MAX_RETRIES", "rdap_max_retries"),
+            ("RDAP_MAX_RETRY_WAIT_SECONDS", "rdap_max_retry_wait_seconds"),
+            ("WHOIS_TLD_REFRESH_SECONDS", "whois_tld_refresh_seconds"),
+            ("TLD_SERVER_OVERRIDES", "tld_server_overrides"),
+            ("WHOIS_SERVER_OVERRIDES", "tld_server_overrides"),
+            ("TLD_MAPPINGS_PATH", "tld_mappings_path"),
+            ("RDAP_BOOTSTRAP_URLS", "rdap_bootstrap_urls"),
+            ("RDAP_BOOTSTRAP_URL", "rdap_bootstrap_urls"),
+            ("ROOT_WHOIS_SERVERS", "root_whois_servers"),
+            ("WHOIS_ROOT_SERVERS", "root_whois_servers"),
+            ("TLD_TIMEOUTS", "tld_timeouts"),
+            ("WHOIS_TLD_TIMEOUTS", "tld_timeouts"),
+            ("TLD_MAX_REFERRALS", "tld_max_referrals"),
+            ("DNS_SERVERS", "dns_servers"),
+            ("DNS_CACHE_TTL_SECONDS", "dns_cache_ttl_seconds"),
+            ("WHOIS_POOL_SIZE", "whois_pool_size"),
+            ("WHOIS_KEEPALIVE_SERVERS", "whois_keepalive_servers"),
+            ("FIXTURE_MODE", "fixture_mode"),
+            ("FIXTURE_DIR", "fixture_dir"),
+            ("CLIENT_RATE_LIMIT_QPS", "client_rate_limit_qps"),
+            ("CLIENT_RATE_LIMIT_BURST", "client_rate_limit_burst"),
+            ("CLIENT_API_KEYS", "client_api_keys"),
+            ("TRUST_FORWARDED_FOR", "trust_forwarded_for"),
+            ("TRUSTED_PROXY_HOPS", "trusted_proxy_hops"),
+            ("ADMIN_TOKEN", "admin_token"),
+            ("TLS_CERT_PATH", "tls_cert_path"),
+            ("TLS_KEY_PATH", "tls_key_path"),
+            ("TLS_RELOAD_SECONDS", "tls_reload_seconds"),
+            ("WHOIS_PROXY_PORT", "whois_proxy_port"),
+            ("WEBHOOK_URL", "webhook_url"),
+            ("WEBHOOK_SECRET", "webhook_secret"),
+            ("WEBHOOK_MAX_RETRIES", "webhook_max_retries"),
+            ("HISTORY_PATH", "history_path"),
+            ("HISTORY_MAX_ENTRIES", "history_max_entries"),
+            ("HISTORY_MAX_DOMAINS", "history_max_domains"),
+            ("COLLAPSE_SUBDOMAINS", "collapse_subdomains"),
+            ("PROVIDER_PRIORITY", "provider_priority"),
+            ("WHOISXMLAPI_API_KEY", "whoisxmlapi_api_key"),
+            ("SERVER_HEALTH_FAILURE_THRESHOLD", "server_health_failure_threshold"),
+            ("SERVER_HEALTH_COOLDOWN_SECONDS", "server_health_cooldown_seconds"),
+            ("ADAPTIVE_TIMEOUT_FACTOR", "adaptive_timeout_factor"),
+            ("ADAPTIVE_TIMEOUT_MIN_SECONDS", "adaptive_timeout_min_seconds"),
+            ("HEDGE_PERCENTILE", "hedge_percentile"),
+            ("REFERRAL_DENY_PRIVATE_IPS", "referral_deny_private_ips"),
+            ("REFERRAL_ALLOWLIST", "referral_allowlist"),
+            ("REFERRAL_DENYLIST", "referral_denylist"),
         ];
 
         for (env_var, config_key) in env_mappings {
@@ -210,6 +878,315 @@ impl Config {
     }
 }
 
+/// Fluent alternative to mutating `Config` fields directly
+///
+/// Unset values come from `Config::load()` (or `file`), so new config fields
+/// never break existing builder code.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    file: Option<PathBuf>,
+    port: Option<u16>,
+    bind_address: Option<String>,
+    whois_timeout: Option<Duration>,
+    discovery_timeout: Option<Duration>,
+    cache_ttl: Option<Duration>,
+    cache_max_entries: Option<u64>,
+    cache_stale: Option<Duration>,
+    cache_path: Option<String>,
+    concurrent_queries: Option<usize>,
+    max_referrals: Option<usize>,
+    proxy_url: Option<String>,
+    address_family: Option<AddressFamilyPreference>,
+    protocol_preference: Option<ProtocolPreference>,
+    whois_rate_limits: HashMap<String, f64>,
+    tld_server_overrides: HashMap<String, String>,
+    tld_timeouts: HashMap<String, u64>,
+    tld_max_referrals: HashMap<String, usize>,
+    rdap_bootstrap_urls: Option<Vec<String>>,
+    root_whois_servers: Option<Vec<String>>,
+    dns_servers: Option<Vec<SocketAddr>>,
+    fixtures: Option<(FixtureMode, String)>,
+    collapse_subdomains: Option<bool>,
+    provider_priority: Option<Vec<String>>,
+    whoisxmlapi_api_key: Option<String>,
+    referral_deny_private_ips: Option<bool>,
+    referral_allowlist: Option<Vec<String>>,
+    referral_denylist: Option<Vec<String>>,
+}
+
+impl ConfigBuilder {
+    /// Seed from a config file instead of the default search path
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// HTTP server bind address (IP or socket address)
+    pub fn bind_address(mut self, address: impl Into<String>) -> Self {
+        self.bind_address = Some(address.into());
+        self
+    }
+
+    /// Per-query whois timeout (whole seconds)
+    pub fn whois_timeout(mut self, timeout: Duration) -> Self {
+        self.whois_timeout = Some(timeout);
+        self
+    }
+
+    /// RDAP and server discovery timeout (whole seconds)
+    pub fn discovery_timeout(mut self, timeout: Duration) -> Self {
+        self.discovery_timeout = Some(timeout);
+        self
+    }
+
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    pub fn cache_max_entries(mut self, entries: u64) -> Self {
+        self.cache_max_entries = Some(entries);
+        self
+    }
+
+    /// How long past the TTL stale entries may be served on upstream failure
+    pub fn cache_stale(mut self, window: Duration) -> Self {
+        self.cache_stale = Some(window);
+        self
+    }
+
+    /// On-disk cache location (requires disk-cache feature)
+    pub fn cache_path(mut self, path: impl Into<String>) -> Self {
+        self.cache_path = Some(path.into());
+        self
+    }
+
+    pub fn concurrent_queries(mut self, queries: usize) -> Self {
+        self.concurrent_queries = Some(queries);
+        self
+    }
+
+    pub fn max_referrals(mut self, referrals: usize) -> Self {
+        self.max_referrals = Some(referrals);
+        self
+    }
+
+    /// socks5://, socks5h:// or http:// proxy for all traffic
+    pub fn proxy_url(mut self, url: impl Into<String>) -> Self {
+        self.proxy_url = Some(url.into());
+        self
+    }
+
+    pub fn address_family(mut self, family: AddressFamilyPreference) -> Self {
+        self.address_family = Some(family);
+        self
+    }
+
+    pub fn protocol_preference(mut self, preference: ProtocolPreference) -> Self {
+        self.protocol_preference = Some(preference);
+        self
+    }
+
+    /// Per-server query rate (queries/second, 0 = unlimited)
+    pub fn whois_rate_limit(mut self, server: impl Into<String>, qps: f64) -> Self {
+        self.whois_rate_limits.insert(server.into().to_lowercase(), qps);
+        self
+    }
+
+    /// Pin the whois server for a TLD
+    pub fn tld_server_override(mut self, tld: impl Into<String>, server: impl Into<String>) -> Self {
+        let tld = tld.into();
+        self.tld_server_overrides.insert(tld.trim_start_matches('.').to_lowercase(), server.into());
+        self
+    }
+
+    /// Longer (or shorter) whois timeout for a slow registry (whole seconds)
+    pub fn tld_timeout(mut self, tld: impl Into<String>, timeout: Duration) -> Self {
+        let tld = tld.into();
+        self.tld_timeouts.insert(tld.trim_start_matches('.').to_lowercase(), timeout.as_secs());
+        self
+    }
+
+    /// Referral depth for a TLD
+    pub fn tld_max_referrals(mut self, tld: impl Into<String>, referrals: usize) -> Self {
+        let tld = tld.into();
+        self.tld_max_referrals.insert(tld.trim_start_matches('.').to_lowercase(), referrals);
+        self
+    }
+
+    /// RDAP bootstrap sources, tried in order
+    pub fn rdap_bootstrap_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rdap_bootstrap_urls = Some(urls.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Root whois servers (host or host:port), tried in order
+    pub fn root_whois_servers<I, S>(mut self, servers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.root_whois_servers = Some(servers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Upstream DNS servers for whois hosts (requires the dns-resolver feature)
+    pub fn dns_servers(mut self, servers: impl IntoIterator<Item = SocketAddr>) -> Self {
+        self.dns_servers = Some(servers.into_iter().collect());
+        self
+    }
+
+    /// Record upstream responses to, or replay them from, `dir`
+    pub fn fixtures(mut self, mode: FixtureMode, dir: impl Into<String>) -> Self {
+        self.fixtures = Some((mode, dir.into()));
+        self
+    }
+
+    /// Look up subdomains as their registrable domain (on by default)
+    pub fn collapse_subdomains(mut self, collapse: bool) -> Self {
+        self.collapse_subdomains = Some(collapse);
+        self
+    }
+
+    /// Provider names in the order the commercial provider tier tries them
+    pub fn provider_priority<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.provider_priority = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enable the WhoisXML API provider with this key
+    pub fn whoisxmlapi_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.whoisxmlapi_api_key = Some(api_key.into());
+        self
+    }
+
+    /// Refuse referrals to loopback, private and link-local addresses
+    pub fn referral_deny_private_ips(mut self, deny: bool) -> Self {
+        self.referral_deny_private_ips = Some(deny);
+        self
+    }
+
+    /// Only follow referrals to these hosts and their subdomains
+    pub fn referral_allowlist<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.referral_allowlist = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Never follow referrals to these hosts and their subdomains
+    pub fn referral_denylist<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.referral_denylist = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Resolve the base configuration, apply builder values and validate the result
+    pub fn build(self) -> Result<Config, config::ConfigError> {
+        let mut config = match self.file {
+            Some(path) => Config::from_file(path)?,
+            None => Config::load()?,
+        };
+
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(address) = self.bind_address {
+            config.bind_address = address;
+        }
+        if let Some(timeout) = self.whois_timeout {
+            config.whois_timeout_seconds = timeout.as_secs();
+        }
+        if let Some(timeout) = self.discovery_timeout {
+            config.discovery_timeout_seconds = timeout.as_secs();
+        }
+        if let Some(ttl) = self.cache_ttl {
+            config.cache_ttl_seconds = ttl.as_secs();
+        }
+        if let Some(entries) = self.cache_max_entries {
+            config.cache_max_entries = entries;
+        }
+        if let Some(window) = self.cache_stale {
+            config.cache_stale_seconds = window.as_secs();
+        }
+        if let Some(path) = self.cache_path {
+            config.cache_path = Some(path);
+        }
+        if let Some(queries) = self.concurrent_queries {
+            config.concurrent_whois_queries = queries;
+        }
+        if let Some(referrals) = self.max_referrals {
+            config.max_referrals = referrals;
+        }
+        if let Some(url) = self.proxy_url {
+            config.proxy_url = Some(url);
+        }
+        if let Some(family) = self.address_family {
+            config.address_family = family;
+        }
+        if let Some(preference) = self.protocol_preference {
+            config.protocol_preference = preference;
+        }
+        config.whois_rate_limits.extend(self.whois_rate_limits);
+        config.tld_server_overrides.extend(self.tld_server_overrides);
+        config.tld_timeouts.extend(self.tld_timeouts);
+        config.tld_max_referrals.extend(self.tld_max_referrals);
+        if let Some(urls) = self.rdap_bootstrap_urls.filter(|urls| !urls.is_empty()) {
+            config.rdap_bootstrap_urls = urls;
+        }
+        if let Some(servers) = self.root_whois_servers.filter(|servers| !servers.is_empty()) {
+            config.root_whois_servers = servers;
+        }
+
+        if let Some(servers) = self.dns_servers {
+            config.dns_servers = servers;
+        }
+        if let Some((mode, dir)) = self.fixtures {
+            config.fixture_mode = mode;
+            config.fixture_dir = Some(dir);
+        }
+        if let Some(collapse) = self.collapse_subdomains {
+            config.collapse_subdomains = collapse;
+        }
+        if let Some(names) = self.provider_priority {
+            config.provider_priority = names;
+        }
+        if let Some(api_key) = self.whoisxmlapi_api_key {
+            config.whoisxmlapi_api_key = Some(api_key);
+        }
+        if let Some(deny) = self.referral_deny_private_ips {
+            config.referral_deny_private_ips = deny;
+        }
+        if let Some(hosts) = self.referral_allowlist {
+            config.referral_allowlist = hosts;
+        }
+        if let Some(hosts) = self.referral_denylist {
+            config.referral_denylist = hosts;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 struct SystemCapabilities {
     default_timeout: u64,
     max_response_size: usize,
@@ -220,4 +1197,99 @@ struct SystemCapabilities {
     concurrent_whois_queries: usize,
     buffer_pool_size: usize,
     buffer_size: usize,
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_file_nested_sections() {
+        let path = std::env::temp_dir().join(format!("whois-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+port = 8080
+
+[cache]
+ttl_seconds = 600
+
+[rdap]
+bootstrap_urls = ["https://mirror.example/dns.json", "https://data.iana.org/rdap/dns.json"]
+
+[whois]
+timeout_seconds = 7
+root_servers = ["relay.example:4343"]
+server_overrides = { io = "whois.nic.io" }
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(config.cache_ttl_seconds, 600);
+        assert_eq!(config.whois_timeout_seconds, 7);
+        assert_eq!(config.rdap_bootstrap_urls.len(), 2);
+        assert_eq!(config.root_whois_servers, vec!["relay.example:4343".to_string()]);
+        assert_eq!(config.tld_server_overrides.get("io").map(String::as_str), Some("whois.nic.io"));
+        assert!(Config::from_file("/nonexistent/whois.toml").is_err());
+    }
+
+    #[test]
+    fn test_builder_overrides_and_validation() {
+        let config = Config::builder()
+            .whois_timeout(Duration::from_secs(12))
+            .cache_ttl(Duration::from_secs(300))
+            .concurrent_queries(4)
+            .tld_server_override(".IO", "whois.nic.io")
+            .build()
+            .unwrap();
+        assert_eq!(config.whois_timeout_seconds, 12);
+        assert_eq!(config.cache_ttl_seconds, 300);
+        assert_eq!(config.concurrent_whois_queries, 4);
+        assert_eq!(config.tld_server_overrides.get("io").map(String::as_str), Some("whois.nic.io"));
+
+        assert!(Config::builder().concurrent_queries(0).build().is_err());
+        assert!(Config::builder().whois_timeout(Duration::from_millis(500)).build().is_err());
+
+        let config = Config::builder()
+            .whois_timeout(Duration::from_secs(10))
+            .tld_timeout("br", Duration::from_secs(60))
+            .tld_max_referrals("com.br", 0)
+            .build()
+            .unwrap();
+        assert_eq!(config.whois_timeout_for("com.br"), 60);
+        assert_eq!(config.whois_timeout_for("com"), 10);
+        assert_eq!(config.max_referrals_for("com.br"), 0);
+        assert_eq!(config.max_referrals_for("br"), config.max_referrals);
+    }
+
+    #[test]
+    fn test_validate_rejects_nonsense() {
+        let mut config = Config::builder().build().unwrap();
+        config.buffer_size = 0;
+        assert!(config.validate().unwrap_err().to_string().contains("buffer_size"));
+
+        let mut config = Config::builder().build().unwrap();
+        config.concurrent_whois_queries = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::builder().build().unwrap();
+        config.rdap_bootstrap_urls = vec!["ftp://example.com/dns.json".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_bind_addr() {
+        let mut config = Config::builder().port(3000).build().unwrap();
+        config.bind_address = "127.0.0.1".to_string();
+        assert_eq!(config.bind_addr().unwrap(), "127.0.0.1:3000".parse().unwrap());
+        config.bind_address = "[::1]:8080".to_string();
+        assert_eq!(config.bind_addr().unwrap(), "[::1]:8080".parse().unwrap());
+        config.bind_address = "::1".to_string();
+        assert_eq!(config.bind_addr().unwrap(), "[::1]:3000".parse().unwrap());
+        config.bind_address = "localhost".to_string();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Per-host pool of persistent whois connections
+//!
+//! Port-43 servers normally close the connection after every answer. RIPE-style
+//! servers (RIPE NCC, APNIC, AFRINIC) accept `-k` to keep it open for further
+//! queries, ending each answer with two blank lines instead of closing. Idle
+//! connections to those servers are parked here so bulk lookups skip the TCP
+//! handshake; every other server keeps connect-per-query.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpStream, sync::Mutex};
+use tracing::debug;
+
+// Servers known to support the RIPE `-k` persistent connection flag
+const KNOWN_KEEPALIVE_SERVERS: &[&str] = &["whois.ripe.net", "whois.apnic.net", "whois.afrinic.net"];
+
+// Servers drop idle persistent connections after a while; don't hand out stale ones
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct IdleConnection {
+    stream: TcpStream,
+    since: Instant,
+}
+
+pub struct ConnectionPool {
+    max_idle_per_host: usize,
+    servers: HashSet<String>,
+    idle: Mutex<HashMap<String, Vec<IdleConnection>>>,
+}
+
+impl ConnectionPool {
+    /// `extra_servers` are added to the known keep-alive servers; a pool size of 0 disables pooling
+    pub fn new(max_idle_per_host: usize, extra_servers: &[String]) -> Self {
+        let servers = KNOWN_KEEPALIVE_SERVERS
+            .iter()
+            .map(|server| server.to_string())
+            .chain(extra_servers.iter().map(|server| server.to_lowercase()))
+            .collect();
+
+        Self {
+            max_idle_per_host,
+            servers,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether queries to this server should go over pooled persistent connections
+    pub fn supports(&self, server: &str) -> bool {
+        self.max_idle_per_host > 0 && self.servers.contains(&server.to_lowercase())
+    }
+
+    /// Take an idle connection for the server, discarding any that sat too long
+    pub async fn take(&self, server: &str) -> Option<TcpStream> {
+        let mut idle = self.idle.lock().await;
+        let connections = idle.get_mut(server)?;
+        while let Some(connection) = connections.pop() {
+            if connection.since.elapsed() < IDLE_TIMEOUT {
+                return Some(connection.stream);
+            }
+        }
+        None
+    }
+
+    /// Park a connection for reuse, closing it if the host already has enough idle ones
+    pub async fn put(&self, server: &str, stream: TcpStream) {
+        let mut idle = self.idle.lock().await;
+        let connections = idle.entry(server.to_string()).or_default();
+        connections.retain(|connection| connection.since.elapsed() < IDLE_TIMEOUT);
+        if connections.len() < self.max_idle_per_host {
+            connections.push(IdleConnection { stream, since: Instant::now() });
+        } else {
+            debug!("Connection pool for {} is full, closing connection", server);
+        }
+    }
+
+    /// Whether a persistent-mode answer is complete (terminated by two blank lines)
+    pub fn is_response_complete(data: &[u8]) -> bool {
+        data.ends_with(b"\n\n\n") || data.ends_with(b"\r\n\r\n\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_pool_reuses_connections_per_host() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let pool = ConnectionPool::new(1, &["relay.example".to_string()]);
+        assert!(pool.supports("whois.ripe.net"));
+        assert!(pool.supports("RELAY.example"));
+        assert!(!pool.supports("whois.verisign-grs.com"));
+        assert!(!ConnectionPool::new(0, &[]).supports("whois.ripe.net"));
+
+        let first = TcpStream::connect(addr).await.unwrap();
+        let second = TcpStream::connect(addr).await.unwrap();
+        pool.put("whois.ripe.net", first).await;
+        pool.put("whois.ripe.net", second).await; // over the per-host limit, dropped
+
+        assert!(pool.take("whois.ripe.net").await.is_some());
+        assert!(pool.take("whois.ripe.net").await.is_none());
+        assert!(pool.take("whois.apnic.net").await.is_none());
+
+        assert!(ConnectionPool::is_response_complete(b"inetnum: 193.0.0.0\n\n\n"));
+        assert!(!ConnectionPool::is_response_complete(b"inetnum: 193.0.0.0\n\n"));
+    }
+}
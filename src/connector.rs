@@ -0,0 +1,134 @@
+//! Direct TCP connections to whois servers
+//!
+//! Resolves every A/AAAA record for a host, orders the addresses by the
+//! configured address-family preference and races them happy-eyeballs
+//! style (RFC 8305) instead of trusting whichever address resolves first.
+
+use crate::{config::AddressFamilyPreference, dns::DnsResolver, errors::WhoisError};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+// RFC 8305 recommended delay before starting the next connection attempt
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+pub struct Connector {
+    preference: AddressFamilyPreference,
+    resolver: DnsResolver,
+}
+
+impl Connector {
+    pub fn new(preference: AddressFamilyPreference, resolver: DnsResolver) -> Self {
+        Self { preference, resolver }
+    }
+
+    /// Resolve `host` and connect to the first address that answers
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream, WhoisError> {
+        let resolved = self.resolver.resolve(host, port).await?;
+        self.connect_to(host, resolved).await
+    }
+
+    /// Connect to the first of `host`'s already resolved addresses that answers
+    pub async fn connect_to(&self, host: &str, resolved: Vec<SocketAddr>) -> Result<TcpStream, WhoisError> {
+        let addresses = Self::order_addresses(self.preference, resolved);
+
+        if addresses.is_empty() {
+            return Err(WhoisError::IoError(std::io::Error::other(format!(
+                "No usable addresses for {} (address family: {:?})",
+                host, self.preference
+            ))));
+        }
+
+        debug!("Connecting to {} via {} candidate addresses", host, addresses.len());
+        Ok(Self::race_connections(&addresses).await?)
+    }
+
+    /// Resolve `host` without connecting
+    pub async fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, WhoisError> {
+        self.resolver.resolve(host, port).await
+    }
+
+    /// Filter and interleave addresses according to the family preference
+    fn order_addresses(preference: AddressFamilyPreference, addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        let (v6, v4): (Vec<_>, Vec<_>) = addresses.iter().copied().partition(SocketAddr::is_ipv6);
+
+        let (first, second) = match preference {
+            AddressFamilyPreference::Ipv4Only => return v4,
+            AddressFamilyPreference::Ipv6Only => return v6,
+            AddressFamilyPreference::PreferIpv4 => (v4, v6),
+            AddressFamilyPreference::PreferIpv6 => (v6, v4),
+            // Start with whichever family the resolver returned first
+            AddressFamilyPreference::Auto => match addresses.first() {
+                Some(addr) if addr.is_ipv4() => (v4, v6),
+                _ => (v6, v4),
+            },
+        };
+
+        // Alternate families so a broken family only costs one attempt delay
+        let mut ordered = Vec::with_capacity(first.len() + second.len());
+        let (mut first, mut second) = (first.into_iter(), second.into_iter());
+        loop {
+            match (first.next(), second.next()) {
+                (None, None) => break,
+                (a, b) => ordered.extend(a.into_iter().chain(b)),
+            }
+        }
+        ordered
+    }
+
+    /// Start attempts in order, staggered by the attempt delay; first success wins
+    async fn race_connections(addresses: &[SocketAddr]) -> std::io::Result<TcpStream> {
+        let mut remaining = addresses.iter().copied();
+        let mut attempts = FuturesUnordered::new();
+        let mut last_error = None;
+
+        if let Some(addr) = remaining.next() {
+            attempts.push(TcpStream::connect(addr));
+        }
+
+        while !attempts.is_empty() {
+            tokio::select! {
+                result = attempts.next() => match result {
+                    Some(Ok(stream)) => return Ok(stream),
+                    Some(Err(e)) => {
+                        debug!("Connection attempt failed: {}", e);
+                        last_error = Some(e);
+                        // Don't wait out the delay once an attempt has failed
+                        if let Some(addr) = remaining.next() {
+                            attempts.push(TcpStream::connect(addr));
+                        }
+                    }
+                    None => {}
+                },
+                _ = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY) => {
+                    if let Some(addr) = remaining.next() {
+                        attempts.push(TcpStream::connect(addr));
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| std::io::Error::other("No connection attempts made")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_addresses_interleaves_preferred_family() {
+        let addresses: Vec<SocketAddr> = vec![
+            "192.0.2.1:43".parse().unwrap(),
+            "192.0.2.2:43".parse().unwrap(),
+            "[2001:db8::1]:43".parse().unwrap(),
+        ];
+
+        let ordered = Connector::order_addresses(AddressFamilyPreference::PreferIpv6, addresses.clone());
+        assert_eq!(ordered, vec![addresses[2], addresses[0], addresses[1]]);
+
+        let ordered = Connector::order_addresses(AddressFamilyPreference::Ipv4Only, addresses.clone());
+        assert_eq!(ordered, vec![addresses[0], addresses[1]]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Field-level comparison of lookup results
+//!
+//! `WhoisResponse::diff` compares the parsed data of two responses and lists
+//! every field whose value differs. Nested objects are flattened into dotted
+//! paths (`registrant.email`), lists compare as a whole, and a field missing on
+//! one side shows up as `null`. The day counters (`created_ago`, `updated_ago`,
+//! `expires_in`), the `*_date_raw` texts and `field_sources` are skipped: they
+//! change with the lookup time or protocol rather than with the registration.
+
+use crate::{ParsedWhoisData, WhoisResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const IGNORED_FIELDS: &[&str] = &[
+    "created_ago",
+    "updated_ago",
+    "expires_in",
+    "creation_date_raw",
+    "expiration_date_raw",
+    "updated_date_raw",
+    "field_sources",
+];
+
+/// One changed field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    /// Field name, dotted for nested values ("registrant.email")
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Changed fields between two lookups of a domain, in field-name order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WhoisDiff {
+    pub domain: String,
+    pub changes: Vec<FieldDiff>,
+}
+
+impl WhoisDiff {
+    /// Compare two parsed snapshots
+    pub fn between(domain: impl Into<String>, old: Option<&ParsedWhoisData>, new: Option<&ParsedWhoisData>) -> Self {
+        let old = flatten_parsed(old);
+        let new = flatten_parsed(new);
+
+        let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+        fields.sort();
+        fields.dedup();
+
+        let changes = fields
+            .into_iter()
+            .filter_map(|field| {
+                let old_value = old.get(field).cloned().unwrap_or(Value::Null);
+                let new_value = new.get(field).cloned().unwrap_or(Value::Null);
+                (old_value != new_value).then(|| FieldDiff { field: field.clone(), old: old_value, new: new_value })
+            })
+            .collect();
+
+        Self { domain: domain.into(), changes }
+    }
+
+    /// True when nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The change to `field`, if it changed
+    pub fn get(&self, field: &str) -> Option<&FieldDiff> {
+        self.changes.iter().find(|change| change.field == field)
+    }
+}
+
+impl WhoisResponse {
+    /// Fields of the parsed data that differ from `other` (`self` is the old side)
+    pub fn diff(&self, other: &WhoisResponse) -> WhoisDiff {
+        WhoisDiff::between(self.domain.clone(), self.parsed_data.as_ref(), other.parsed_data.as_ref())
+    }
+}
+
+fn flatten_parsed(parsed: Option<&ParsedWhoisData>) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    if let Some(Value::Object(members)) = parsed.and_then(|parsed| serde_json::to_value(parsed).ok()) {
+        for (name, value) in members {
+            if !IGNORED_FIELDS.contains(&name.as_str()) {
+                flatten(name, value, &mut fields);
+            }
+        }
+    }
+    fields
+}
+
+// Empty lists and maps count as absent so `[]` vs a missing field isn't a change
+fn flatten(path: String, value: Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Null => {}
+        Value::Object(members) => {
+            for (name, value) in members {
+                flatten(format!("{}.{}", path, name), value, out);
+            }
+        }
+        Value::Array(ref items) if items.is_empty() => {}
+        value => {
+            out.insert(path, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Contact;
+
+    #[test]
+    fn test_diff_parsed_fields() {
+        let old = ParsedWhoisData {
+            registrar: Some("Old Registrar".to_string()),
+            name_servers: vec!["ns1.example.net".to_string()],
+            expires_in: Some(30),
+            ..ParsedWhoisData::default()
+        };
+        let new = ParsedWhoisData {
+            registrar: Some("New Registrar".to_string()),
+            name_servers: vec!["ns1.example.net".to_string()],
+            registrant: Some(Contact { email: Some("owner@example.com".to_string()), ..Contact::default() }),
+            expires_in: Some(29),
+            ..ParsedWhoisData::default()
+        };
+
+        let diff = WhoisDiff::between("example.com", Some(&old), Some(&new));
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(diff.get("registrar").unwrap().new, Value::from("New Registrar"));
+        let email = diff.get("registrant.email").unwrap();
+        assert_eq!((email.old.clone(), email.new.clone()), (Value::Null, Value::from("owner@example.com")));
+
+        assert!(WhoisDiff::between("example.com", Some(&old), Some(&old)).is_empty());
+    }
+}
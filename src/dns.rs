@@ -0,0 +1,129 @@
+//! Hostname resolution for whois connections
+//!
+//! Uses the OS resolver by default. With the `dns-resolver` feature, whois
+//! hosts are resolved by a built-in hickory resolver with its own answer
+//! cache, querying `dns_servers` (or the system resolv.conf when none are set)
+//! so discovery isn't held up by slow container DNS.
+//!
+//! Either way, resolved addresses are cached per host for
+//! `dns_cache_ttl_seconds` so bulk runs against one registry resolve it once.
+
+use crate::{config::Config, errors::WhoisError};
+use moka::future::Cache;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+#[cfg(not(feature = "dns-resolver"))]
+use tracing::warn;
+
+#[cfg(feature = "dns-resolver")]
+use hickory_resolver::{
+    config::{NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts},
+    TokioAsyncResolver,
+};
+
+// Whois hosts cached at once; a bulk run only touches a handful of registries
+const DNS_CACHE_SIZE: u64 = 1024;
+
+pub struct DnsResolver {
+    cache: Option<Cache<String, Arc<Vec<IpAddr>>>>, // Host -> addresses (None when caching is off)
+    #[cfg(feature = "dns-resolver")]
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsResolver {
+    pub fn new(config: &Config) -> Result<Self, WhoisError> {
+        #[cfg(not(feature = "dns-resolver"))]
+        if !config.dns_servers.is_empty() {
+            warn!("dns_servers is set but the dns-resolver feature is disabled; using the system resolver");
+        }
+
+        let cache = (config.dns_cache_ttl_seconds > 0).then(|| {
+            Cache::builder()
+                .max_capacity(DNS_CACHE_SIZE)
+                .time_to_live(Duration::from_secs(config.dns_cache_ttl_seconds))
+                .build()
+        });
+
+        Ok(Self {
+            cache,
+            #[cfg(feature = "dns-resolver")]
+            resolver: Self::build_resolver(config)?,
+        })
+    }
+
+    #[cfg(feature = "dns-resolver")]
+    fn build_resolver(config: &Config) -> Result<TokioAsyncResolver, WhoisError> {
+        let mut options = ResolverOpts::default();
+        options.cache_size = DNS_CACHE_SIZE as usize;
+        options.timeout = Duration::from_secs(config.discovery_timeout_seconds.min(5));
+
+        if config.dns_servers.is_empty() {
+            let (system_config, _) = hickory_resolver::system_conf::read_system_conf()
+                .map_err(|e| WhoisError::Internal(format!("Failed to read system DNS configuration: {}", e)))?;
+            return Ok(TokioAsyncResolver::tokio(system_config, options));
+        }
+
+        let name_servers: Vec<NameServerConfig> = config
+            .dns_servers
+            .iter()
+            .flat_map(|addr| [NameServerConfig::new(*addr, Protocol::Udp), NameServerConfig::new(*addr, Protocol::Tcp)])
+            .collect();
+        let resolver_config = ResolverConfig::from_parts(None, vec![], NameServerConfigGroup::from(name_servers));
+        Ok(TokioAsyncResolver::tokio(resolver_config, options))
+    }
+
+    /// Resolve `host` to every address it has, in resolver order
+    pub async fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, WhoisError> {
+        let with_port = |addresses: &[IpAddr]| addresses.iter().map(|ip| SocketAddr::new(*ip, port)).collect();
+
+        if let Some(ref cache) = self.cache {
+            if let Some(addresses) = cache.get(host).await {
+                return Ok(with_port(&addresses));
+            }
+        }
+
+        let addresses = self.lookup(host, port).await?;
+        if let Some(ref cache) = self.cache {
+            if !addresses.is_empty() {
+                cache.insert(host.to_string(), Arc::new(addresses.clone())).await;
+            }
+        }
+        Ok(with_port(&addresses))
+    }
+
+    #[cfg(feature = "dns-resolver")]
+    async fn lookup(&self, host: &str, _port: u16) -> Result<Vec<IpAddr>, WhoisError> {
+        let lookup = self.resolver.lookup_ip(host).await.map_err(|e| {
+            WhoisError::IoError(std::io::Error::other(format!("DNS lookup for {} failed: {}", host, e)))
+        })?;
+        Ok(lookup.iter().collect())
+    }
+
+    #[cfg(not(feature = "dns-resolver"))]
+    async fn lookup(&self, host: &str, port: u16) -> Result<Vec<IpAddr>, WhoisError> {
+        Ok(tokio::net::lookup_host((host, port)).await?.map(|addr| addr.ip()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_caches_addresses_per_host() {
+        let mut config = Config::load().unwrap();
+        config.dns_cache_ttl_seconds = 60;
+        let resolver = DnsResolver::new(&config).unwrap();
+
+        let addresses = resolver.resolve("127.0.0.1", 43).await.unwrap();
+        assert_eq!(addresses, vec!["127.0.0.1:43".parse().unwrap()]);
+
+        // Cached entries are re-used with whatever port the caller asks for
+        let cache = resolver.cache.as_ref().unwrap();
+        assert!(cache.get("127.0.0.1").await.is_some());
+        assert_eq!(resolver.resolve("127.0.0.1", 4343).await.unwrap(), vec!["127.0.0.1:4343".parse().unwrap()]);
+    }
+}
@@ -0,0 +1,77 @@
+//! Public-suffix aware domain helpers
+//!
+//! These are the rules the crate itself uses to pick a whois server: the
+//! effective TLD comes from the ICANN section of the Public Suffix List
+//! (vendored as `data/public_suffix_list.dat`), so `foo.co.uk` belongs to
+//! `co.uk` while private suffixes such as `blogspot.com` are ignored - whois
+//! only knows about `blogspot.com` itself. Unknown TLDs fall back to the PSL's
+//! default `*` rule (the last label).
+//!
+//! Inputs are trimmed, lowercased, stripped of a trailing dot and converted to
+//! their ASCII (punycode) form; results are returned in that form.
+
+use once_cell::sync::Lazy;
+use publicsuffix::{List, Psl};
+
+const PUBLIC_SUFFIX_LIST: &str = include_str!("../data/public_suffix_list.dat");
+const ICANN_END_MARKER: &str = "// ===END ICANN DOMAINS===";
+
+static ICANN_SUFFIXES: Lazy<List> = Lazy::new(|| {
+    let icann = PUBLIC_SUFFIX_LIST
+        .split_once(ICANN_END_MARKER)
+        .map_or(PUBLIC_SUFFIX_LIST, |(icann, _)| icann);
+    icann.parse().unwrap_or_else(|e| {
+        tracing::warn!("Failed to parse the vendored public suffix list, using last-label TLDs: {}", e);
+        List::new()
+    })
+});
+
+/// The public suffix of `domain` ("www.foo.co.uk" -> "co.uk")
+pub fn effective_tld(domain: &str) -> Option<String> {
+    let domain = normalize(domain)?;
+    let suffix = ICANN_SUFFIXES.suffix(domain.as_bytes())?;
+    std::str::from_utf8(suffix.as_bytes()).ok().map(str::to_string)
+}
+
+/// The domain a registrant registers under its public suffix ("www.foo.co.uk" -> "foo.co.uk")
+///
+/// `None` when `domain` is itself a public suffix ("co.uk") or not a valid domain.
+pub fn registrable_domain(domain: &str) -> Option<String> {
+    let domain = normalize(domain)?;
+    let registrable = ICANN_SUFFIXES.domain(domain.as_bytes())?;
+    std::str::from_utf8(registrable.as_bytes()).ok().map(str::to_string)
+}
+
+/// The Unicode form of an ASCII (punycode) name ("xn--bcher-kva.de" -> "bücher.de")
+///
+/// `None` when the name has no internationalized labels or isn't valid IDNA.
+pub fn unicode_form(name: &str) -> Option<String> {
+    let (unicode, result) = idna::domain_to_unicode(name);
+    (result.is_ok() && unicode != name.to_lowercase()).then_some(unicode)
+}
+
+fn normalize(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    if domain.is_empty() {
+        return None;
+    }
+    idna::domain_to_ascii(&domain).ok().filter(|domain| !domain.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registrable_domain_and_effective_tld() {
+        assert_eq!(registrable_domain("www.foo.co.uk").as_deref(), Some("foo.co.uk"));
+        assert_eq!(effective_tld("www.foo.co.uk").as_deref(), Some("co.uk"));
+        assert_eq!(registrable_domain("Mail.Google.COM.").as_deref(), Some("google.com"));
+        assert_eq!(registrable_domain("foo.blogspot.com").as_deref(), Some("blogspot.com"));
+        assert_eq!(registrable_domain("bücher.example.de").as_deref(), Some("example.de"));
+        assert_eq!(registrable_domain("münchen.de").as_deref(), Some("xn--mnchen-3ya.de"));
+        assert_eq!(effective_tld("example.unknowntld").as_deref(), Some("unknowntld"));
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain(""), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! EPP domain status codes
+//!
+//! Typed form of the status values registries publish, covering RFC 5731 EPP
+//! statuses, RFC 3915 grace periods and their RFC 8056 RDAP spellings
+//! ("client transfer prohibited", "active", ...).
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum EppStatus {
+    Ok,
+    Inactive,
+    ClientDeleteProhibited,
+    ClientHold,
+    ClientRenewProhibited,
+    ClientTransferProhibited,
+    ClientUpdateProhibited,
+    ServerDeleteProhibited,
+    ServerHold,
+    ServerRenewProhibited,
+    ServerTransferProhibited,
+    ServerUpdateProhibited,
+    PendingCreate,
+    PendingDelete,
+    PendingRenew,
+    PendingRestore,
+    PendingTransfer,
+    PendingUpdate,
+    AddPeriod,
+    AutoRenewPeriod,
+    RenewPeriod,
+    TransferPeriod,
+    RedemptionPeriod,
+    /// Registry-specific status that isn't part of the EPP/RGP set
+    Other(String),
+}
+
+impl EppStatus {
+    /// Parse a raw status value, ignoring a trailing ICANN URL or comment
+    ///
+    /// Accepts EPP ("clientTransferProhibited https://icann.org/epp#...") and
+    /// RDAP ("client transfer prohibited") spellings.
+    pub fn parse(raw: &str) -> Self {
+        let value = raw
+            .split(" http")
+            .next()
+            .unwrap_or(raw)
+            .split(" (")
+            .next()
+            .unwrap_or(raw)
+            .trim();
+
+        let key: String = value
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        match key.as_str() {
+            "ok" | "active" => Self::Ok,
+            "inactive" => Self::Inactive,
+            "clientdeleteprohibited" => Self::ClientDeleteProhibited,
+            "clienthold" => Self::ClientHold,
+            "clientrenewprohibited" => Self::ClientRenewProhibited,
+            "clienttransferprohibited" => Self::ClientTransferProhibited,
+            "clientupdateprohibited" => Self::ClientUpdateProhibited,
+            "serverdeleteprohibited" => Self::ServerDeleteProhibited,
+            "serverhold" => Self::ServerHold,
+            "serverrenewprohibited" => Self::ServerRenewProhibited,
+            "servertransferprohibited" => Self::ServerTransferProhibited,
+            "serverupdateprohibited" => Self::ServerUpdateProhibited,
+            "pendingcreate" => Self::PendingCreate,
+            "pendingdelete" => Self::PendingDelete,
+            "pendingrenew" => Self::PendingRenew,
+            "pendingrestore" => Self::PendingRestore,
+            "pendingtransfer" => Self::PendingTransfer,
+            "pendingupdate" => Self::PendingUpdate,
+            "addperiod" => Self::AddPeriod,
+            "autorenewperiod" => Self::AutoRenewPeriod,
+            "renewperiod" => Self::RenewPeriod,
+            "transferperiod" => Self::TransferPeriod,
+            "redemptionperiod" => Self::RedemptionPeriod,
+            _ => Self::Other(value.to_string()),
+        }
+    }
+
+    /// Parse a list of raw status values, dropping duplicates
+    pub fn parse_all(raw: &[String]) -> Vec<Self> {
+        let mut codes = Vec::new();
+        for status in raw.iter().map(|s| Self::parse(s)) {
+            if !codes.contains(&status) {
+                codes.push(status);
+            }
+        }
+        codes
+    }
+
+    /// EPP spelling of the status
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Ok => "ok",
+            Self::Inactive => "inactive",
+            Self::ClientDeleteProhibited => "clientDeleteProhibited",
+            Self::ClientHold => "clientHold",
+            Self::ClientRenewProhibited => "clientRenewProhibited",
+            Self::ClientTransferProhibited => "clientTransferProhibited",
+            Self::ClientUpdateProhibited => "clientUpdateProhibited",
+            Self::ServerDeleteProhibited => "serverDeleteProhibited",
+            Self::ServerHold => "serverHold",
+            Self::ServerRenewProhibited => "serverRenewProhibited",
+            Self::ServerTransferProhibited => "serverTransferProhibited",
+            Self::ServerUpdateProhibited => "serverUpdateProhibited",
+            Self::PendingCreate => "pendingCreate",
+            Self::PendingDelete => "pendingDelete",
+            Self::PendingRenew => "pendingRenew",
+            Self::PendingRestore => "pendingRestore",
+            Self::PendingTransfer => "pendingTransfer",
+            Self::PendingUpdate => "pendingUpdate",
+            Self::AddPeriod => "addPeriod",
+            Self::AutoRenewPeriod => "autoRenewPeriod",
+            Self::RenewPeriod => "renewPeriod",
+            Self::TransferPeriod => "transferPeriod",
+            Self::RedemptionPeriod => "redemptionPeriod",
+            Self::Other(value) => value,
+        }
+    }
+
+    /// RFC 8056 RDAP spelling of the status ("client transfer prohibited", "active")
+    pub fn rdap_str(&self) -> String {
+        match self {
+            Self::Ok => "active".to_string(),
+            Self::Other(value) => value.clone(),
+            status => {
+                let mut words = String::new();
+                for c in status.as_str().chars() {
+                    if c.is_ascii_uppercase() {
+                        words.push(' ');
+                    }
+                    words.push(c.to_ascii_lowercase());
+                }
+                words
+            }
+        }
+    }
+
+    /// Statuses that take the domain out of the DNS (clientHold, serverHold)
+    pub fn is_hold(&self) -> bool {
+        matches!(self, Self::ClientHold | Self::ServerHold)
+    }
+}
+
+impl fmt::Display for EppStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<String> for EppStatus {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
+}
+
+impl From<EppStatus> for String {
+    fn from(status: EppStatus) -> Self {
+        status.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epp_and_rdap_spellings() {
+        assert_eq!(
+            EppStatus::parse("clientTransferProhibited https://icann.org/epp#clientTransferProhibited"),
+            EppStatus::ClientTransferProhibited
+        );
+        assert_eq!(EppStatus::parse("client transfer prohibited"), EppStatus::ClientTransferProhibited);
+        assert_eq!(EppStatus::parse("active"), EppStatus::Ok);
+        assert_eq!(EppStatus::parse("connect"), EppStatus::Other("connect".to_string()));
+
+        assert_eq!(EppStatus::ClientTransferProhibited.rdap_str(), "client transfer prohibited");
+        assert_eq!(EppStatus::Ok.rdap_str(), "active");
+
+        let json = serde_json::to_string(&EppStatus::PendingDelete).unwrap();
+        assert_eq!(json, "\"pendingDelete\"");
+    }
+}
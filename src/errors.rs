@@ -1,11 +1,10 @@
 #[cfg(feature = "server")]
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-#[cfg(feature = "server")]
-use serde_json::json;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,6 +12,12 @@ pub enum WhoisError {
     #[error("Invalid domain: {0}")]
     InvalidDomain(String),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Domain not found: {0}")]
+    DomainNotFound(String),
+
     #[error("Unsupported TLD: {0}")]
     UnsupportedTld(String),
 
@@ -37,13 +42,96 @@ pub enum WhoisError {
     #[error("Configuration error: {0}")]
     ConfigError(#[from] config::ConfigError),
 
+    #[error("Rate limited by {server}")]
+    RateLimited {
+        server: String,
+        /// Seconds the server asked us to wait, when it said
+        retry_after: Option<u64>,
+    },
+
     #[error("Cache error: {0}")]
     CacheError(String),
 
     #[error("Internal server error: {0}")]
     Internal(String),
+
+    /// An upstream failure shared with a caller that didn't make the request
+    /// (coalesced lookups); keeps the original error's `code`
+    #[error("{message}")]
+    Upstream { code: &'static str, message: String },
+}
+
+/// JSON body of the server's error responses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ErrorBody {
+    /// Stable machine-readable code, e.g. `INVALID_DOMAIN` (see `WhoisError::code`)
+    pub code: String,
+    pub error: String,
+    /// HTTP status code, repeated from the response
+    pub status: u16,
+}
+
+impl ErrorBody {
+    pub fn new(code: impl Into<String>, error: impl Into<String>, status: u16) -> Self {
+        Self { code: code.into(), error: error.into(), status }
+    }
+}
+
+impl WhoisError {
+    /// Stable identifier of the error kind, for branching without matching messages
+    ///
+    /// Codes are SCREAMING_SNAKE_CASE and never change once published; the
+    /// server sends them as `code` in its JSON error bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WhoisError::InvalidDomain(_) => "INVALID_DOMAIN",
+            WhoisError::InvalidRequest(_) => "INVALID_REQUEST",
+            WhoisError::DomainNotFound(_) => "DOMAIN_NOT_FOUND",
+            WhoisError::UnsupportedTld(_) => "TLD_UNSUPPORTED",
+            WhoisError::Timeout => "UPSTREAM_TIMEOUT",
+            WhoisError::IoError(_) => "UPSTREAM_IO_ERROR",
+            WhoisError::HttpError(_) => "UPSTREAM_HTTP_ERROR",
+            WhoisError::ResponseTooLarge => "UPSTREAM_RESPONSE_TOO_LARGE",
+            WhoisError::InvalidUtf8 => "UPSTREAM_INVALID_UTF8",
+            WhoisError::RateLimited { .. } => "RATE_LIMITED",
+            WhoisError::ConfigError(_) => "CONFIGURATION_ERROR",
+            WhoisError::CacheError(_) => "CACHE_ERROR",
+            WhoisError::RegexError(_) | WhoisError::Internal(_) => "INTERNAL_ERROR",
+            WhoisError::Upstream { code, .. } => code,
+        }
+    }
+
+    /// A copy for sharing one outcome between callers, with the same `code`
+    ///
+    /// HTTP errors can't be cloned and become `Upstream`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            WhoisError::InvalidDomain(message) => WhoisError::InvalidDomain(message.clone()),
+            WhoisError::InvalidRequest(message) => WhoisError::InvalidRequest(message.clone()),
+            WhoisError::DomainNotFound(domain) => WhoisError::DomainNotFound(domain.clone()),
+            WhoisError::UnsupportedTld(tld) => WhoisError::UnsupportedTld(tld.clone()),
+            WhoisError::Timeout => WhoisError::Timeout,
+            WhoisError::ResponseTooLarge => WhoisError::ResponseTooLarge,
+            WhoisError::InvalidUtf8 => WhoisError::InvalidUtf8,
+            WhoisError::RateLimited { server, retry_after } => WhoisError::RateLimited {
+                server: server.clone(),
+                retry_after: *retry_after,
+            },
+            WhoisError::CacheError(message) => WhoisError::CacheError(message.clone()),
+            WhoisError::Internal(message) => WhoisError::Internal(message.clone()),
+            WhoisError::Upstream { code, message } => WhoisError::Upstream { code, message: message.clone() },
+            WhoisError::IoError(e) => WhoisError::IoError(std::io::Error::new(e.kind(), e.to_string())),
+            WhoisError::ConfigError(e) => WhoisError::ConfigError(config::ConfigError::Message(e.to_string())),
+            WhoisError::RegexError(e) => WhoisError::Internal(e.to_string()),
+            WhoisError::HttpError(_) => WhoisError::Upstream { code: self.code(), message: self.to_string() },
+        }
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<tokio::time::error::Elapsed> for WhoisError {
     fn from(_: tokio::time::error::Elapsed) -> Self {
         WhoisError::Timeout
@@ -53,18 +141,66 @@ impl From<tokio::time::error::Elapsed> for WhoisError {
 #[cfg(feature = "server")]
 impl IntoResponse for WhoisError {
     fn into_response(self) -> Response {
+        let retry_after = match self {
+            WhoisError::RateLimited { retry_after, .. } => retry_after,
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             WhoisError::InvalidDomain(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            WhoisError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             WhoisError::UnsupportedTld(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            WhoisError::DomainNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             WhoisError::Timeout => (StatusCode::REQUEST_TIMEOUT, self.to_string()),
+            // The registry limited us, not the caller; 429 is for the per-client limit
+            WhoisError::RateLimited { .. } => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string()),
         };
 
-        let body = Json(json!({
-            "error": error_message,
-            "status": status.as_u16()
-        }));
+        let body = Json(ErrorBody::new(self.code(), error_message, status.as_u16()));
+
+        match retry_after {
+            Some(seconds) => (status, [(header::RETRY_AFTER, seconds.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_stable_and_survive_duplication() {
+        let errors = [
+            (WhoisError::InvalidDomain("x".to_string()), "INVALID_DOMAIN"),
+            (WhoisError::InvalidRequest("no domains".to_string()), "INVALID_REQUEST"),
+            (WhoisError::UnsupportedTld("zz".to_string()), "TLD_UNSUPPORTED"),
+            (WhoisError::Timeout, "UPSTREAM_TIMEOUT"),
+            (WhoisError::RateLimited { server: "rdap.example".to_string(), retry_after: Some(5) }, "RATE_LIMITED"),
+            (WhoisError::Internal("boom".to_string()), "INTERNAL_ERROR"),
+            (WhoisError::IoError(std::io::ErrorKind::ConnectionReset.into()), "UPSTREAM_IO_ERROR"),
+            (WhoisError::HttpError(reqwest::Client::new().get("not a url").build().unwrap_err()), "UPSTREAM_HTTP_ERROR"),
+            (WhoisError::ConfigError(config::ConfigError::Message("bad".to_string())), "CONFIGURATION_ERROR"),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.code(), code);
+            let duplicate = error.duplicate();
+            assert_eq!(duplicate.code(), code);
+            assert_eq!(duplicate.duplicate().code(), code);
+            assert_eq!(duplicate.to_string(), error.to_string());
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_upstream_rate_limits_are_service_unavailable() {
+        let response = WhoisError::RateLimited { server: "rdap.example".to_string(), retry_after: Some(30) }.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
 
-        (status, body).into_response()
+        let response = WhoisError::RateLimited { server: "whois.example".to_string(), retry_after: None }.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,311 @@
+//! CSV and JSON Lines export of lookup results
+//!
+//! Both formats take a column selection (see `COLUMNS`). CSV rows hold the
+//! flattened fields, with lists joined by `;` and RFC 4180 quoting; cells that
+//! a spreadsheet would evaluate as formulas (leading `=`, `+`, `-`, `@`) are
+//! prefixed with `'`. JSON Lines write the selected fields as one object per
+//! line, or the whole `WhoisResponse` when no columns are given.
+//!
+//! ```rust,no_run
+//! # fn example(responses: Vec<whois_service::WhoisResponse>) -> std::io::Result<()> {
+//! use whois_service::export;
+//!
+//! let file = std::fs::File::create("domains.csv")?;
+//! export::write_csv(file, &responses, &["domain", "registrar", "expiration_date"])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{errors::WhoisError, DnssecStatus, WhoisResponse};
+use serde_json::{Map, Value};
+use std::{borrow::Borrow, io::Write};
+
+/// Columns used when no selection is given
+pub const DEFAULT_COLUMNS: &[&str] = &[
+    "domain",
+    "domain_unicode",
+    "whois_server",
+    "cached",
+    "query_time_ms",
+    "registrar",
+    "registrar_iana_id",
+    "creation_date",
+    "expiration_date",
+    "updated_date",
+    "name_servers",
+    "status",
+    "dnssec",
+    "registrant_name",
+    "registrant_email",
+    "privacy_protected",
+    "created_ago",
+    "updated_ago",
+    "expires_in",
+    "confidence",
+];
+
+/// Every column that can be selected
+pub const COLUMNS: &[&str] = &[
+    "domain",
+    "domain_unicode",
+    "whois_server",
+    "cached",
+    "degraded",
+    "data_age_seconds",
+    "query_time_ms",
+    "registrar",
+    "registrar_iana_id",
+    "registrar_url",
+    "registrar_abuse_email",
+    "registrar_abuse_phone",
+    "reseller",
+    "registry_domain_id",
+    "creation_date",
+    "expiration_date",
+    "updated_date",
+    "name_servers",
+    "status",
+    "dnssec",
+    "registrant_name",
+    "registrant_organization",
+    "registrant_email",
+    "registrant_country",
+    "admin_email",
+    "tech_email",
+    "privacy_protected",
+    "created_ago",
+    "updated_ago",
+    "expires_in",
+    "confidence",
+    "raw_data",
+];
+
+/// Parse a comma-separated column list ("domain,registrar"); empty selects `DEFAULT_COLUMNS`
+pub fn parse_columns(spec: &str) -> Result<Vec<String>, WhoisError> {
+    let columns: Vec<String> = spec
+        .split(',')
+        .map(|column| column.trim().to_ascii_lowercase())
+        .filter(|column| !column.is_empty())
+        .collect();
+    if columns.is_empty() {
+        return Ok(DEFAULT_COLUMNS.iter().map(|column| column.to_string()).collect());
+    }
+    validate_columns(&columns)?;
+    Ok(columns)
+}
+
+/// Reject columns not listed in `COLUMNS`
+pub fn validate_columns<S: AsRef<str>>(columns: &[S]) -> Result<(), WhoisError> {
+    match columns.iter().find(|column| !COLUMNS.contains(&column.as_ref())) {
+        Some(column) => Err(WhoisError::InvalidDomain(format!(
+            "Unknown export column '{}'; available: {}",
+            column.as_ref(),
+            COLUMNS.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The value of one column (`Null` when absent or the column is unknown)
+pub fn column_value(response: &WhoisResponse, column: &str) -> Value {
+    let parsed = response.parsed_data.as_ref();
+    let text = |value: Option<&str>| value.map_or(Value::Null, Value::from);
+    let contact = |contact: Option<&crate::Contact>, field: fn(&crate::Contact) -> Option<&String>| {
+        text(contact.and_then(field).map(String::as_str))
+    };
+
+    match column {
+        "domain" => Value::from(response.domain.as_str()),
+        "domain_unicode" => text(response.domain_unicode.as_deref()),
+        "whois_server" => Value::from(response.whois_server.as_str()),
+        "cached" => Value::from(response.cached),
+        "degraded" => Value::from(response.degraded),
+        "data_age_seconds" => response.data_age_seconds.map_or(Value::Null, Value::from),
+        "query_time_ms" => Value::from(response.query_time_ms),
+        "raw_data" => Value::from(response.raw_data.as_str()),
+        _ => {
+            let Some(parsed) = parsed else {
+                return Value::Null;
+            };
+            match column {
+                "registrar" => text(parsed.registrar.as_deref()),
+                "registrar_iana_id" => text(parsed.registrar_iana_id.as_deref()),
+                "registrar_url" => text(parsed.registrar_url.as_deref()),
+                "registrar_abuse_email" => text(parsed.registrar_abuse_email.as_deref()),
+                "registrar_abuse_phone" => text(parsed.registrar_abuse_phone.as_deref()),
+                "reseller" => text(parsed.reseller.as_deref()),
+                "registry_domain_id" => text(parsed.registry_domain_id.as_deref()),
+                "creation_date" => text(parsed.creation_date.as_deref()),
+                "expiration_date" => text(parsed.expiration_date.as_deref()),
+                "updated_date" => text(parsed.updated_date.as_deref()),
+                "name_servers" => Value::from(parsed.name_servers.clone()),
+                "status" => Value::from(parsed.status.clone()),
+                "dnssec" => text(parsed.dnssec.map(dnssec_label)),
+                "registrant_name" => text(parsed.registrant_name()),
+                "registrant_organization" => contact(parsed.registrant.as_ref(), |c| c.organization.as_ref()),
+                "registrant_email" => text(parsed.registrant_email()),
+                "registrant_country" => contact(parsed.registrant.as_ref(), |c| c.country.as_ref()),
+                "admin_email" => text(parsed.admin_email()),
+                "tech_email" => text(parsed.tech_email()),
+                "privacy_protected" => parsed.privacy_protected.map_or(Value::Null, Value::from),
+                "created_ago" => parsed.created_ago.map_or(Value::Null, Value::from),
+                "updated_ago" => parsed.updated_ago.map_or(Value::Null, Value::from),
+                "expires_in" => parsed.expires_in.map_or(Value::Null, Value::from),
+                // Through f64 keeps the two decimals instead of the f32 expansion
+                "confidence" => Value::from((f64::from(parsed.confidence) * 100.0).round() / 100.0),
+                _ => Value::Null,
+            }
+        }
+    }
+}
+
+/// The selected columns of a response as a JSON object
+pub fn select<S: AsRef<str>>(response: &WhoisResponse, columns: &[S]) -> Map<String, Value> {
+    columns
+        .iter()
+        .map(|column| (column.as_ref().to_string(), column_value(response, column.as_ref())))
+        .collect()
+}
+
+/// CSV header line for `columns`, CRLF-terminated
+pub fn csv_header<S: AsRef<str>>(columns: &[S]) -> String {
+    csv_record(columns.iter().map(|column| column.as_ref().to_string()))
+}
+
+/// CSV line with the selected columns of a response, CRLF-terminated
+pub fn csv_row<S: AsRef<str>>(response: &WhoisResponse, columns: &[S]) -> String {
+    csv_record(csv_fields(response, columns))
+}
+
+/// The selected columns rendered as CSV cell text, unquoted
+pub fn csv_fields<S: AsRef<str>>(response: &WhoisResponse, columns: &[S]) -> Vec<String> {
+    columns.iter().map(|column| csv_text(column_value(response, column.as_ref()))).collect()
+}
+
+/// One CSV line from already rendered fields, quoted per RFC 4180
+pub fn csv_record(fields: impl IntoIterator<Item = String>) -> String {
+    let mut line = fields.into_iter().map(|field| csv_field(&field)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Write a header and one row per response
+pub fn write_csv<W, I, S>(mut writer: W, responses: I, columns: &[S]) -> std::io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<WhoisResponse>,
+    S: AsRef<str>,
+{
+    validate_columns(columns).map_err(invalid_input)?;
+    writer.write_all(csv_header(columns).as_bytes())?;
+    for response in responses {
+        writer.write_all(csv_row(response.borrow(), columns).as_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Write one JSON object per line: the selected columns, or whole responses when `columns` is `None`
+pub fn write_jsonl<W, I, S>(mut writer: W, responses: I, columns: Option<&[S]>) -> std::io::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<WhoisResponse>,
+    S: AsRef<str>,
+{
+    if let Some(columns) = columns {
+        validate_columns(columns).map_err(invalid_input)?;
+    }
+    for response in responses {
+        let response = response.borrow();
+        match columns {
+            Some(columns) => serde_json::to_writer(&mut writer, &select(response, columns))?,
+            None => serde_json::to_writer(&mut writer, response)?,
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+fn invalid_input(error: WhoisError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string())
+}
+
+// Lists join with ';' so a field stays one CSV cell
+fn csv_text(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        Value::Array(items) => items.into_iter().map(csv_text).collect::<Vec<_>>().join(";"),
+        value => value.to_string(),
+    }
+}
+
+// RFC 4180 quoting: only fields containing separators, quotes or line breaks.
+// WHOIS text is attacker-controlled, so a cell a spreadsheet would run as a
+// formula gets a leading `'`; plain numbers like "-3" are left alone.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) && value.parse::<f64>().is_err() {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn dnssec_label(status: DnssecStatus) -> &'static str {
+    match status {
+        DnssecStatus::Signed => "signedDelegation",
+        DnssecStatus::Unsigned => "unsigned",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParsedWhoisData;
+
+    #[test]
+    fn test_csv_and_jsonl_with_column_selection() {
+        let response = WhoisResponse {
+            parsed_data: Some(ParsedWhoisData {
+                registrar: Some("Example, Inc.".to_string()),
+                name_servers: vec!["a.iana-servers.net".to_string(), "b.iana-servers.net".to_string()],
+                ..ParsedWhoisData::default()
+            }),
+            query_time_ms: 12,
+            ..crate::test_response("example.com")
+        };
+        let columns = parse_columns("domain, registrar,name_servers,expires_in").unwrap();
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, [&response], &columns).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "domain,registrar,name_servers,expires_in\r\nexample.com,\"Example, Inc.\",a.iana-servers.net;b.iana-servers.net,\r\n"
+        );
+
+        let mut jsonl = Vec::new();
+        write_jsonl(&mut jsonl, [&response], Some(&columns[..2])).unwrap();
+        assert_eq!(String::from_utf8(jsonl).unwrap(), "{\"domain\":\"example.com\",\"registrar\":\"Example, Inc.\"}\n");
+
+        assert!(parse_columns("domain,nope").is_err());
+        assert_eq!(parse_columns("").unwrap().len(), DEFAULT_COLUMNS.len());
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_csv_cells_cannot_start_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"http://evil.example\")"), "\"'=HYPERLINK(\"\"http://evil.example\"\")\"");
+        for formula in ["+cmd", "-2+3", "@SUM(A1)", "\tx"] {
+            assert_eq!(csv_field(formula), format!("'{}", formula));
+        }
+        assert_eq!(csv_field("-3"), "-3");
+        assert_eq!(csv_field("+1.5555551234"), "+1.5555551234");
+        assert_eq!(csv_field("Example, Inc."), "\"Example, Inc.\"");
+    }
+}
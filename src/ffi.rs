@@ -0,0 +1,237 @@
+//! C API (feature `ffi`)
+//!
+//! Exposes the lookup engine to C, C++, Go (cgo) and anything else that can
+//! call C functions. Build the shared library with
+//!
+//! ```text
+//! cargo rustc --release --lib --no-default-features --features ffi,whois-tcp --crate-type cdylib
+//! ```
+//!
+//! and include `include/whois_service.h`. Every lookup returns a
+//! NUL-terminated JSON string owned by the library: the `WhoisResponse` (or IP
+//! / ASN response) on success, `{"error": "...", "code": "...", "status": N}`
+//! otherwise, where `code` is the stable `WhoisError::code`. The status is
+//! also written to `status` when it isn't NULL. Release strings with
+//! `whois_string_free`.
+//!
+//! The functions share one `blocking::WhoisClient`, created on first use and
+//! configured from the environment like the server (`PROTOCOL_PREFERENCE`,
+//! `CACHE_TTL_SECONDS`, ...). They are safe to call from several threads.
+
+use crate::{blocking::WhoisClient, WhoisError};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{
+    ffi::{c_char, CStr, CString},
+    net::IpAddr,
+    panic::{catch_unwind, UnwindSafe},
+};
+
+/// Outcome of an FFI call; mirrors the `WHOIS_*` constants in the C header
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhoisStatus {
+    Ok = 0,
+    /// NULL or non-UTF-8 argument, or an unparsable IP address
+    InvalidArgument = 1,
+    InvalidDomain = 2,
+    NotFound = 3,
+    UnsupportedTld = 4,
+    Timeout = 5,
+    RateLimited = 6,
+    /// Connection, HTTP or I/O failure talking to upstream servers
+    Network = 7,
+    /// The client could not be created (configuration or runtime error)
+    Init = 8,
+    Internal = 9,
+}
+
+impl From<&WhoisError> for WhoisStatus {
+    fn from(error: &WhoisError) -> Self {
+        match error {
+            WhoisError::InvalidDomain(_) => WhoisStatus::InvalidDomain,
+            WhoisError::InvalidRequest(_) => WhoisStatus::InvalidArgument,
+            WhoisError::DomainNotFound(_) => WhoisStatus::NotFound,
+            WhoisError::UnsupportedTld(_) => WhoisStatus::UnsupportedTld,
+            WhoisError::Timeout => WhoisStatus::Timeout,
+            WhoisError::RateLimited { .. } => WhoisStatus::RateLimited,
+            WhoisError::IoError(_) | WhoisError::HttpError(_) | WhoisError::Upstream { .. } => WhoisStatus::Network,
+            WhoisError::ConfigError(_) => WhoisStatus::Init,
+            _ => WhoisStatus::Internal,
+        }
+    }
+}
+
+/// A failed call: its status, `WhoisError::code` and message
+struct Failure {
+    status: WhoisStatus,
+    code: &'static str,
+    message: String,
+}
+
+impl From<WhoisError> for Failure {
+    fn from(error: WhoisError) -> Self {
+        Failure { status: WhoisStatus::from(&error), code: error.code(), message: error.to_string() }
+    }
+}
+
+static CLIENT: Lazy<Result<WhoisClient, String>> = Lazy::new(|| WhoisClient::new().map_err(|e| e.to_string()));
+
+/// Look up a domain, using the cache
+///
+/// # Safety
+///
+/// `domain` must be NULL or a valid NUL-terminated string, and `status` NULL
+/// or valid for a write. Free the result with `whois_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn whois_lookup_json(domain: *const c_char, status: *mut WhoisStatus) -> *mut c_char {
+    let domain = read_str(domain);
+    respond(status, move || {
+        let domain = domain.ok_or_else(invalid_argument)?;
+        json(client()?.lookup(&domain))
+    })
+}
+
+/// Look up a domain, bypassing the cache
+///
+/// # Safety
+///
+/// Same contract as `whois_lookup_json`.
+#[no_mangle]
+pub unsafe extern "C" fn whois_lookup_fresh_json(domain: *const c_char, status: *mut WhoisStatus) -> *mut c_char {
+    let domain = read_str(domain);
+    respond(status, move || {
+        let domain = domain.ok_or_else(invalid_argument)?;
+        json(client()?.lookup_fresh(&domain))
+    })
+}
+
+/// Check whether a domain is registered; the JSON is `"available"`, `"registered"` or `"unknown"`
+///
+/// # Safety
+///
+/// Same contract as `whois_lookup_json`.
+#[no_mangle]
+pub unsafe extern "C" fn whois_availability_json(domain: *const c_char, status: *mut WhoisStatus) -> *mut c_char {
+    let domain = read_str(domain);
+    respond(status, move || {
+        let domain = domain.ok_or_else(invalid_argument)?;
+        json(client()?.is_available(&domain))
+    })
+}
+
+/// Look up the netblock holding an IPv4 or IPv6 address
+///
+/// # Safety
+///
+/// Same contract as `whois_lookup_json`.
+#[no_mangle]
+pub unsafe extern "C" fn whois_lookup_ip_json(ip: *const c_char, status: *mut WhoisStatus) -> *mut c_char {
+    let ip = read_str(ip).and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+    respond(status, move || {
+        let ip = ip.ok_or_else(invalid_argument)?;
+        json(client()?.lookup_ip(&ip))
+    })
+}
+
+/// Look up an autonomous system number
+///
+/// # Safety
+///
+/// `status` must be NULL or valid for a write. Free the result with `whois_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn whois_lookup_asn_json(asn: u32, status: *mut WhoisStatus) -> *mut c_char {
+    respond(status, move || json(client()?.lookup_asn(asn)))
+}
+
+/// Free a string returned by this library; NULL is ignored
+///
+/// # Safety
+///
+/// `string` must be NULL or a pointer returned by one of the `whois_*_json`
+/// functions that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn whois_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+fn client() -> Result<&'static WhoisClient, Failure> {
+    CLIENT.as_ref().map_err(|e| Failure { status: WhoisStatus::Init, code: "CONFIGURATION_ERROR", message: e.clone() })
+}
+
+unsafe fn read_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok().map(str::to_string)
+}
+
+fn invalid_argument() -> Failure {
+    Failure {
+        status: WhoisStatus::InvalidArgument,
+        code: "INVALID_REQUEST",
+        message: "NULL, non-UTF-8 or malformed argument".to_string(),
+    }
+}
+
+fn json<T: Serialize>(result: Result<T, WhoisError>) -> Result<String, Failure> {
+    serde_json::to_string(&result?)
+        .map_err(|e| Failure { status: WhoisStatus::Internal, code: "INTERNAL_ERROR", message: e.to_string() })
+}
+
+/// Run `call`, report its status and hand the JSON (or error object) to the caller
+unsafe fn respond(
+    status: *mut WhoisStatus,
+    call: impl FnOnce() -> Result<String, Failure> + UnwindSafe,
+) -> *mut c_char {
+    let result = catch_unwind(call).unwrap_or_else(|_| {
+        Err(Failure { status: WhoisStatus::Internal, code: "INTERNAL_ERROR", message: "lookup panicked".to_string() })
+    });
+    let (outcome, body) = match result {
+        Ok(body) => (WhoisStatus::Ok, body),
+        Err(Failure { status, code, message }) => {
+            (status, serde_json::json!({ "error": message, "code": code, "status": status as i32 }).to_string())
+        }
+    };
+    if !status.is_null() {
+        *status = outcome;
+    }
+    // JSON escapes control characters, so the body never contains a NUL byte
+    CString::new(body).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(f: impl FnOnce(*mut WhoisStatus) -> *mut c_char) -> (WhoisStatus, serde_json::Value) {
+        let mut status = WhoisStatus::Internal;
+        let ptr = f(&mut status);
+        assert!(!ptr.is_null());
+        let body = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        unsafe { whois_string_free(ptr) };
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn test_errors_come_back_as_status_and_json() {
+        let domain = CString::new("not-a-domain").unwrap();
+        let (status, body) = call(|status| unsafe { whois_lookup_json(domain.as_ptr(), status) });
+        assert_eq!(status, WhoisStatus::InvalidDomain);
+        assert_eq!(body["status"], WhoisStatus::InvalidDomain as i32);
+        assert_eq!(body["code"], "INVALID_DOMAIN");
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid domain"));
+
+        let (status, body) = call(|status| unsafe { whois_lookup_json(std::ptr::null(), status) });
+        assert_eq!(status, WhoisStatus::InvalidArgument);
+        assert_eq!(body["code"], "INVALID_REQUEST");
+
+        let ip = CString::new("999.1.1.1").unwrap();
+        let (status, _) = call(|status| unsafe { whois_lookup_ip_json(ip.as_ptr(), status) });
+        assert_eq!(status, WhoisStatus::InvalidArgument);
+
+        unsafe { whois_string_free(std::ptr::null_mut()) };
+    }
+}
@@ -0,0 +1,140 @@
+//! Record-and-replay of raw upstream responses
+//!
+//! With `fixture_mode = record` every successful raw whois answer and RDAP body
+//! is saved under `fixture_dir`; with `replay` the saved copies are served back
+//! and the network is never touched. This makes parser changes testable
+//! deterministically against real registry formats.
+//!
+//! Layout: `<dir>/whois/<server>/<query>.txt` and `<dir>/rdap/<host>/<path>.json`.
+//! Characters other than ASCII alphanumerics, `.`, `-` and `_` are replaced by `_`.
+
+use crate::config::{Config, FixtureMode};
+use crate::errors::WhoisError;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+use url::Url;
+
+pub struct FixtureStore {
+    mode: FixtureMode,
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new(config: &Config) -> Self {
+        let dir = config.fixture_dir.as_deref().map(PathBuf::from).unwrap_or_default();
+        if config.fixture_mode != FixtureMode::Off {
+            tracing::info!("Upstream fixtures: {:?} mode in {}", config.fixture_mode, dir.display());
+        }
+        Self { mode: config.fixture_mode, dir }
+    }
+
+    /// Fixture file for a port-43 query
+    pub fn whois_path(&self, server: &str, query: &str) -> PathBuf {
+        self.dir
+            .join("whois")
+            .join(Self::sanitize(&server.to_lowercase()))
+            .join(format!("{}.txt", Self::sanitize(query.trim())))
+    }
+
+    /// Fixture file for an RDAP request
+    pub fn rdap_path(&self, url: &Url) -> PathBuf {
+        let mut name = url.path().trim_matches('/').to_string();
+        if let Some(query) = url.query() {
+            name = format!("{}?{}", name, query);
+        }
+        self.dir
+            .join("rdap")
+            .join(Self::sanitize(url.host_str().unwrap_or_default()))
+            .join(format!("{}.json", Self::sanitize(&name)))
+    }
+
+    /// Run `fetch` according to the fixture mode
+    ///
+    /// `Off` passes it through, `Record` saves its successful output to `path`,
+    /// `Replay` reads `path` instead and never polls `fetch`.
+    pub async fn through<F>(&self, path: PathBuf, fetch: F) -> Result<String, WhoisError>
+    where
+        F: Future<Output = Result<String, WhoisError>>,
+    {
+        match self.mode {
+            FixtureMode::Off => fetch.await,
+            FixtureMode::Replay => match Self::read(&path).await {
+                Ok(body) => {
+                    debug!("Replaying fixture {}", path.display());
+                    Ok(body)
+                }
+                Err(e) => Err(WhoisError::Internal(format!("No recorded fixture at {}: {}", path.display(), e))),
+            },
+            FixtureMode::Record => {
+                let body = fetch.await?;
+                if let Err(e) = Self::save(&path, &body).await {
+                    warn!("Failed to record fixture {}: {}", path.display(), e);
+                }
+                Ok(body)
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn read(path: &Path) -> std::io::Result<String> {
+        tokio::fs::read_to_string(path).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn save(path: &Path, body: &str) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, body).await
+    }
+
+    // No tokio::fs without a blocking pool; on wasm32-unknown-unknown std::fs reports Unsupported
+    #[cfg(target_arch = "wasm32")]
+    async fn read(path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn save(path: &Path, body: &str) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, body)
+    }
+
+    fn sanitize(component: &str) -> String {
+        component
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_then_replay_without_network() {
+        let dir = std::env::temp_dir().join(format!("whois-fixtures-{}", std::process::id()));
+        let mut config = Config::load().unwrap();
+        config.fixture_dir = Some(dir.to_string_lossy().into_owned());
+
+        config.fixture_mode = FixtureMode::Record;
+        let recorder = FixtureStore::new(&config);
+        let path = recorder.whois_path("whois.nic.example:43", "n + 8.8.8.8");
+        assert!(path.ends_with("whois/whois.nic.example_43/n___8.8.8.8.txt"));
+        let body = recorder.through(path.clone(), async { Ok("Domain Name: EXAMPLE.COM\n".to_string()) }).await;
+        assert_eq!(body.unwrap(), "Domain Name: EXAMPLE.COM\n");
+
+        config.fixture_mode = FixtureMode::Replay;
+        let replayer = FixtureStore::new(&config);
+        let replayed = replayer.through(path, async { panic!("replay must not query upstream") }).await;
+        assert_eq!(replayed.unwrap(), "Domain Name: EXAMPLE.COM\n");
+        let missing = replayer.whois_path("whois.nic.example", "missing.example");
+        assert!(replayer.through(missing, async { panic!("replay must not query upstream") }).await.is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,193 @@
+//! Content negotiation for lookup responses
+//!
+//! Lookup endpoints honour the `Accept` header:
+//!
+//! - `application/json` (default): the `WhoisResponse` document
+//! - `application/x-ndjson`: the same document on a single line
+//! - `text/csv`: a header row plus one row of flattened parsed fields
+//! - `text/plain`: classic whois text (the raw whois reply, or one rebuilt from
+//!   the parsed fields when the data came from RDAP)
+//!
+//! A request accepting none of these gets a 406.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use whois_service::{export, DnssecStatus, ErrorBody, WhoisResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    NdJson,
+    Csv,
+    Text,
+}
+
+const MEDIA_TYPES: &[(&str, OutputFormat)] = &[
+    ("application/json", OutputFormat::Json),
+    ("application/x-ndjson", OutputFormat::NdJson),
+    ("text/csv", OutputFormat::Csv),
+    ("text/plain", OutputFormat::Text),
+];
+
+impl OutputFormat {
+    /// Best supported format for an `Accept` header value, honouring q-values
+    ///
+    /// Ties go to the earlier entry in the header; wildcards resolve to JSON.
+    pub fn negotiate(accept: &str) -> Option<Self> {
+        let mut best: Option<(f32, Self)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';');
+            let media_type = params.next().unwrap_or("").trim().to_ascii_lowercase();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+
+            let format = match media_type.as_str() {
+                "*/*" | "application/*" => Some(Self::Json),
+                "text/*" => Some(Self::Text),
+                _ => MEDIA_TYPES.iter().find(|(name, _)| *name == media_type).map(|(_, format)| *format),
+            };
+            if let Some(format) = format {
+                if best.is_none_or(|(best_quality, _)| quality > best_quality) {
+                    best = Some((quality, format));
+                }
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::NdJson => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for OutputFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(accept) = parts.headers.get(header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+            return Ok(Self::Json);
+        };
+
+        Self::negotiate(accept).ok_or_else(|| {
+            crate::metrics::increment_errors("not_acceptable");
+            let supported: Vec<&str> = MEDIA_TYPES.iter().map(|(name, _)| *name).collect();
+            let body = ErrorBody::new(
+                "NOT_ACCEPTABLE",
+                format!("Not acceptable; supported types: {}", supported.join(", ")),
+                StatusCode::NOT_ACCEPTABLE.as_u16(),
+            );
+            (StatusCode::NOT_ACCEPTABLE, Json(body)).into_response()
+        })
+    }
+}
+
+/// A lookup result rendered in the negotiated format
+pub struct Formatted(pub OutputFormat, pub WhoisResponse);
+
+impl IntoResponse for Formatted {
+    fn into_response(self) -> Response {
+        let Formatted(format, response) = self;
+        let body = match format {
+            OutputFormat::Json => return Json(response).into_response(),
+            OutputFormat::NdJson => {
+                let mut line = serde_json::to_string(&response).unwrap_or_default();
+                line.push('\n');
+                line
+            }
+            OutputFormat::Csv => {
+                let columns = export::DEFAULT_COLUMNS;
+                export::csv_header(columns) + &export::csv_row(&response, columns)
+            }
+            OutputFormat::Text => classic_text(&response),
+        };
+        ([(header::CONTENT_TYPE, format.content_type())], body).into_response()
+    }
+}
+
+fn dnssec_label(status: DnssecStatus) -> &'static str {
+    match status {
+        DnssecStatus::Signed => "signedDelegation",
+        DnssecStatus::Unsigned => "unsigned",
+    }
+}
+
+/// Whois-style text for a response: the raw reply for port-43 data, otherwise
+/// `Key: value` lines rebuilt from the parsed fields
+pub fn classic_text(response: &WhoisResponse) -> String {
+    let is_rdap_json = response.raw_data.trim_start().starts_with('{');
+    let Some(parsed) = response.parsed_data.as_ref().filter(|_| is_rdap_json) else {
+        let mut text = response.raw_data.clone();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        return text;
+    };
+
+    let mut lines = vec![format!("Domain Name: {}", response.domain.to_uppercase())];
+    let mut push = |key: &str, value: Option<&str>| {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", key, value));
+        }
+    };
+    push("Registry Domain ID", parsed.registry_domain_id.as_deref());
+    push("Registrar", parsed.registrar.as_deref());
+    push("Registrar IANA ID", parsed.registrar_iana_id.as_deref());
+    push("Registrar URL", parsed.registrar_url.as_deref());
+    push("Registrar Abuse Contact Email", parsed.registrar_abuse_email.as_deref());
+    push("Registrar Abuse Contact Phone", parsed.registrar_abuse_phone.as_deref());
+    push("Reseller", parsed.reseller.as_deref());
+    push("Creation Date", parsed.creation_date.as_deref());
+    push("Updated Date", parsed.updated_date.as_deref());
+    push("Registry Expiry Date", parsed.expiration_date.as_deref());
+    for status in &parsed.status {
+        push("Domain Status", Some(status));
+    }
+    push("Registrant Name", parsed.registrant_name());
+    push("Registrant Email", parsed.registrant_email());
+    push("Admin Email", parsed.admin_email());
+    push("Tech Email", parsed.tech_email());
+    for name_server in &parsed.name_servers {
+        match parsed.name_server_ips.get(name_server) {
+            Some(ips) => push("Name Server", Some(&format!("{} {}", name_server, ips.join(" ")))),
+            None => push("Name Server", Some(name_server)),
+        }
+    }
+    push("DNSSEC", parsed.dnssec.map(dnssec_label));
+    lines.push(format!(">>> Source: {} <<<", response.whois_server));
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_accept_header() {
+        assert_eq!(OutputFormat::negotiate("text/csv"), Some(OutputFormat::Csv));
+        assert_eq!(OutputFormat::negotiate("*/*"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::negotiate("text/plain;q=0.5, application/x-ndjson"), Some(OutputFormat::NdJson));
+        assert_eq!(OutputFormat::negotiate("text/html, text/*;q=0.8"), Some(OutputFormat::Text));
+        assert_eq!(OutputFormat::negotiate("image/png, text/csv;q=0"), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! Per-server health of upstream whois servers
+//!
+//! Every port-43 query records its outcome and latency. A server that failed
+//! `server_health_failure_threshold` times in a row (timeouts, connection
+//! errors or rate-limit replies) is degraded for `server_health_cooldown_seconds`
+//! after its last failure: lookups skip it in favour of alternative discovery,
+//! referrals to it are not followed, and the client's fallback chain moves on
+//! to RDAP without waiting for another timeout. Once the cooldown has passed
+//! the next query probes the server again; a success clears the state. When
+//! alternative discovery finds nothing for a degraded server, that is
+//! remembered for the cooldown too, so lookups go straight to the fallback
+//! instead of asking IANA again each time.
+//!
+//! The recent latencies also drive adaptive timeouts: once a server has
+//! answered `MIN_TIMEOUT_SAMPLES` times, its queries get
+//! p99 × `adaptive_timeout_factor`, at least `adaptive_timeout_min_seconds`
+//! and at most the configured (per-TLD) `whois_timeout_seconds`. Fast
+//! registries fail fast while slow ones keep the budget they need; a timeout
+//! counts as a sample at the full budget, so a server that slows down gets
+//! longer timeouts rather than a run of failures.
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// Weight of the newest sample in the latency moving average
+const LATENCY_SMOOTHING: f64 = 0.2;
+// Latencies kept per server for percentiles
+const LATENCY_WINDOW: usize = 128;
+/// Samples needed before a server's timeout adapts to its latency
+pub const MIN_TIMEOUT_SAMPLES: usize = 10;
+
+/// Health of one upstream server, as reported by `WhoisService::server_health`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ServerHealth {
+    pub server: String,
+    pub successes: u64,
+    pub failures: u64,
+    /// Share of queries that succeeded, 0.0 - 1.0
+    pub success_rate: f64,
+    /// Moving average of successful query latency
+    pub avg_latency_ms: u64,
+    /// 99th percentile of recent query latency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_latency_ms: Option<u64>,
+    /// Timeout currently applied to queries (adaptive, or the configured one)
+    pub timeout_ms: u64,
+    pub consecutive_failures: u32,
+    /// Lookups currently avoid this server
+    pub degraded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct Stats {
+    successes: u64,
+    failures: u64,
+    latency_ms: f64,
+    samples: VecDeque<Duration>,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_failure: Option<(Instant, DateTime<Utc>)>,
+    no_alternative_at: Option<Instant>, // When discovery last found no replacement while degraded
+}
+
+/// Outcome counters and latency per server
+pub(crate) struct HealthTracker {
+    servers: Mutex<HashMap<String, Stats>>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    timeout_factor: f64,
+    min_timeout: Duration,
+    default_timeout: Duration,
+}
+
+impl HealthTracker {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            servers: Mutex::new(HashMap::new()),
+            failure_threshold: config.server_health_failure_threshold,
+            cooldown: Duration::from_secs(config.server_health_cooldown_seconds),
+            timeout_factor: config.adaptive_timeout_factor,
+            min_timeout: Duration::from_secs(config.adaptive_timeout_min_seconds),
+            default_timeout: Duration::from_secs(config.whois_timeout_seconds),
+        }
+    }
+
+    pub(crate) fn record_success(&self, server: &str, elapsed: Duration) {
+        let mut servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = servers.entry(server.to_lowercase()).or_default();
+        let latency = elapsed.as_secs_f64() * 1000.0;
+        stats.latency_ms = if stats.successes == 0 {
+            latency
+        } else {
+            stats.latency_ms + LATENCY_SMOOTHING * (latency - stats.latency_ms)
+        };
+        stats.successes += 1;
+        stats.consecutive_failures = 0;
+        stats.no_alternative_at = None;
+        stats.push_sample(elapsed);
+    }
+
+    /// Record a failed query; `timed_out_after` is the budget a timeout used up
+    pub(crate) fn record_failure(&self, server: &str, error: &str, timed_out_after: Option<Duration>) {
+        let mut servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = servers.entry(server.to_lowercase()).or_default();
+        if let Some(elapsed) = timed_out_after {
+            stats.push_sample(elapsed);
+        }
+        stats.failures += 1;
+        stats.consecutive_failures = stats.consecutive_failures.saturating_add(1);
+        stats.last_error = Some(error.to_string());
+        stats.last_failure = Some((Instant::now(), Utc::now()));
+    }
+
+    /// Whether lookups should avoid `server` right now
+    pub(crate) fn is_degraded(&self, server: &str) -> bool {
+        let servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        servers.get(&server.to_lowercase()).is_some_and(|stats| self.degraded(stats))
+    }
+
+    /// Remember that discovery found nothing to use in place of degraded `server`
+    pub(crate) fn record_no_alternative(&self, server: &str) {
+        let mut servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        servers.entry(server.to_lowercase()).or_default().no_alternative_at = Some(Instant::now());
+    }
+
+    /// Whether `server` is degraded and discovery already came up empty within the cooldown
+    pub(crate) fn lacks_alternative(&self, server: &str) -> bool {
+        let servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        servers.get(&server.to_lowercase()).is_some_and(|stats| {
+            self.degraded(stats) && stats.no_alternative_at.is_some_and(|at| at.elapsed() < self.cooldown)
+        })
+    }
+
+    /// Timeout for the next query to `server`, at most `ceiling` (the configured timeout)
+    pub(crate) fn timeout_for(&self, server: &str, ceiling: Duration) -> Duration {
+        let servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        match servers.get(&server.to_lowercase()) {
+            Some(stats) => self.adaptive_timeout(stats, ceiling),
+            None => ceiling,
+        }
+    }
+
+    /// The `percentile` (0.0 - 1.0) of `server`'s recent latency, once enough samples exist
+    pub(crate) fn latency_percentile(&self, server: &str, percentile: f64) -> Option<Duration> {
+        let servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        servers.get(&server.to_lowercase()).and_then(|stats| stats.percentile(percentile))
+    }
+
+    /// Every server queried so far, sorted by name
+    pub(crate) fn snapshot(&self) -> Vec<ServerHealth> {
+        let servers = self.servers.lock().unwrap_or_else(|e| e.into_inner());
+        let mut health: Vec<_> = servers
+            .iter()
+            .map(|(server, stats)| {
+                let total = stats.successes + stats.failures;
+                ServerHealth {
+                    server: server.clone(),
+                    successes: stats.successes,
+                    failures: stats.failures,
+                    success_rate: if total == 0 { 1.0 } else { stats.successes as f64 / total as f64 },
+                    avg_latency_ms: stats.latency_ms.round() as u64,
+                    p99_latency_ms: stats.percentile(0.99).map(|p99| p99.as_millis() as u64),
+                    timeout_ms: self.adaptive_timeout(stats, self.default_timeout).as_millis() as u64,
+                    consecutive_failures: stats.consecutive_failures,
+                    degraded: self.degraded(stats),
+                    last_error: stats.last_error.clone(),
+                    last_failure_at: stats.last_failure.map(|(_, at)| at),
+                }
+            })
+            .collect();
+        health.sort_by(|a, b| a.server.cmp(&b.server));
+        health
+    }
+
+    fn adaptive_timeout(&self, stats: &Stats, ceiling: Duration) -> Duration {
+        if self.timeout_factor <= 0.0 {
+            return ceiling;
+        }
+        match stats.percentile(0.99) {
+            Some(p99) => p99.mul_f64(self.timeout_factor).clamp(self.min_timeout.min(ceiling), ceiling),
+            None => ceiling,
+        }
+    }
+
+    fn degraded(&self, stats: &Stats) -> bool {
+        self.failure_threshold > 0
+            && stats.consecutive_failures >= self.failure_threshold
+            && stats.last_failure.is_some_and(|(at, _)| at.elapsed() < self.cooldown)
+    }
+}
+
+impl Stats {
+    fn push_sample(&mut self, elapsed: Duration) {
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(elapsed);
+    }
+
+    // Nearest-rank percentile over the window
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.samples.len() < MIN_TIMEOUT_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<_> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (percentile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consecutive_failures_degrade_until_success() {
+        let mut config = Config::builder().build().unwrap();
+        config.server_health_failure_threshold = 2;
+        config.server_health_cooldown_seconds = 60;
+        let tracker = HealthTracker::new(&config);
+
+        tracker.record_success("Whois.Example", Duration::from_millis(100));
+        tracker.record_failure("whois.example", "Connection refused", None);
+        assert!(!tracker.is_degraded("whois.example"));
+        tracker.record_failure("whois.example", "Network timeout", None);
+        assert!(tracker.is_degraded("whois.example"));
+
+        let health = tracker.snapshot();
+        assert_eq!(health.len(), 1);
+        assert_eq!(health[0].avg_latency_ms, 100);
+        assert!((health[0].success_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(health[0].last_error.as_deref(), Some("Network timeout"));
+
+        assert!(!tracker.lacks_alternative("whois.example"));
+        tracker.record_no_alternative("Whois.Example");
+        assert!(tracker.lacks_alternative("whois.example"));
+
+        tracker.record_success("whois.example", Duration::from_millis(200));
+        assert!(!tracker.is_degraded("whois.example"));
+        assert!(!tracker.lacks_alternative("whois.example"));
+        assert_eq!(tracker.snapshot()[0].avg_latency_ms, 120);
+    }
+
+    #[test]
+    fn test_adaptive_timeout_follows_p99_within_bounds() {
+        let mut config = Config::builder().build().unwrap();
+        config.adaptive_timeout_factor = 3.0;
+        config.adaptive_timeout_min_seconds = 1;
+        let tracker = HealthTracker::new(&config);
+        let ceiling = Duration::from_secs(30);
+
+        for _ in 0..MIN_TIMEOUT_SAMPLES - 1 {
+            tracker.record_success("fast.example", Duration::from_millis(100));
+            tracker.record_success("slow.example", Duration::from_secs(4));
+        }
+        // Too few samples to adapt yet
+        assert_eq!(tracker.timeout_for("fast.example", ceiling), ceiling);
+
+        tracker.record_success("fast.example", Duration::from_millis(100));
+        tracker.record_success("slow.example", Duration::from_secs(4));
+        assert_eq!(tracker.timeout_for("fast.example", ceiling), Duration::from_secs(1));
+        assert_eq!(tracker.timeout_for("slow.example", ceiling), Duration::from_secs(12));
+        assert_eq!(tracker.timeout_for("slow.example", Duration::from_secs(10)), Duration::from_secs(10));
+        assert_eq!(tracker.timeout_for("unknown.example", ceiling), ceiling);
+    }
+}
@@ -0,0 +1,224 @@
+//! Lookup history (feature `history`)
+//!
+//! Every fresh lookup result is appended to a `HistoryStore` together with the
+//! time it was recorded, so `WhoisClient::history` can show how a registration
+//! changed over time. Cache hits are not recorded since they repeat an earlier
+//! result. `history_path` selects the on-disk store (sled); without it entries
+//! are kept in memory. At most `history_max_entries` entries are kept per
+//! domain, dropping the oldest first. The in-memory store also keeps at most
+//! `history_max_domains` domains, forgetting the one recorded longest ago.
+
+use crate::{config::Config, WhoisResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, RwLock},
+};
+
+/// One recorded lookup result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub recorded_at: DateTime<Utc>,
+    pub response: WhoisResponse,
+}
+
+impl HistoryEntry {
+    pub fn new(response: WhoisResponse) -> Self {
+        Self { recorded_at: Utc::now(), response }
+    }
+}
+
+/// Storage for lookup history; implement this to keep history elsewhere
+///
+/// Domains are passed already normalized (lowercase ASCII, no trailing dot).
+#[async_trait]
+pub trait HistoryStore: Send + Sync {
+    /// Append an entry for `domain`
+    async fn record(&self, domain: &str, entry: &HistoryEntry) -> Result<(), String>;
+
+    /// All entries kept for `domain`, oldest first
+    async fn history(&self, domain: &str) -> Result<Vec<HistoryEntry>, String>;
+}
+
+/// Open the store configured by `history_path`: on disk if set, in memory otherwise
+pub fn open(config: &Config) -> Result<Arc<dyn HistoryStore>, String> {
+    Ok(match config.history_path {
+        Some(ref path) => Arc::new(DiskHistory::open(path, config.history_max_entries)?),
+        None => Arc::new(MemoryHistory::new(config.history_max_entries).with_max_domains(config.history_max_domains)),
+    })
+}
+
+/// In-process history, lost on restart
+pub struct MemoryHistory {
+    entries: RwLock<MemoryEntries>,
+    max_entries: usize,
+    max_domains: usize,
+}
+
+#[derive(Default)]
+struct MemoryEntries {
+    // Domain -> (its entries, the sequence number of its last record)
+    domains: HashMap<String, (VecDeque<HistoryEntry>, u64)>,
+    // Sequence number of each domain's last record -> domain, oldest first
+    recency: BTreeMap<u64, String>,
+    sequence: u64,
+}
+
+impl MemoryHistory {
+    /// Keep at most `max_entries` entries per domain (0 = unlimited), for any number of domains
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: RwLock::new(MemoryEntries::default()),
+            max_entries,
+            max_domains: 0,
+        }
+    }
+
+    /// Keep at most `max_domains` domains (0 = unlimited), forgetting the one recorded longest ago
+    pub fn with_max_domains(mut self, max_domains: usize) -> Self {
+        self.max_domains = max_domains;
+        self
+    }
+}
+
+#[async_trait]
+impl HistoryStore for MemoryHistory {
+    async fn record(&self, domain: &str, entry: &HistoryEntry) -> Result<(), String> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let MemoryEntries { domains, recency, sequence } = &mut *entries;
+        *sequence += 1;
+
+        let (domain_entries, last_recorded) = domains.entry(domain.to_string()).or_default();
+        recency.remove(last_recorded);
+        *last_recorded = *sequence;
+        recency.insert(*sequence, domain.to_string());
+        domain_entries.push_back(entry.clone());
+        while self.max_entries > 0 && domain_entries.len() > self.max_entries {
+            domain_entries.pop_front();
+        }
+
+        while self.max_domains > 0 && domains.len() > self.max_domains {
+            let Some((_, oldest)) = recency.pop_first() else { break };
+            domains.remove(&oldest);
+        }
+        Ok(())
+    }
+
+    async fn history(&self, domain: &str) -> Result<Vec<HistoryEntry>, String> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.domains.get(domain).map(|(entries, _)| entries.iter().cloned().collect()).unwrap_or_default())
+    }
+}
+
+/// On-disk history backed by sled
+///
+/// Keys are `domain \0 recorded_at (big-endian micros) id`, so a prefix scan
+/// returns a domain's entries in recording order.
+pub struct DiskHistory {
+    db: sled::Db,
+    max_entries: usize,
+}
+
+impl DiskHistory {
+    /// Open (or create) the history database at `path`
+    pub fn open(path: &str, max_entries: usize) -> Result<Self, String> {
+        let db = sled::open(path).map_err(|e| format!("Failed to open history store at {}: {}", path, e))?;
+        tracing::info!("History store opened at {} ({} entries)", path, db.len());
+        Ok(Self { db, max_entries })
+    }
+
+    fn prefix(domain: &str) -> Vec<u8> {
+        let mut prefix = domain.as_bytes().to_vec();
+        prefix.push(0);
+        prefix
+    }
+}
+
+#[async_trait]
+impl HistoryStore for DiskHistory {
+    async fn record(&self, domain: &str, entry: &HistoryEntry) -> Result<(), String> {
+        let mut key = Self::prefix(domain);
+        key.extend_from_slice(&entry.recorded_at.timestamp_micros().to_be_bytes());
+        // Entries recorded within the same microsecond stay distinct
+        key.extend_from_slice(&self.db.generate_id().map_err(|e| e.to_string())?.to_be_bytes());
+
+        let value = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+        self.db.insert(key, value).map_err(|e| e.to_string())?;
+
+        if self.max_entries > 0 {
+            let keys: Vec<_> = self
+                .db
+                .scan_prefix(Self::prefix(domain))
+                .keys()
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?;
+            for key in keys.iter().take(keys.len().saturating_sub(self.max_entries)) {
+                self.db.remove(key).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn history(&self, domain: &str) -> Result<Vec<HistoryEntry>, String> {
+        let mut entries = Vec::new();
+        for item in self.db.scan_prefix(Self::prefix(domain)) {
+            let (_, value) = item.map_err(|e| e.to_string())?;
+            // Skip entries an older version wrote in a different format
+            if let Ok(entry) = serde_json::from_slice(&value) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(registrar: &str) -> HistoryEntry {
+        HistoryEntry::new(WhoisResponse {
+            raw_data: format!("Registrar: {}", registrar),
+            ..crate::test_response("example.com")
+        })
+    }
+
+    #[tokio::test]
+    async fn test_history_keeps_newest_entries_in_order() {
+        let path = std::env::temp_dir().join(format!("whois-history-{}", std::process::id()));
+        let disk = DiskHistory::open(path.to_str().unwrap(), 2).unwrap();
+        let stores: [&dyn HistoryStore; 2] = [&MemoryHistory::new(2), &disk];
+
+        for store in stores {
+            for registrar in ["A", "B", "C"] {
+                store.record("example.com", &entry(registrar)).await.unwrap();
+            }
+            store.record("example.co", &entry("Other")).await.unwrap();
+
+            let raw: Vec<_> = store.history("example.com").await.unwrap().into_iter().map(|e| e.response.raw_data).collect();
+            assert_eq!(raw, ["Registrar: B", "Registrar: C"]);
+            assert_eq!(store.history("example.co").await.unwrap().len(), 1);
+            assert!(store.history("example.org").await.unwrap().is_empty());
+        }
+
+        drop(disk);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_memory_history_forgets_least_recently_recorded_domains() {
+        let store = MemoryHistory::new(10).with_max_domains(2);
+        store.record("a.example", &entry("A")).await.unwrap();
+        store.record("b.example", &entry("B")).await.unwrap();
+        // Recording again makes a.example the most recent...
+        store.record("a.example", &entry("A2")).await.unwrap();
+        // ...so the third domain pushes out b.example
+        store.record("c.example", &entry("C")).await.unwrap();
+
+        assert_eq!(store.history("a.example").await.unwrap().len(), 2);
+        assert!(store.history("b.example").await.unwrap().is_empty());
+        assert_eq!(store.history("c.example").await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Hooks around `WhoisClient` domain lookups
+//!
+//! Interceptors registered with `WhoisClient::with_interceptor` see every call
+//! that goes through `lookup_opts` (and so `lookup`, `lookup_fresh`,
+//! `lookup_with_server` and `lookup_stream`). They can adjust or reject the
+//! options before the lookup and observe or rewrite the result after it, for
+//! custom logging, quota accounting or result mutation.
+
+use crate::{LookupOptions, WhoisError, WhoisResponse};
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait LookupInterceptor: Send + Sync {
+    /// Runs before the lookup; an error aborts it and is returned to the caller
+    async fn before_lookup(&self, _domain: &str, _options: &mut LookupOptions) -> Result<(), WhoisError> {
+        Ok(())
+    }
+
+    /// Runs after the lookup with its outcome, which may be replaced or modified
+    async fn after_lookup(&self, _domain: &str, _result: &mut Result<WhoisResponse, WhoisError>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WhoisClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Quota(AtomicUsize);
+
+    #[async_trait]
+    impl LookupInterceptor for Quota {
+        async fn before_lookup(&self, _domain: &str, _options: &mut LookupOptions) -> Result<(), WhoisError> {
+            self.0
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| left.checked_sub(1))
+                .map(|_| ())
+                .map_err(|_| WhoisError::RateLimited { server: "quota".to_string(), retry_after: None })
+        }
+    }
+
+    struct Rewrite(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl LookupInterceptor for Rewrite {
+        async fn after_lookup(&self, domain: &str, result: &mut Result<WhoisResponse, WhoisError>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            if matches!(result, Err(WhoisError::InvalidDomain(_))) {
+                *result = Err(WhoisError::DomainNotFound(domain.to_string()));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_reject_and_rewrite() {
+        let observed = Arc::new(AtomicUsize::new(0));
+        let client = WhoisClient::new_without_cache()
+            .await
+            .unwrap()
+            .with_interceptor(Quota(AtomicUsize::new(1)))
+            .with_interceptor(Rewrite(observed.clone()));
+
+        // Invalid input fails before any network access; the after hook rewrites the error
+        assert!(matches!(client.lookup("not a domain").await, Err(WhoisError::DomainNotFound(_))));
+        // Quota exhausted: rejected before the lookup, so later hooks never run
+        assert!(matches!(client.lookup("not a domain").await, Err(WhoisError::RateLimited { .. })));
+        assert_eq!(observed.load(Ordering::Relaxed), 1);
+    }
+}
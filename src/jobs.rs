@@ -0,0 +1,570 @@
+//! Asynchronous bulk lookup jobs for the HTTP server
+//!
+//! `POST /jobs` takes a domain list, either as JSON (`{"domains": [...]}`) or as
+//! a plain-text upload with one domain per line, and returns a job ID right
+//! away. The job runs in the background through `WhoisClient::lookup_stream`,
+//! so the usual concurrency limits apply. `GET /jobs/:id` reports progress and
+//! `GET /jobs/:id/results` pages through results (`?offset=&limit=`) or streams
+//! them all as JSON lines (`?format=jsonl`) or CSV (`?format=csv`); streams can
+//! be narrowed to `?columns=domain,registrar,...` (see `whois_service::export`).
+//!
+//! When a job finishes, its final status is POSTed as a `job.completed` webhook
+//! event to the job's own `webhook` (JSON submissions only) or the configured
+//! `webhook_url`. A job's own webhook must be public: loopback, private and
+//! link-local targets are refused with `INVALID_REQUEST`.
+//!
+//! A job queues up to `MAX_JOB_DOMAINS` upstream lookups for one request, so
+//! the request rate limit alone can't hold a client back: each client (keyed
+//! like the rate limiter, see `rate_limit::ClientIdentity`) may run
+//! `MAX_RUNNING_JOBS_PER_CLIENT` jobs at once and the server
+//! `MAX_RUNNING_JOBS`. Submissions beyond that get a 429 `TOO_MANY_JOBS`.
+//!
+//! Jobs live in memory and are dropped `JOB_RETENTION_SECS` after finishing.
+
+use crate::{AppState, ValidatedDomain};
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing::{info, Instrument};
+use whois_service::{errors::WhoisError, export, webhook::WebhookTarget, ErrorBody, WhoisResponse};
+
+// Upper bound on domains per job, to keep a single job's results in memory reasonable
+pub const MAX_JOB_DOMAINS: usize = 100_000;
+// Request body limit for job submissions (~100k domains as plain text)
+pub const MAX_JOB_BODY_BYTES: usize = 8 * 1024 * 1024;
+// Jobs running at once, per client and in total
+pub const MAX_RUNNING_JOBS_PER_CLIENT: usize = 2;
+pub const MAX_RUNNING_JOBS: usize = 16;
+const JOB_RETENTION_SECS: i64 = 3600;
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResult {
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<WhoisResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Stable code of the failure (see `WhoisError::code`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+}
+
+impl JobResult {
+    fn new(domain: String, result: Result<WhoisResponse, WhoisError>) -> Self {
+        match result {
+            Ok(response) => Self { domain, response: Some(response), error: None, code: None },
+            Err(e) => Self { domain, response: None, error: Some(e.to_string()), code: Some(e.code()) },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub state: JobState,
+    pub total: usize,
+    pub completed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+struct Job {
+    client: String, // `ClientIdentity` key of the submitter
+    status: RwLock<JobStatus>,
+    results: RwLock<Vec<JobResult>>,
+}
+
+// A panic while holding a lock leaves plain counters and a result list, still fine to use
+impl Job {
+    fn new(id: String, total: usize, client: String) -> Self {
+        Self {
+            client,
+            status: RwLock::new(JobStatus {
+                id,
+                state: JobState::Running,
+                total,
+                completed: 0,
+                succeeded: 0,
+                failed: 0,
+                created_at: Utc::now(),
+                finished_at: None,
+            }),
+            results: RwLock::new(Vec::with_capacity(total)),
+        }
+    }
+
+    fn status(&self) -> JobStatus {
+        self.status.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn push(&self, result: JobResult) {
+        {
+            let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+            status.completed += 1;
+            if result.error.is_some() {
+                status.failed += 1;
+            } else {
+                status.succeeded += 1;
+            }
+        }
+        self.results.write().unwrap_or_else(|e| e.into_inner()).push(result);
+    }
+
+    fn finish(&self) {
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        status.state = JobState::Completed;
+        status.finished_at = Some(Utc::now());
+    }
+}
+
+/// In-memory registry of bulk jobs
+pub struct JobStore {
+    jobs: RwLock<HashMap<String, Arc<Job>>>,
+    max_running: usize,
+    max_running_per_client: usize,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self {
+            jobs: RwLock::default(),
+            max_running: MAX_RUNNING_JOBS,
+            max_running_per_client: MAX_RUNNING_JOBS_PER_CLIENT,
+        }
+    }
+}
+
+impl JobStore {
+    fn get(&self, id: &str) -> Option<Arc<Job>> {
+        self.jobs.read().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+    }
+
+    /// Add `job` unless its client, or the server, already runs as many jobs as allowed
+    fn try_insert(&self, job: Arc<Job>) -> bool {
+        let cutoff = Utc::now() - chrono::Duration::seconds(JOB_RETENTION_SECS);
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|_, job| job.status().finished_at.is_none_or(|finished| finished > cutoff));
+
+        let running: Vec<&Arc<Job>> = jobs.values().filter(|job| job.status().state == JobState::Running).collect();
+        let running_for_client = running.iter().filter(|running| running.client == job.client).count();
+        if running.len() >= self.max_running || running_for_client >= self.max_running_per_client {
+            return false;
+        }
+        jobs.insert(job.status().id, job);
+        true
+    }
+}
+
+#[derive(Deserialize)]
+pub struct JobRequest {
+    domains: Vec<String>,
+    /// Overrides the configured webhook for this job
+    #[serde(default)]
+    webhook: Option<WebhookTarget>,
+}
+
+#[derive(Deserialize)]
+pub struct ResultsQuery {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+    format: Option<String>,
+    columns: Option<String>,
+}
+
+// How streamed results are written
+enum Encoding {
+    // Whole results, or `response` narrowed to the selected columns
+    JsonLines(Option<Vec<String>>),
+    // Selected columns plus a trailing `error` column for failed lookups
+    Csv(Vec<String>),
+}
+
+impl Encoding {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::JsonLines(_) => "application/x-ndjson",
+            Self::Csv(_) => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn header(&self) -> Option<String> {
+        match self {
+            Self::JsonLines(_) => None,
+            Self::Csv(columns) => Some(export::csv_record(columns.iter().cloned().chain(["error_code".to_string(), "error".to_string()]))),
+        }
+    }
+
+    fn encode(&self, result: &JobResult) -> String {
+        match (self, &result.response) {
+            (Self::JsonLines(Some(columns)), Some(response)) => {
+                let line = json!({ "domain": result.domain, "response": export::select(response, columns) });
+                format!("{}\n", line)
+            }
+            (Self::JsonLines(_), _) => format!("{}\n", serde_json::to_string(result).unwrap_or_default()),
+            (Self::Csv(columns), Some(response)) => {
+                export::csv_record(export::csv_fields(response, columns).into_iter().chain([String::new(), String::new()]))
+            }
+            (Self::Csv(columns), None) => {
+                let fields = columns.iter().map(|column| if column == "domain" { result.domain.clone() } else { String::new() });
+                let error = [result.code.unwrap_or_default().to_string(), result.error.clone().unwrap_or_default()];
+                export::csv_record(fields.chain(error))
+            }
+        }
+    }
+}
+
+fn not_found(id: &str) -> Response {
+    let body = ErrorBody::new("JOB_NOT_FOUND", format!("Job not found: {}", id), 404);
+    (StatusCode::NOT_FOUND, Json(body)).into_response()
+}
+
+fn too_many_jobs() -> Response {
+    let message = "Too many bulk jobs running; wait for one to finish";
+    let body = ErrorBody::new("TOO_MANY_JOBS", message, StatusCode::TOO_MANY_REQUESTS.as_u16());
+    (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response()
+}
+
+/// Domain list from a JSON body or a plain-text upload (one domain per line, `#` comments)
+fn parse_request(headers: &HeaderMap, body: &[u8]) -> Result<JobRequest, WhoisError> {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+
+    if is_json {
+        let request: JobRequest = serde_json::from_slice(body)
+            .map_err(|e| WhoisError::InvalidRequest(format!("Invalid job request: {}", e)))?;
+        Ok(request)
+    } else {
+        let text = std::str::from_utf8(body)
+            .map_err(|_| WhoisError::InvalidRequest("Job upload is not UTF-8 text".to_string()))?;
+        let domains = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        Ok(JobRequest { domains, webhook: None })
+    }
+}
+
+/// `POST /jobs`: queue a bulk lookup and return its ID
+pub async fn create_job(
+    State(state): State<AppState>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, WhoisError> {
+    let JobRequest { domains, webhook } = parse_request(&headers, &body)?;
+    if domains.is_empty() {
+        return Err(WhoisError::InvalidRequest("No domains provided".to_string()));
+    }
+    if domains.len() > MAX_JOB_DOMAINS {
+        return Err(WhoisError::InvalidRequest(format!(
+            "Too many domains: {} (limit {})",
+            domains.len(),
+            MAX_JOB_DOMAINS
+        )));
+    }
+    // A client's own webhook must not reach into our network, now or when the job
+    // finishes; the configured one is trusted. Checked last, as it resolves the host
+    let (webhook, client_webhook) = match webhook {
+        Some(webhook) => {
+            webhook.validate_public().await?;
+            (Some(webhook), true)
+        }
+        None => (WebhookTarget::from_config(&state.config), false),
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let client = state.clients.key(&headers, peer.map(|ConnectInfo(addr)| addr));
+    let job = Arc::new(Job::new(id.clone(), domains.len(), client));
+    if !state.jobs.try_insert(job.clone()) {
+        tracing::debug!(client = %job.client, "Bulk job refused, too many running");
+        return Ok(too_many_jobs());
+    }
+    info!(job = %id, domains = domains.len(), "Bulk job queued");
+
+    // Malformed entries fail immediately; the rest go through the batch engine
+    let mut valid = Vec::with_capacity(domains.len());
+    for domain in domains {
+        match ValidatedDomain::validate_domain(domain.clone()) {
+            Ok(validated) => valid.push(validated.0),
+            Err(e) => job.push(JobResult::new(domain, Err(e))),
+        }
+    }
+
+    let client = state.client.clone();
+    let webhooks = state.webhooks.clone();
+    let span = tracing::info_span!("bulk_job", job = %id);
+    let worker = job.clone();
+    tokio::spawn(
+        async move {
+            let mut results = client.lookup_stream(stream::iter(valid));
+            while let Some((domain, result)) = results.next().await {
+                worker.push(JobResult::new(domain, result));
+            }
+            worker.finish();
+            let status = worker.status();
+            info!(succeeded = status.succeeded, failed = status.failed, "Bulk job finished");
+
+            if let Some(webhook) = webhook {
+                let delivery = if client_webhook {
+                    webhooks.send_public(&webhook, "job.completed", &status).await
+                } else {
+                    webhooks.send(&webhook, "job.completed", &status).await
+                };
+                if let Err(e) = delivery {
+                    tracing::warn!(url = %webhook.url, "Job webhook delivery failed: {}", e);
+                }
+            }
+        }
+        .instrument(span),
+    );
+
+    let status = job.status();
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, format!("/jobs/{}", id))], Json(status)).into_response())
+}
+
+/// `GET /jobs/:id`: progress of a job
+pub async fn job_status(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match state.jobs.get(&id) {
+        Some(job) => Json(job.status()).into_response(),
+        None => not_found(&id),
+    }
+}
+
+/// `GET /jobs/:id/results`: a page of results, or all of them as JSON lines or CSV
+pub async fn job_results(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ResultsQuery>,
+) -> Response {
+    let Some(job) = state.jobs.get(&id) else {
+        return not_found(&id);
+    };
+
+    let columns = match query.columns.as_deref().map(export::parse_columns).transpose() {
+        Ok(columns) => columns,
+        Err(e) => return e.into_response(),
+    };
+    match query.format.as_deref() {
+        Some("jsonl") => return stream_results(job, query.offset, Encoding::JsonLines(columns)),
+        Some("csv") => {
+            let columns = columns.unwrap_or_else(|| export::DEFAULT_COLUMNS.iter().map(|column| column.to_string()).collect());
+            return stream_results(job, query.offset, Encoding::Csv(columns));
+        }
+        _ => {}
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let status = job.status();
+    let results = job.results.read().unwrap_or_else(|e| e.into_inner());
+    let page: Vec<&JobResult> = results.iter().skip(query.offset).take(limit).collect();
+    let next_offset = query.offset + page.len();
+    let more = next_offset < results.len() || status.state == JobState::Running;
+
+    Json(json!({
+        "job": status,
+        "offset": query.offset,
+        "results": page,
+        "next_offset": more.then_some(next_offset),
+    }))
+    .into_response()
+}
+
+// Streams results available so far plus those still arriving, until the job finishes
+fn stream_results(job: Arc<Job>, offset: usize, encoding: Encoding) -> Response {
+    let content_type = encoding.content_type();
+    let header = stream::iter(encoding.header().map(Ok::<_, std::io::Error>));
+    let lines = stream::unfold((job, offset, encoding), |(job, offset, encoding)| async move {
+        loop {
+            // Checked before reading so results pushed right before finishing aren't missed
+            let done = job.status().state == JobState::Completed;
+            let chunk: Vec<JobResult> = job.results.read().unwrap_or_else(|e| e.into_inner()).iter().skip(offset).take(DEFAULT_PAGE_SIZE).cloned().collect();
+            if !chunk.is_empty() {
+                let body: String = chunk.iter().map(|result| encoding.encode(result)).collect();
+                return Some((Ok(body), (job, offset + chunk.len(), encoding)));
+            }
+            if done {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    });
+
+    ([(header::CONTENT_TYPE, content_type)], Body::from_stream(header.chain(lines))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use whois_service::config::Config;
+
+    async fn app_state() -> AppState {
+        AppState::for_tests(Config::load().unwrap()).await
+    }
+
+    /// Running job "job-1" with a failed lookup already recorded for each domain
+    fn running_job(state: &AppState, domains: &[&str]) -> Arc<Job> {
+        let job = Arc::new(Job::new("job-1".to_string(), domains.len() + 1, "ip:192.0.2.1".to_string()));
+        for domain in domains {
+            job.push(JobResult::new(domain.to_string(), Err(WhoisError::DomainNotFound(domain.to_string()))));
+        }
+        assert!(state.jobs.try_insert(job.clone()));
+        job
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX);
+        let bytes = tokio::time::timeout(Duration::from_secs(5), body).await.expect("body should end").unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_job_uploads_are_parsed() {
+        let mut json = HeaderMap::new();
+        json.insert(header::CONTENT_TYPE, "application/json; charset=utf-8".parse().unwrap());
+        let body = br#"{"domains": ["example.com", "example.org"], "webhook": {"url": "https://hooks.example/jobs"}}"#;
+        let request = parse_request(&json, body).unwrap();
+        assert_eq!(request.domains, vec!["example.com", "example.org"]);
+        assert_eq!(request.webhook.unwrap().url, "https://hooks.example/jobs");
+        assert!(matches!(parse_request(&json, b"example.com"), Err(WhoisError::InvalidRequest(_))));
+
+        // Anything else is a text upload, which can't carry a webhook
+        let request = parse_request(&HeaderMap::new(), b"# watch list\nexample.com\n\n  example.org  \r\n").unwrap();
+        assert_eq!(request.domains, vec!["example.com", "example.org"]);
+        assert!(request.webhook.is_none());
+        assert!(matches!(parse_request(&HeaderMap::new(), b"example.com\n\xff\n"), Err(WhoisError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_job_size_limits() {
+        let state = app_state().await;
+        let result = create_job(State(state.clone()), None, HeaderMap::new(), Bytes::from("# nothing yet\n\n")).await;
+        assert!(matches!(result, Err(WhoisError::InvalidRequest(e)) if e.contains("No domains")));
+
+        let upload: String = (0..=MAX_JOB_DOMAINS).map(|i| format!("domain{}.com\n", i)).collect();
+        let result = create_job(State(state.clone()), None, HeaderMap::new(), Bytes::from(upload)).await;
+        assert!(matches!(result, Err(WhoisError::InvalidRequest(e)) if e.contains("Too many domains")));
+
+        // Size is checked before the webhook host is resolved
+        let mut json = HeaderMap::new();
+        json.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        let body = Bytes::from(r#"{"domains": [], "webhook": {"url": "https://hooks.invalid/jobs"}}"#);
+        let result = create_job(State(state.clone()), None, json, body).await;
+        assert!(matches!(result, Err(WhoisError::InvalidRequest(e)) if e.contains("No domains")));
+        assert!(state.jobs.jobs.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_running_jobs_are_capped_per_client_and_in_total() {
+        let store = JobStore { max_running: 3, ..JobStore::default() };
+        let job = |id: &str, client: &str| Arc::new(Job::new(id.to_string(), 1, client.to_string()));
+        let first = job("a-1", "ip:192.0.2.1");
+        assert!(store.try_insert(first.clone()));
+        assert!(store.try_insert(job("a-2", "ip:192.0.2.1")));
+        assert!(!store.try_insert(job("a-3", "ip:192.0.2.1")));
+        // Other clients have slots of their own, up to the server-wide cap
+        assert!(store.try_insert(job("b-1", "key:team-b")));
+        assert!(!store.try_insert(job("c-1", "ip:192.0.2.3")));
+
+        // A finished job frees its slot
+        first.finish();
+        assert!(store.try_insert(job("a-3", "ip:192.0.2.1")));
+
+        // Refused submissions get a 429 before any lookup is queued
+        let state = app_state().await;
+        let peer = || Some(ConnectInfo(SocketAddr::from(([192, 0, 2, 1], 40000))));
+        for i in 0..MAX_RUNNING_JOBS_PER_CLIENT {
+            assert!(state.jobs.try_insert(job(&format!("running-{}", i), "ip:192.0.2.1")));
+        }
+        let response = create_job(State(state.clone()), peer(), HeaderMap::new(), Bytes::from("example.com\n")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(body_text(response).await.contains("TOO_MANY_JOBS"));
+        assert_eq!(state.jobs.jobs.read().unwrap().len(), MAX_RUNNING_JOBS_PER_CLIENT);
+    }
+
+    #[tokio::test]
+    async fn test_results_page_until_the_job_finishes() {
+        let state = app_state().await;
+        let job = running_job(&state, &["a.test", "b.test", "c.test"]);
+        let page = |offset, limit| {
+            let query = ResultsQuery { offset, limit: Some(limit), format: None, columns: None };
+            let response = job_results(State(state.clone()), Path("job-1".to_string()), Query(query));
+            async { serde_json::from_str::<serde_json::Value>(&body_text(response.await).await).unwrap() }
+        };
+
+        let body = page(0, 2).await;
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+        assert_eq!(body["next_offset"], 2);
+        // Caught up with a running job: nothing yet, but ask again from the same offset
+        let body = page(3, 2).await;
+        assert!(body["results"].as_array().unwrap().is_empty());
+        assert_eq!(body["next_offset"], 3);
+
+        job.finish();
+        let body = page(2, 2).await;
+        assert_eq!(body["results"][0]["domain"], "c.test");
+        assert!(body["next_offset"].is_null());
+        assert_eq!(body["job"]["state"], "completed");
+    }
+
+    #[tokio::test]
+    async fn test_result_streams_end_with_the_job() {
+        let state = app_state().await;
+        let job = running_job(&state, &["a.test"]);
+        let csv = stream_results(job.clone(), 0, Encoding::Csv(vec!["domain".to_string(), "registrar".to_string()]));
+        let jsonl = stream_results(job.clone(), 0, Encoding::JsonLines(None));
+
+        // Results arriving after the stream started are included, then the stream ends
+        let worker = job.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            worker.push(JobResult::new("b.test".to_string(), Err(WhoisError::Timeout)));
+            worker.finish();
+        });
+
+        let csv = body_text(csv).await;
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], "domain,registrar,error_code,error");
+        assert!(rows[1].starts_with("a.test,,"));
+        assert!(rows[2].starts_with("b.test,,UPSTREAM_TIMEOUT,"));
+
+        let jsonl = body_text(jsonl).await;
+        let domains: Vec<String> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["domain"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(domains, vec!["a.test", "b.test"]);
+        let failed: serde_json::Value = serde_json::from_str(jsonl.lines().last().unwrap()).unwrap();
+        assert_eq!(failed["code"], "UPSTREAM_TIMEOUT");
+    }
+}
@@ -10,6 +10,9 @@
 //! - Optional caching with smart domain normalization
 //! - Production-ready error handling with graceful degradation
 //! - High-performance async implementation with connection pooling
+//! - RDAP lookups on `wasm32-unknown-unknown` (browser extensions, Workers) with
+//!   `--no-default-features`: `RdapService` builds over fetch; `WhoisClient`,
+//!   the cache and the watcher are native-only
 //! 
 //! ## Quick Start
 //! 
@@ -30,12 +33,68 @@
 
 pub mod whois;
 pub mod rdap;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cache;
 pub mod config;
 pub mod errors;
 pub mod tld_mappings;
+#[cfg(feature = "whois-tcp")]
 pub mod buffer_pool;
 pub mod parser;
+#[cfg(feature = "whois-tcp")]
+pub mod proxy;
+#[cfg(feature = "whois-tcp")]
+pub mod connector;
+#[cfg(feature = "whois-tcp")]
+pub mod connection_pool;
+#[cfg(feature = "whois-tcp")]
+pub mod response_reader;
+pub mod fixtures;
+mod telemetry;
+#[cfg(feature = "whois-tcp")]
+pub mod dns;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rate_limiter;
+pub mod epp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lookup;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod interceptor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
+pub mod diff;
+pub mod domain;
+pub mod export;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod typosquat;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod provider;
+#[cfg(feature = "whois-tcp")]
+pub mod health;
+#[cfg(feature = "whois-tcp")]
+mod referral;
+#[cfg(not(target_arch = "wasm32"))]
+mod singleflight;
+#[cfg(feature = "system-whois")]
+pub mod system_whois;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "threat-intel")]
+pub mod threat_intel;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "test-util")]
+pub mod mock;
+#[cfg(feature = "test-util")]
+pub mod test_server;
+
+// Port-43 whois needs TCP sockets, which wasm32 doesn't have
+#[cfg(all(target_arch = "wasm32", feature = "whois-tcp"))]
+compile_error!("the `whois-tcp` feature is not available on wasm32; build with --no-default-features");
 
 // OpenAPI support (optional)
 #[cfg(feature = "openapi")]
@@ -44,55 +103,165 @@ use utoipa::ToSchema;
 use serde_json::json;
 
 // Re-export main types for easy access
-pub use whois::{WhoisService, WhoisResult};
-pub use rdap::{RdapService, RdapResult};
-pub use cache::CacheService;
-pub use config::Config;
-pub use errors::WhoisError;
+pub use whois::{WhoisResult, IpWhoisResult, AsnWhoisResult, ReferralHop};
+#[cfg(feature = "whois-tcp")]
+pub use whois::WhoisService;
+pub use rdap::{
+    RdapService, RdapResult, RdapIpResult, RdapAsnResult, RdapCapabilities,
+    RdapDomain, RdapEntity, RdapEvent, RdapIpAddresses, RdapLink, RdapNameserver, RdapNotice, RdapPublicId, RdapSecureDns,
+};
+#[cfg(feature = "whois-tcp")]
+pub use buffer_pool::BufferPoolStats;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::{CacheService, CacheStats};
+pub use config::{AddressFamilyPreference, Config, ConfigBuilder, FixtureMode, ProtocolPreference};
+pub use errors::{ErrorBody, WhoisError};
+pub use parser::{ParsingReport, StreamingParser, WhoisParser};
+pub use epp::EppStatus;
+#[cfg(not(target_arch = "wasm32"))]
+pub use lookup::WhoisLookup;
+#[cfg(not(target_arch = "wasm32"))]
+pub use interceptor::LookupInterceptor;
+#[cfg(not(target_arch = "wasm32"))]
+pub use watch::{DomainChange, DomainChangeEvent, Watcher};
+pub use diff::{FieldDiff, WhoisDiff};
+#[cfg(not(target_arch = "wasm32"))]
+pub use typosquat::TyposquatResult;
+#[cfg(not(target_arch = "wasm32"))]
+pub use provider::Provider;
+#[cfg(feature = "whois-tcp")]
+pub use health::ServerHealth;
+#[cfg(feature = "history")]
+pub use history::{HistoryEntry, HistoryStore};
+#[cfg(feature = "threat-intel")]
+pub use threat_intel::{MispObject, StixBundle};
+#[cfg(feature = "test-util")]
+pub use mock::MockWhoisClient;
+
 
 
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::stream::{Stream, StreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{net::IpAddr, sync::Arc, time::Duration};
 
-use std::sync::Arc;
+/// `ParsedWhoisData::confidence` below which `needs_review` flags a record
+pub const REVIEW_CONFIDENCE: f32 = 0.6;
 
 /// Parsed whois data structure with calculated fields
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ParsedWhoisData {
     /// Domain registrar name
     #[cfg_attr(feature = "openapi", schema(example = "MarkMonitor Inc."))]
     pub registrar: Option<String>,
     
-    /// Domain creation date in ISO 8601 format
+    /// Registrar IANA ID (abuse reporting and registrar lookups)
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = "292"))]
+    pub registrar_iana_id: Option<String>,
+    
+    /// Registrar website
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = "http://www.markmonitor.com"))]
+    pub registrar_url: Option<String>,
+    
+    /// Registrar abuse contact email
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = "abusecomplaints@markmonitor.com"))]
+    pub registrar_abuse_email: Option<String>,
+    
+    /// Registrar abuse contact phone
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = "+1.2086851750"))]
+    pub registrar_abuse_phone: Option<String>,
+
+    /// Reseller the domain was registered through, when the registrar publishes one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reseller: Option<String>,
+
+    /// Registry domain ID (ROID), the registry's handle for the domain (RDAP `handle`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = "2138514_DOMAIN_COM-VRSN"))]
+    pub registry_domain_id: Option<String>,
+    
+    /// Domain creation date, RFC 3339 in UTC (None if the registry's date couldn't be parsed)
     #[cfg_attr(feature = "openapi", schema(example = "1997-09-15T04:00:00Z"))]
     pub creation_date: Option<String>,
     
-    /// Domain expiration date in ISO 8601 format
+    /// Domain expiration date, RFC 3339 in UTC
     #[cfg_attr(feature = "openapi", schema(example = "2028-09-14T04:00:00Z"))]
     pub expiration_date: Option<String>,
     
-    /// Last update date in ISO 8601 format
+    /// Last update date, RFC 3339 in UTC
     #[cfg_attr(feature = "openapi", schema(example = "2019-09-09T15:39:04Z"))]
     pub updated_date: Option<String>,
+
+    /// Creation date as the registry wrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = "1997-09-15T04:00:00Z"))]
+    pub creation_date_raw: Option<String>,
+
+    /// Expiration date as the registry wrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = "2028-09-13T07:00:00Z"))]
+    pub expiration_date_raw: Option<String>,
+
+    /// Last update date as the registry wrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = "2019-09-09T15:39:04Z"))]
+    pub updated_date_raw: Option<String>,
     
-    /// Domain name servers
-    #[cfg_attr(feature = "openapi", schema(example = json!(["NS1.GOOGLE.COM", "NS2.GOOGLE.COM"])))]
+    /// Domain name servers as lowercase FQDNs without trailing dot, deduplicated;
+    /// internationalized names in ASCII (punycode) form
+    #[cfg_attr(feature = "openapi", schema(example = json!(["ns1.google.com", "ns2.google.com"])))]
     pub name_servers: Vec<String>,
+
+    /// Glue addresses published with the name servers, keyed by name server
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(example = json!({"ns1.example.de": ["192.0.2.1"]})))]
+    pub name_server_ips: HashMap<String, Vec<String>>,
+
+    /// Unicode forms of `name_servers`, in the same order; empty unless one of them is an IDN
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub name_servers_unicode: Vec<String>,
     
     /// Domain status codes (useful for security analysis)
     #[cfg_attr(feature = "openapi", schema(example = json!(["clientDeleteProhibited", "clientTransferProhibited"])))]
     pub status: Vec<String>,
+
+    /// Typed EPP status codes parsed from `status`
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>, example = json!(["clientDeleteProhibited", "clientTransferProhibited"])))]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<String>"))]
+    pub status_codes: Vec<EppStatus>,
     
-    /// Registrant name
-    pub registrant_name: Option<String>,
+    /// DNSSEC delegation state (`DNSSEC:` line or RDAP `secureDNS`)
+    #[serde(default)]
+    pub dnssec: Option<DnssecStatus>,
     
-    /// Registrant email
-    pub registrant_email: Option<String>,
+    /// Registrant contact
+    #[serde(default)]
+    pub registrant: Option<Contact>,
     
-    /// Administrative contact email
-    pub admin_email: Option<String>,
+    /// Administrative contact
+    #[serde(default)]
+    pub admin: Option<Contact>,
     
-    /// Technical contact email
-    pub tech_email: Option<String>,
+    /// Technical contact
+    #[serde(default)]
+    pub tech: Option<Contact>,
+    
+    /// Billing contact
+    #[serde(default)]
+    pub billing: Option<Contact>,
+    
+    /// Whether contact data is hidden by a privacy/proxy service or GDPR redaction (None when no contacts are published)
+    #[serde(default)]
+    pub privacy_protected: Option<bool>,
     
     /// Days since domain creation (threat indicator - newly registered domains are suspicious)
     #[cfg_attr(feature = "openapi", schema(example = 10117))]
@@ -105,15 +274,403 @@ pub struct ParsedWhoisData {
     /// Days until expiration (domain monitoring - negative if expired)
     #[cfg_attr(feature = "openapi", schema(example = 1204))]
     pub expires_in: Option<i64>,
+
+    /// How complete and clean the parse was, 0.0 - 1.0: the weighted share of
+    /// key fields found, dates only counting when they parsed
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = 0.9))]
+    pub confidence: f32,
+    
+    /// Whois `key: value` lines not mapped to a typed field (e.g. "owner-c"), keyed as written
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_fields: HashMap<String, Vec<String>>,
+
+    /// Protocol each field came from ("rdap" or "whois"), set by `LookupStrategy::Merge`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub field_sources: HashMap<String, String>,
+}
+
+impl ParsedWhoisData {
+    /// Registrant name, falling back to the organization
+    pub fn registrant_name(&self) -> Option<&str> {
+        let registrant = self.registrant.as_ref()?;
+        registrant.name.as_deref().or(registrant.organization.as_deref())
+    }
+
+    /// Registrant email
+    pub fn registrant_email(&self) -> Option<&str> {
+        self.registrant.as_ref()?.email.as_deref()
+    }
+
+    /// Administrative contact email
+    pub fn admin_email(&self) -> Option<&str> {
+        self.admin.as_ref()?.email.as_deref()
+    }
+
+    /// Technical contact email
+    pub fn tech_email(&self) -> Option<&str> {
+        self.tech.as_ref()?.email.as_deref()
+    }
+
+    /// Whether the record looks incomplete enough to review or re-query at the registrar
+    pub fn needs_review(&self) -> bool {
+        self.confidence < REVIEW_CONFIDENCE
+    }
+
+    /// Score `confidence` from the fields present; call after the relative dates are set
+    pub(crate) fn set_confidence(&mut self) {
+        // A date only counts when it parsed, i.e. its day count could be computed
+        let found = [
+            (self.registrar.is_some(), 0.2),
+            (self.created_ago.is_some(), 0.2),
+            (self.expires_in.is_some(), 0.2),
+            (!self.name_servers.is_empty(), 0.15),
+            (!self.status.is_empty(), 0.1),
+            (self.registrant.is_some(), 0.1),
+            (self.updated_ago.is_some(), 0.05),
+        ];
+        let score: f32 = found.iter().filter(|(present, _)| *present).map(|(_, weight)| weight).sum();
+        self.confidence = (score * 100.0).round() / 100.0;
+    }
+
+    /// Rewrite the dates as RFC 3339 UTC, keeping what the source wrote in the
+    /// `*_raw` fields, and derive the day counts from them
+    ///
+    /// A date `parse` can't read is dropped from the typed field and only kept raw.
+    pub(crate) fn normalize_dates(&mut self, parse: impl Fn(&str) -> Option<DateTime<Utc>>) {
+        let now = Utc::now();
+        let dates = [
+            (&mut self.creation_date, &mut self.creation_date_raw, &mut self.created_ago, false),
+            (&mut self.expiration_date, &mut self.expiration_date_raw, &mut self.expires_in, true),
+            (&mut self.updated_date, &mut self.updated_date_raw, &mut self.updated_ago, false),
+        ];
+        for (date, raw, days, in_future) in dates {
+            let Some(original) = date.take() else {
+                continue;
+            };
+            let parsed = parse(&original);
+            // Normalizing again must not overwrite the source's text
+            raw.get_or_insert(original);
+            if let Some(parsed) = parsed {
+                *date = Some(parsed.to_rfc3339_opts(SecondsFormat::Secs, true));
+                *days = Some(if in_future { parsed - now } else { now - parsed }.num_days());
+            }
+        }
+    }
+
+    /// Normalize the name servers to lowercase ASCII FQDNs without trailing dot,
+    /// drop duplicates, rekey the glue addresses to match and fill
+    /// `name_servers_unicode` when any is an IDN
+    pub(crate) fn normalize_name_servers(&mut self) {
+        let normalize = |name: &str| {
+            let name = name.trim().trim_end_matches('.');
+            idna::domain_to_ascii(name).ok().filter(|ascii| !ascii.is_empty()).unwrap_or_else(|| name.to_lowercase())
+        };
+
+        let mut name_servers: Vec<String> = Vec::with_capacity(self.name_servers.len());
+        for name_server in &self.name_servers {
+            let name_server = normalize(name_server);
+            if !name_server.is_empty() && !name_servers.contains(&name_server) {
+                name_servers.push(name_server);
+            }
+        }
+        self.name_servers = name_servers;
+
+        let mut name_server_ips: HashMap<String, Vec<String>> = HashMap::new();
+        for (name_server, ips) in std::mem::take(&mut self.name_server_ips) {
+            let known = name_server_ips.entry(normalize(&name_server)).or_default();
+            for ip in ips {
+                if !known.contains(&ip) {
+                    known.push(ip);
+                }
+            }
+        }
+        self.name_server_ips = name_server_ips;
+
+        let unicode: Vec<Option<String>> = self.name_servers.iter().map(|name| domain::unicode_form(name)).collect();
+        self.name_servers_unicode = if unicode.iter().any(Option::is_some) {
+            unicode.into_iter().zip(&self.name_servers).map(|(form, ascii)| form.unwrap_or_else(|| ascii.clone())).collect()
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Combine RDAP and WHOIS data field by field, recording each field's source in `field_sources`
+    ///
+    /// RDAP wins for registrar data, dates, statuses and name servers; WHOIS wins for
+    /// contacts, which RDAP responses frequently redact. Either side fills the other's gaps.
+    pub fn merge(rdap: ParsedWhoisData, whois: ParsedWhoisData) -> ParsedWhoisData {
+        const RDAP: &str = "rdap";
+        const WHOIS: &str = "whois";
+        let mut sources = HashMap::new();
+        let non_empty = |values: Vec<String>| (!values.is_empty()).then_some(values);
+
+        let registrar = pick_field(&mut sources, "registrar", (RDAP, rdap.registrar), (WHOIS, whois.registrar));
+        let registrar_iana_id = pick_field(&mut sources, "registrar_iana_id", (RDAP, rdap.registrar_iana_id), (WHOIS, whois.registrar_iana_id));
+        let registrar_url = pick_field(&mut sources, "registrar_url", (RDAP, rdap.registrar_url), (WHOIS, whois.registrar_url));
+        let registrar_abuse_email = pick_field(&mut sources, "registrar_abuse_email", (RDAP, rdap.registrar_abuse_email), (WHOIS, whois.registrar_abuse_email));
+        let registrar_abuse_phone = pick_field(&mut sources, "registrar_abuse_phone", (RDAP, rdap.registrar_abuse_phone), (WHOIS, whois.registrar_abuse_phone));
+        let reseller = pick_field(&mut sources, "reseller", (RDAP, rdap.reseller), (WHOIS, whois.reseller));
+        let registry_domain_id = pick_field(&mut sources, "registry_domain_id", (RDAP, rdap.registry_domain_id), (WHOIS, whois.registry_domain_id));
+
+        // Raw text and relative day counts travel with the date they belong to
+        let creation = pick_field(
+            &mut sources,
+            "creation_date",
+            (RDAP, rdap.creation_date.map(|date| (date, rdap.creation_date_raw, rdap.created_ago))),
+            (WHOIS, whois.creation_date.map(|date| (date, whois.creation_date_raw, whois.created_ago))),
+        );
+        let expiration = pick_field(
+            &mut sources,
+            "expiration_date",
+            (RDAP, rdap.expiration_date.map(|date| (date, rdap.expiration_date_raw, rdap.expires_in))),
+            (WHOIS, whois.expiration_date.map(|date| (date, whois.expiration_date_raw, whois.expires_in))),
+        );
+        let updated = pick_field(
+            &mut sources,
+            "updated_date",
+            (RDAP, rdap.updated_date.map(|date| (date, rdap.updated_date_raw, rdap.updated_ago))),
+            (WHOIS, whois.updated_date.map(|date| (date, whois.updated_date_raw, whois.updated_ago))),
+        );
+
+        // Glue addresses travel with the name servers they belong to
+        let name_servers = pick_field(
+            &mut sources,
+            "name_servers",
+            (RDAP, non_empty(rdap.name_servers).map(|name_servers| (name_servers, rdap.name_server_ips))),
+            (WHOIS, non_empty(whois.name_servers).map(|name_servers| (name_servers, whois.name_server_ips))),
+        );
+        let status = pick_field(
+            &mut sources,
+            "status",
+            (RDAP, non_empty(rdap.status).map(|status| (status, rdap.status_codes))),
+            (WHOIS, non_empty(whois.status).map(|status| (status, whois.status_codes))),
+        );
+        let dnssec = pick_field(&mut sources, "dnssec", (RDAP, rdap.dnssec), (WHOIS, whois.dnssec));
+
+        let registrant = pick_field(&mut sources, "registrant", (WHOIS, whois.registrant), (RDAP, rdap.registrant));
+        let admin = pick_field(&mut sources, "admin", (WHOIS, whois.admin), (RDAP, rdap.admin));
+        let tech = pick_field(&mut sources, "tech", (WHOIS, whois.tech), (RDAP, rdap.tech));
+        let billing = pick_field(&mut sources, "billing", (WHOIS, whois.billing), (RDAP, rdap.billing));
+        // Privacy follows whichever side supplied the registrant
+        let privacy_protected = match sources.get("registrant").map(String::as_str) {
+            Some(RDAP) => pick_field(&mut sources, "privacy_protected", (RDAP, rdap.privacy_protected), (WHOIS, whois.privacy_protected)),
+            _ => pick_field(&mut sources, "privacy_protected", (WHOIS, whois.privacy_protected), (RDAP, rdap.privacy_protected)),
+        };
+
+        let mut extra_fields = whois.extra_fields;
+        for (key, values) in rdap.extra_fields {
+            extra_fields.entry(key).or_insert(values);
+        }
+
+        let (creation_date, creation_date_raw, created_ago) = creation.map_or((None, None, None), |(date, raw, ago)| (Some(date), raw, ago));
+        let (expiration_date, expiration_date_raw, expires_in) = expiration.map_or((None, None, None), |(date, raw, days)| (Some(date), raw, days));
+        let (updated_date, updated_date_raw, updated_ago) = updated.map_or((None, None, None), |(date, raw, ago)| (Some(date), raw, ago));
+        let (status, status_codes) = status.unwrap_or_default();
+        let (name_servers, name_server_ips) = name_servers.unwrap_or_default();
+
+        let mut merged = ParsedWhoisData {
+            registrar,
+            registrar_iana_id,
+            registrar_url,
+            registrar_abuse_email,
+            registrar_abuse_phone,
+            reseller,
+            registry_domain_id,
+            creation_date,
+            expiration_date,
+            updated_date,
+            creation_date_raw,
+            expiration_date_raw,
+            updated_date_raw,
+            name_servers,
+            name_server_ips,
+            name_servers_unicode: Vec::new(),
+            status,
+            status_codes,
+            dnssec,
+            registrant,
+            admin,
+            tech,
+            billing,
+            privacy_protected,
+            created_ago,
+            updated_ago,
+            expires_in,
+            confidence: 0.0,
+            extra_fields,
+            field_sources: sources,
+        };
+        merged.normalize_name_servers();
+        merged.set_confidence();
+        merged
+    }
+}
+
+/// First present value of a field, recording which protocol supplied it
+fn pick_field<T>(
+    sources: &mut HashMap<String, String>,
+    field: &str,
+    primary: (&str, Option<T>),
+    secondary: (&str, Option<T>),
+) -> Option<T> {
+    let (source, value) = match primary {
+        (source, Some(value)) => (source, value),
+        _ => match secondary {
+            (source, Some(value)) => (source, value),
+            _ => return None,
+        },
+    };
+    sources.insert(field.to_string(), source.to_string());
+    Some(value)
+}
+
+/// Contact details for a domain role (registrant, admin, tech, billing)
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Contact {
+    pub name: Option<String>,
+    pub organization: Option<String>,
+    /// Street address lines
+    pub street: Vec<String>,
+    pub city: Option<String>,
+    /// ISO 3166 country code (or name, as published)
+    pub country: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+/// DNSSEC state of a domain's delegation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DnssecStatus {
+    /// DS records are published in the parent zone
+    Signed,
+    /// No DS records in the parent zone
+    Unsigned,
+}
+
+impl DnssecStatus {
+    /// Interpret registry spellings such as "signedDelegation", "yes" or "unsigned"
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value.starts_with("unsigned") || value == "no" || value == "inactive" || value == "false" {
+            Some(Self::Unsigned)
+        } else if value.starts_with("signed") || value == "yes" || value == "active" || value == "true" {
+            Some(Self::Signed)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parsed IP whois data (RIR netblock allocation)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ParsedIpWhoisData {
+    /// Address range as published by the RIR
+    #[cfg_attr(feature = "openapi", schema(example = "8.8.8.0 - 8.8.8.255"))]
+    pub netblock: Option<String>,
+
+    /// CIDR prefixes covering the netblock
+    #[cfg_attr(feature = "openapi", schema(example = json!(["8.8.8.0/24"])))]
+    pub cidr: Vec<String>,
+
+    /// Network name / handle
+    #[cfg_attr(feature = "openapi", schema(example = "GOGL"))]
+    pub net_name: Option<String>,
+
+    /// Organization holding the allocation
+    #[cfg_attr(feature = "openapi", schema(example = "Google LLC"))]
+    pub organization: Option<String>,
+
+    /// ISO 3166 country code of the holder
+    #[cfg_attr(feature = "openapi", schema(example = "US"))]
+    pub country: Option<String>,
+
+    /// Abuse contact email addresses (incident-response reporting)
+    #[cfg_attr(feature = "openapi", schema(example = json!(["network-abuse@google.com"])))]
+    pub abuse_emails: Vec<String>,
+
+    /// Abuse contact phone numbers
+    pub abuse_phones: Vec<String>,
+
+    /// Allocation date as published by the RIR
+    pub registration_date: Option<String>,
+
+    /// Last update date as published by the RIR
+    pub updated_date: Option<String>,
+}
+
+/// Parsed ASN whois data (autonomous system registration)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ParsedAsnWhoisData {
+    /// Autonomous system number
+    #[cfg_attr(feature = "openapi", schema(example = 15169))]
+    pub asn: u32,
+
+    /// Registry handle for the AS object
+    #[cfg_attr(feature = "openapi", schema(example = "AS15169"))]
+    pub handle: Option<String>,
+
+    /// AS name
+    #[cfg_attr(feature = "openapi", schema(example = "GOOGLE"))]
+    pub as_name: Option<String>,
+
+    /// First AS number of the registered block (RDAP `startAutnum`)
+    pub start_autnum: Option<u32>,
+
+    /// Last AS number of the registered block (RDAP `endAutnum`)
+    pub end_autnum: Option<u32>,
+
+    /// Organization holding the AS number
+    #[cfg_attr(feature = "openapi", schema(example = "Google LLC"))]
+    pub holder: Option<String>,
+
+    /// ISO 3166 country code of the holder
+    #[cfg_attr(feature = "openapi", schema(example = "US"))]
+    pub country: Option<String>,
+
+    /// Allocation date as published by the RIR
+    #[cfg_attr(feature = "openapi", schema(example = "2000-03-30"))]
+    pub allocation_date: Option<String>,
+
+    /// Contact email addresses (admin, tech, NOC)
+    pub contact_emails: Vec<String>,
+
+    /// Abuse contact email addresses
+    pub abuse_emails: Vec<String>,
 }
 
 /// High-level whois client with optional caching
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Clone)]
 pub struct WhoisClient {
+    #[cfg(feature = "whois-tcp")]
     service: Arc<WhoisService>,
+    rdap: Arc<RdapService>,
     cache: Option<Arc<CacheService>>,
+    protocol: ProtocolPreference,
+    concurrency: usize,
+    collapse_subdomains: bool,
+    hedge_percentile: f64,
+    interceptors: Vec<Arc<dyn LookupInterceptor>>,
+    providers: Vec<Arc<dyn Provider>>,
+    provider_priority: Vec<String>,
+    in_flight: Arc<singleflight::InFlight<InFlightKey, Result<WhoisResult, Arc<WhoisError>>>>,
+    #[cfg(feature = "system-whois")]
+    system_whois: Arc<system_whois::SystemWhois>,
+    #[cfg(feature = "history")]
+    history: Arc<dyn history::HistoryStore>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl WhoisClient {
     // === Constructor Methods ===
     
@@ -125,18 +682,86 @@ impl WhoisClient {
 
     /// Create a new whois client with custom configuration
     pub async fn new_with_config(config: Arc<Config>) -> Result<Self, WhoisError> {
-        let service = Arc::new(WhoisService::new(config.clone()).await?);
-        let cache = Self::initialize_cache(config)?;
-        
-        Ok(Self { service, cache })
+        let cache = Self::initialize_cache(config.clone())?;
+        Self::build(config, cache).await
     }
 
     /// Create a new whois client without caching
     pub async fn new_without_cache() -> Result<Self, WhoisError> {
         let config = Self::load_default_config()?;
-        let service = Arc::new(WhoisService::new(config).await?);
-        
-        Ok(Self { service, cache: None })
+        Self::build(config, None).await
+    }
+
+    /// Create a new whois client with custom configuration and no caching
+    pub async fn new_with_config_without_cache(config: Arc<Config>) -> Result<Self, WhoisError> {
+        Self::build(config, None).await
+    }
+
+    /// Fluent constructor taking a `Config::builder()`
+    pub fn builder() -> WhoisClientBuilder {
+        WhoisClientBuilder::default()
+    }
+
+    /// Initialize services - shared by all constructors
+    async fn build(config: Arc<Config>, cache: Option<Arc<CacheService>>) -> Result<Self, WhoisError> {
+        #[cfg(feature = "whois-tcp")]
+        let service = Arc::new(WhoisService::new(config.clone()).await?);
+        let rdap = Arc::new(RdapService::new(config.clone()).await?);
+        // Keep server discovery current in long-running processes; the tasks stop when the client is dropped
+        #[cfg(feature = "whois-tcp")]
+        {
+            service.spawn_tld_refresh();
+            service.spawn_mapping_flush();
+        }
+        rdap.spawn_bootstrap_refresh();
+
+        let mut providers = provider::configured(&config)?;
+        provider::order(&mut providers, &config.provider_priority);
+
+        Ok(Self {
+            #[cfg(feature = "whois-tcp")]
+            service,
+            rdap,
+            cache,
+            protocol: config.protocol_preference,
+            concurrency: config.concurrent_whois_queries.max(1),
+            collapse_subdomains: config.collapse_subdomains,
+            hedge_percentile: config.hedge_percentile,
+            interceptors: Vec::new(),
+            providers,
+            provider_priority: config.provider_priority.clone(),
+            in_flight: singleflight::InFlight::new(),
+            #[cfg(feature = "system-whois")]
+            system_whois: Arc::new(system_whois::SystemWhois::new(config.clone())),
+            #[cfg(feature = "history")]
+            history: history::open(&config).map_err(WhoisError::Internal)?,
+        })
+    }
+
+    /// Add a hook around domain lookups (see `LookupInterceptor`)
+    ///
+    /// `before_lookup` hooks run in registration order and `after_lookup` hooks
+    /// in reverse, so the first interceptor wraps all the others.
+    pub fn with_interceptor(mut self, interceptor: impl LookupInterceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Add a commercial whois provider as a fallback tier (see `Provider`)
+    ///
+    /// Providers are tried in `provider_priority` order, unlisted ones in the
+    /// order they were added.
+    pub fn with_provider(mut self, provider: impl Provider + 'static) -> Self {
+        self.providers.push(Arc::new(provider));
+        provider::order(&mut self.providers, &self.provider_priority);
+        self
+    }
+
+    /// Keep lookup history in `store` instead of the one configured by `history_path`
+    #[cfg(feature = "history")]
+    pub fn with_history_store(mut self, store: Arc<dyn history::HistoryStore>) -> Self {
+        self.history = store;
+        self
     }
 
     /// Initialize cache - follows SRP
@@ -154,47 +779,542 @@ impl WhoisClient {
     /// 
     /// This method will use cache if available, unless `fresh` is true.
     pub async fn lookup(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
-        self.lookup_with_options(domain, false).await
+        self.lookup_opts(domain, &LookupOptions::default()).await
     }
 
     /// Perform a fresh whois lookup, bypassing cache
     pub async fn lookup_fresh(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
-        self.lookup_with_options(domain, true).await
+        self.lookup_opts(domain, &LookupOptions::new().fresh(true)).await
     }
 
     /// Perform a whois lookup with caching options
+    #[deprecated(note = "use `lookup_opts(domain, &LookupOptions::new().fresh(fresh))`")]
     pub async fn lookup_with_options(&self, domain: &str, fresh: bool) -> Result<WhoisResponse, WhoisError> {
+        self.lookup_opts(domain, &LookupOptions::new().fresh(fresh)).await
+    }
+
+    /// Perform a whois lookup against a specific whois server, bypassing TLD discovery
+    ///
+    /// Results from a forced server are neither read from nor written to the cache.
+    pub async fn lookup_with_server(&self, domain: &str, server: &str) -> Result<WhoisResponse, WhoisError> {
+        self.lookup_opts(domain, &LookupOptions::new().server(server)).await
+    }
+
+    /// Perform a whois lookup with per-call options
+    ///
+    /// Registered interceptors run around the lookup.
+    pub async fn lookup_opts(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResponse, WhoisError> {
+        options.validate()?;
+        if self.interceptors.is_empty() {
+            return self.boxed_lookup(domain, options).await;
+        }
+
+        let mut options = options.clone();
+        for interceptor in &self.interceptors {
+            interceptor.before_lookup(domain, &mut options).await?;
+        }
+        options.validate()?;
+        let mut result = self.boxed_lookup(domain, &options).await;
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_lookup(domain, &mut result).await;
+        }
+        result
+    }
+
+    // Type-erased so callers spawning lookups needn't prove `Send` through the whole
+    // pipeline's future type, which exceeds rustc's default recursion limit
+    fn boxed_lookup<'a>(
+        &'a self,
+        domain: &'a str,
+        options: &'a LookupOptions,
+    ) -> futures::future::BoxFuture<'a, Result<WhoisResponse, WhoisError>> {
+        Box::pin(self.run_lookup(domain, options))
+    }
+
+    #[tracing::instrument(name = "lookup", skip_all, fields(domain = %domain, fresh = options.fresh, strategy = ?options.strategy))]
+    async fn run_lookup(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResponse, WhoisError> {
         let start_time = std::time::Instant::now();
-        let normalized_domain = Self::validate_and_normalize_domain(domain)?;
+        let normalized_domain = self.lookup_target(domain, options)?;
+
+        // A forced server or protocol, or a registry-only answer, may disagree with the default path - keep it out of the cache
+        let use_cache = options.server.is_none() && options.protocol.is_none() && options.follow_referrals;
 
         // Check cache first (if available and not requesting fresh)
-        if !fresh {
+        if use_cache && !options.fresh {
             if let Some(cached_result) = self.check_cache(&normalized_domain).await {
-                return Ok(cached_result);
+                return Ok(options.shape(cached_result));
             }
         }
 
-        // Perform fresh lookup
-        let result = self.service.lookup(&normalized_domain).await?;
+        // Perform fresh lookup, falling back to stale cached data if every protocol fails
+        let result = match self.query_protocols_within(&normalized_domain, options).await {
+            Ok(result) => result,
+            Err(e) => {
+                // Stale data covers upstream failures, not a definite "not registered"
+                if use_cache && !matches!(e, WhoisError::DomainNotFound(_)) {
+                    if let Some(stale) = self.check_stale_cache(&normalized_domain).await {
+                        tracing::warn!("Serving stale cache entry for {} after lookup failure: {}", normalized_domain, e);
+                        return Ok(options.shape(stale));
+                    }
+                }
+                return Err(e);
+            }
+        };
         let query_time = start_time.elapsed().as_millis() as u64;
         
+        let domain_unicode = Self::unicode_domain(&normalized_domain);
         let response = WhoisResponse {
             domain: normalized_domain.clone(),
+            domain_ascii: domain_unicode.is_some().then(|| normalized_domain.clone()),
+            domain_unicode,
             whois_server: result.server,
             raw_data: result.raw_data,
             parsed_data: result.parsed_data,
             cached: false,
+            degraded: false,
+            data_age_seconds: None,
             query_time_ms: query_time,
             parsing_analysis: None, // No debug info in library mode
+            parsing_report: None,
+            referral_chain: result.referral_chain,
         };
 
         // Cache the result if cache is available
-        self.cache_result(&normalized_domain, &response).await;
+        if use_cache {
+            self.cache_result(&normalized_domain, &response).await;
+        }
+        #[cfg(feature = "history")]
+        self.record_history(&response).await;
+
+        Ok(options.shape(response))
+    }
+
+    /// Look up a stream of domains, yielding `(domain, result)` pairs as they complete
+    ///
+    /// At most `concurrent_whois_queries` lookups are in flight; the input stream is
+    /// only polled for more domains as slots free up, so arbitrarily large inputs
+    /// are processed in constant memory. Results arrive in completion order.
+    pub fn lookup_stream<S>(&self, domains: S) -> impl Stream<Item = (String, Result<WhoisResponse, WhoisError>)>
+    where
+        S: Stream,
+        S::Item: Into<String>,
+    {
+        let client = self.clone();
+        domains
+            .map(move |domain| {
+                let client = client.clone();
+                let domain = domain.into();
+                async move {
+                    let result = client.lookup(&domain).await;
+                    (domain, result)
+                }
+            })
+            .buffer_unordered(self.concurrency)
+    }
+
+    /// Run the protocol chain for a domain without touching the cache
+    ///
+    /// Returns the underlying result including parsing analysis; `server` is
+    /// prefixed with the tier that answered ("RDAP: ...", "WHOIS: ...", "COMMAND: ..."
+    /// or "PROVIDER: ...").
+    pub async fn lookup_uncached(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        let normalized_domain = self.lookup_target(domain, options)?;
+        self.query_protocols_within(&normalized_domain, options).await
+    }
+
+    /// `query_protocols` bounded by the options' deadline, if any
+    ///
+    /// Identical concurrent queries (same domain, server, protocol and strategy)
+    /// are coalesced: only the first reaches upstream and the rest await its
+    /// result. Everything below is cancellation-safe (semaphore permits, pooled
+    /// buffers and connections are released on drop), so the deadline simply
+    /// stops waiting - as does a caller racing the lookup in `tokio::select!` -
+    /// and the query itself is dropped once no caller is waiting on it.
+    async fn query_protocols_within(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        let query = self.coalesced_query(domain, options);
+        let result = match options.effective_deadline() {
+            Some(deadline) => tokio::time::timeout_at(deadline, query).await?,
+            None => query.await,
+        };
+        result.map_err(|e| Arc::try_unwrap(e).unwrap_or_else(|shared| shared.duplicate()))
+    }
+
+    fn coalesced_query(&self, domain: &str, options: &LookupOptions) -> singleflight::Call<Result<WhoisResult, Arc<WhoisError>>> {
+        let key = InFlightKey {
+            domain: domain.to_string(),
+            server: options.server.clone(),
+            protocol: options.protocol,
+            strategy: options.strategy,
+            follow_referrals: options.follow_referrals,
+        };
+        let client = self.clone();
+        let domain = domain.to_string();
+        let options = options.clone();
+        self.in_flight.run(key, move || async move {
+            client.query_protocols(&domain, &options).await.map_err(Arc::new)
+        })
+    }
+
+    /// Query RDAP and/or WHOIS according to the protocol preference
+    async fn query_protocols(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        // A forced server is a port-43 whois server, so RDAP doesn't apply
+        if options.server.is_some() {
+            return self.query_whois(domain, options).await;
+        }
+
+        let preference = options.protocol.unwrap_or(self.protocol);
+        let result = match preference {
+            ProtocolPreference::RdapOnly => self.query_rdap(domain).await,
+            ProtocolPreference::WhoisOnly => self.query_whois(domain, options).await,
+            _ if options.strategy == LookupStrategy::Race => self.race_protocols(domain, options).await,
+            _ if options.strategy == LookupStrategy::Merge => self.merge_protocols(domain, options).await,
+            ProtocolPreference::RdapFirst => match self.query_rdap(domain).await {
+                // The registry answered authoritatively, another protocol won't know better
+                Err(e @ WhoisError::DomainNotFound(_)) => Err(e),
+                Ok(result) => Ok(result),
+                // Nothing to fall back to without port-43 support
+                Err(e) if !cfg!(feature = "whois-tcp") => Err(e),
+                Err(e) => {
+                    tracing::info!("RDAP lookup failed for {}: {} - falling back to WHOIS", domain, e);
+                    self.query_whois(domain, options).await
+                }
+            },
+            ProtocolPreference::WhoisFirst => match self.hedge_delay(domain).await {
+                Some(delay) => self.hedge_protocols(domain, options, delay).await,
+                None => match self.query_whois(domain, options).await {
+                    Err(e @ WhoisError::DomainNotFound(_)) => Err(e),
+                    Ok(result) => Ok(result),
+                    Err(e) => {
+                        tracing::info!("WHOIS lookup failed for {}: {} - falling back to RDAP", domain, e);
+                        self.query_rdap(domain).await
+                    }
+                },
+            },
+        };
+
+        // Next for fallback chains: the system whois command
+        #[cfg(feature = "system-whois")]
+        let result = match (result, preference) {
+            (Err(e), ProtocolPreference::RdapFirst | ProtocolPreference::WhoisFirst)
+                if !matches!(e, WhoisError::DomainNotFound(_)) =>
+            {
+                let command = tracing::debug_span!("query_command", tier = "command");
+                match tracing::Instrument::instrument(self.system_whois.lookup(domain), command).await {
+                    Ok(result) => Ok(Self::tag_protocol("COMMAND", result)),
+                    Err(command_error) => {
+                        tracing::debug!("System whois fallback failed for {}: {}", domain, command_error);
+                        Err(e)
+                    }
+                }
+            }
+            (result, _) => result,
+        };
+
+        // Then commercial providers, in priority order
+        match (result, preference) {
+            (Err(e), ProtocolPreference::RdapFirst | ProtocolPreference::WhoisFirst)
+                if !matches!(e, WhoisError::DomainNotFound(_)) && !self.providers.is_empty() =>
+            {
+                self.query_providers(domain, e).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Try each provider in turn, returning `error` if none answers
+    #[tracing::instrument(level = "debug", skip_all, fields(tier = "provider"))]
+    async fn query_providers(&self, domain: &str, error: WhoisError) -> Result<WhoisResult, WhoisError> {
+        for provider in &self.providers {
+            match provider.lookup(domain).await {
+                Ok(result) => return Ok(Self::tag_protocol("PROVIDER", result)),
+                Err(e @ WhoisError::DomainNotFound(_)) => return Err(e),
+                Err(e) => tracing::info!("Provider {} failed for {}: {}", provider.name(), domain, e),
+            }
+        }
+        Err(error)
+    }
+
+    /// Query RDAP and WHOIS in parallel, returning the first success
+    ///
+    /// Dropping the slower future cancels its in-flight request. A "not found"
+    /// answer is authoritative and ends the race like a success.
+    async fn race_protocols(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        let rdap = self.query_rdap(domain);
+        let whois = self.query_whois(domain, options);
+        Self::first_success(domain, ("RDAP", rdap), ("WHOIS", whois)).await
+    }
+
+    /// How long to wait on WHOIS before hedging with RDAP, when hedging is on and the server's latency is known
+    #[cfg_attr(not(feature = "whois-tcp"), allow(unused_variables))]
+    async fn hedge_delay(&self, domain: &str) -> Option<Duration> {
+        if self.hedge_percentile <= 0.0 {
+            return None;
+        }
+        #[cfg(feature = "whois-tcp")]
+        return self.service.expected_latency(domain, self.hedge_percentile).await;
+        #[cfg(not(feature = "whois-tcp"))]
+        None
+    }
+
+    /// Query WHOIS, adding a parallel RDAP query if it hasn't answered within `delay`
+    ///
+    /// Whichever succeeds first wins and the other is cancelled; a WHOIS
+    /// failure before the delay falls back to RDAP as usual.
+    async fn hedge_protocols(&self, domain: &str, options: &LookupOptions, delay: Duration) -> Result<WhoisResult, WhoisError> {
+        let mut whois = std::pin::pin!(self.query_whois(domain, options));
+        match tokio::time::timeout(delay, whois.as_mut()).await {
+            Ok(Err(e @ WhoisError::DomainNotFound(_))) => Err(e),
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(e)) => {
+                tracing::info!("WHOIS lookup failed for {}: {} - falling back to RDAP", domain, e);
+                self.query_rdap(domain).await
+            }
+            Err(_) => {
+                tracing::debug!("WHOIS for {} slower than {:?} - hedging with RDAP", domain, delay);
+                Self::first_success(domain, ("WHOIS", whois), ("RDAP", self.query_rdap(domain))).await
+            }
+        }
+    }
+
+    /// Run two protocol queries concurrently: the first success or "not found" wins, else the later result
+    async fn first_success<A, B>(domain: &str, left: (&str, A), right: (&str, B)) -> Result<WhoisResult, WhoisError>
+    where
+        A: std::future::Future<Output = Result<WhoisResult, WhoisError>>,
+        B: std::future::Future<Output = Result<WhoisResult, WhoisError>>,
+    {
+        use futures::future::{select, Either};
+
+        let (left_name, left) = (left.0, std::pin::pin!(left.1));
+        let (right_name, right) = (right.0, std::pin::pin!(right.1));
+
+        let (error, remaining) = match select(left, right).await {
+            Either::Left((Ok(result), _)) | Either::Right((Ok(result), _)) => return Ok(result),
+            Either::Left((Err(e @ WhoisError::DomainNotFound(_)), _))
+            | Either::Right((Err(e @ WhoisError::DomainNotFound(_)), _)) => return Err(e),
+            Either::Left((Err(e), right)) => ((left_name, e), Either::Right(right)),
+            Either::Right((Err(e), left)) => ((right_name, e), Either::Left(left)),
+        };
+
+        tracing::info!("{} lookup failed for {}: {} - waiting on the other protocol", error.0, domain, error.1);
+        match remaining {
+            Either::Left(left) => left.await,
+            Either::Right(right) => right.await,
+        }
+    }
+
+    /// Query RDAP and WHOIS in parallel and merge their parsed data
+    ///
+    /// If only one protocol answers its result is returned as is.
+    async fn merge_protocols(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        let (rdap, whois) = tokio::join!(self.query_rdap(domain), self.query_whois(domain, options));
+
+        match (rdap, whois) {
+            (Ok(rdap), Ok(whois)) => {
+                let parsed_data = match (rdap.parsed_data, whois.parsed_data) {
+                    (Some(rdap_data), Some(whois_data)) => Some(ParsedWhoisData::merge(rdap_data, whois_data)),
+                    (rdap_data, whois_data) => rdap_data.or(whois_data),
+                };
+                // Found/missing fields describe the merged record, lines and notes come from both sources
+                let mut parsing_report = parsed_data.as_ref().map(ParsingReport::for_parsed).unwrap_or_default();
+                parsing_report.absorb(rdap.parsing_report);
+                parsing_report.absorb(whois.parsing_report);
+                Ok(WhoisResult {
+                    server: format!("{} + {}", rdap.server, whois.server),
+                    raw_data: format!("{}\n\n{}", rdap.raw_data, whois.raw_data),
+                    parsed_data,
+                    parsing_report,
+                    referral_chain: whois.referral_chain,
+                })
+            }
+            (Ok(result), Err(e)) | (Err(e), Ok(result)) => {
+                tracing::info!("Merge lookup for {} got a single protocol: {}", domain, e);
+                Ok(result)
+            }
+            (Err(e @ WhoisError::DomainNotFound(_)), Err(_)) | (Err(_), Err(e)) => Err(e),
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(tier = "rdap"))]
+    async fn query_rdap(&self, domain: &str) -> Result<WhoisResult, WhoisError> {
+        let result = self.rdap.lookup(domain).await?;
+        Ok(Self::tag_protocol("RDAP", WhoisResult {
+            server: result.server,
+            raw_data: result.raw_data,
+            parsed_data: result.parsed_data,
+            parsing_report: result.parsing_report,
+            referral_chain: Vec::new(),
+        }))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(tier = "whois"))]
+    #[cfg(feature = "whois-tcp")]
+    async fn query_whois(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        let result = self.service.lookup_opts(domain, options).await?;
+        Ok(Self::tag_protocol("WHOIS", result))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(tier = "whois"))]
+    #[cfg(not(feature = "whois-tcp"))]
+    async fn query_whois(&self, domain: &str, _options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
+        Err(WhoisError::Internal(format!(
+            "Port-43 whois for {} requires the `whois-tcp` feature",
+            domain
+        )))
+    }
+
+    /// Prefix the server with the protocol that answered
+    fn tag_protocol(protocol: &str, mut result: WhoisResult) -> WhoisResult {
+        result.server = format!("{}: {}", protocol, result.server);
+        result
+    }
+
+    /// Check whether a domain is registered
+    ///
+    /// RDAP is asked first (404 means available); if it can't give a definite
+    /// answer the whois response is classified using registry "not found" patterns.
+    pub async fn is_available(&self, domain: &str) -> Result<DomainAvailability, WhoisError> {
+        let normalized_domain = self.lookup_target(domain, &LookupOptions::default())?;
+
+        match self.rdap.check_availability(&normalized_domain).await {
+            Ok(DomainAvailability::Unknown) => {}
+            Ok(availability) => return Ok(availability),
+            Err(e) => tracing::debug!("RDAP availability check failed for {}: {}", normalized_domain, e),
+        }
+
+        // Classify the port-43 reply directly; a cached or RDAP response wouldn't match the patterns
+        #[cfg(feature = "whois-tcp")]
+        return match self.service.lookup(&normalized_domain).await {
+            Ok(result) => Ok(parser::WhoisParser::new().detect_availability(&result.raw_data)),
+            Err(WhoisError::DomainNotFound(_)) => Ok(DomainAvailability::Available),
+            Err(e) => Err(e),
+        };
+        #[cfg(not(feature = "whois-tcp"))]
+        Ok(DomainAvailability::Unknown)
+    }
+
+    /// Re-fetch the IANA RDAP bootstrap registries now instead of waiting for the TTL
+    pub async fn refresh_bootstrap(&self) -> Result<(), WhoisError> {
+        self.rdap.refresh_bootstrap().await
+    }
+
+    /// List every TLD with a known whois or RDAP server, sorted by TLD
+    ///
+    /// Merges hardcoded, build-time generated, configured and discovered mappings.
+    /// TLDs not listed may still work through dynamic discovery.
+    pub async fn supported_tlds(&self) -> Vec<SupportedTld> {
+        #[cfg(feature = "whois-tcp")]
+        let whois = self.service.known_servers().await;
+        #[cfg(not(feature = "whois-tcp"))]
+        let whois: HashMap<String, String> = HashMap::new();
+        let rdap = self.rdap.known_servers().await;
 
-        Ok(response)
+        let tlds: std::collections::BTreeSet<&String> = whois.keys().chain(rdap.keys()).collect();
+        tlds.into_iter()
+            .map(|tld| SupportedTld {
+                tld: tld.clone(),
+                whois_server: whois.get(tld).cloned(),
+                rdap_server: rdap.get(tld).cloned(),
+            })
+            .collect()
+    }
+
+    /// Describe a TLD's registry: servers, operator, registry model, limits and quirks
+    ///
+    /// Assembled from the build-time IANA snapshot and hardcoded tables; with
+    /// `whois-tcp`, gaps are filled from the IANA root zone database over port
+    /// 43. No domain query is made, so this is safe to call ahead of bulk runs.
+    /// Accepts "io", ".io", multi-label suffixes ("co.uk") and IDN TLDs.
+    pub async fn tld_info(&self, tld: &str) -> Result<TldInfo, WhoisError> {
+        let tld = tld.trim().trim_start_matches('.').trim_end_matches('.');
+        let tld = idna::domain_to_ascii(tld)
+            .ok()
+            .filter(|tld| !tld.is_empty() && !tld.split('.').any(str::is_empty))
+            .ok_or_else(|| WhoisError::InvalidDomain(format!("Invalid TLD: {}", tld)))?;
+        let top_level = tld.rsplit('.').next().unwrap_or(&tld).to_string();
+
+        let mut rdap = self.rdap.known_servers().await;
+        #[cfg_attr(not(feature = "whois-tcp"), allow(unused_mut))]
+        let mut info = TldInfo {
+            rdap_server: rdap.remove(&tld).or_else(|| rdap.remove(&top_level)),
+            registry_operator: tld_mappings::GENERATED_TLD_OPERATORS.get(top_level.as_str()).map(|operator| operator.to_string()),
+            thin_registry: tld_mappings::THIN_REGISTRY_TLDS.contains(&top_level.as_str()),
+            quirks: tld_mappings::TLD_QUIRKS
+                .iter()
+                .filter(|(quirk_tld, _)| *quirk_tld == top_level)
+                .map(|(_, quirk)| quirk.to_string())
+                .collect(),
+            whois_server: None,
+            whois_qps: None,
+            whois_timeout_seconds: None,
+            tld,
+        };
+        #[cfg(feature = "whois-tcp")]
+        self.service.fill_tld_info(&mut info).await;
+
+        if info.whois_server.is_none() && info.rdap_server.is_none() && info.registry_operator.is_none() {
+            return Err(WhoisError::UnsupportedTld(info.tld));
+        }
+        Ok(info)
+    }
+
+    /// Write discovered TLD mappings to `tld_mappings_path` now (also flushed every minute)
+    #[cfg(feature = "whois-tcp")]
+    pub async fn save_tld_mappings(&self) -> Result<(), WhoisError> {
+        self.service.save_tld_mappings().await
+    }
+
+    /// Re-query IANA for whois servers now, returning how many TLD mappings changed
+    #[cfg(feature = "whois-tcp")]
+    pub async fn refresh_tld_mappings(&self) -> usize {
+        self.service.refresh_tld_mappings().await
+    }
+
+    /// Perform a whois lookup for an IP address
+    ///
+    /// The query is routed to the RIR holding the allocation (ARIN, RIPE, APNIC,
+    /// LACNIC or AFRINIC), following referrals between registries. IP lookups
+    /// are not cached. Without the `whois-tcp` feature the RIRs' RDAP services
+    /// answer instead.
+    pub async fn lookup_ip(&self, ip: &IpAddr) -> Result<IpWhoisResponse, WhoisError> {
+        let start_time = std::time::Instant::now();
+        #[cfg(feature = "whois-tcp")]
+        let result = self.service.lookup_ip(*ip).await?;
+        #[cfg(not(feature = "whois-tcp"))]
+        let result = self.rdap.lookup_ip(*ip).await?;
+
+        Ok(IpWhoisResponse {
+            ip: ip.to_string(),
+            whois_server: result.server,
+            raw_data: result.raw_data,
+            parsed_data: result.parsed_data,
+            query_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Perform a whois lookup for an autonomous system number
+    ///
+    /// Routed to the RIR that allocated the ASN, like `lookup_ip`. ASN lookups
+    /// are not cached.
+    pub async fn lookup_asn(&self, asn: u32) -> Result<AsnWhoisResponse, WhoisError> {
+        let start_time = std::time::Instant::now();
+        #[cfg(feature = "whois-tcp")]
+        let result = self.service.lookup_asn(asn).await?;
+        #[cfg(not(feature = "whois-tcp"))]
+        let result = self.rdap.lookup_asn(asn).await?;
+
+        Ok(AsnWhoisResponse {
+            asn,
+            whois_server: result.server,
+            raw_data: result.raw_data,
+            parsed_data: result.parsed_data,
+            query_time_ms: start_time.elapsed().as_millis() as u64,
+        })
     }
 
     /// Validate and normalize domain - eliminates DRY violation
+    ///
+    /// Internationalized domains are converted to their ASCII (punycode) form,
+    /// which is what registries expect on the wire.
     fn validate_and_normalize_domain(domain: &str) -> Result<String, WhoisError> {
         let normalized_domain = domain.trim().to_lowercase();
         
@@ -207,7 +1327,32 @@ impl WhoisClient {
             return Err(WhoisError::InvalidDomain("Invalid domain format".to_string()));
         }
 
-        Ok(normalized_domain)
+        idna::domain_to_ascii(&normalized_domain)
+            .map_err(|_| WhoisError::InvalidDomain(format!("Invalid internationalized domain: {}", domain.trim())))
+    }
+
+    /// The domain actually queried and cached for an input
+    ///
+    /// Subdomains collapse to their registrable domain ("mail.example.com" ->
+    /// "example.com") unless `collapse_subdomains` is off or the call asks for
+    /// `exact_domain`.
+    fn lookup_target(&self, domain: &str, options: &LookupOptions) -> Result<String, WhoisError> {
+        let normalized_domain = Self::validate_and_normalize_domain(domain)?;
+        if !self.collapse_subdomains || options.exact_domain {
+            return Ok(normalized_domain);
+        }
+        match crate::domain::registrable_domain(&normalized_domain) {
+            Some(registrable) if registrable != normalized_domain => {
+                tracing::debug!("Collapsed {} to registrable domain {}", normalized_domain, registrable);
+                Ok(registrable)
+            }
+            _ => Ok(normalized_domain),
+        }
+    }
+
+    /// Unicode form of an ASCII domain, if it contains IDN (xn--) labels
+    fn unicode_domain(ascii_domain: &str) -> Option<String> {
+        domain::unicode_form(ascii_domain)
     }
 
     /// Check cache - follows SRP
@@ -229,6 +1374,18 @@ impl WhoisClient {
         None
     }
 
+    /// Check cache for an entry past its TTL - used only when upstream lookups fail
+    async fn check_stale_cache(&self, domain: &str) -> Option<WhoisResponse> {
+        let cache = self.cache.as_ref()?;
+        match cache.get_stale(domain).await {
+            Ok(stale) => stale,
+            Err(e) => {
+                tracing::warn!("Cache read error for {}: {}", domain, e);
+                None
+            }
+        }
+    }
+
     /// Cache result - follows SRP
     async fn cache_result(&self, domain: &str, response: &WhoisResponse) {
         if let Some(cache) = &self.cache {
@@ -239,34 +1396,480 @@ impl WhoisClient {
         }
     }
 
+    // === History Methods ===
+
+    /// Recorded lookup results for `domain`, oldest first
+    #[cfg(feature = "history")]
+    pub async fn history(&self, domain: &str) -> Result<Vec<history::HistoryEntry>, WhoisError> {
+        let normalized_domain = self.lookup_target(domain, &LookupOptions::default())?;
+        self.history
+            .history(&normalized_domain)
+            .await
+            .map_err(|e| WhoisError::Internal(format!("Failed to read history for {}: {}", normalized_domain, e)))
+    }
+
+    /// Append a lookup result to the history
+    ///
+    /// Fresh lookups made by this client are recorded automatically; this is for
+    /// results obtained elsewhere. Failures are logged rather than returned.
+    #[cfg(feature = "history")]
+    pub async fn record_history(&self, response: &WhoisResponse) {
+        let entry = history::HistoryEntry::new(response.clone());
+        if let Err(e) = self.history.record(&response.domain, &entry).await {
+            tracing::warn!("Failed to record history for {}: {}", response.domain, e);
+        }
+    }
+
     // === Utility Methods ===
 
-    /// Get cache statistics if caching is enabled
+    /// Check whether caching is enabled
     pub fn cache_enabled(&self) -> bool {
         self.cache.is_some()
     }
 
+    /// Network buffer pool utilization (hits, misses, buffers in use)
+    #[cfg(feature = "whois-tcp")]
+    pub fn buffer_pool_stats(&self) -> BufferPoolStats {
+        self.service.buffer_pool_stats()
+    }
+
+    /// Health of the whois servers queried so far (see `WhoisService::server_health`)
+    #[cfg(feature = "whois-tcp")]
+    pub fn server_health(&self) -> Vec<ServerHealth> {
+        self.service.server_health()
+    }
+
+    /// Get cache statistics if caching is enabled
+    pub async fn cache_stats(&self) -> Option<CacheStats> {
+        match &self.cache {
+            Some(cache) => Some(cache.stats().await),
+            None => None,
+        }
+    }
+
+    /// Pre-seed the cache by looking up domains that aren't already cached
+    ///
+    /// Returns the number of domains that are cached afterwards.
+    pub async fn warm_cache<I>(&self, domains: I) -> usize
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.lookup_stream(futures::stream::iter(domains))
+            .filter(|(_, result)| futures::future::ready(result.is_ok()))
+            .count()
+            .await
+    }
+
+    /// Export unexpired cache entries as JSONL; see `CacheService::export`
+    pub async fn export_cache<W>(&self, writer: &mut W) -> Result<usize, WhoisError>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        let cache = self.cache.as_ref()
+            .ok_or_else(|| WhoisError::CacheError("Caching is disabled".to_string()))?;
+        cache.export(writer).await.map_err(WhoisError::CacheError)
+    }
+
+    /// Import cache entries written by `export_cache`, keeping their remaining TTL
+    pub async fn import_cache<R>(&self, reader: R) -> Result<usize, WhoisError>
+    where
+        R: tokio::io::AsyncBufRead + Unpin,
+    {
+        let cache = self.cache.as_ref()
+            .ok_or_else(|| WhoisError::CacheError("Caching is disabled".to_string()))?;
+        cache.import(reader).await.map_err(WhoisError::CacheError)
+    }
+
     // === Private Helper Methods ===
 
     /// Load default configuration - eliminates DRY violation
     fn load_default_config() -> Result<Arc<Config>, WhoisError> {
-        let config = Arc::new(Config::load().map_err(|e| WhoisError::ConfigError(e))?);
+        let config = Arc::new(Config::load().map_err(WhoisError::ConfigError)?);
         Ok(config)
     }
 }
 
+/// Builder returned by `WhoisClient::builder()`
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct WhoisClientBuilder {
+    config: Option<ConfigBuilder>,
+    resolved: Option<Arc<Config>>,
+    cache: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for WhoisClientBuilder {
+    fn default() -> Self {
+        Self {
+            config: None,
+            resolved: None,
+            cache: true,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WhoisClientBuilder {
+    /// Configure the client from a `Config::builder()`
+    pub fn config(mut self, config: ConfigBuilder) -> Self {
+        self.config = Some(config);
+        self.resolved = None;
+        self
+    }
+
+    /// Use an already-built configuration
+    pub fn with_config(mut self, config: Arc<Config>) -> Self {
+        self.resolved = Some(config);
+        self.config = None;
+        self
+    }
+
+    /// Skip the response cache
+    pub fn without_cache(mut self) -> Self {
+        self.cache = false;
+        self
+    }
+
+    /// Validate the configuration and start the client
+    pub async fn build(self) -> Result<WhoisClient, WhoisError> {
+        let config = match self.resolved {
+            Some(config) => config,
+            None => Arc::new(self.config.unwrap_or_default().build().map_err(WhoisError::ConfigError)?),
+        };
+
+        if self.cache {
+            WhoisClient::new_with_config(config).await
+        } else {
+            WhoisClient::new_with_config_without_cache(config).await
+        }
+    }
+}
+
+// Lookups that would send the same upstream queries
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct InFlightKey {
+    domain: String,
+    server: Option<String>,
+    protocol: Option<ProtocolPreference>,
+    strategy: LookupStrategy,
+    follow_referrals: bool,
+}
+
+/// Per-call options for `WhoisClient::lookup_opts`
+///
+/// Build with `LookupOptions::new()` and the chainable setters, or set the
+/// public fields directly:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use whois_service::{LookupOptions, ProtocolPreference};
+///
+/// let options = LookupOptions::new()
+///     .fresh(true)
+///     .protocol(ProtocolPreference::WhoisOnly)
+///     .timeout(Duration::from_secs(5))
+///     .follow_referrals(false);
+/// assert!(options.include_raw);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct LookupOptions {
+    /// Bypass the cache and always query upstream
+    pub fresh: bool,
+
+    /// Force a specific whois server (e.g. "whois.verisign-grs.com"), bypassing TLD discovery
+    pub server: Option<String>,
+
+    /// Override the configured protocol preference for this call
+    pub protocol: Option<ProtocolPreference>,
+
+    /// How RDAP and WHOIS are combined when the preference allows both
+    pub strategy: LookupStrategy,
+
+    /// Time budget for the whole call; also replaces `whois_timeout_seconds` for port-43 queries
+    pub timeout: Option<Duration>,
+
+    /// Instant by which the call must finish; the earlier of this and `timeout` applies
+    pub deadline: Option<tokio::time::Instant>,
+
+    /// Query the domain as given instead of collapsing subdomains to the registrable domain
+    pub exact_domain: bool,
+
+    /// Follow port-43 referrals from the registry to the registrar's whois server (default true);
+    /// every server's answer is kept in `WhoisResponse::referral_chain`
+    pub follow_referrals: bool,
+
+    /// Leave `parsed_data` out of the response; the cache still keeps the parsed record
+    pub skip_parsing: bool,
+
+    /// Return `raw_data` (default true); when false it is left empty
+    pub include_raw: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for LookupOptions {
+    fn default() -> Self {
+        Self {
+            fresh: false,
+            server: None,
+            protocol: None,
+            strategy: LookupStrategy::default(),
+            timeout: None,
+            deadline: None,
+            exact_domain: false,
+            follow_referrals: true,
+            skip_parsing: false,
+            include_raw: true,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LookupOptions {
+    /// Default options: cached, configured protocols, referrals followed, raw and parsed data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bypass the cache and always query upstream
+    pub fn fresh(mut self, fresh: bool) -> Self {
+        self.fresh = fresh;
+        self
+    }
+
+    /// Query this port-43 whois server, bypassing TLD discovery
+    ///
+    /// Surrounding whitespace is dropped; a blank server makes the lookup fail
+    /// with `WhoisError::InvalidRequest`.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.server = Some(server.into().trim().to_string());
+        self
+    }
+
+    /// Override the configured protocol preference
+    pub fn protocol(mut self, protocol: ProtocolPreference) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// How RDAP and WHOIS are combined
+    pub fn strategy(mut self, strategy: LookupStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Time budget for the whole call
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Instant by which the call must finish
+    pub fn deadline(mut self, deadline: tokio::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Query the domain as given instead of its registrable domain
+    pub fn exact_domain(mut self, exact: bool) -> Self {
+        self.exact_domain = exact;
+        self
+    }
+
+    /// Follow registrar referrals, or stop at the first whois server's answer
+    pub fn follow_referrals(mut self, follow: bool) -> Self {
+        self.follow_referrals = follow;
+        self
+    }
+
+    /// Leave `parsed_data` out of the response
+    pub fn skip_parsing(mut self, skip: bool) -> Self {
+        self.skip_parsing = skip;
+        self
+    }
+
+    /// Return `raw_data` in the response
+    pub fn include_raw(mut self, include: bool) -> Self {
+        self.include_raw = include;
+        self
+    }
+
+    /// Drop the parts of a response the caller opted out of
+    fn shape(&self, mut response: WhoisResponse) -> WhoisResponse {
+        if self.skip_parsing {
+            response.parsed_data = None;
+        }
+        if !self.include_raw {
+            response.raw_data = String::new();
+            for hop in &mut response.referral_chain {
+                hop.raw_data = String::new();
+            }
+        }
+        response
+    }
+
+    /// Reject options no lookup could honour, before any work is done
+    fn validate(&self) -> Result<(), WhoisError> {
+        if self.server.as_deref().is_some_and(|server| server.trim().is_empty()) {
+            return Err(WhoisError::InvalidRequest("Empty whois server override".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The deadline implied by `timeout` and `deadline`, counting `timeout` from now
+    fn effective_deadline(&self) -> Option<tokio::time::Instant> {
+        let from_timeout = self.timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+        match (from_timeout, self.deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// How a domain lookup combines RDAP and WHOIS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupStrategy {
+    /// Try protocols one after another in preference order
+    #[default]
+    Sequential,
+    /// Query RDAP and WHOIS in parallel; the first success wins and the other is cancelled
+    Race,
+    /// Query both protocols and combine their fields (see `ParsedWhoisData::field_sources`)
+    Merge,
+}
+
+/// Registration state of a domain, as reported by `WhoisClient::is_available`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DomainAvailability {
+    /// The registry holds a registration for the domain
+    Registered,
+    /// The registry reports no registration (RDAP 404 or a "not found" whois reply)
+    Available,
+    /// The response couldn't be classified either way
+    Unknown,
+}
+
+/// A TLD the client knows how to query, as reported by `WhoisClient::supported_tlds`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SupportedTld {
+    pub tld: String,
+    /// Port-43 server (configured override, discovered or hardcoded)
+    pub whois_server: Option<String>,
+    /// RDAP base URL (discovered or generated at build time)
+    pub rdap_server: Option<String>,
+}
+
+impl SupportedTld {
+    pub fn supports_whois(&self) -> bool {
+        self.whois_server.is_some()
+    }
+
+    pub fn supports_rdap(&self) -> bool {
+        self.rdap_server.is_some()
+    }
+}
+
+/// A TLD's registry as reported by `WhoisClient::tld_info`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TldInfo {
+    /// The TLD or public suffix in ASCII (punycode) form
+    pub tld: String,
+    /// Port-43 server lookups would use
+    pub whois_server: Option<String>,
+    /// RDAP base URL lookups would use
+    pub rdap_server: Option<String>,
+    /// Sponsoring organisation from the IANA root zone database
+    pub registry_operator: Option<String>,
+    /// The registry only holds registrar data; contacts come from the registrar's server
+    pub thin_registry: bool,
+    /// Queries per second allowed to the whois server (0 = unlimited)
+    pub whois_qps: Option<f64>,
+    /// Timeout applied to whois queries for this TLD
+    pub whois_timeout_seconds: Option<u64>,
+    /// Known registry behaviour affecting lookups (query format, access restrictions)
+    pub quirks: Vec<String>,
+}
+
 /// Response structure for whois lookups
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct WhoisResponse {
+    /// Queried domain in ASCII (punycode) form
     pub domain: String,
+    /// Unicode form of the domain, present for internationalized domains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_unicode: Option<String>,
+    /// ASCII (punycode) form of the domain, present alongside `domain_unicode`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_ascii: Option<String>,
     pub whois_server: String,
     pub raw_data: String,
     pub parsed_data: Option<ParsedWhoisData>,
     pub cached: bool,
+    /// True when upstream lookups failed and an expired cache entry was served instead
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// Seconds since the data was fetched from upstream, for cached responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_age_seconds: Option<u64>,
     pub query_time_ms: u64,
+    /// Text rendering of `parsing_report` (debug mode only)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parsing_analysis: Option<Vec<String>>,
+    /// What the parser found and missed (debug mode only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsing_report: Option<ParsingReport>,
+    /// Every port-43 answer, registry first, when a referral was followed;
+    /// `raw_data` holds the last one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referral_chain: Vec<ReferralHop>,
+}
+
+/// Response structure for IP address whois lookups
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IpWhoisResponse {
+    pub ip: String,
+    pub whois_server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedIpWhoisData>,
+    pub query_time_ms: u64,
+}
+
+/// Response structure for ASN whois lookups
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AsnWhoisResponse {
+    pub asn: u32,
+    pub whois_server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedAsnWhoisData>,
+    pub query_time_ms: u64,
+}
+
+/// A plain, uncached lookup result for `domain`, for tests to adjust with `..`
+#[cfg(test)]
+pub(crate) fn test_response(domain: &str) -> WhoisResponse {
+    WhoisResponse {
+        domain: domain.to_string(),
+        whois_server: "whois.verisign-grs.com".to_string(),
+        raw_data: format!("Domain Name: {}", domain.to_uppercase()),
+        ..WhoisResponse::default()
+    }
 }
 
 #[cfg(test)]
@@ -291,4 +1894,282 @@ mod tests {
         let result = client.lookup("invalid").await;
         assert!(matches!(result, Err(WhoisError::InvalidDomain(_))));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_lookup_options_shape_response() {
+        let defaults = LookupOptions::new();
+        assert!(defaults.follow_referrals && defaults.include_raw && !defaults.skip_parsing);
+
+        let response = WhoisResponse {
+            domain: "example.com".to_string(),
+            domain_unicode: None,
+            domain_ascii: None,
+            whois_server: "whois.example".to_string(),
+            raw_data: "Registrar: Example Registrar".to_string(),
+            parsed_data: Some(parser::WhoisParser::parse("Registrar: Example Registrar")),
+            cached: false,
+            degraded: false,
+            data_age_seconds: None,
+            query_time_ms: 0,
+            parsing_analysis: None,
+            parsing_report: None,
+            referral_chain: Vec::new(),
+        };
+        assert!(defaults.shape(response.clone()).parsed_data.is_some());
+
+        let shaped = LookupOptions::new().skip_parsing(true).include_raw(false).shape(response);
+        assert!(shaped.parsed_data.is_none());
+        assert!(shaped.raw_data.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_dates_keeps_raw_text() {
+        let parser = parser::WhoisParser::new();
+        let mut parsed = ParsedWhoisData {
+            creation_date: Some("1997-09-15 04:00:00 (UTC+9)".to_string()),
+            expiration_date: Some("sometime in 2030".to_string()),
+            updated_date: Some("2024-05-18T13:36:06.0Z".to_string()),
+            ..Default::default()
+        };
+        parsed.normalize_dates(|date| parser.parse_date(date));
+
+        assert_eq!(parsed.creation_date.as_deref(), Some("1997-09-14T19:00:00Z"));
+        assert_eq!(parsed.creation_date_raw.as_deref(), Some("1997-09-15 04:00:00 (UTC+9)"));
+        assert_eq!(parsed.updated_date.as_deref(), Some("2024-05-18T13:36:06Z"));
+        assert!(parsed.created_ago.is_some_and(|days| days > 9000));
+        // An unreadable date is only kept raw
+        assert_eq!(parsed.expiration_date, None);
+        assert_eq!(parsed.expiration_date_raw.as_deref(), Some("sometime in 2030"));
+        assert_eq!(parsed.expires_in, None);
+
+        // Normalizing again keeps the registry's text
+        parsed.normalize_dates(|date| parser.parse_date(date));
+        assert_eq!(parsed.creation_date.as_deref(), Some("1997-09-14T19:00:00Z"));
+        assert_eq!(parsed.creation_date_raw.as_deref(), Some("1997-09-15 04:00:00 (UTC+9)"));
+        assert_eq!(parsed.updated_date_raw.as_deref(), Some("2024-05-18T13:36:06.0Z"));
+    }
+
+    #[test]
+    fn test_merge_records_field_sources() {
+        let parser = parser::WhoisParser::new();
+        let rdap = parser
+            .parse_whois_data("Registrar: RDAP Registrar\nCreation Date: 2020-01-01T00:00:00Z\n")
+            .unwrap();
+        let whois = parser
+            .parse_whois_data("Registrar: Whois Registrar\nCreation Date: 01-Jan-2020\nName Server: ns1.example.com\nRegistrant Name: Jane Doe\n")
+            .unwrap();
+
+        let merged = ParsedWhoisData::merge(rdap, whois);
+        assert_eq!(merged.registrar.as_deref(), Some("RDAP Registrar"));
+        assert_eq!(merged.registrant_name(), Some("Jane Doe"));
+        assert_eq!(merged.field_sources["registrar"], "rdap");
+        assert_eq!(merged.field_sources["creation_date"], "rdap");
+        assert_eq!(merged.field_sources["name_servers"], "whois");
+        assert_eq!(merged.field_sources["registrant"], "whois");
+        assert!(!merged.field_sources.contains_key("dnssec"));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_target_collapses_subdomains() {
+        let exact = LookupOptions::new().exact_domain(true);
+
+        let client = WhoisClient::builder().without_cache().build().await.unwrap();
+        assert_eq!(client.lookup_target("Mail.Example.co.uk", &LookupOptions::default()).unwrap(), "example.co.uk");
+        assert_eq!(client.lookup_target("example.com", &LookupOptions::default()).unwrap(), "example.com");
+        assert_eq!(client.lookup_target("mail.example.com", &exact).unwrap(), "mail.example.com");
+
+        let client = WhoisClient::builder()
+            .config(Config::builder().collapse_subdomains(false))
+            .without_cache()
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(client.lookup_target("mail.example.com", &LookupOptions::default()).unwrap(), "mail.example.com");
+        assert_eq!(client.lookup_target("mail.example.com", &exact).unwrap(), "mail.example.com");
+        assert!(client.lookup_target("invalid", &LookupOptions::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_blank_server_override_is_invalid_request() {
+        assert_eq!(LookupOptions::new().server("  whois.example  ").server.as_deref(), Some("whois.example"));
+
+        let client = WhoisClient::builder().without_cache().build().await.unwrap();
+        for server in ["", "   "] {
+            let error = client.lookup_with_server("example.com", server).await.unwrap_err();
+            assert!(matches!(error, WhoisError::InvalidRequest(_)), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_idn_normalization() {
+        let ascii = WhoisClient::validate_and_normalize_domain("Bücher.de").unwrap();
+        assert_eq!(ascii, "xn--bcher-kva.de");
+        assert_eq!(WhoisClient::unicode_domain(&ascii).as_deref(), Some("bücher.de"));
+        assert_eq!(WhoisClient::unicode_domain("example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_stream_yields_every_domain() {
+        let client = WhoisClient::new_without_cache().await.unwrap();
+        let domains = futures::stream::iter(vec!["", "invalid", "also-invalid"]);
+
+        let results: Vec<_> = client.lookup_stream(domains).collect().await;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, result)| matches!(result, Err(WhoisError::InvalidDomain(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_tld_info_from_static_data() {
+        // Replaying from an empty directory keeps the IANA fallback offline
+        let dir = std::env::temp_dir().join(format!("whois-tld-info-{}", std::process::id()));
+        let config = Config::builder().fixtures(FixtureMode::Replay, dir.to_string_lossy());
+        let client = WhoisClient::builder().config(config).without_cache().build().await.unwrap();
+
+        let info = client.tld_info(".COM").await.unwrap();
+        assert_eq!(info.tld, "com");
+        assert!(info.thin_registry);
+        assert!(info.rdap_server.is_some());
+        #[cfg(feature = "whois-tcp")]
+        assert_eq!(info.whois_server.as_deref(), Some("whois.verisign-grs.com"));
+
+        // DENIC has no RDAP service, so only whois-tcp builds know .de
+        #[cfg(feature = "whois-tcp")]
+        {
+            let info = client.tld_info("de").await.unwrap();
+            assert!(!info.thin_registry);
+            assert!(info.quirks[0].contains("-T dn,ace"));
+            assert_eq!(info.whois_qps, Some(1.0));
+        }
+
+        assert!(matches!(client.tld_info("").await, Err(WhoisError::InvalidDomain(_))));
+        assert!(matches!(client.tld_info("co..uk").await, Err(WhoisError::InvalidDomain(_))));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema_matches_serialized_response() {
+        let schema = serde_json::to_value(schemars::schema_for!(WhoisResponse)).unwrap();
+        let parsed = &schema["definitions"]["ParsedWhoisData"]["properties"];
+        // EPP codes serialize as strings, not as the enum's variants
+        assert_eq!(parsed["status_codes"]["items"]["type"], "string");
+        assert!(schema["definitions"]["Contact"].is_object());
+
+        let error = serde_json::to_value(schemars::schema_for!(ErrorBody)).unwrap();
+        assert_eq!(error["required"], serde_json::json!(["code", "error", "status"]));
+    }
+
+    #[tokio::test]
+    async fn test_first_success_cancels_the_loser() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        /// Records that the future holding it was dropped
+        struct DropFlag(Arc<AtomicBool>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let answer = |server: &str| WhoisResult {
+            server: server.to_string(),
+            raw_data: String::new(),
+            parsed_data: None,
+            parsing_report: ParsingReport::default(),
+            referral_chain: Vec::new(),
+        };
+        let never = |dropped: &Arc<AtomicBool>| {
+            let flag = DropFlag(dropped.clone());
+            async move {
+                let _flag = flag;
+                std::future::pending::<Result<WhoisResult, WhoisError>>().await
+            }
+        };
+
+        // The first success wins and the pending query is dropped with the race
+        let dropped = Arc::new(AtomicBool::new(false));
+        let winner = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(answer("fast"))
+        };
+        let result = WhoisClient::first_success("example.com", ("RDAP", winner), ("WHOIS", never(&dropped))).await;
+        assert_eq!(result.unwrap().server, "fast");
+        assert!(dropped.load(Ordering::SeqCst));
+
+        // "Not found" is authoritative and ends the race too
+        let dropped = Arc::new(AtomicBool::new(false));
+        let not_found = async { Err(WhoisError::DomainNotFound("example.com".to_string())) };
+        let result = WhoisClient::first_success("example.com", ("WHOIS", never(&dropped)), ("RDAP", not_found)).await;
+        assert!(matches!(result, Err(WhoisError::DomainNotFound(_))));
+        assert!(dropped.load(Ordering::SeqCst));
+
+        // Any other failure waits for the other protocol
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(answer("slow"))
+        };
+        let result = WhoisClient::first_success("example.com", ("WHOIS", async { Err(WhoisError::Timeout) }), ("RDAP", slow)).await;
+        assert_eq!(result.unwrap().server, "slow");
+    }
+
+    /// Client resolving `.test` against the fixture servers, each answering after its delay
+    #[cfg(all(feature = "test-util", feature = "whois-tcp"))]
+    async fn delayed_fixture_client(
+        whois_delay: Duration,
+        rdap_delay: Duration,
+    ) -> (WhoisClient, test_server::MockWhoisServer, test_server::MockRdapServer) {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let whois = test_server::MockWhoisServer::from_dir(fixtures.join("whois")).await.unwrap().with_delay(whois_delay);
+        let rdap = test_server::MockRdapServer::from_dir(fixtures.join("rdap")).await.unwrap().with_delay(rdap_delay);
+        let config = Config::builder()
+            .tld_server_override("test", whois.address())
+            .rdap_bootstrap_urls(vec![rdap.bootstrap_url()]);
+        let client = WhoisClient::builder().config(config).without_cache().build().await.unwrap();
+        (client, whois, rdap)
+    }
+
+    #[cfg(all(feature = "test-util", feature = "whois-tcp"))]
+    #[tokio::test]
+    async fn test_race_protocols_takes_the_faster_upstream() {
+        let (slow, options) = (Duration::from_secs(5), LookupOptions::default());
+        for (whois_delay, rdap_delay, winner) in [(slow, Duration::ZERO, "RDAP"), (Duration::ZERO, slow, "WHOIS")] {
+            let (client, _whois, _rdap) = delayed_fixture_client(whois_delay, rdap_delay).await;
+            let started = std::time::Instant::now();
+            let result = client.race_protocols("example.test", &options).await.unwrap();
+            assert!(result.server.starts_with(winner), "{} should win, got {}", winner, result.server);
+            // The slow upstream is not waited for
+            assert!(started.elapsed() < Duration::from_secs(2));
+
+            // Nor is it when the fast one says the domain doesn't exist
+            let started = std::time::Instant::now();
+            let result = client.race_protocols("missing.test", &options).await;
+            assert!(matches!(result, Err(WhoisError::DomainNotFound(_))));
+            assert!(started.elapsed() < Duration::from_secs(2));
+        }
+    }
+
+    #[cfg(all(feature = "test-util", feature = "whois-tcp"))]
+    #[tokio::test]
+    async fn test_hedge_protocols_adds_rdap_for_slow_whois() {
+        let (slow, options, hedge_after) = (Duration::from_secs(5), LookupOptions::default(), Duration::from_millis(100));
+
+        // WHOIS answering within the delay is used as is
+        let (client, _whois, _rdap) = delayed_fixture_client(Duration::ZERO, slow).await;
+        let result = client.hedge_protocols("example.test", &options, hedge_after).await.unwrap();
+        assert!(result.server.starts_with("WHOIS"));
+
+        // Slow WHOIS is overtaken by RDAP, and RDAP's "not found" ends the wait
+        let (client, _whois, _rdap) = delayed_fixture_client(slow, Duration::ZERO).await;
+        let started = std::time::Instant::now();
+        let result = client.hedge_protocols("example.test", &options, hedge_after).await.unwrap();
+        assert!(result.server.starts_with("RDAP"));
+        let result = client.hedge_protocols("missing.test", &options, hedge_after).await;
+        assert!(matches!(result, Err(WhoisError::DomainNotFound(_))));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // WHOIS past the delay still wins over a slower RDAP
+        let (client, _whois, _rdap) = delayed_fixture_client(Duration::from_millis(300), slow).await;
+        let started = std::time::Instant::now();
+        let result = client.hedge_protocols("example.test", &options, hedge_after).await.unwrap();
+        assert!(result.server.starts_with("WHOIS"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
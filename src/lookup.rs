@@ -0,0 +1,27 @@
+//! Abstraction over domain lookups
+//!
+//! Code that only needs `lookup`/`lookup_fresh` can take a `WhoisLookup`
+//! instead of a concrete `WhoisClient`, and swap in `MockWhoisClient`
+//! (feature `test-util`) or another implementation in tests.
+
+use crate::{WhoisClient, WhoisError, WhoisResponse};
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait WhoisLookup: Send + Sync {
+    /// Look up a domain, serving cached data when available
+    async fn lookup(&self, domain: &str) -> Result<WhoisResponse, WhoisError>;
+    /// Look up a domain, bypassing the cache
+    async fn lookup_fresh(&self, domain: &str) -> Result<WhoisResponse, WhoisError>;
+}
+
+#[async_trait]
+impl WhoisLookup for WhoisClient {
+    async fn lookup(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        WhoisClient::lookup(self, domain).await
+    }
+
+    async fn lookup_fresh(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        WhoisClient::lookup_fresh(self, domain).await
+    }
+}
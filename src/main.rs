@@ -1,17 +1,24 @@
 use axum::{
-    extract::{FromRequestParts, Query, State},
+    extract::{DefaultBodyLimit, FromRequestParts, Query, State},
     http::request::Parts,
     response::Json,
     routing::{get, post},
     Router,
 };
 
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
-use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
 use tracing::{info, warn};
+use format::{Formatted, OutputFormat};
 #[cfg(feature = "openapi")]
 use utoipa::{OpenApi, ToSchema};
 #[cfg(feature = "openapi")]
@@ -22,17 +29,32 @@ const CACHE_WRITE_TIMEOUT_SECS: u64 = 5;
 
 // Import from the library instead of local modules
 use whois_service::{
-    cache::CacheService,
+    cache::{CacheService, NullCache},
     config::Config,
     errors::WhoisError,
-    rdap::RdapService,
-    whois::WhoisService,
-    ParsedWhoisData, // Import for OpenAPI schema
+    LookupOptions,
+    ParsingReport,
+    ReferralHop,
+    WhoisClient,
     WhoisResponse,   // Use the library's WhoisResponse
+    webhook::WebhookSender,
 };
+#[cfg(feature = "openapi")]
+use whois_service::{ErrorBody, ParsedWhoisData}; // Import for OpenAPI schema
 
 // Import metrics module locally (API-only)
 mod metrics;
+#[cfg(feature = "otel")]
+mod otel;
+mod request_id;
+mod jobs;
+mod rate_limit;
+mod admin;
+mod format;
+mod rdap_proxy;
+mod whois_proxy;
+#[cfg(feature = "tls")]
+mod tls;
 
 #[cfg(feature = "openapi")]
 #[derive(OpenApi)]
@@ -44,7 +66,7 @@ mod metrics;
         whois_debug_path,
         health_check
     ),
-    components(schemas(HealthResponse, WhoisResponse, ParsedWhoisData)),
+    components(schemas(HealthResponse, WhoisResponse, ParsedWhoisData, ErrorBody)),
     tags(
         (name = "whois", description = "Domain whois lookup operations"),
         (name = "system", description = "System health and monitoring")
@@ -70,10 +92,28 @@ struct ApiDoc;
 
 #[derive(Clone)]
 pub struct AppState {
-    whois_service: Arc<WhoisService>,
-    rdap_service: Arc<RdapService>,
+    client: WhoisClient,
     cache_service: Arc<CacheService>,
     config: Arc<Config>,
+    jobs: Arc<jobs::JobStore>,
+    clients: Arc<rate_limit::ClientIdentity>,
+    webhooks: WebhookSender,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State for handler tests: `config` with an uncached client
+    pub(crate) async fn for_tests(config: Config) -> Self {
+        let config = Arc::new(config);
+        Self {
+            client: WhoisClient::new_with_config_without_cache(config.clone()).await.unwrap(),
+            cache_service: Arc::new(CacheService::with_backend(Box::new(NullCache), config.cache_ttl_seconds)),
+            jobs: Arc::new(jobs::JobStore::default()),
+            clients: Arc::new(rate_limit::ClientIdentity::from_config(&config)),
+            webhooks: WebhookSender::from_config(&config),
+            config,
+        }
+    }
 }
 
 // Domain validation extractor
@@ -127,6 +167,12 @@ impl ValidatedDomain {
             return Err(WhoisError::InvalidDomain("Empty domain".to_string()));
         }
 
+        // Convert internationalized domains to their ASCII (punycode) form
+        let domain = idna::domain_to_ascii(&domain).map_err(|_| {
+            metrics::increment_errors("invalid_idn");
+            WhoisError::InvalidDomain("Invalid internationalized domain".to_string())
+        })?;
+
         if domain.len() > 253 {
             metrics::increment_errors("domain_too_long");
             return Err(WhoisError::InvalidDomain(
@@ -181,87 +227,230 @@ struct HealthResponse {
     uptime_seconds: u64,
 }
 
+/// Command-line flags; each one takes precedence over the environment and config file
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// HTTP port to listen on
+    #[arg(long)]
+    port: Option<u16>,
+    /// Config file (TOML/YAML/JSON) instead of the default search paths
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Bind address: an IP ("::1") or a full socket address ("[::1]:3000")
+    #[arg(long)]
+    bind: Option<String>,
+    /// Don't cache lookup results
+    #[arg(long)]
+    no_cache: bool,
+    /// Log line format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "whois_service=info,tower_http=debug".into()),
-        )
-        .init();
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+    let filter = EnvFilter::new(
+        std::env::var("RUST_LOG").unwrap_or_else(|_| "whois_service=info,tower_http=debug".into()),
+    );
+    let fmt_layer = match cli.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+    };
+    let registry = tracing_subscriber::registry().with(filter).with(fmt_layer);
+
+    #[cfg(feature = "otel")]
+    let tracer_provider = {
+        let (otel_layer, provider) = otel::init()?;
+        registry.with(otel_layer).init();
+        provider
+    };
+    #[cfg(not(feature = "otel"))]
+    registry.init();
 
     // Load configuration
-    let config = Arc::new(Config::load()?);
+    let mut config = match &cli.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::load()?,
+    };
+    if let Some(port) = cli.port {
+        config.port = port;
+    }
+    if let Some(bind) = cli.bind {
+        config.bind_address = bind;
+    }
+    config.validate()?;
+    #[cfg(not(feature = "history"))]
+    if config.history_path.is_some() {
+        return Err("history_path is set but the history feature is not enabled".into());
+    }
+    let config = Arc::new(config);
     info!("Configuration loaded successfully");
 
-    // Initialize services
-    let whois_service = Arc::new(WhoisService::new(config.clone()).await?);
-    let rdap_service = Arc::new(RdapService::new(config.clone()).await?);
-    let cache_service = Arc::new(CacheService::new(config.clone())?); // Handle cache initialization error
+    // Initialize services (the API manages its own cache with metrics and write timeouts)
+    let client = WhoisClient::new_with_config_without_cache(config.clone()).await?;
+    let cache_service = Arc::new(if cli.no_cache {
+        info!("Result caching disabled");
+        CacheService::with_backend(Box::new(NullCache), config.cache_ttl_seconds)
+    } else {
+        CacheService::new(config.clone())? // Handle cache initialization error
+    });
 
     // Initialize metrics
     metrics::init_metrics();
 
     let app_state = AppState {
-        whois_service,
-        rdap_service,
+        client: client.clone(),
         cache_service,
         config: config.clone(),
+        jobs: Arc::new(jobs::JobStore::default()),
+        clients: Arc::new(rate_limit::ClientIdentity::from_config(&config)),
+        webhooks: WebhookSender::from_config(&config),
     };
 
     // Build the application
-    let mut app = Router::new()
+    let app = Router::new()
         .route("/", get(whois_lookup))
         .route("/", post(whois_lookup_post))
         .route("/:domain", get(whois_lookup_path)) // Path-based route for easier testing
         .route("/debug", get(whois_debug))
         .route("/debug/:domain", get(whois_debug_path)) // Path-based debug route
+        .route("/rdap/domain/:domain", get(rdap_proxy::domain))
+        .route("/jobs", post(jobs::create_job).layer(DefaultBodyLimit::max(jobs::MAX_JOB_BODY_BYTES)))
+        .route("/jobs/:id", get(jobs::job_status))
+        .route("/jobs/:id/results", get(jobs::job_results))
         .route("/health", get(health_check))
-        .route("/metrics", get(metrics::metrics_handler))
-        .with_state(app_state);
+        .route("/metrics", get(metrics::metrics_handler));
+    #[cfg(feature = "history")]
+    let app = app.route("/whois/:domain/history", get(whois_history));
+
+    // Legacy whois clients share the API's cache and discovery
+    if config.whois_proxy_port != 0 {
+        let whois_addr = SocketAddr::new(config.bind_addr()?.ip(), config.whois_proxy_port);
+        whois_proxy::spawn(app_state.clone(), whois_addr).await?;
+    }
+
+    // Operator endpoints exist only when an admin token is configured
+    let app = match admin::router(config.admin_token.as_deref()) {
+        Some(admin) => app.merge(admin),
+        None => app,
+    }
+    .with_state(app_state);
 
     // Add OpenAPI documentation if feature is enabled
     #[cfg(feature = "openapi")]
-    {
-        app = app.merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()));
-    }
+    let app = app.merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()));
+
+    // Per-client rate limiting sits inside the request ID layers so 429s carry the ID
+    let app = match rate_limit::ClientRateLimit::from_config(&config) {
+        Some(limit) => {
+            info!(
+                "Client rate limit: {} req/s, burst {}",
+                config.client_rate_limit_qps, config.client_rate_limit_burst
+            );
+            app.layer(axum::middleware::from_fn_with_state(limit, rate_limit::enforce))
+        }
+        None => app,
+    };
 
     // Apply middleware layers AFTER all routes are added (including OpenAPI routes)
     let app = app.layer(
         ServiceBuilder::new()
-            .layer(TraceLayer::new_for_http())
+            .layer(axum::middleware::from_fn(request_id::drop_unusable_id))
+            .layer(SetRequestIdLayer::new(request_id::X_REQUEST_ID, MakeRequestUuid))
+            .layer(TraceLayer::new_for_http().make_span_with(request_id::RequestSpan))
+            .layer(PropagateRequestIdLayer::new(request_id::X_REQUEST_ID))
             .layer(CompressionLayer::new())
             .layer(CorsLayer::permissive())
+            .layer(axum::middleware::from_fn(request_id::tag_error_body))
             .into_inner(),
     );
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    let listener = TcpListener::bind(addr).await?;
+    let addr = config.bind_addr()?;
+    let scheme = if config.tls_cert_path.is_some() { "https" } else { "http" };
 
     info!("Whois service listening on {}", addr);
-    info!("Health check: http://{}/health", addr);
-    info!("Metrics: http://{}/metrics", addr);
+    info!("Health check: {}://{}/health", scheme, addr);
+    info!("Metrics: {}://{}/metrics", scheme, addr);
     #[cfg(feature = "openapi")]
-    info!("API Documentation: http://{}/docs", addr);
+    info!("API Documentation: {}://{}/docs", scheme, addr);
     info!("API expects pre-parsed domain names (e.g., 'example.com')");
 
-    // Graceful shutdown handling
-    let shutdown_signal = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to install CTRL+C signal handler");
-        info!("Received shutdown signal, gracefully shutting down...");
-    };
+    serve(app, addr, &config).await?;
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
+    // Don't lose TLD servers discovered since the last periodic flush
+    if let Err(e) = client.save_tld_mappings().await {
+        warn!("Failed to persist TLD mappings on shutdown: {}", e);
+    }
+
+    // Flush spans still buffered in the batch exporter
+    #[cfg(feature = "otel")]
+    if let Err(e) = tracer_provider.shutdown() {
+        warn!("Failed to flush OpenTelemetry spans on shutdown: {}", e);
+    }
 
     Ok(())
 }
 
-// Three-tier lookup: RDAP -> WHOIS -> (Command-line skipped for now)
+// Graceful shutdown handling
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to install CTRL+C signal handler");
+    info!("Received shutdown signal, gracefully shutting down...");
+}
+
+// Peer addresses key the client rate limiter
+async fn serve_plain(app: Router, addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+}
+
+#[cfg(feature = "tls")]
+async fn serve(app: Router, addr: SocketAddr, config: &Config) -> std::io::Result<()> {
+    let Some(files) = tls::TlsFiles::from_config(config) else {
+        return serve_plain(app, addr).await;
+    };
+
+    let reload = (config.tls_reload_seconds > 0).then(|| std::time::Duration::from_secs(config.tls_reload_seconds));
+    let tls_config = files.load(reload).await?;
+
+    let handle = axum_server::Handle::new();
+    let shutdown = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown.graceful_shutdown(None);
+    });
+
+    axum_server::bind_rustls(addr, tls_config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
+
+#[cfg(not(feature = "tls"))]
+async fn serve(app: Router, addr: SocketAddr, config: &Config) -> std::io::Result<()> {
+    if config.tls_cert_path.is_some() {
+        return Err(std::io::Error::other("tls_cert_path is set but the tls feature is not enabled"));
+    }
+    serve_plain(app, addr).await
+}
+
+// Three-tier lookup: RDAP/WHOIS ordering per the configured protocol preference,
+// then the system whois command when built with the `system-whois` feature and
+// any configured commercial providers (shared with library consumers via WhoisClient)
 async fn three_tier_lookup(
     state: &AppState,
     domain: &str,
@@ -270,42 +459,24 @@ async fn three_tier_lookup(
         String,
         String,
         Option<whois_service::ParsedWhoisData>,
-        Vec<String>,
+        ParsingReport,
+        Vec<ReferralHop>,
     ),
     WhoisError,
 > {
-    // Tier 1: Try RDAP first (modern, structured JSON)
-    match state.rdap_service.lookup(domain).await {
-        Ok(rdap_result) => {
-            info!("✓ RDAP lookup successful for {}", domain);
-            return Ok((
-                format!("RDAP: {}", rdap_result.server),
-                rdap_result.raw_data,
-                rdap_result.parsed_data,
-                rdap_result.parsing_analysis,
-            ));
-        }
-        Err(e) => {
-            info!(
-                "⚠ RDAP lookup failed for {}: {} - falling back to WHOIS",
-                domain, e
-            );
-        }
-    }
-
-    // Tier 2: Fallback to WHOIS (legacy but comprehensive)
-    match state.whois_service.lookup(domain).await {
-        Ok(whois_result) => {
-            info!("✓ WHOIS lookup successful for {}", domain);
+    match state.client.lookup_uncached(domain, &LookupOptions::default()).await {
+        Ok(result) => {
+            info!(domain, server = %result.server, "Lookup succeeded");
             Ok((
-                format!("WHOIS: {}", whois_result.server),
-                whois_result.raw_data,
-                whois_result.parsed_data,
-                whois_result.parsing_analysis,
+                result.server,
+                result.raw_data,
+                result.parsed_data,
+                result.parsing_report,
+                result.referral_chain,
             ))
         }
         Err(e) => {
-            warn!("❌ Both RDAP and WHOIS lookups failed for {}", domain);
+            warn!(domain, error = %e, "All lookup protocols failed");
             Err(e)
         }
     }
@@ -325,26 +496,42 @@ async fn three_tier_lookup(
 async fn whois_lookup(
     Query(params): Query<WhoisQuery>,
     State(state): State<AppState>,
-) -> Result<Json<WhoisResponse>, WhoisError> {
-    let start_time = std::time::Instant::now();
-
+    format: OutputFormat,
+) -> Result<Formatted, WhoisError> {
     // Validate domain using centralized validation
     let validated_domain = ValidatedDomain::from_query_params(&params)?;
-    let domain = validated_domain.0;
+    let response = cached_lookup(&state, validated_domain.0, params.fresh).await?;
+    Ok(Formatted(format, response))
+}
+
+// Cache-first lookup shared by every endpoint serving lookup results
+pub(crate) async fn cached_lookup(state: &AppState, domain: String, fresh: bool) -> Result<WhoisResponse, WhoisError> {
+    let start_time = std::time::Instant::now();
+    let domain = lookup_domain(&state.config, domain);
 
     // Increment request counter
     metrics::increment_requests(&domain);
 
     // Check cache first (unless fresh is requested)
-    if !params.fresh {
+    if !fresh {
         if let Some(cached_result) = check_cache(&state.cache_service, &domain).await {
-            metrics::increment_cache_hits();
-            return Ok(Json(cached_result));
+            return Ok(cached_result);
         }
     }
 
-    // Perform three-tier lookup
-    let result = three_tier_lookup(&state, &domain).await?;
+    // Perform three-tier lookup, serving stale cached data if every tier fails
+    let result = match three_tier_lookup(state, &domain).await {
+        Ok(result) => result,
+        Err(e @ WhoisError::DomainNotFound(_)) => return Err(e),
+        Err(e) => {
+            if let Some(stale) = check_stale_cache(&state.cache_service, &domain).await {
+                warn!("Serving stale cache entry for {} after lookup failure: {}", domain, e);
+                metrics::increment_errors("stale_cache_served");
+                return Ok(stale);
+            }
+            return Err(e);
+        }
+    };
 
     let query_time = start_time.elapsed().as_millis() as u64;
 
@@ -352,11 +539,21 @@ async fn whois_lookup(
 
     // Cache the result (with error handling)
     handle_cache_write(&state.cache_service, &domain, &response).await;
+    #[cfg(feature = "history")]
+    state.client.record_history(&response).await;
 
     metrics::record_query_time(query_time);
-    metrics::increment_cache_misses();
 
-    Ok(Json(response))
+    Ok(response)
+}
+
+// The domain queried and cached for a request: subdomains share their registrable
+// domain's lookup ("mail.example.com" -> "example.com") unless collapse_subdomains is off
+fn lookup_domain(config: &Config, domain: String) -> String {
+    if !config.collapse_subdomains {
+        return domain;
+    }
+    whois_service::domain::registrable_domain(&domain).unwrap_or(domain)
 }
 
 // Helper function to handle cache writes - follows SRP
@@ -388,27 +585,36 @@ fn build_whois_response(
         String,
         String,
         Option<whois_service::ParsedWhoisData>,
-        Vec<String>,
+        ParsingReport,
+        Vec<ReferralHop>,
     ),
     query_time: u64,
     include_debug: bool,
 ) -> WhoisResponse {
+    let domain_unicode = whois_service::domain::unicode_form(&domain);
     WhoisResponse {
+        domain_ascii: domain_unicode.is_some().then(|| domain.clone()),
+        domain_unicode,
         domain,
         whois_server: result.0,
         raw_data: result.1,
         parsed_data: result.2,
         cached: false,
+        degraded: false,
+        data_age_seconds: None,
         query_time_ms: query_time,
-        parsing_analysis: if include_debug { Some(result.3) } else { None },
+        parsing_analysis: include_debug.then(|| result.3.lines()),
+        parsing_report: include_debug.then_some(result.3),
+        referral_chain: result.4,
     }
 }
 
 async fn whois_lookup_post(
     State(state): State<AppState>,
+    format: OutputFormat,
     Json(payload): Json<WhoisQuery>,
-) -> Result<Json<WhoisResponse>, WhoisError> {
-    whois_lookup(Query(payload), State(state)).await
+) -> Result<Formatted, WhoisError> {
+    whois_lookup(Query(payload), State(state), format).await
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -425,12 +631,13 @@ async fn whois_lookup_post(
 async fn whois_debug(
     Query(params): Query<WhoisQuery>,
     State(state): State<AppState>,
-) -> Result<Json<WhoisResponse>, WhoisError> {
+    format: OutputFormat,
+) -> Result<Formatted, WhoisError> {
     let start_time = std::time::Instant::now();
 
     // Validate domain using centralized validation
     let validated_domain = ValidatedDomain::from_query_params(&params)?;
-    let domain = validated_domain.0;
+    let domain = lookup_domain(&state.config, validated_domain.0);
 
     // Increment request counter
     metrics::increment_requests(&domain);
@@ -444,7 +651,7 @@ async fn whois_debug(
 
     metrics::record_query_time(query_time);
 
-    Ok(Json(response))
+    Ok(Formatted(format, response))
 }
 
 // Path-based whois lookup for easier testing
@@ -464,12 +671,33 @@ async fn whois_debug(
 async fn whois_lookup_path(
     validated_domain: ValidatedDomain,
     State(state): State<AppState>,
-) -> Result<Json<WhoisResponse>, WhoisError> {
+    format: OutputFormat,
+) -> Result<Formatted, WhoisError> {
     let query = WhoisQuery {
         domain: validated_domain.0,
         fresh: false,
     };
-    whois_lookup(Query(query), State(state)).await
+    whois_lookup(Query(query), State(state), format).await
+}
+
+#[cfg(feature = "history")]
+#[derive(Serialize)]
+struct HistoryResponse {
+    domain: String,
+    entries: Vec<whois_service::HistoryEntry>,
+}
+
+// Every recorded lookup result for a domain, oldest first
+#[cfg(feature = "history")]
+async fn whois_history(
+    validated_domain: ValidatedDomain,
+    State(state): State<AppState>,
+) -> Result<Json<HistoryResponse>, WhoisError> {
+    let entries = state.client.history(&validated_domain.0).await?;
+    Ok(Json(HistoryResponse {
+        domain: validated_domain.0,
+        entries,
+    }))
 }
 
 // Path-based debug lookup for easier testing
@@ -489,12 +717,13 @@ async fn whois_lookup_path(
 async fn whois_debug_path(
     validated_domain: ValidatedDomain,
     State(state): State<AppState>,
-) -> Result<Json<WhoisResponse>, WhoisError> {
+    format: OutputFormat,
+) -> Result<Formatted, WhoisError> {
     let query = WhoisQuery {
         domain: validated_domain.0,
         fresh: false,
     };
-    whois_debug(Query(query), State(state)).await
+    whois_debug(Query(query), State(state), format).await
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -529,3 +758,15 @@ async fn check_cache(cache_service: &CacheService, domain: &str) -> Option<Whois
         }
     }
 }
+
+// Helper function to fetch expired cache entries when upstream lookups fail
+async fn check_stale_cache(cache_service: &CacheService, domain: &str) -> Option<WhoisResponse> {
+    match cache_service.get_stale(domain).await {
+        Ok(stale) => stale,
+        Err(e) => {
+            tracing::warn!("Cache read error for {}: {}", domain, e);
+            metrics::increment_errors("cache_read_error");
+            None
+        }
+    }
+}
@@ -57,16 +57,6 @@ pub fn increment_requests(domain: &str) {
     counter!("whois_requests_total", "tld" => tld).increment(1);
 }
 
-#[cfg(feature = "server")]
-pub fn increment_cache_hits() {
-    counter!("whois_cache_hits_total").increment(1);
-}
-
-#[cfg(feature = "server")]
-pub fn increment_cache_misses() {
-    counter!("whois_cache_misses_total").increment(1);
-}
-
 #[cfg(feature = "server")]
 pub fn increment_errors(error_type: &str) {
     counter!("whois_errors_total", "error_type" => error_type.to_string()).increment(1);
@@ -97,7 +87,7 @@ pub async fn metrics_handler() -> impl IntoResponse {
 fn extract_tld(domain: &str) -> String {
     domain
         .split('.')
-        .last()
+        .next_back()
         .unwrap_or("unknown")
         .to_lowercase()
 } 
\ No newline at end of file
@@ -0,0 +1,118 @@
+//! In-memory `WhoisLookup` for tests (feature `test-util`)
+//!
+//! Returns canned responses without touching the network and records every
+//! domain it was asked for. Domains without a canned reply fail with
+//! `WhoisError::DomainNotFound`, like an unregistered domain would.
+
+use crate::{lookup::WhoisLookup, ParsedWhoisData, WhoisError, WhoisResponse};
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+type ErrorFactory = Arc<dyn Fn(&str) -> WhoisError + Send + Sync>;
+
+#[derive(Clone)]
+enum Reply {
+    Response(Box<WhoisResponse>),
+    Error(ErrorFactory),
+}
+
+/// Canned lookup results keyed by domain; clones share replies and call log
+#[derive(Clone, Default)]
+pub struct MockWhoisClient {
+    replies: Arc<Mutex<HashMap<String, Reply>>>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockWhoisClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer lookups of `domain` with `response`
+    pub fn with_response(self, domain: &str, response: WhoisResponse) -> Self {
+        self.set_reply(domain, Reply::Response(Box::new(response)));
+        self
+    }
+
+    /// Answer lookups of `domain` with a response built around `parsed`
+    pub fn with_parsed(self, domain: &str, parsed: ParsedWhoisData) -> Self {
+        let response = WhoisResponse {
+            domain: Self::normalize(domain),
+            whois_server: "mock".to_string(),
+            parsed_data: Some(parsed),
+            ..WhoisResponse::default()
+        };
+        self.with_response(domain, response)
+    }
+
+    /// Fail lookups of `domain` with the error built by `error`
+    pub fn with_error<F>(self, domain: &str, error: F) -> Self
+    where
+        F: Fn(&str) -> WhoisError + Send + Sync + 'static,
+    {
+        self.set_reply(domain, Reply::Error(Arc::new(error)));
+        self
+    }
+
+    /// Domains looked up so far, in call order
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn set_reply(&self, domain: &str, reply: Reply) {
+        self.replies.lock().unwrap().insert(Self::normalize(domain), reply);
+    }
+
+    fn normalize(domain: &str) -> String {
+        domain.trim().trim_end_matches('.').to_lowercase()
+    }
+
+    fn reply(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        let domain = Self::normalize(domain);
+        self.calls.lock().unwrap().push(domain.clone());
+
+        let reply = self.replies.lock().unwrap().get(&domain).cloned();
+        match reply {
+            Some(Reply::Response(response)) => Ok(*response),
+            Some(Reply::Error(error)) => Err(error(&domain)),
+            None => Err(WhoisError::DomainNotFound(domain)),
+        }
+    }
+}
+
+#[async_trait]
+impl WhoisLookup for MockWhoisClient {
+    async fn lookup(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        self.reply(domain)
+    }
+
+    async fn lookup_fresh(&self, domain: &str) -> Result<WhoisResponse, WhoisError> {
+        self.reply(domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_replies_and_records_calls() {
+        let parsed = ParsedWhoisData {
+            registrar: Some("Mock Registrar".to_string()),
+            ..Default::default()
+        };
+        let mock = MockWhoisClient::new()
+            .with_parsed("example.com", parsed)
+            .with_error("slow.com", |_| WhoisError::Timeout);
+        let lookup: &dyn WhoisLookup = &mock;
+
+        let response = lookup.lookup("Example.COM.").await.unwrap();
+        assert_eq!(response.parsed_data.unwrap().registrar.as_deref(), Some("Mock Registrar"));
+        assert!(matches!(lookup.lookup_fresh("slow.com").await, Err(WhoisError::Timeout)));
+        assert!(matches!(lookup.lookup("unknown.com").await, Err(WhoisError::DomainNotFound(_))));
+        assert_eq!(mock.calls(), vec!["example.com", "slow.com", "unknown.com"]);
+    }
+}
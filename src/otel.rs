@@ -0,0 +1,46 @@
+//! OpenTelemetry trace export for the server (feature `otel`)
+//!
+//! Spans are exported over OTLP/HTTP and every request span joins the trace
+//! named by the caller's `traceparent` header, so lookups appear inside the
+//! distributed traces of the services calling this API. The exporter is
+//! configured through the standard `OTEL_*` environment variables
+//! (`OTEL_EXPORTER_OTLP_ENDPOINT`, `OTEL_SERVICE_NAME`, ...).
+
+use axum::http::HeaderMap;
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Build the OTLP pipeline and the tracing layer feeding it
+///
+/// Keep the returned provider alive and shut it down on exit so buffered spans
+/// are flushed.
+pub fn init<S>() -> Result<(OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>, SdkTracerProvider), Box<dyn std::error::Error>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = opentelemetry_otlp::SpanExporter::builder().with_http().build()?;
+    let mut resource = Resource::builder();
+    if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name(env!("CARGO_PKG_NAME"));
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")));
+    Ok((layer, provider))
+}
+
+/// Parent `span` to the trace named by the request's `traceparent` header, if any
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent);
+}
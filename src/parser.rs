@@ -1,238 +1,1234 @@
-use crate::ParsedWhoisData;
+//! Whois response parsing, usable on its own
+//!
+//! `WhoisParser::parse` turns the text of a port-43 domain record into
+//! `ParsedWhoisData` without touching the network, so archived responses
+//! (historical dumps, fixtures, logs) parse exactly like live ones.
+//! `WhoisParser::parse`, `WhoisParser::parse_with_report` and
+//! `StreamingParser` are the stable entry points; the field patterns behind
+//! them keep improving between releases, the signatures don't.
+//!
+//! ```rust
+//! use whois_service::WhoisParser;
+//!
+//! let parsed = WhoisParser::parse("Registrar: Example Registrar, Inc.\nName Server: NS1.EXAMPLE.COM\n");
+//! assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar, Inc."));
+//! assert_eq!(parsed.name_servers, vec!["ns1.example.com"]);
+//! ```
+
+use crate::{Contact, DnssecStatus, DomainAvailability, EppStatus, ParsedAsnWhoisData, ParsedIpWhoisData, ParsedWhoisData};
 use chrono::{DateTime, Utc, NaiveDateTime};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use tracing::debug;
 
+// Lowercased "domain not registered" phrases used by registries
+const NOT_FOUND_PATTERNS: &[&str] = &[
+    "no match for",                  // Verisign (.com, .net)
+    "no match!!",                    // JPRS (.jp)
+    "not found",                     // PIR (.org), AFNIC (.fr), many ccTLDs
+    "no data found",                 // Afilias legacy
+    "no entries found",              // Traficom (.fi), others
+    "no object found",
+    "no matching record",            // CNNIC (.cn)
+    "status: free",                  // DENIC (.de)
+    "status: available",             // DNS Belgium (.be), EURid (.eu)
+    "is free",                       // SIDN (.nl)
+    "is available for registration",
+    "object does not exist",
+];
+
+// Lowercased throttling messages registries send instead of data
+const RATE_LIMIT_PATTERNS: &[&str] = &[
+    "access is too fast",            // CNNIC (.cn)
+    "query rate exceeded",
+    "rate limit exceeded",
+    "limit exceeded",                // DENIC (.de), Nominet (.uk)
+    "access control limit",
+    "too many queries",
+    "too many requests",
+    "quota exceeded",
+    "exceeded the maximum allowable number",
+    "please wait a moment",
+];
+
+// Lowercased markers of privacy/proxy services and GDPR redaction in contact fields
+const PRIVACY_PATTERNS: &[&str] = &[
+    "redacted",                      // "REDACTED FOR PRIVACY" (ICANN temp spec)
+    "privacy",                       // "Whois Privacy Service", "Contact Privacy Inc."
+    "domains by proxy",
+    "withheld",                      // Namecheap "Withheld for Privacy ehf"
+    "not disclosed",
+    "data protected",
+    "gdpr masked",
+    "identity protect",
+    "perfect privacy",
+];
+
+// Fields a usable domain record normally has, checked by `ParsingReport`
+const REPORTED_FIELDS: &[&str] =
+    &["registrar", "creation_date", "expiration_date", "updated_date", "name_servers", "status", "registrant"];
+
+/// What a parse found, for judging parse quality (the `/debug` endpoint)
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ParsingReport {
+    /// Key fields present in the record
+    pub fields_found: Vec<String>,
+    /// Key fields the record didn't yield
+    pub fields_missing: Vec<String>,
+    /// 1-based line numbers of the record lines each typed field was read from
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub matched_lines: BTreeMap<String, Vec<usize>>,
+    /// Dates found but in a format the parser doesn't understand, by field
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unparsed_dates: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Informational steps, e.g. followed RDAP redirects
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl ParsingReport {
+    /// Report the key fields and unparsed dates of a parsed record
+    pub fn for_parsed(parsed: &ParsedWhoisData) -> Self {
+        let mut report = Self::default();
+        for &field in REPORTED_FIELDS {
+            let found = match field {
+                "registrar" => parsed.registrar.is_some(),
+                "creation_date" => parsed.creation_date_raw.is_some() || parsed.creation_date.is_some(),
+                "expiration_date" => parsed.expiration_date_raw.is_some() || parsed.expiration_date.is_some(),
+                "updated_date" => parsed.updated_date_raw.is_some() || parsed.updated_date.is_some(),
+                "name_servers" => !parsed.name_servers.is_empty(),
+                "status" => !parsed.status.is_empty(),
+                _ => parsed.registrant.is_some(),
+            };
+            let list = if found { &mut report.fields_found } else { &mut report.fields_missing };
+            list.push(field.to_string());
+        }
+
+        // Unparsable dates are only kept raw
+        let dates = [
+            ("creation_date", &parsed.creation_date_raw, &parsed.creation_date),
+            ("expiration_date", &parsed.expiration_date_raw, &parsed.expiration_date),
+            ("updated_date", &parsed.updated_date_raw, &parsed.updated_date),
+        ];
+        for (field, raw, normalized) in dates {
+            if let (Some(value), None) = (raw, normalized) {
+                report.unparsed_dates.insert(field.to_string(), value.clone());
+                report.warnings.push(format!("Unrecognized {} format: {}", field, value));
+            }
+        }
+        report
+    }
+
+    /// Merge another source's report into this one (e.g. RDAP and WHOIS of a merged lookup)
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn absorb(&mut self, other: ParsingReport) {
+        for (field, lines) in other.matched_lines {
+            self.matched_lines.entry(field).or_default().extend(lines);
+        }
+        self.warnings.extend(other.warnings);
+        self.notes.extend(other.notes);
+    }
+
+    /// The report as text lines
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["=== PARSING ANALYSIS ===".to_string()];
+        lines.extend(self.fields_found.iter().map(|field| format!("✓ {}", field)));
+        lines.extend(self.fields_missing.iter().map(|field| format!("✗ {}: NOT FOUND", field)));
+        for (field, numbers) in &self.matched_lines {
+            let numbers: Vec<String> = numbers.iter().map(usize::to_string).collect();
+            lines.push(format!("{} from line {}", field, numbers.join(", ")));
+        }
+        lines.extend(self.warnings.iter().map(|warning| format!("! {}", warning)));
+        lines.extend(self.notes.iter().map(|note| format!("✓ {}", note)));
+        lines
+    }
+}
+
+impl fmt::Display for ParsingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lines().join("\n"))
+    }
+}
+
+// Date-time layouts after any timezone suffix is removed, most common first
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",            // 2025-05-18T13:36:06.0 (ISO 8601, "Z" stripped)
+    "%Y-%m-%d %H:%M:%S%.f",            // 2025-05-18 13:36:06
+    "%Y/%m/%d %H:%M:%S",               // 2025/05/18 13:36:06 (JPRS .jp)
+    "%Y/%m/%d %H:%M",                  // 2025/05/18 13:36
+    "%d.%m.%Y %H:%M:%S",               // 18.05.2025 14:00:00 (NIC Chile .cl, .ru resellers)
+    "%Y.%m.%d %H:%M:%S",               // 2025.05.18 13:36:06 (.kr, .hu)
+    "%d-%b-%Y %H:%M:%S",               // 18-May-2025 13:36:06
+    "%a %b %d %H:%M:%S %Y",            // Sun May 18 13:36:06 2025 (.tw)
+    "%Y%m%d %H:%M:%S",                 // 20250518 13:36:06
+];
+
+// Date-only layouts, taken as midnight
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",                        // 2025-05-18
+    "%d-%b-%Y",                        // 18-May-2025 (Nominet .uk)
+    "%d %b %Y",                        // 18 May 2025
+    "%Y/%m/%d",                        // 2025/05/18 (JPRS .jp)
+    "%d.%m.%Y",                        // 18.05.2025 (DENIC .de, .cl)
+    "%Y.%m.%d",                        // 2025.05.18 (.hu)
+    "%Y. %m. %d.",                     // 2025. 05. 18. (KISA .kr)
+    "%Y%m%d",                          // 20250518 (Registro.br)
+    "%B %d %Y",                        // May 18 2025
+];
+
+// Registries writing slash dates day first: 05/03/2025 is 5 March (.pt, .br).
+// Elsewhere a slash date is read only when its order is unambiguous (see `parse_slash_date`).
+const DAY_FIRST_TLDS: &[&str] = &["pt", "br"];
+
+// Timezone abbreviations seen in registry output, with their UTC offset in minutes.
+// Ambiguous ones (CST, IST) use the registry's region: China and India.
+const TIMEZONE_ABBREVIATIONS: &[(&str, i32)] = &[
+    ("UTC", 0), ("GMT", 0), ("UT", 0), ("Z", 0), ("WET", 0),
+    ("BST", 60), ("CET", 60), ("WEST", 60), ("MET", 60),
+    ("CEST", 120), ("EET", 120), ("MEST", 120),
+    ("EEST", 180), ("MSK", 180), ("TRT", 180),
+    ("IST", 330),
+    ("ICT", 420), ("WIB", 420),
+    ("CST", 480), ("HKT", 480), ("SGT", 480), ("PHT", 480), ("AWST", 480),
+    ("JST", 540), ("KST", 540),
+    ("AEST", 600), ("AEDT", 660),
+    ("NZST", 720), ("NZDT", 780),
+    ("BRT", -180), ("ART", -180), ("CLST", -180),
+    ("CLT", -240), ("EDT", -240), ("AST", -240),
+    ("EST", -300), ("CDT", -300),
+    ("MDT", -360),
+    ("MST", -420), ("PDT", -420),
+    ("PST", -480),
+];
+
 pub struct WhoisParser;
 
+impl Default for WhoisParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WhoisParser {
     pub fn new() -> Self {
         Self
     }
 
+    /// Parse the text of a whois domain record
+    ///
+    /// Fields the record doesn't contain are left empty; there is no failure
+    /// case, so check the fields you need (e.g. `registrar`, `creation_date`).
+    pub fn parse(data: &str) -> ParsedWhoisData {
+        Self::parse_with_report(data).0
+    }
+
+    /// Parse a domain record and report what was found, for judging parse quality
+    pub fn parse_with_report(data: &str) -> (ParsedWhoisData, ParsingReport) {
+        Self::parse_with_report_for(data, None)
+    }
+
+    /// `parse_with_report` for a record from a known registry
+    ///
+    /// The TLD ("pt", "com.br" or a whole domain) decides how registry-specific
+    /// formats such as slash dates are read. Without it the TLD of the record's
+    /// own "Domain Name" line is used.
+    pub fn parse_with_report_for(data: &str, tld: Option<&str>) -> (ParsedWhoisData, ParsingReport) {
+        let mut parser = StreamingParser::new();
+        parser.feed(data.as_bytes());
+        parser.finish_with_report(tld)
+    }
+
+    /// `parse` for callers of the original API; always `Some`
     pub fn parse_whois_data(&self, data: &str) -> Option<ParsedWhoisData> {
-        let mut parsed = ParsedWhoisData {
+        Some(Self::parse(data))
+    }
+
+    fn empty_parsed() -> ParsedWhoisData {
+        ParsedWhoisData {
             registrar: None,
+            registrar_iana_id: None,
+            registrar_url: None,
+            registrar_abuse_email: None,
+            registrar_abuse_phone: None,
+            reseller: None,
+            registry_domain_id: None,
             creation_date: None,
             expiration_date: None,
             updated_date: None,
+            creation_date_raw: None,
+            expiration_date_raw: None,
+            updated_date_raw: None,
             name_servers: Vec::new(),
+            name_servers_unicode: Vec::new(),
+            name_server_ips: HashMap::new(),
             status: Vec::new(),
-            registrant_name: None,
-            registrant_email: None,
-            admin_email: None,
-            tech_email: None,
+            status_codes: Vec::new(),
+            dnssec: None,
+            registrant: None,
+            admin: None,
+            tech: None,
+            billing: None,
+            privacy_protected: None,
             created_ago: None,
             updated_ago: None,
             expires_in: None,
+            confidence: 0.0,
+            extra_fields: HashMap::new(),
+            field_sources: HashMap::new(),
+        }
+    }
+
+    /// Fold one `key: value` line into the parsed record
+    // Arms are first-match-wins on the key; folding the "already set" checks into
+    // the guards would let a repeated key fall through to a less specific arm.
+    #[allow(clippy::collapsible_match)]
+    fn apply_field(parsed: &mut ParsedWhoisData, raw_key: &str, value: &str) -> Option<&'static str> {
+        let key = raw_key.to_lowercase();
+
+        // Match field patterns more intelligently (order matters - most specific first)
+        match key.as_str() {
+            // Expiration date patterns (check first to catch "Registrar Registration Expiration Date")
+            k if k.contains("expir") || k.contains("expires") => {
+                if parsed.expiration_date.is_none() {
+                    parsed.expiration_date = Some(value.to_string());
+                }
+                Some("expiration_date")
+            },
+            
+            // Creation date patterns
+            k if k.contains("creation") || k.contains("created") || k == "registered" => {
+                if parsed.creation_date.is_none() {
+                    parsed.creation_date = Some(value.to_string());
+                }
+                Some("creation_date")
+            },
+            
+            // Updated date patterns
+            k if k.contains("updated") || k.contains("modified") || k.contains("last updated") => {
+                if parsed.updated_date.is_none() {
+                    parsed.updated_date = Some(value.to_string());
+                }
+                Some("updated_date")
+            },
+            
+            // Registry handle ("Registry Domain ID", "Domain ID" at older registries, "ROID")
+            k if k == "registry domain id" || k == "domain id" || k == "roid" => {
+                if parsed.registry_domain_id.is_none() {
+                    parsed.registry_domain_id = Some(value.to_string());
+                }
+                Some("registry_domain_id")
+            },
+            k if k == "reseller" || k == "reseller name" => {
+                if parsed.reseller.is_none() {
+                    parsed.reseller = Some(value.to_string());
+                }
+                Some("reseller")
+            },
+
+            // Registrar detail patterns (before the generic registrar arm)
+            k if k.contains("registrar") && k.contains("iana") => {
+                if parsed.registrar_iana_id.is_none() {
+                    parsed.registrar_iana_id = Some(value.to_string());
+                }
+                Some("registrar_iana_id")
+            },
+            k if (k.contains("registrar") || k == "referral url") && k.contains("url") => {
+                if parsed.registrar_url.is_none() {
+                    parsed.registrar_url = Some(value.to_string());
+                }
+                Some("registrar_url")
+            },
+            k if k.contains("abuse") && k.contains("email") => {
+                if parsed.registrar_abuse_email.is_none() {
+                    parsed.registrar_abuse_email = Some(value.to_string());
+                }
+                Some("registrar_abuse_email")
+            },
+            k if k.contains("abuse") && k.contains("phone") => {
+                if parsed.registrar_abuse_phone.is_none() {
+                    parsed.registrar_abuse_phone = Some(value.to_string());
+                }
+                Some("registrar_abuse_phone")
+            },
+            
+            // Registrar patterns (after date patterns to avoid conflicts)
+            k if k.contains("registrar") && !k.contains("whois") && !k.contains("url") && !k.contains("abuse") && !k.contains("expir") && !k.contains("registration") => {
+                if parsed.registrar.is_none() {
+                    parsed.registrar = Some(value.to_string());
+                }
+                Some("registrar")
+            },
+            
+            // Name server patterns
+            k if k.contains("name server") || k == "nserver" || k == "ns" => {
+                // "ns1.example.de 192.0.2.1 2001:db8::1": the host name, then any glue addresses
+                let mut words = value.split(|c: char| c.is_whitespace() || c == ',');
+                let server = words.next().unwrap_or(value);
+                if !parsed.name_servers.contains(&server.to_string()) {
+                    parsed.name_servers.push(server.to_string());
+                }
+                let glue = words
+                    .map(|word| word.trim_matches(|c| c == '[' || c == ']' || c == '(' || c == ')'))
+                    .filter_map(|word| word.parse::<std::net::IpAddr>().ok())
+                    .map(|ip| ip.to_string());
+                for ip in glue {
+                    let ips = parsed.name_server_ips.entry(server.to_string()).or_default();
+                    if !ips.contains(&ip) {
+                        ips.push(ip);
+                    }
+                }
+                Some("name_servers")
+            },
+            
+            // Contact patterns ("Registrant Name", "Admin Email", "Tech Phone", ...)
+            // Checked before status so "Registrant State/Province" isn't read as a status
+            k if Self::contact_role(k).is_some() => {
+                let (role, field) = Self::contact_role(k)?;
+                if !value.to_lowercase().contains("select request") {
+                    let contact = match role {
+                        "registrant" => &mut parsed.registrant,
+                        "admin" => &mut parsed.admin,
+                        "tech" => &mut parsed.tech,
+                        _ => &mut parsed.billing,
+                    };
+                    Self::set_contact_field(contact, field, value);
+                }
+                Some(role)
+            },
+            
+            // Status patterns
+            k if k.contains("status") || k.contains("state") => {
+                if !parsed.status.contains(&value.to_string()) {
+                    parsed.status.push(value.to_string());
+                }
+                Some("status")
+            },
+            
+            // DNSSEC patterns ("DNSSEC: signedDelegation", "dnssec: yes")
+            k if k.contains("dnssec") => {
+                if parsed.dnssec.is_none() {
+                    parsed.dnssec = DnssecStatus::parse(value);
+                }
+                Some("dnssec")
+            },
+            
+            // Keep unrecognized fields for registry-specific consumers
+            _ => {
+                parsed.extra_fields.entry(raw_key.to_string()).or_default().push(value.to_string());
+                None
+            }
+        }
+    }
+
+    /// Derive status codes, privacy and relative dates once every line is applied
+    fn finish_parsed(&self, mut parsed: ParsedWhoisData, tld: Option<&str>) -> ParsedWhoisData {
+        parsed.status_codes = EppStatus::parse_all(&parsed.status);
+        parsed.privacy_protected = Self::detect_privacy(&parsed);
+        parsed.normalize_name_servers();
+
+        parsed.normalize_dates(|date| self.parse_date_for_tld(date, tld));
+        parsed.set_confidence();
+        parsed
+    }
+
+    /// `parse_with_report` for callers of the original API; the record is always `Some`
+    pub fn parse_whois_data_with_analysis(&self, data: &str) -> (Option<ParsedWhoisData>, Vec<String>) {
+        let (parsed, report) = Self::parse_with_report(data);
+        (Some(parsed), report.lines())
+    }
+
+    /// Collect every `key: value` line as written, without interpreting any field
+    pub fn parse_to_map(&self, data: &str) -> HashMap<String, Vec<String>> {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (_, key, value) in Self::key_value_lines(data) {
+            map.entry(key.to_string()).or_default().push(value.to_string());
+        }
+        map
+    }
+
+    /// Non-empty `key: value` pairs with their 1-based line number, skipping
+    /// comments and the ">>> Last update" trailer
+    fn key_value_lines(data: &str) -> impl Iterator<Item = (usize, &str, &str)> {
+        data.lines().enumerate().filter_map(|(i, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') || line.starts_with('#') || line.starts_with(">>>") {
+                return None;
+            }
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| (i + 1, key.trim(), value))
+        })
+    }
+
+    /// Detect a "slow down" reply in place of whois data
+    pub fn detect_rate_limit(&self, data: &str) -> bool {
+        let lower = data.to_lowercase();
+        // Real records can mention limits in their legal notice; only flag replies without a domain record
+        !lower.contains("domain name:") && RATE_LIMIT_PATTERNS.iter().any(|pattern| lower.contains(pattern))
+    }
+
+    /// Check published contacts for privacy/redaction markers (None when there are no contacts)
+    pub(crate) fn detect_privacy(parsed: &ParsedWhoisData) -> Option<bool> {
+        let contacts: Vec<&Contact> = [&parsed.registrant, &parsed.admin, &parsed.tech]
+            .into_iter()
+            .flatten()
+            .collect();
+        if contacts.is_empty() {
+            return None;
+        }
+
+        let protected = contacts.iter().any(|contact| {
+            [&contact.name, &contact.organization, &contact.email]
+                .into_iter()
+                .flatten()
+                .any(|value| {
+                    let value = value.to_lowercase();
+                    PRIVACY_PATTERNS.iter().any(|pattern| value.contains(pattern))
+                })
+        });
+        Some(protected)
+    }
+
+    /// Split a contact key such as "registrant name" or "admin e-mail" into role and field
+    fn contact_role(key: &str) -> Option<(&'static str, &str)> {
+        let roles = [
+            ("registrant", "registrant"),
+            ("administrative contact", "admin"),
+            ("admin", "admin"),
+            ("technical contact", "tech"),
+            ("tech", "tech"),
+            ("billing", "billing"),
+        ];
+        roles.iter().find_map(|(prefix, role)| {
+            key.strip_prefix(prefix).map(|field| (*role, field.trim()))
+        })
+    }
+
+    /// Store one contact field, creating the contact on first use (first value wins)
+    fn set_contact_field(contact: &mut Option<Contact>, field: &str, value: &str) {
+        let value = value.to_string();
+        let contact = contact.get_or_insert_with(Contact::default);
+        let slot = match field {
+            f if f.contains("email") || f.contains("e-mail") => &mut contact.email,
+            f if f.contains("phone") && !f.contains("ext") => &mut contact.phone,
+            f if f.contains("organization") || f.contains("organisation") || f == "org" => &mut contact.organization,
+            f if f.contains("street") || f.contains("address") => {
+                contact.street.push(value);
+                return;
+            },
+            f if f.contains("city") => &mut contact.city,
+            f if f.contains("country") => &mut contact.country,
+            f if f.contains("name") || f.is_empty() => &mut contact.name,
+            _ => return, // IDs, fax, postal code, etc.
         };
+        if slot.is_none() {
+            *slot = Some(value);
+        }
+    }
+
+    /// Classify a whois response as registered, available or unknown
+    ///
+    /// Registration data (registrar, creation date or name servers) wins over
+    /// not-found phrases, since some registries include boilerplate mentioning them.
+    pub fn detect_availability(&self, data: &str) -> DomainAvailability {
+        let registered = self.parse_whois_data(data).is_some_and(|parsed| {
+            // A creation date counts even when only its raw text could be kept
+            parsed.registrar.is_some()
+                || parsed.creation_date.is_some()
+                || parsed.creation_date_raw.is_some()
+                || !parsed.name_servers.is_empty()
+        });
+        if registered {
+            return DomainAvailability::Registered;
+        }
+
+        let lower = data.to_lowercase();
+        if NOT_FOUND_PATTERNS.iter().any(|pattern| lower.contains(pattern)) {
+            return DomainAvailability::Available;
+        }
+
+        DomainAvailability::Unknown
+    }
+
+    /// Parse RIR whois output (ARIN, RIPE, APNIC, LACNIC, AFRINIC) for an IP address
+    pub fn parse_ip_whois_data(&self, data: &str) -> Option<ParsedIpWhoisData> {
+        let empty = || ParsedIpWhoisData {
+            netblock: None,
+            cidr: Vec::new(),
+            net_name: None,
+            organization: None,
+            country: None,
+            abuse_emails: Vec::new(),
+            abuse_phones: Vec::new(),
+            registration_date: None,
+            updated_date: None,
+        };
+        let mut parsed = empty();
+        let mut description = None;
 
         for line in data.lines() {
             let line = line.trim();
-            if line.is_empty() || line.starts_with('%') || line.starts_with('#') || line.starts_with(">>>") {
+
+            // RIPE-style databases announce the abuse mailbox in a comment line
+            if let Some(email) = Self::extract_abuse_comment(line) {
+                Self::push_unique(&mut parsed.abuse_emails, email);
                 continue;
             }
 
-            if let Some((key, value)) = line.split_once(':') {
-                let key = key.trim().to_lowercase();
-                let value = value.trim();
-                
-                if value.is_empty() {
-                    continue;
-                }
-
-                // Match field patterns more intelligently (order matters - most specific first)
-                match key.as_str() {
-                    // Expiration date patterns (check first to catch "Registrar Registration Expiration Date")
-                    k if k.contains("expir") || k.contains("expires") => {
-                        if parsed.expiration_date.is_none() {
-                            parsed.expiration_date = Some(value.to_string());
-                        }
-                    },
-                    
-                    // Creation date patterns
-                    k if k.contains("creation") || k.contains("created") || k == "registered" => {
-                        if parsed.creation_date.is_none() {
-                            parsed.creation_date = Some(value.to_string());
-                        }
-                    },
-                    
-                    // Updated date patterns
-                    k if k.contains("updated") || k.contains("modified") || k.contains("last updated") => {
-                        if parsed.updated_date.is_none() {
-                            parsed.updated_date = Some(value.to_string());
-                        }
-                    },
-                    
-                    // Registrar patterns (after date patterns to avoid conflicts)
-                    k if k.contains("registrar") && !k.contains("whois") && !k.contains("url") && !k.contains("abuse") && !k.contains("expir") && !k.contains("registration") => {
-                        if parsed.registrar.is_none() {
-                            parsed.registrar = Some(value.to_string());
-                        }
-                    },
-                    
-                    // Name server patterns
-                    k if k.contains("name server") || k == "nserver" || k == "ns" => {
-                        // Extract just the hostname, ignore IP addresses
-                        let server = value.split_whitespace().next().unwrap_or(value);
-                        if !parsed.name_servers.contains(&server.to_string()) {
-                            parsed.name_servers.push(server.to_string());
-                        }
-                    },
-                    
-                    // Status patterns
-                    k if k.contains("status") || k.contains("state") => {
-                        if !parsed.status.contains(&value.to_string()) {
-                            parsed.status.push(value.to_string());
-                        }
-                    },
-                    
-                    // Registrant name patterns
-                    k if k.starts_with("registrant") && (k.contains("name") || k.contains("organization") || k.contains("org") || k == "registrant") => {
-                        if parsed.registrant_name.is_none() && !value.to_lowercase().contains("select request") {
-                            parsed.registrant_name = Some(value.to_string());
-                        }
-                    },
-                    
-                    // Email patterns
-                    k if k.contains("registrant") && k.contains("email") => {
-                        if parsed.registrant_email.is_none() && !value.to_lowercase().contains("select request") {
-                            parsed.registrant_email = Some(value.to_string());
-                        }
-                    },
-                    k if k.contains("admin") && k.contains("email") => {
-                        if parsed.admin_email.is_none() && !value.to_lowercase().contains("select request") {
-                            parsed.admin_email = Some(value.to_string());
-                        }
-                    },
-                    k if k.contains("tech") && k.contains("email") => {
-                        if parsed.tech_email.is_none() && !value.to_lowercase().contains("select request") {
-                            parsed.tech_email = Some(value.to_string());
-                        }
-                    },
-                    
-                    _ => {} // Ignore unrecognized fields
-                }
+            let Some((key, value)) = Self::split_rir_line(line) else {
+                continue;
+            };
+
+            match key.as_str() {
+                "netrange" | "inetnum" | "inet6num" => {
+                    // ARIN lists the parent allocation first - the last network is the most specific
+                    if parsed.netblock.is_some() {
+                        parsed = empty();
+                        description = None;
+                    }
+                    parsed.netblock = Some(value.to_string());
+                    if value.contains('/') {
+                        Self::push_unique(&mut parsed.cidr, value.to_string());
+                    }
+                },
+                "cidr" => {
+                    for prefix in value.split(',') {
+                        Self::push_unique(&mut parsed.cidr, prefix.trim().to_string());
+                    }
+                },
+                "netname" if parsed.net_name.is_none() => parsed.net_name = Some(value.to_string()),
+                "orgname" | "org-name" | "organization" | "owner" if parsed.organization.is_none() => {
+                    parsed.organization = Some(value.to_string());
+                },
+                "descr" if description.is_none() => description = Some(value.to_string()),
+                "country" if parsed.country.is_none() => parsed.country = Some(value.to_uppercase()),
+                "orgabuseemail" | "abuse-mailbox" => Self::push_unique(&mut parsed.abuse_emails, value.to_string()),
+                "orgabusephone" => Self::push_unique(&mut parsed.abuse_phones, value.to_string()),
+                "regdate" | "created" if parsed.registration_date.is_none() => {
+                    parsed.registration_date = Some(value.to_string());
+                },
+                "updated" | "last-modified" | "changed" if parsed.updated_date.is_none() => {
+                    parsed.updated_date = Some(value.to_string());
+                },
+                _ => {}
             }
         }
 
-        // Calculate date-based fields
-        let now = Utc::now();
-        
-        // Calculate created_ago (days since creation)
-        if let Some(ref creation_date) = parsed.creation_date {
-            if let Some(created_dt) = self.parse_date(creation_date) {
-                let days_ago = (now - created_dt).num_days();
-                parsed.created_ago = Some(days_ago);
-            }
+        // APNIC/AFRINIC records often carry the holder only in the description
+        if parsed.organization.is_none() {
+            parsed.organization = description;
         }
-        
-        // Calculate updated_ago (days since last update)
-        if let Some(ref updated_date) = parsed.updated_date {
-            if let Some(updated_dt) = self.parse_date(updated_date) {
-                let days_ago = (now - updated_dt).num_days();
-                parsed.updated_ago = Some(days_ago);
+
+        Some(parsed)
+    }
+
+    /// Parse RIR whois output for an autonomous system number
+    pub fn parse_asn_whois_data(&self, data: &str, asn: u32) -> Option<ParsedAsnWhoisData> {
+        let mut parsed = ParsedAsnWhoisData {
+            asn,
+            handle: None,
+            as_name: None,
+            start_autnum: None,
+            end_autnum: None,
+            holder: None,
+            country: None,
+            allocation_date: None,
+            contact_emails: Vec::new(),
+            abuse_emails: Vec::new(),
+        };
+        let mut description = None;
+
+        for line in data.lines() {
+            let line = line.trim();
+
+            if let Some(email) = Self::extract_abuse_comment(line) {
+                Self::push_unique(&mut parsed.abuse_emails, email);
+                continue;
             }
-        }
-        
-        // Calculate expires_in (days until expiration, negative if expired)
-        if let Some(ref expiration_date) = parsed.expiration_date {
-            if let Some(expires_dt) = self.parse_date(expiration_date) {
-                let days_until = (expires_dt - now).num_days();
-                parsed.expires_in = Some(days_until);
+
+            let Some((key, value)) = Self::split_rir_line(line) else {
+                continue;
+            };
+
+            match key.as_str() {
+                "ashandle" | "aut-num" if parsed.handle.is_none() => parsed.handle = Some(value.to_string()),
+                "asname" | "as-name" if parsed.as_name.is_none() => parsed.as_name = Some(value.to_string()),
+                "orgname" | "org-name" | "organization" | "owner" if parsed.holder.is_none() => {
+                    parsed.holder = Some(value.to_string());
+                },
+                "descr" if description.is_none() => description = Some(value.to_string()),
+                "country" if parsed.country.is_none() => parsed.country = Some(value.to_uppercase()),
+                "regdate" | "created" if parsed.allocation_date.is_none() => {
+                    parsed.allocation_date = Some(value.to_string());
+                },
+                "orgabuseemail" | "abuse-mailbox" => Self::push_unique(&mut parsed.abuse_emails, value.to_string()),
+                k if k.contains("email") || k == "e-mail" => {
+                    Self::push_unique(&mut parsed.contact_emails, value.to_string());
+                },
+                _ => {}
             }
         }
 
+        if parsed.holder.is_none() {
+            parsed.holder = description;
+        }
+
         Some(parsed)
     }
 
-    pub fn parse_whois_data_with_analysis(&self, data: &str) -> (Option<ParsedWhoisData>, Vec<String>) {
-        let mut analysis = Vec::new();
-        
-        // Parse the data
-        let parsed_data = self.parse_whois_data(data);
-        
-        // Analyze what was found
-        analysis.push("=== PARSING ANALYSIS ===".to_string());
-        
-        if let Some(ref parsed) = parsed_data {
-            analysis.push(format!("✓ Registrar: {}", parsed.registrar.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-            analysis.push(format!("✓ Creation Date: {}", parsed.creation_date.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-            analysis.push(format!("✓ Expiration Date: {}", parsed.expiration_date.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-            analysis.push(format!("✓ Updated Date: {}", parsed.updated_date.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-            analysis.push(format!("✓ Registrant Name: {}", parsed.registrant_name.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-            analysis.push(format!("✓ Name Servers: {} found", parsed.name_servers.len()));
-            analysis.push(format!("✓ Status: {} found", parsed.status.len()));
-        }
-        
-        // Show lines that might contain registrant info
-        analysis.push("\n=== LINES CONTAINING 'REGISTRANT' ===".to_string());
-        for (i, line) in data.lines().enumerate() {
-            if line.to_lowercase().contains("registrant") {
-                analysis.push(format!("Line {}: {}", i + 1, line.trim()));
-            }
+    /// Split an RIR "key: value" line, skipping comments and empty values
+    fn split_rir_line(line: &str) -> Option<(String, &str)> {
+        if line.is_empty() || line.starts_with('%') || line.starts_with('#') {
+            return None;
         }
-        
-        // Show lines that might contain expiry info
-        analysis.push("\n=== LINES CONTAINING 'EXPIR' ===".to_string());
-        for (i, line) in data.lines().enumerate() {
-            if line.to_lowercase().contains("expir") {
-                analysis.push(format!("Line {}: {}", i + 1, line.trim()));
-            }
+
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        Some((key.trim().to_lowercase(), value))
+    }
+
+    /// Extract the address from "% Abuse contact for '...' is 'abuse@example.net'"
+    fn extract_abuse_comment(line: &str) -> Option<String> {
+        if !line.starts_with('%') || !line.to_lowercase().contains("abuse contact for") {
+            return None;
+        }
+
+        let email = line.rsplit('\'').nth(1)?.trim();
+        if email.contains('@') {
+            Some(email.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn push_unique(values: &mut Vec<String>, value: String) {
+        if !value.is_empty() && !values.contains(&value) {
+            values.push(value);
         }
-        
-        (parsed_data, analysis)
     }
 
     /// Parse various date formats commonly found in whois data
-    fn parse_date(&self, date_str: &str) -> Option<DateTime<Utc>> {
+    ///
+    /// A trailing timezone ("(UTC+9)", "(JST)", "CLST", "+09:00") is applied;
+    /// dates without one are taken as UTC. Slash dates whose day/month order
+    /// depends on the registry are only read when unambiguous; use
+    /// `parse_date_for_tld` when the registry is known.
+    pub fn parse_date(&self, date_str: &str) -> Option<DateTime<Utc>> {
+        self.parse_date_for_tld(date_str, None)
+    }
+
+    /// `parse_date` for a date from the registry of `tld` ("pt", "com.br" or a whole domain)
+    pub fn parse_date_for_tld(&self, date_str: &str, tld: Option<&str>) -> Option<DateTime<Utc>> {
         let date_str = date_str.trim();
-        
-        // Common whois date formats to try
-        let formats = [
-            "%Y-%m-%dT%H:%M:%S%.fZ",           // 2025-05-18T13:36:06.0Z
-            "%Y-%m-%dT%H:%M:%S%z",             // 2025-05-18T13:36:06+0000
-            "%Y-%m-%d %H:%M:%S",               // 2025-05-18 13:36:06
-            "%Y-%m-%d",                        // 2025-05-18
-            "%d-%b-%Y",                        // 18-May-2025
-            "%d %b %Y",                        // 18 May 2025
-            "%Y/%m/%d",                        // 2025/05/18
-            "%m/%d/%Y",                        // 05/18/2025
-            "%d.%m.%Y",                        // 18.05.2025
-        ];
 
-        // Try parsing with timezone first
-        for format in &formats {
+        // ISO 8601 with an offset: 2025-05-18T13:36:06+0000, 2025-05-18T13:36:06.0+09:00
+        for format in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"] {
             if let Ok(dt) = DateTime::parse_from_str(date_str, format) {
                 return Some(dt.with_timezone(&Utc));
             }
         }
 
-        // Try parsing as naive datetime and assume UTC
-        for format in &formats {
-            if let Ok(naive_dt) = NaiveDateTime::parse_from_str(date_str, format) {
-                return Some(DateTime::from_naive_utc_and_offset(naive_dt, Utc));
+        let (local, offset_minutes) = Self::split_timezone(date_str);
+        let offset = chrono::Duration::minutes(offset_minutes.into());
+        // Dates at the edge of chrono's range can't be shifted; those are unparseable
+        let to_utc = |naive_dt: NaiveDateTime| Some(DateTime::from_naive_utc_and_offset(naive_dt.checked_sub_signed(offset)?, Utc));
+        for format in DATE_TIME_FORMATS {
+            if let Ok(naive_dt) = NaiveDateTime::parse_from_str(local, format) {
+                return to_utc(naive_dt);
             }
         }
 
-        // Try parsing just the date part and assume midnight UTC
-        let date_only_formats = [
-            "%Y-%m-%d",
-            "%d-%b-%Y", 
-            "%d %b %Y",
-            "%Y/%m/%d",
-            "%m/%d/%Y",
-            "%d.%m.%Y",
-        ];
-
-        for format in &date_only_formats {
-            if let Ok(naive_date) = chrono::NaiveDate::parse_from_str(date_str, format) {
+        // Date only, midnight in the given timezone
+        for format in DATE_FORMATS {
+            if let Ok(naive_date) = chrono::NaiveDate::parse_from_str(local, format) {
                 if let Some(naive_dt) = naive_date.and_hms_opt(0, 0, 0) {
-                    return Some(DateTime::from_naive_utc_and_offset(naive_dt, Utc));
+                    return to_utc(naive_dt);
                 }
             }
         }
 
+        if let Some(naive_dt) = Self::parse_slash_date(local, tld) {
+            return to_utc(naive_dt);
+        }
+
         debug!("Failed to parse date: {}", date_str);
         None
     }
-} 
\ No newline at end of file
+
+    /// "dd/mm/yyyy" or "mm/dd/yyyy", optionally followed by "HH:MM:SS"
+    ///
+    /// Registries in `DAY_FIRST_TLDS` are read day first. For any other the date
+    /// is accepted only when one order is invalid (18/05/2025, 05/18/2025) or
+    /// both give the same day; 05/03/2025 is left unparsed.
+    fn parse_slash_date(local: &str, tld: Option<&str>) -> Option<NaiveDateTime> {
+        let parse = |date_format: &str| {
+            NaiveDateTime::parse_from_str(local, &format!("{} %H:%M:%S", date_format))
+                .ok()
+                .or_else(|| chrono::NaiveDate::parse_from_str(local, date_format).ok()?.and_hms_opt(0, 0, 0))
+        };
+        let tld = tld.and_then(|tld| tld.trim_end_matches('.').rsplit('.').next()).map(str::to_ascii_lowercase);
+        if tld.is_some_and(|tld| DAY_FIRST_TLDS.contains(&tld.as_str())) {
+            return parse("%d/%m/%Y");
+        }
+        match (parse("%d/%m/%Y"), parse("%m/%d/%Y")) {
+            (Some(day_first), Some(month_first)) => (day_first == month_first).then_some(day_first),
+            (day_first, month_first) => day_first.or(month_first),
+        }
+    }
+
+    /// Split a trailing timezone off a date, returning the local part and its UTC offset in minutes
+    fn split_timezone(date_str: &str) -> (&str, i32) {
+        // "(UTC+9)", "(JST)"
+        if let Some(open) = date_str.strip_suffix(')').and_then(|rest| rest.rfind('(')) {
+            let zone = &date_str[open + 1..date_str.len() - 1];
+            if let Some(offset) = Self::timezone_offset(zone.trim()) {
+                return (date_str[..open].trim_end(), offset);
+            }
+        }
+        // "CLST", "UTC", "+09:00", "GMT-3"
+        if let Some((local, zone)) = date_str.rsplit_once(char::is_whitespace) {
+            if let Some(offset) = Self::timezone_offset(zone) {
+                return (local.trim_end(), offset);
+            }
+        }
+        // "2025-05-18T13:36:06Z"
+        if let Some(local) = date_str.strip_suffix('Z').filter(|local| local.ends_with(|c: char| c.is_ascii_digit())) {
+            return (local, 0);
+        }
+        (date_str, 0)
+    }
+
+    /// UTC offset in minutes of an abbreviation ("JST") or numeric zone ("UTC+9", "+0900", "GMT-03:00")
+    fn timezone_offset(zone: &str) -> Option<i32> {
+        let upper = zone.to_ascii_uppercase();
+        if let Some(&(_, offset)) = TIMEZONE_ABBREVIATIONS.iter().find(|(name, _)| *name == upper) {
+            return Some(offset);
+        }
+
+        let numeric = upper.strip_prefix("UTC").or_else(|| upper.strip_prefix("GMT")).unwrap_or(&upper);
+        let (sign, digits) = match numeric.split_at_checked(1)? {
+            ("+", digits) => (1, digits),
+            ("-", digits) => (-1, digits),
+            _ => return None,
+        };
+        let (hours, minutes) = match digits.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            // Checked: registry text may put non-ASCII characters here
+            None if digits.len() == 4 => digits.split_at_checked(2)?,
+            None => (digits, "0"),
+        };
+        if !(1..=2).contains(&hours.len()) || !hours.bytes().chain(minutes.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+    }
+} 
+
+/// Incremental domain parser fed with response chunks as they arrive
+///
+/// Chunks may split lines (and UTF-8 sequences) anywhere; only complete lines
+/// are parsed, the rest is held until the next chunk or `finish`.
+pub struct StreamingParser {
+    parser: WhoisParser,
+    parsed: ParsedWhoisData,
+    partial: Vec<u8>,
+    domain: Option<String>, // The record's own "Domain Name", for registry-specific date formats
+    matched_lines: BTreeMap<String, Vec<usize>>,
+    lines_seen: usize,
+    has_text: bool,
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self {
+            parser: WhoisParser::new(),
+            parsed: WhoisParser::empty_parsed(),
+            partial: Vec::new(),
+            domain: None,
+            matched_lines: BTreeMap::new(),
+            lines_seen: 0,
+            has_text: false,
+        }
+    }
+
+    /// Parse every complete line in `chunk`, buffering a trailing partial line
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.partial.extend_from_slice(chunk);
+        let Some(last_newline) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return;
+        };
+        let rest = self.partial.split_off(last_newline + 1);
+        let lines = std::mem::replace(&mut self.partial, rest);
+        self.apply(&lines);
+    }
+
+    /// Parse any buffered partial line and return the finished record
+    pub fn finish(self) -> Option<ParsedWhoisData> {
+        Some(self.finish_with_report(None).0)
+    }
+
+    /// `finish` with a report of what was found, as `WhoisParser::parse_with_report_for`
+    pub fn finish_with_report(mut self, tld: Option<&str>) -> (ParsedWhoisData, ParsingReport) {
+        let rest = std::mem::take(&mut self.partial);
+        self.apply(&rest);
+        let parsed = self.parser.finish_parsed(self.parsed, tld.or(self.domain.as_deref()));
+
+        let mut report = ParsingReport::for_parsed(&parsed);
+        report.matched_lines = self.matched_lines;
+        if report.fields_found.is_empty() && self.has_text {
+            report.warnings.push("No known fields in the record".to_string());
+        }
+        (parsed, report)
+    }
+
+    fn apply(&mut self, lines: &[u8]) {
+        let text = String::from_utf8_lossy(lines);
+        self.has_text |= !text.trim().is_empty();
+        for (line_no, raw_key, value) in WhoisParser::key_value_lines(&text) {
+            if self.domain.is_none() && (raw_key.eq_ignore_ascii_case("domain name") || raw_key.eq_ignore_ascii_case("domain")) {
+                self.domain = Some(value.to_string());
+            }
+            if let Some(field) = WhoisParser::apply_field(&mut self.parsed, raw_key, value) {
+                self.matched_lines.entry(field.to_string()).or_default().push(self.lines_seen + line_no);
+            }
+        }
+        // Only whole lines get here, so numbering carries over to the next batch
+        self.lines_seen += text.lines().count();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One registration as a registry's whois server publishes it (the RDAP form is in the rdap tests)
+    const TEXT_RECORD: &str = "\
+Domain Name: EXAMPLE.COM
+Registry Domain ID: 2336799_DOMAIN_COM-VRSN
+Registrar URL: https://registrar.example
+Updated Date: 2024-08-14T07:01:34Z
+Creation Date: 1995-08-14T04:00:00Z
+Registry Expiry Date: 2030-08-13T04:00:00Z
+Registrar: Example Registrar, Inc.
+Registrar IANA ID: 292
+Registrar Abuse Contact Email: abuse@registrar.example
+Registrar Abuse Contact Phone: +1.2083895740
+Reseller: Example Reseller Ltd
+Domain Status: clientTransferProhibited https://icann.org/epp#clientTransferProhibited
+Registrant Name: Jane Doe
+Registrant Organization: Example Org
+Registrant Street: 1 Main St
+Registrant Street: Suite 100
+Registrant City: Springfield
+Registrant Country: US
+Registrant Phone: +1.5555550100
+Registrant Email: jane@example.com
+Tech Email: tech@example.com
+Name Server: NS1.EXAMPLE.COM
+Name Server: NS2.EXAMPLE.COM
+DNSSEC: unsigned
+owner-c: EXA-123
+";
+
+    #[test]
+    fn test_parse_full_registry_record() {
+        let parsed = WhoisParser::new().parse_whois_data(TEXT_RECORD).unwrap();
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(parsed.registrar_iana_id.as_deref(), Some("292"));
+        assert_eq!(parsed.registrar_url.as_deref(), Some("https://registrar.example"));
+        assert_eq!(parsed.registrar_abuse_email.as_deref(), Some("abuse@registrar.example"));
+        assert_eq!(parsed.registrar_abuse_phone.as_deref(), Some("+1.2083895740"));
+        assert_eq!(parsed.registry_domain_id.as_deref(), Some("2336799_DOMAIN_COM-VRSN"));
+        assert_eq!(parsed.reseller.as_deref(), Some("Example Reseller Ltd"));
+
+        let registrant = parsed.registrant.as_ref().unwrap();
+        assert_eq!(registrant.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(registrant.organization.as_deref(), Some("Example Org"));
+        assert_eq!(registrant.street, vec!["1 Main St", "Suite 100"]);
+        assert_eq!(registrant.city.as_deref(), Some("Springfield"));
+        assert_eq!(registrant.country.as_deref(), Some("US"));
+        assert_eq!(registrant.phone.as_deref(), Some("+1.5555550100"));
+        assert_eq!(parsed.registrant_name(), Some("Jane Doe"));
+        assert_eq!(parsed.registrant_email(), Some("jane@example.com"));
+        assert_eq!(parsed.tech_email(), Some("tech@example.com"));
+        assert_eq!(parsed.admin_email(), None);
+        assert!(parsed.admin.is_none() && parsed.billing.is_none());
+
+        assert_eq!(parsed.extra_fields["owner-c"], vec!["EXA-123"]);
+        for typed in ["Registrar IANA ID", "Registrant Street", "Reseller", "Name Server", "DNSSEC"] {
+            assert!(!parsed.extra_fields.contains_key(typed), "{} is a typed field", typed);
+        }
+        assert_eq!(parsed.privacy_protected, Some(false));
+        assert_eq!(parsed.confidence, 1.0);
+        assert!(!parsed.needs_review());
+    }
+
+    #[test]
+    fn test_registry_record_variants() {
+        let parse = |text: &str| WhoisParser::new().parse_whois_data(text).unwrap();
+
+        let text = TEXT_RECORD.replace("Registrant Name: Jane Doe", "Registrant Name: REDACTED FOR PRIVACY");
+        assert_eq!(parse(&text).privacy_protected, Some(true));
+
+        // An unreadable creation date, no expiry and no registrant cost 0.2 + 0.2 + 0.1
+        let text: String = TEXT_RECORD
+            .replace("1995-08-14T04:00:00Z", "sometime in 1995")
+            .lines()
+            .filter(|line| !line.starts_with("Registry Expiry Date") && !line.starts_with("Registrant"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let parsed = parse(&text);
+        assert_eq!(parsed.confidence, 0.5);
+        assert!(parsed.needs_review());
+        assert_eq!(parsed.creation_date_raw.as_deref(), Some("sometime in 1995"));
+
+        // Older registries label the handle "Domain ID"; a reseller is optional
+        let text = TEXT_RECORD.replace("Registry Domain ID:", "Domain ID:").replace("Reseller: Example Reseller Ltd\n", "");
+        let parsed = parse(&text);
+        assert_eq!(parsed.registry_domain_id.as_deref(), Some("2336799_DOMAIN_COM-VRSN"));
+        assert_eq!(parsed.reseller, None);
+    }
+
+    #[test]
+    fn test_parse_domain_fields() {
+        let data = "\
+Domain Name: EXAMPLE.COM
+Registry Domain ID: 2336799_DOMAIN_COM-VRSN
+Registrar IANA ID: 292
+Registrar: Example Registrar, Inc.
+Reseller: Example Reseller Ltd
+Registrar Abuse Contact Email: abuse@registrar.example
+Registrant Organization: Example Org
+Registrant Street: 1 Main St
+Registrant State/Province: CA
+Registrant Country: US
+Admin Email: admin@example.com
+owner-c: EXA-123
+Domain Status: clientTransferProhibited https://icann.org/epp#clientTransferProhibited
+Domain Status: serverHold https://icann.org/epp#serverHold
+DNSSEC: signedDelegation
+";
+        let parsed = WhoisParser::new().parse_whois_data(data).unwrap();
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(parsed.registrar_iana_id.as_deref(), Some("292"));
+        assert_eq!(parsed.registry_domain_id.as_deref(), Some("2336799_DOMAIN_COM-VRSN"));
+        assert_eq!(parsed.reseller.as_deref(), Some("Example Reseller Ltd"));
+        assert_eq!(parsed.registrar_abuse_email.as_deref(), Some("abuse@registrar.example"));
+        assert_eq!(parsed.dnssec, Some(DnssecStatus::Signed));
+
+        let registrant = parsed.registrant.as_ref().unwrap();
+        assert_eq!(registrant.street, vec!["1 Main St"]);
+        assert_eq!(registrant.country.as_deref(), Some("US"));
+        assert_eq!(parsed.registrant_name(), Some("Example Org"));
+        assert_eq!(parsed.admin_email(), Some("admin@example.com"));
+        assert_eq!(parsed.extra_fields["owner-c"], vec!["EXA-123"]);
+        assert_eq!(parsed.privacy_protected, Some(false));
+
+        let redacted = WhoisParser::new().parse_whois_data("Registrant Name: REDACTED FOR PRIVACY\n").unwrap();
+        assert_eq!(redacted.privacy_protected, Some(true));
+        assert!(!parsed.extra_fields.contains_key("Domain Status"));
+        assert_eq!(parsed.status_codes, vec![EppStatus::ClientTransferProhibited, EppStatus::ServerHold]);
+    }
+
+    #[test]
+    fn test_parse_report_tracks_fields_and_lines() {
+        let data = "\
+% comment
+Domain Name: EXAMPLE.COM
+Registrar: Example Registrar, Inc.
+Creation Date: sometime in 1995
+Name Server: NS1.EXAMPLE.COM
+Name Server: NS2.EXAMPLE.COM
+";
+        let (parsed, report) = WhoisParser::parse_with_report(data);
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(report.fields_found, vec!["registrar", "creation_date", "name_servers"]);
+        assert!(report.fields_missing.contains(&"expiration_date".to_string()));
+        assert_eq!(report.matched_lines["registrar"], vec![3]);
+        assert_eq!(report.matched_lines["name_servers"], vec![5, 6]);
+        assert_eq!(report.unparsed_dates["creation_date"], "sometime in 1995");
+        assert!(report.to_string().contains("expiration_date: NOT FOUND"));
+
+        // Registrar and name servers count, the unparsable creation date doesn't
+        assert_eq!(parsed.confidence, 0.35);
+        assert!(parsed.needs_review());
+    }
+
+    #[test]
+    fn test_parse_date_with_timezones_and_registry_formats() {
+        let parser = WhoisParser::new();
+        let utc = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert_eq!(parser.parse_date("2024-05-18 13:36:06 (UTC+9)"), Some(utc("2024-05-18T04:36:06Z")));
+        assert_eq!(parser.parse_date("2024/05/18 13:36:06 (JST)"), Some(utc("2024-05-18T04:36:06Z")));
+        assert_eq!(parser.parse_date("18.05.2024 14:00:00 CLST"), Some(utc("2024-05-18T17:00:00Z")));
+        assert_eq!(parser.parse_date("2024-05-18 13:36:06 GMT-03:30"), Some(utc("2024-05-18T17:06:06Z")));
+        assert_eq!(parser.parse_date("2024-05-18T13:36:06+0200"), Some(utc("2024-05-18T11:36:06Z")));
+        assert_eq!(parser.parse_date("2024-05-18T13:36:06.0Z"), Some(utc("2024-05-18T13:36:06Z")));
+        assert_eq!(parser.parse_date("2024-05-18 13:36:06"), Some(utc("2024-05-18T13:36:06Z")));
+        assert_eq!(parser.parse_date("2024/05/31"), Some(utc("2024-05-31T00:00:00Z")));
+        assert_eq!(parser.parse_date("2007. 03. 02."), Some(utc("2007-03-02T00:00:00Z")));
+        assert_eq!(parser.parse_date("20240518"), Some(utc("2024-05-18T00:00:00Z")));
+        assert_eq!(parser.parse_date("18-May-2024"), Some(utc("2024-05-18T00:00:00Z")));
+        assert_eq!(parser.parse_date("sometime in 1995"), None);
+        assert_eq!(parser.parse_date("2024-05-18 13:36:06 (XYZ)"), None);
+        assert_eq!(parser.parse_date("2024-05-18 13:36:06 +a€"), None);
+        // Shifting past the edge of chrono's range is unparseable, not a panic
+        assert_eq!(parser.parse_date("-262143-01-01 00:00:00 JST"), None);
+
+        // Slash dates: day first for .pt/.br, otherwise only when the order is unambiguous
+        assert_eq!(parser.parse_date_for_tld("05/03/2024", Some("pt")), Some(utc("2024-03-05T00:00:00Z")));
+        assert_eq!(parser.parse_date_for_tld("05/03/2024 10:20:30", Some("com.br")), Some(utc("2024-03-05T10:20:30Z")));
+        assert_eq!(parser.parse_date_for_tld("05/18/2024", Some("pt")), None);
+        assert_eq!(parser.parse_date("05/03/2024"), None);
+        assert_eq!(parser.parse_date("03/03/2024"), Some(utc("2024-03-03T00:00:00Z")));
+        assert_eq!(parser.parse_date("18/05/2024"), Some(utc("2024-05-18T00:00:00Z")));
+        assert_eq!(parser.parse_date("05/18/2024"), Some(utc("2024-05-18T00:00:00Z")));
+
+        // The registry comes from the caller or the record's own domain line
+        let record = "Domain: exemplo.pt\nCreation Date: 05/03/2024 10:00:00\nExpiration Date: 04/03/2026 23:59:59\n";
+        let (parsed, _) = WhoisParser::parse_with_report(record);
+        assert_eq!(parsed.creation_date.as_deref(), Some("2024-03-05T10:00:00Z"));
+        assert_eq!(parsed.expiration_date.as_deref(), Some("2026-03-04T23:59:59Z"));
+        let (parsed, _) = WhoisParser::parse_with_report_for("Creation Date: 01/02/2024\n", Some("br"));
+        assert_eq!(parsed.creation_date.as_deref(), Some("2024-02-01T00:00:00Z"));
+
+        // Records carry the normalized date and keep the registry's text
+        let parsed = WhoisParser::parse("Created on: 2024/05/18 13:36:06 (JST)\nExpires on: soon\n");
+        assert_eq!(parsed.creation_date.as_deref(), Some("2024-05-18T04:36:06Z"));
+        assert_eq!(parsed.creation_date_raw.as_deref(), Some("2024/05/18 13:36:06 (JST)"));
+        assert_eq!(parsed.expiration_date, None);
+        assert_eq!(parsed.expiration_date_raw.as_deref(), Some("soon"));
+    }
+
+    #[test]
+    fn test_idn_name_servers_have_both_forms() {
+        let parser = WhoisParser::new();
+        let parsed = parser.parse_whois_data("Name Server: ns1.xn--bcher-kva.de\nName Server: NS2.EXAMPLE.COM\n").unwrap();
+        assert_eq!(parsed.name_servers, vec!["ns1.xn--bcher-kva.de", "ns2.example.com"]);
+        assert_eq!(parsed.name_servers_unicode, vec!["ns1.bücher.de", "ns2.example.com"]);
+
+        // Registries answering in Unicode get the ASCII form in `name_servers`
+        let parsed = parser.parse_whois_data("Name Server: ns.bücher.de\n").unwrap();
+        assert_eq!(parsed.name_servers, vec!["ns.xn--bcher-kva.de"]);
+        assert_eq!(parsed.name_servers_unicode, vec!["ns.bücher.de"]);
+
+        let parsed = parser.parse_whois_data("Name Server: ns1.example.com\n").unwrap();
+        assert!(parsed.name_servers_unicode.is_empty());
+    }
+
+    #[test]
+    fn test_name_servers_are_normalized_with_glue() {
+        let data = "\
+Name Server: NS1.EXAMPLE.COM
+Name Server: ns1.example.com.
+Nserver: ns2.example.de. 192.0.2.1, 2001:DB8::1
+Nserver: NS2.EXAMPLE.DE 192.0.2.1
+";
+        let parsed = WhoisParser::parse(data);
+        assert_eq!(parsed.name_servers, vec!["ns1.example.com", "ns2.example.de"]);
+        assert_eq!(parsed.name_server_ips.len(), 1);
+        assert_eq!(parsed.name_server_ips["ns2.example.de"], vec!["192.0.2.1", "2001:db8::1"]);
+    }
+
+    #[test]
+    fn test_streaming_parser_matches_whole_response() {
+        let data = "Domain Name: EXAMPLE.COM\nRegistrar: Exämple Registrar\nName Server: NS1.EXAMPLE.COM\nDomain Status: ok\nRegistrant Email: owner@example.com";
+        let mut streaming = StreamingParser::new();
+        // 3-byte chunks split lines and the multi-byte "ä" mid-sequence
+        for chunk in data.as_bytes().chunks(3) {
+            streaming.feed(chunk);
+        }
+        let (streamed, report) = streaming.finish_with_report(None);
+        let (whole, whole_report) = WhoisParser::parse_with_report(data);
+
+        assert_eq!(streamed.registrar.as_deref(), Some("Exämple Registrar"));
+        assert_eq!(streamed.name_servers, whole.name_servers);
+        // Line numbers carry across chunks
+        assert_eq!(report.matched_lines, whole_report.matched_lines);
+        assert_eq!(report.matched_lines["name_servers"], vec![3]);
+        assert_eq!(streamed.status_codes, vec![EppStatus::Ok]);
+        assert_eq!(streamed.registrant_email(), Some("owner@example.com"));
+    }
+
+    #[test]
+    fn test_parse_ip_whois_prefers_most_specific_network() {
+        let data = "\
+NetRange:       8.0.0.0 - 8.127.255.255
+CIDR:           8.0.0.0/9
+NetName:        LVLT-ORG-8-8
+Organization:   Level 3 Parent, LLC (LPL-141)
+OrgAbuseEmail:  abuse@level3.com
+
+NetRange:       8.8.8.0 - 8.8.8.255
+CIDR:           8.8.8.0/24
+NetName:        GOGL
+Organization:   Google LLC (GOGL)
+Country:        us
+OrgAbuseEmail:  network-abuse@google.com
+";
+        let parsed = WhoisParser::new().parse_ip_whois_data(data).unwrap();
+        assert_eq!(parsed.netblock.as_deref(), Some("8.8.8.0 - 8.8.8.255"));
+        assert_eq!(parsed.cidr, vec!["8.8.8.0/24"]);
+        assert_eq!(parsed.net_name.as_deref(), Some("GOGL"));
+        assert_eq!(parsed.country.as_deref(), Some("US"));
+        assert_eq!(parsed.abuse_emails, vec!["network-abuse@google.com"]);
+    }
+
+    #[test]
+    fn test_parse_asn_whois_ripe_format() {
+        let data = "\
+% Abuse contact for 'AS3333' is 'abuse@ripe.net'
+
+aut-num:        AS3333
+as-name:        RIPE-NCC-AS
+descr:          Reseaux IP Europeens Network Coordination Centre
+created:        2002-09-18T13:17:59Z
+
+organisation:   ORG-RIEN1-RIPE
+org-name:       Reseaux IP Europeens Network Coordination Centre (RIPE NCC)
+country:        NL
+e-mail:         ops@ripe.net
+";
+        let parsed = WhoisParser::new().parse_asn_whois_data(data, 3333).unwrap();
+        assert_eq!(parsed.as_name.as_deref(), Some("RIPE-NCC-AS"));
+        assert_eq!(parsed.holder.as_deref(), Some("Reseaux IP Europeens Network Coordination Centre (RIPE NCC)"));
+        assert_eq!(parsed.allocation_date.as_deref(), Some("2002-09-18T13:17:59Z"));
+        assert_eq!(parsed.abuse_emails, vec!["abuse@ripe.net"]);
+        assert_eq!(parsed.contact_emails, vec!["ops@ripe.net"]);
+    }
+
+    #[test]
+    fn test_detect_availability() {
+        let parser = WhoisParser::new();
+
+        let verisign = "No match for domain \"UNREGISTERED-EXAMPLE.COM\".\n>>> Last update of whois database: 2024-01-01T00:00:00Z <<<";
+        assert_eq!(parser.detect_availability(verisign), DomainAvailability::Available);
+        assert_eq!(parser.detect_availability("Domain: example.de\nStatus: free\n"), DomainAvailability::Available);
+
+        let registered = "Domain Name: EXAMPLE.COM\nRegistrar: Example Registrar, Inc.\nCreation Date: 1995-08-14T04:00:00Z\n";
+        assert_eq!(parser.detect_availability(registered), DomainAvailability::Registered);
+        // An unreadable creation date still marks a record, even next to a "not found" notice
+        let unreadable = "Domain: example.xx\nCreated: le 14 août 1995\nNotice: contacts not found\n";
+        assert_eq!(parser.detect_availability(unreadable), DomainAvailability::Registered);
+
+        assert_eq!(parser.detect_availability("Rate limit exceeded"), DomainAvailability::Unknown);
+        assert!(parser.detect_rate_limit("Your access is too fast,please try again later."));
+        assert!(!parser.detect_rate_limit(registered));
+    }
+}
@@ -0,0 +1,289 @@
+//! Commercial whois API providers
+//!
+//! A `Provider` is an extra tier after RDAP, port-43 whois and (with the
+//! `system-whois` feature) the system command: when those fail or are rate
+//! limited, registered providers are tried in priority order until one
+//! answers. A "not found" answer from an earlier tier is authoritative and
+//! never reaches the providers.
+//!
+//! The built-in `WhoisXmlApi` provider is enabled by setting
+//! `whoisxmlapi_api_key`; other services plug in through
+//! `WhoisClient::with_provider`. `provider_priority` lists provider names in
+//! the order they are tried, unlisted providers follow in registration order.
+
+use crate::{config::Config, parser::WhoisParser, whois::WhoisResult, WhoisError};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+
+const WHOISXMLAPI_URL: &str = "https://www.whoisxmlapi.com/whoisserver/WhoisService";
+
+/// A whois data source queried after the built-in tiers fail
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Short identifier used by `provider_priority` and in `WhoisResponse::whois_server`
+    fn name(&self) -> &str;
+
+    /// Look up a normalized (lowercase ASCII) domain
+    ///
+    /// Return `DomainNotFound` only when the service says the domain isn't
+    /// registered; any other error moves on to the next provider.
+    async fn lookup(&self, domain: &str) -> Result<WhoisResult, WhoisError>;
+}
+
+/// Providers enabled by the configuration
+pub(crate) fn configured(config: &Config) -> Result<Vec<Arc<dyn Provider>>, WhoisError> {
+    let mut providers: Vec<Arc<dyn Provider>> = Vec::new();
+    if let Some(ref api_key) = config.whoisxmlapi_api_key {
+        providers.push(Arc::new(WhoisXmlApi::new(api_key, config)?));
+    }
+    Ok(providers)
+}
+
+/// Sort providers by their position in `priority`, keeping registration order otherwise
+pub(crate) fn order(providers: &mut [Arc<dyn Provider>], priority: &[String]) {
+    providers.sort_by_key(|provider| {
+        priority
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(provider.name()))
+            .unwrap_or(usize::MAX)
+    });
+}
+
+/// WhoisXML API (whoisxmlapi.com) Whois API
+pub struct WhoisXmlApi {
+    client: reqwest::Client,
+    api_key: String,
+    url: String,
+}
+
+impl WhoisXmlApi {
+    pub fn new(api_key: &str, config: &Config) -> Result<Self, WhoisError> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.whois_timeout_seconds))
+            .user_agent(concat!("whois-service/", env!("CARGO_PKG_VERSION"), " (provider)"))
+            .gzip(true);
+        if let Some(proxy_url) = config.rdap_proxy() {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url).map_err(WhoisError::HttpError)?);
+        }
+
+        Ok(Self {
+            client: builder.build().map_err(WhoisError::HttpError)?,
+            api_key: api_key.to_string(),
+            url: WHOISXMLAPI_URL.to_string(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct XmlApiResponse {
+    #[serde(rename = "WhoisRecord")]
+    record: Option<XmlApiRecord>,
+    #[serde(rename = "ErrorMessage")]
+    error: Option<XmlApiError>,
+}
+
+#[derive(Deserialize)]
+struct XmlApiError {
+    msg: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct XmlApiRecord {
+    whois_server: Option<String>,
+    registrar_name: Option<String>,
+    #[serde(rename = "registrarIANAID")]
+    registrar_iana_id: Option<String>,
+    created_date: Option<String>,
+    updated_date: Option<String>,
+    expires_date: Option<String>,
+    name_servers: Option<XmlApiNameServers>,
+    status: Option<String>,
+    raw_text: Option<String>,
+    data_error: Option<String>,
+    registry_data: Option<Box<XmlApiRecord>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct XmlApiNameServers {
+    #[serde(default)]
+    host_names: Vec<String>,
+}
+
+impl XmlApiRecord {
+    // The registrar record when present, else the registry's; structured
+    // fields are rendered as whois lines when no raw text was captured
+    fn raw_text(&self) -> Option<String> {
+        let registry = self.registry_data.as_deref();
+        if let Some(raw) = self.raw_text.as_deref().or(registry.and_then(|r| r.raw_text.as_deref())) {
+            return Some(raw.to_string()).filter(|raw| !raw.trim().is_empty());
+        }
+
+        let mut lines = Vec::new();
+        for record in std::iter::once(self).chain(registry) {
+            let mut line = |key: &str, value: &Option<String>| {
+                if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+                    lines.push(format!("{}: {}", key, value));
+                }
+            };
+            line("Registrar", &record.registrar_name);
+            line("Registrar IANA ID", &record.registrar_iana_id);
+            line("Creation Date", &record.created_date);
+            line("Updated Date", &record.updated_date);
+            line("Registry Expiry Date", &record.expires_date);
+            for status in record.status.iter().flat_map(|status| status.split_whitespace()) {
+                lines.push(format!("Domain Status: {}", status));
+            }
+            for name_server in record.name_servers.iter().flat_map(|ns| &ns.host_names) {
+                lines.push(format!("Name Server: {}", name_server));
+            }
+            if !lines.is_empty() {
+                break;
+            }
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl Provider for WhoisXmlApi {
+    fn name(&self) -> &str {
+        "whoisxmlapi"
+    }
+
+    async fn lookup(&self, domain: &str) -> Result<WhoisResult, WhoisError> {
+        // The API key travels in the query string, so errors must not carry the URL into logs
+        let response = self
+            .client
+            .get(&self.url)
+            .query(&[("apiKey", self.api_key.as_str()), ("domainName", domain), ("outputFormat", "JSON")])
+            .send()
+            .await
+            .map_err(|e| WhoisError::HttpError(e.without_url()))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(crate::rdap::parse_retry_after);
+            return Err(WhoisError::RateLimited { server: self.name().to_string(), retry_after });
+        }
+        if !status.is_success() {
+            return Err(WhoisError::Internal(format!("WhoisXML API answered {}", status)));
+        }
+
+        let body: XmlApiResponse = response.json().await.map_err(|e| WhoisError::HttpError(e.without_url()))?;
+        if let Some(error) = body.error {
+            return Err(WhoisError::Internal(format!(
+                "WhoisXML API error: {}",
+                error.msg.unwrap_or_else(|| "unknown".to_string())
+            )));
+        }
+        let record = body.record.unwrap_or_default();
+        let Some(raw_data) = record.raw_text() else {
+            return match record.data_error.as_deref() {
+                Some("MISSING_WHOIS_DATA") => Err(WhoisError::DomainNotFound(domain.to_string())),
+                _ => Err(WhoisError::Internal(format!("WhoisXML API returned no data for {}", domain))),
+            };
+        };
+
+        let (parsed_data, parsing_report) = WhoisParser::parse_with_report_for(&raw_data, Some(domain));
+        let server = record
+            .whois_server
+            .or_else(|| record.registry_data.and_then(|registry| registry.whois_server))
+            .map_or_else(|| self.name().to_string(), |server| format!("{} via {}", server, self.name()));
+        Ok(WhoisResult { server, raw_data, parsed_data: Some(parsed_data), parsing_report, referral_chain: Vec::new() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixtureMode, WhoisClient};
+
+    struct Static(&'static str);
+
+    #[async_trait]
+    impl Provider for Static {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn lookup(&self, _domain: &str) -> Result<WhoisResult, WhoisError> {
+            Ok(WhoisResult {
+                server: self.0.to_string(),
+                raw_data: "Registrar: Example Registrar".to_string(),
+                parsed_data: None,
+                parsing_report: Default::default(),
+                referral_chain: Vec::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_providers_answer_after_builtin_tiers_fail() {
+        // Replaying from an empty directory makes RDAP and WHOIS fail without network access
+        let dir = std::env::temp_dir().join(format!("whois-provider-{}", std::process::id()));
+        let config = Config::builder()
+            .fixtures(FixtureMode::Replay, dir.to_string_lossy())
+            .provider_priority(["second"]);
+        let client = WhoisClient::builder()
+            .config(config)
+            .without_cache()
+            .build()
+            .await
+            .unwrap()
+            .with_provider(Static("first"))
+            .with_provider(Static("second"));
+
+        let response = client.lookup("example.com").await.unwrap();
+        assert_eq!(response.whois_server, "PROVIDER: second");
+    }
+
+    #[tokio::test]
+    async fn test_errors_do_not_leak_the_api_key() {
+        let mut provider = WhoisXmlApi::new("secret-key", &Config::builder().build().unwrap()).unwrap();
+
+        // Nothing listening
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        provider.url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let error = provider.lookup("example.com").await.err().unwrap().to_string();
+        assert!(!error.contains("secret-key"), "{}", error);
+
+        // An answer that isn't the API's JSON
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        provider.url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = tokio::io::AsyncReadExt::read(&mut stream, &mut [0u8; 1024]).await;
+            let response = "HTTP/1.1 200 OK\r\nContent-Length: 9\r\nConnection: close\r\n\r\nnot json!";
+            let _ = tokio::io::AsyncWriteExt::write_all(&mut stream, response.as_bytes()).await;
+        });
+        let error = provider.lookup("example.com").await.err().unwrap().to_string();
+        assert!(!error.contains("secret-key"), "{}", error);
+    }
+
+    #[test]
+    fn test_structured_record_renders_as_whois() {
+        let body: XmlApiResponse = serde_json::from_str(
+            r#"{"WhoisRecord": {"domainName": "example.com", "registryData": {
+                "registrarName": "RESERVED-Internet Assigned Numbers Authority",
+                "createdDate": "1995-08-14T04:00:00Z",
+                "status": "clientDeleteProhibited clientTransferProhibited",
+                "nameServers": {"hostNames": ["A.IANA-SERVERS.NET"]}}}}"#,
+        )
+        .unwrap();
+        let raw = body.record.unwrap().raw_text().unwrap();
+        let parsed = WhoisParser::new().parse_whois_data(&raw).unwrap();
+
+        assert_eq!(parsed.registrar.as_deref(), Some("RESERVED-Internet Assigned Numbers Authority"));
+        assert_eq!(parsed.status.len(), 2);
+        assert_eq!(parsed.name_servers.len(), 1);
+        assert!(parsed.created_ago.is_some());
+    }
+}
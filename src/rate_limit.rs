@@ -0,0 +1,225 @@
+//! Per-client request rate limiting for the HTTP server
+//!
+//! Each client gets a token bucket of `client_rate_limit_qps` requests per
+//! second with bursts of `client_rate_limit_burst`. Clients are keyed by their
+//! `X-API-Key` header when it is one of `client_api_keys`, otherwise by IP.
+//! Unknown keys are ignored, so made-up keys can't buy fresh buckets.
+//!
+//! The IP is the peer address, or with `trust_forwarded_for` the
+//! `X-Forwarded-For` hop `trusted_proxy_hops` from the right. Proxies append to
+//! the header, so anything left of that hop was written by the client and
+//! can't be trusted. Requests over the limit get a 429 with `Retry-After`;
+//! every limited route reports `X-RateLimit-Limit` and `X-RateLimit-Remaining`.
+//!
+//! `ClientIdentity` is the same keying on its own, for limits kept elsewhere
+//! (running bulk jobs per client).
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{collections::HashSet, net::SocketAddr, sync::Arc};
+use whois_service::{
+    config::Config,
+    rate_limiter::{KeyedRateLimiter, RateLimitDecision},
+    ErrorBody,
+};
+
+// Probes and scrapers must keep working while a client is being limited
+const EXEMPT_PATHS: &[&str] = &["/health", "/metrics"];
+
+/// Which client a request counts against
+pub struct ClientIdentity {
+    api_keys: HashSet<String>,
+    trust_forwarded_for: bool,
+    trusted_proxy_hops: usize,
+}
+
+impl ClientIdentity {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            api_keys: config.client_api_keys.iter().cloned().collect(),
+            trust_forwarded_for: config.trust_forwarded_for,
+            trusted_proxy_hops: config.trusted_proxy_hops.max(1) as usize,
+        }
+    }
+
+    /// Key for a request with `headers` from `peer`: "key:<api key>" or "ip:<address>"
+    pub fn key(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+        let api_key = headers.get("x-api-key").and_then(|value| value.to_str().ok());
+        if let Some(api_key) = api_key.filter(|api_key| self.api_keys.contains(*api_key)) {
+            return format!("key:{}", api_key);
+        }
+
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for").and_then(|value| value.to_str().ok()))
+            .flatten()
+            .and_then(|value| value.rsplit(',').nth(self.trusted_proxy_hops - 1))
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty());
+        let peer = || peer.map(|addr| addr.ip().to_string());
+
+        format!("ip:{}", forwarded.or_else(peer).unwrap_or_default())
+    }
+}
+
+pub struct ClientRateLimit {
+    limiter: KeyedRateLimiter,
+    identity: ClientIdentity,
+}
+
+impl ClientRateLimit {
+    /// `None` when client rate limiting is disabled (`client_rate_limit_qps = 0`)
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        (config.client_rate_limit_qps > 0.0).then(|| {
+            Arc::new(Self {
+                limiter: KeyedRateLimiter::new(config.client_rate_limit_qps, config.client_rate_limit_burst),
+                identity: ClientIdentity::from_config(config),
+            })
+        })
+    }
+
+    fn client_key(&self, request: &Request) -> String {
+        let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| *addr);
+        self.identity.key(request.headers(), peer)
+    }
+}
+
+/// Middleware enforcing the per-client limit
+pub async fn enforce(State(limit): State<Arc<ClientRateLimit>>, request: Request, next: Next) -> Response {
+    if EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let key = limit.client_key(&request);
+    let burst = HeaderValue::from(limit.limiter.burst());
+    match limit.limiter.check(&key) {
+        RateLimitDecision::Allowed { remaining } => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert("x-ratelimit-limit", burst);
+            headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+            response
+        }
+        RateLimitDecision::Limited { retry_after } => {
+            crate::metrics::increment_errors("client_rate_limited");
+            tracing::debug!(client = %key, "Client rate limit exceeded");
+            let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            let body = Json(ErrorBody::new("RATE_LIMITED", "Too many requests", StatusCode::TOO_MANY_REQUESTS.as_u16()));
+            let headers = [
+                (header::RETRY_AFTER, HeaderValue::from(retry_after)),
+                (header::HeaderName::from_static("x-ratelimit-limit"), burst),
+                (header::HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from(0)),
+            ];
+            (StatusCode::TOO_MANY_REQUESTS, headers, body).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app(api_keys: &[&str]) -> Router {
+        app_with(|config| config.client_api_keys = api_keys.iter().map(|key| key.to_string()).collect())
+    }
+
+    fn app_with(configure: impl FnOnce(&mut Config)) -> Router {
+        let mut config = Config::builder().build().unwrap();
+        config.client_rate_limit_qps = 0.01;
+        config.client_rate_limit_burst = 2;
+        configure(&mut config);
+        let limit = ClientRateLimit::from_config(&config).unwrap();
+        Router::new()
+            .route("/whois/:domain", get(|| async { "ok" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(limit, enforce))
+    }
+
+    async fn send(app: &Router, path: &str, api_key: Option<&str>) -> Response {
+        send_with(app, path, api_key.map(|api_key| ("x-api-key", api_key))).await
+    }
+
+    async fn send_with(app: &Router, path: &str, header: Option<(&str, &str)>) -> Response {
+        let mut request = Request::builder().uri(path);
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([192, 0, 32, 10], 40000))));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+        response.headers()[name].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_over_limit_requests_get_429_with_headers() {
+        let app = app(&[]);
+        for remaining in ["1", "0"] {
+            let response = send(&app, "/whois/example.com", None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header(&response, "x-ratelimit-limit"), "2");
+            assert_eq!(header(&response, "x-ratelimit-remaining"), remaining);
+        }
+
+        let response = send(&app, "/whois/example.com", None).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(header(&response, "retry-after").parse::<u64>().unwrap() >= 1);
+        assert_eq!(header(&response, "x-ratelimit-limit"), "2");
+        assert_eq!(header(&response, "x-ratelimit-remaining"), "0");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap()["code"], "RATE_LIMITED");
+
+        // Probes stay unlimited
+        assert_eq!(send(&app, "/health", None).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_only_configured_api_keys_get_their_own_bucket() {
+        let app = app(&["team-a"]);
+        for _ in 0..2 {
+            assert_eq!(send(&app, "/whois/example.com", None).await.status(), StatusCode::OK);
+        }
+
+        // A made-up key counts against the caller's IP...
+        let response = send(&app, "/whois/example.com", Some("made-up")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        // ...a configured one has a bucket of its own
+        let response = send(&app, "/whois/example.com", Some("team-a")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "x-ratelimit-remaining"), "1");
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_forwarded_for_hops_are_ignored() {
+        let app = app_with(|config| config.trust_forwarded_for = true);
+        // The proxy appends the real client after whatever the client sent
+        for spoofed in ["198.51.100.1", "198.51.100.2"] {
+            let forwarded = format!("{}, 203.0.113.7", spoofed);
+            let response = send_with(&app, "/whois/example.com", Some(("x-forwarded-for", &forwarded))).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = send_with(&app, "/whois/example.com", Some(("x-forwarded-for", "198.51.100.3, 203.0.113.7"))).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Behind two proxies the client is the second hop from the right
+        let app = app_with(|config| {
+            config.trust_forwarded_for = true;
+            config.trusted_proxy_hops = 2;
+        });
+        for _ in 0..2 {
+            let response = send_with(&app, "/whois/example.com", Some(("x-forwarded-for", "203.0.113.7, 10.0.0.2"))).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = send_with(&app, "/whois/example.com", Some(("x-forwarded-for", "198.51.100.4, 203.0.113.7, 10.0.0.3"))).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
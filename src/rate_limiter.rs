@@ -0,0 +1,266 @@
+//! Per-server rate limiting for whois queries
+//!
+//! Token bucket per whois host so bulk lookups stay under registry limits.
+//! Registries known to block aggressive clients get conservative defaults;
+//! other hosts use the configured default rate (unlimited when 0).
+//!
+//! `KeyedRateLimiter` applies the same buckets to callers of the HTTP API,
+//! rejecting rather than queueing requests over the limit.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+// Queries per second for registries known to rate-limit or ban bulk clients
+const KNOWN_REGISTRY_LIMITS: &[(&str, f64)] = &[
+    ("whois.denic.de", 1.0),
+    ("whois.afnic.fr", 1.0),
+    ("whois.nic.fr", 1.0),
+    ("whois.eu", 1.0),
+    ("whois.nic.it", 1.0),
+    ("whois.dns.be", 1.0),
+    ("whois.domain-registry.nl", 1.0),
+    ("whois.jprs.jp", 1.0),
+    ("whois.nic.uk", 2.0),
+    ("whois.ripe.net", 2.0),
+];
+
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        // Allow a burst of up to one second's worth of queries
+        Self::with_capacity(rate, rate.max(1.0))
+    }
+
+    fn with_capacity(rate: f64, capacity: f64) -> Self {
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take a token, returning how long the caller must wait before using it
+    fn reserve(&mut self) -> Duration {
+        self.refill();
+
+        // Tokens may go negative: each waiter reserves its own future slot
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Whether the bucket has refilled completely, i.e. forgetting it changes nothing
+    fn is_idle(&self) -> bool {
+        self.tokens + self.last_refill.elapsed().as_secs_f64() * self.rate >= self.capacity
+    }
+
+    /// Take a token if one is available, without queueing behind other callers
+    fn try_take(&mut self) -> Result<f64, Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(self.tokens)
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+pub struct RateLimiter {
+    limits: HashMap<String, f64>,
+    default_qps: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// `overrides` take precedence over the built-in registry limits
+    pub fn new(default_qps: f64, overrides: &HashMap<String, f64>) -> Self {
+        let mut limits: HashMap<String, f64> = KNOWN_REGISTRY_LIMITS
+            .iter()
+            .map(|(host, qps)| (host.to_string(), *qps))
+            .collect();
+        limits.extend(overrides.iter().map(|(host, qps)| (host.to_lowercase(), *qps)));
+
+        Self {
+            limits,
+            default_qps,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configured queries per second for a host (0 means unlimited)
+    pub fn limit_for(&self, server: &str) -> f64 {
+        self.limits
+            .get(&server.to_lowercase())
+            .copied()
+            .unwrap_or(self.default_qps)
+    }
+
+    /// Wait until a query to `server` is allowed
+    pub async fn acquire(&self, server: &str) {
+        let rate = self.limit_for(server);
+        if rate <= 0.0 {
+            return;
+        }
+
+        let wait = {
+            let mut buckets = self.buckets.lock().await;
+            buckets
+                .entry(server.to_lowercase())
+                .or_insert_with(|| TokenBucket::new(rate))
+                .reserve()
+        };
+
+        if !wait.is_zero() {
+            debug!("Rate limiting {}: waiting {:?}", server, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Outcome of [`KeyedRateLimiter::check`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitDecision {
+    /// Request allowed; `remaining` more fit in the current burst
+    Allowed { remaining: u32 },
+    /// Over the limit; a token frees up after `retry_after`
+    Limited { retry_after: Duration },
+}
+
+// Hard cap on buckets; reaching it prunes down to half
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Token bucket per caller key (client IP, API key, ...) that rejects instead of waiting
+pub struct KeyedRateLimiter {
+    rate: f64,
+    burst: u32,
+    max_keys: usize,
+    buckets: std::sync::Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl KeyedRateLimiter {
+    /// `rate` requests per second per key, with bursts of up to `burst` (at least 1)
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            rate,
+            burst: burst.max(1),
+            max_keys: MAX_TRACKED_KEYS,
+            buckets: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Burst size, i.e. requests allowed back to back
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Count a request from `key` against its limit
+    pub fn check(&self, key: &str) -> RateLimitDecision {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= self.max_keys && !buckets.contains_key(key) {
+            Self::prune(&mut buckets, (self.max_keys / 2).max(1));
+        }
+
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::with_capacity(self.rate, self.burst as f64));
+        match bucket.try_take() {
+            Ok(remaining) => RateLimitDecision::Allowed { remaining: remaining as u32 },
+            Err(retry_after) => RateLimitDecision::Limited { retry_after },
+        }
+    }
+
+    /// Drop idle buckets, then the least recently used ones until at most `keep` remain
+    ///
+    /// Pruning well below the cap keeps this O(n) pass rare even when every request
+    /// brings a new key.
+    fn prune(buckets: &mut HashMap<String, TokenBucket>, keep: usize) {
+        buckets.retain(|_, bucket| !bucket.is_idle());
+        if buckets.len() <= keep {
+            return;
+        }
+        let mut by_age: Vec<(Instant, String)> =
+            buckets.iter().map(|(key, bucket)| (bucket.last_refill, key.clone())).collect();
+        let drop = by_age.len() - keep;
+        by_age.select_nth_unstable(drop);
+        for (_, key) in &by_age[..drop] {
+            buckets.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_spaces_out_bursts() {
+        let mut bucket = TokenBucket::new(2.0);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+
+        // Burst exhausted: the next two callers queue behind each other
+        let third = bucket.reserve();
+        let fourth = bucket.reserve();
+        assert!(third > Duration::from_millis(400) && third <= Duration::from_millis(500));
+        assert!(fourth > third);
+    }
+
+    #[test]
+    fn test_keyed_limiter_rejects_over_burst_per_key() {
+        let limiter = KeyedRateLimiter::new(1.0, 2);
+        assert_eq!(limiter.check("10.0.0.1"), RateLimitDecision::Allowed { remaining: 1 });
+        assert_eq!(limiter.check("10.0.0.1"), RateLimitDecision::Allowed { remaining: 0 });
+        match limiter.check("10.0.0.1") {
+            RateLimitDecision::Limited { retry_after } => assert!(retry_after <= Duration::from_secs(1)),
+            other => panic!("expected limit, got {:?}", other),
+        }
+        // Other clients have their own bucket
+        assert_eq!(limiter.check("10.0.0.2"), RateLimitDecision::Allowed { remaining: 1 });
+    }
+
+    #[test]
+    fn test_keyed_limiter_stays_under_its_key_cap() {
+        let mut limiter = KeyedRateLimiter::new(0.001, 2);
+        limiter.max_keys = 8;
+
+        // Rotating keys drains a bucket per key without ever letting one refill
+        for i in 0..100 {
+            limiter.check(&format!("key-{}", i));
+            assert!(limiter.buckets.lock().unwrap().len() <= 8);
+        }
+        // The most recent keys are the ones remembered
+        assert_eq!(limiter.check("key-99"), RateLimitDecision::Allowed { remaining: 0 });
+        assert!(!limiter.buckets.lock().unwrap().contains_key("key-0"));
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let overrides = HashMap::from([("WHOIS.DENIC.DE".to_string(), 5.0)]);
+        let limiter = RateLimiter::new(0.0, &overrides);
+        assert_eq!(limiter.limit_for("whois.denic.de"), 5.0);
+        assert_eq!(limiter.limit_for("whois.nic.fr"), 1.0);
+        assert_eq!(limiter.limit_for("whois.verisign-grs.com"), 0.0);
+    }
+}
@@ -6,122 +6,491 @@
 use crate::{
     config::Config,
     errors::WhoisError,
+    fixtures::FixtureStore,
+    telemetry,
+    parser::{ParsingReport, WhoisParser},
+    Contact,
+    DnssecStatus,
+    DomainAvailability,
+    EppStatus,
+    ParsedAsnWhoisData,
+    ParsedIpWhoisData,
     ParsedWhoisData,
 };
-use once_cell::sync::{Lazy, OnceCell};
+use once_cell::sync::Lazy;
 use publicsuffix::{List, Psl};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    sync::Arc,
+    net::IpAddr,
+    sync::{Arc, Mutex},
     time::Duration,
 };
+// std's Instant panics on wasm32-unknown-unknown
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 use tokio::sync::Semaphore;
-use tracing::{debug, info, warn};
+use tracing::{debug, field, info, instrument, warn, Span};
 use url::Url;
 
 // Global PSL instance - shared across all service instances
-static PSL: Lazy<Option<List>> = Lazy::new(|| {
-    match List::new() {
-        list => Some(list),
-    }
-});
+static PSL: Lazy<Option<List>> = Lazy::new(|| Some(List::new()));
+
+// RDAP Bootstrap Service URLs for dynamic discovery
+const RDAP_IPV4_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/ipv4.json";
+const RDAP_IPV6_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/ipv6.json";
+const RDAP_ASN_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/asn.json";
 
-// RDAP Bootstrap Service URL for dynamic discovery
-const RDAP_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/dns.json";
+// Redirect hops followed per RDAP request
+const MAX_RDAP_REDIRECTS: usize = 5;
 
 // Include the auto-generated RDAP mappings from build script
 include!(concat!(env!("OUT_DIR"), "/rdap_mappings.rs"));
 
 pub struct RdapService {
     client: reqwest::Client,
+    config: Arc<Config>, // For clients pinned to vetted addresses (see `vetted_client`)
     tld_servers: Arc<tokio::sync::RwLock<HashMap<String, String>>>,
-    bootstrap_cache: OnceCell<RdapBootstrap>,
+    bootstrap_cache: BootstrapCache,
+    ipv4_bootstrap_cache: BootstrapCache,
+    ipv6_bootstrap_cache: BootstrapCache,
+    asn_bootstrap_cache: BootstrapCache,
+    bootstrap_ttl: Option<Duration>,
     query_semaphore: Arc<Semaphore>,
     discovery_semaphore: Arc<Semaphore>,
+    max_related_hops: usize,
+    max_retries: u32,
+    max_retry_wait: Duration,
+    backoff: Mutex<HashMap<String, Instant>>, // Host -> end of the wait it asked for with Retry-After
+    capabilities: tokio::sync::RwLock<HashMap<String, (Instant, RdapCapabilities)>>, // Help URL -> probe result, kept for `bootstrap_ttl`
+    fixtures: FixtureStore,
 }
 
 pub struct RdapResult {
     pub server: String,
     pub raw_data: String,
     pub parsed_data: Option<ParsedWhoisData>,
-    pub parsing_analysis: Vec<String>,
+    pub parsing_report: ParsingReport,
+    /// Typed RDAP domain object, for consumers that want RDAP-native data
+    pub rdap_object: Option<RdapDomain>,
+    /// URLs the domain query was redirected to, in order (empty when answered directly)
+    pub redirects: Vec<String>,
+}
+
+pub struct RdapIpResult {
+    pub server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedIpWhoisData>,
+}
+
+pub struct RdapAsnResult {
+    pub server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedAsnWhoisData>,
+}
+
+/// IANA bootstrap registry kept for `bootstrap_ttl`, then re-fetched
+struct BootstrapCache {
+    urls: Vec<String>, // Tried in order until one answers
+
+    snapshot: tokio::sync::RwLock<Option<BootstrapSnapshot>>,
+}
+
+struct BootstrapSnapshot {
+    data: Arc<RdapBootstrap>,
+    fetched_at: Instant,
+}
+
+impl BootstrapCache {
+    fn new(urls: Vec<String>) -> Self {
+        Self { urls, snapshot: tokio::sync::RwLock::new(None) }
+    }
+
+    /// Label for log messages
+    fn name(&self) -> &str {
+        self.urls.first().map(String::as_str).unwrap_or("<no bootstrap url>")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RdapBootstrap {
     services: Vec<RdapBootstrapEntry>,
-    #[serde(rename = "publicationDate")]
+    // IANA publishes "publication"; "publicationDate" is accepted for older mirrors
+    #[serde(rename = "publication", alias = "publicationDate")]
     publication_date: Option<String>,
     version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RdapBootstrapEntry {
+    // TLDs for dns.json, CIDR prefixes for ipv4.json/ipv6.json, AS ranges for asn.json
     #[serde(rename = "0")]
-    tlds: Vec<String>,
+    resources: Vec<String>,
     #[serde(rename = "1")]
     servers: Vec<String>,
 }
 
+/// RDAP domain object (RFC 9083 section 5.3), as returned by the server
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct RdapDomainResponse {
+pub struct RdapDomain {
     #[serde(rename = "objectClassName")]
-    object_class_name: Option<String>,
-    handle: Option<String>,
+    pub object_class_name: Option<String>,
+    pub handle: Option<String>,
     #[serde(rename = "ldhName")]
-    ldh_name: Option<String>,
+    pub ldh_name: Option<String>,
+    #[serde(rename = "unicodeName")]
+    pub unicode_name: Option<String>,
     #[serde(rename = "nameservers")]
-    name_servers: Option<Vec<RdapNameserver>>,
-    events: Option<Vec<RdapEvent>>,
-    entities: Option<Vec<RdapEntity>>,
-    status: Option<Vec<String>>,
+    pub name_servers: Option<Vec<RdapNameserver>>,
+    pub events: Option<Vec<RdapEvent>>,
+    pub entities: Option<Vec<RdapEntity>>,
+    pub status: Option<Vec<String>>,
+    #[serde(rename = "secureDNS")]
+    pub secure_dns: Option<RdapSecureDns>,
+    pub notices: Option<Vec<RdapNotice>>,
+    pub remarks: Option<Vec<RdapNotice>>,
+    pub links: Option<Vec<RdapLink>>,
+    #[serde(rename = "port43")]
+    pub port43: Option<String>,
+    #[serde(rename = "rdapConformance")]
+    pub rdap_conformance: Option<Vec<String>>,
+    /// RFC 9537 redaction extension
+    pub redacted: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct RdapNameserver {
+pub struct RdapSecureDns {
+    #[serde(rename = "zoneSigned")]
+    pub zone_signed: Option<bool>,
+    #[serde(rename = "delegationSigned")]
+    pub delegation_signed: Option<bool>,
+    #[serde(rename = "maxSigLife")]
+    pub max_sig_life: Option<u64>,
+    #[serde(rename = "dsData")]
+    pub ds_data: Option<Vec<serde_json::Value>>,
+    #[serde(rename = "keyData")]
+    pub key_data: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdapNameserver {
     #[serde(rename = "objectClassName")]
-    object_class_name: Option<String>,
+    pub object_class_name: Option<String>,
     #[serde(rename = "ldhName")]
-    ldh_name: Option<String>,
+    pub ldh_name: Option<String>,
     #[serde(rename = "unicodeName")]
-    unicode_name: Option<String>,
+    pub unicode_name: Option<String>,
+    /// Glue addresses (RFC 9083 section 5.2)
+    #[serde(rename = "ipAddresses", default, skip_serializing_if = "Option::is_none")]
+    pub ip_addresses: Option<RdapIpAddresses>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RdapIpAddresses {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v4: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v6: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct RdapEvent {
+pub struct RdapEvent {
     #[serde(rename = "eventAction")]
-    event_action: Option<String>,
+    pub event_action: Option<String>,
+    #[serde(rename = "eventActor")]
+    pub event_actor: Option<String>,
     #[serde(rename = "eventDate")]
-    event_date: Option<String>,
+    pub event_date: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct RdapEntity {
+pub struct RdapEntity {
+    #[serde(rename = "objectClassName")]
+    pub object_class_name: Option<String>,
+    pub handle: Option<String>,
+    pub roles: Option<Vec<String>>,
+    /// jCard (RFC 7095): ["vcard", [[name, params, type, value], ...]]
+    #[serde(rename = "vcardArray")]
+    pub vcard_array: Option<serde_json::Value>,
+    pub entities: Option<Vec<RdapEntity>>,
+    #[serde(rename = "publicIds")]
+    pub public_ids: Option<Vec<RdapPublicId>>,
+    pub events: Option<Vec<RdapEvent>>,
+    pub remarks: Option<Vec<RdapNotice>>,
+    pub links: Option<Vec<RdapLink>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdapPublicId {
+    #[serde(rename = "type")]
+    pub id_type: Option<String>,
+    pub identifier: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdapLink {
+    pub value: Option<String>,
+    pub rel: Option<String>,
+    pub href: Option<String>,
+    #[serde(rename = "type")]
+    pub media_type: Option<String>,
+}
+
+/// Notice or remark: a titled block of text with optional links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdapNotice {
+    pub title: Option<String>,
+    #[serde(rename = "type")]
+    pub notice_type: Option<String>,
+    pub description: Option<Vec<String>>,
+    pub links: Option<Vec<RdapLink>>,
+}
+
+/// What an RDAP server advertises on its help endpoint (RFC 9082 section 3.1.6)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdapCapabilities {
+    pub base_url: String,
+    /// `rdapConformance` identifiers, e.g. "rdap_level_0", "redacted"
+    pub conformance: Vec<String>,
+    pub notices: Vec<RdapNotice>,
+    /// The help query was refused with 401 or 403, so queries need credentials
+    pub requires_auth: bool,
+    /// `RateLimit-*` response headers and notices about query limits
+    pub rate_limit_hints: Vec<String>,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RdapCapabilities {
+    /// Whether the server declares an extension identifier (case-insensitive)
+    pub fn supports(&self, extension: &str) -> bool {
+        self.conformance.iter().any(|c| c.eq_ignore_ascii_case(extension))
+    }
+
+    /// Whether any declared extension is a search extension (e.g. RFC 9536 reverse search)
+    pub fn supports_search(&self) -> bool {
+        self.conformance.iter().any(|c| c.to_ascii_lowercase().contains("search"))
+    }
+}
+
+/// Body of a `/help` response
+#[derive(Deserialize)]
+struct RdapHelp {
+    #[serde(rename = "rdapConformance")]
+    rdap_conformance: Option<Vec<String>>,
+    notices: Option<Vec<RdapNotice>>,
+}
+
+impl RdapDomain {
+    /// Build an RFC 9083 domain object from parsed whois data
+    ///
+    /// Used to answer RDAP clients for TLDs whose registry only runs port 43;
+    /// `port43` names the whois server the data came from.
+    pub fn from_parsed(domain: &str, parsed: &ParsedWhoisData, port43: Option<&str>) -> Self {
+        let (unicode_name, idn_result) = idna::domain_to_unicode(domain);
+        let event = |action: &str, date: &Option<String>| {
+            date.as_ref().map(|date| RdapEvent {
+                event_action: Some(action.to_string()),
+                event_actor: None,
+                event_date: Some(date.clone()),
+            })
+        };
+        let events: Vec<RdapEvent> = [
+            event("registration", &parsed.creation_date),
+            event("expiration", &parsed.expiration_date),
+            event("last changed", &parsed.updated_date),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut entities: Vec<RdapEntity> = parsed.registrar.as_ref().map(|name| Self::registrar_entity(name, parsed)).into_iter().collect();
+        for (role, contact) in [
+            ("registrant", &parsed.registrant),
+            ("administrative", &parsed.admin),
+            ("technical", &parsed.tech),
+            ("billing", &parsed.billing),
+        ] {
+            if let Some(contact) = contact {
+                entities.push(Self::entity(role, Self::vcard(contact)));
+            }
+        }
+        if let Some(ref reseller) = parsed.reseller {
+            entities.push(Self::entity("reseller", Self::vcard(&Contact { name: Some(reseller.clone()), ..Contact::default() })));
+        }
+
+        let status_codes = if parsed.status_codes.is_empty() { EppStatus::parse_all(&parsed.status) } else { parsed.status_codes.clone() };
+
+        Self {
+            object_class_name: Some("domain".to_string()),
+            handle: parsed.registry_domain_id.clone(),
+            ldh_name: Some(domain.to_string()),
+            unicode_name: (idn_result.is_ok() && unicode_name != domain).then_some(unicode_name),
+            name_servers: (!parsed.name_servers.is_empty()).then(|| {
+                parsed
+                    .name_servers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name_server)| RdapNameserver {
+                        object_class_name: Some("nameserver".to_string()),
+                        ldh_name: Some(name_server.to_lowercase()),
+                        unicode_name: parsed.name_servers_unicode.get(i).filter(|unicode| *unicode != name_server).cloned(),
+                        ip_addresses: parsed.name_server_ips.get(name_server).map(|ips| {
+                            let (v6, v4) = ips.iter().cloned().partition(|ip| ip.contains(':'));
+                            RdapIpAddresses { v4, v6 }
+                        }),
+                    })
+                    .collect()
+            }),
+            events: (!events.is_empty()).then_some(events),
+            entities: (!entities.is_empty()).then_some(entities),
+            status: (!status_codes.is_empty()).then(|| status_codes.iter().map(EppStatus::rdap_str).collect()),
+            secure_dns: parsed.dnssec.map(|dnssec| RdapSecureDns {
+                zone_signed: None,
+                delegation_signed: Some(dnssec == DnssecStatus::Signed),
+                max_sig_life: None,
+                ds_data: None,
+                key_data: None,
+            }),
+            notices: Some(vec![RdapNotice {
+                title: Some("Source".to_string()),
+                notice_type: None,
+                description: Some(vec![format!(
+                    "Synthesized from whois data{}",
+                    port43.map(|server| format!(" returned by {}", server)).unwrap_or_default()
+                )]),
+                links: None,
+            }]),
+            remarks: None,
+            links: None,
+            port43: port43.map(str::to_string),
+            rdap_conformance: Some(vec!["rdap_level_0".to_string()]),
+            redacted: None,
+        }
+    }
+
+    fn registrar_entity(name: &str, parsed: &ParsedWhoisData) -> RdapEntity {
+        let mut registrar = Self::entity("registrar", Self::vcard(&Contact { name: Some(name.to_string()), ..Contact::default() }));
+        registrar.public_ids = parsed.registrar_iana_id.as_ref().map(|id| {
+            vec![RdapPublicId { id_type: Some("IANA Registrar ID".to_string()), identifier: Some(id.clone()) }]
+        });
+        registrar.links = parsed.registrar_url.as_ref().map(|url| {
+            vec![RdapLink { value: Some(url.clone()), rel: Some("about".to_string()), href: Some(url.clone()), media_type: Some("text/html".to_string()) }]
+        });
+        if parsed.registrar_abuse_email.is_some() || parsed.registrar_abuse_phone.is_some() {
+            let abuse = Contact {
+                email: parsed.registrar_abuse_email.clone(),
+                phone: parsed.registrar_abuse_phone.clone(),
+                ..Contact::default()
+            };
+            registrar.entities = Some(vec![Self::entity("abuse", Self::vcard(&abuse))]);
+        }
+        registrar
+    }
+
+    fn entity(role: &str, vcard: serde_json::Value) -> RdapEntity {
+        RdapEntity {
+            object_class_name: Some("entity".to_string()),
+            handle: None,
+            roles: Some(vec![role.to_string()]),
+            vcard_array: Some(vcard),
+            entities: None,
+            public_ids: None,
+            events: None,
+            remarks: None,
+            links: None,
+        }
+    }
+
+    // jCard (RFC 7095); "fn" is mandatory, so it's left empty when only an organization is known
+    fn vcard(contact: &Contact) -> serde_json::Value {
+        use serde_json::json;
+
+        let mut properties = vec![
+            json!(["version", {}, "text", "4.0"]),
+            json!(["fn", {}, "text", contact.name.as_deref().unwrap_or_default()]),
+        ];
+        if let Some(organization) = &contact.organization {
+            properties.push(json!(["org", {}, "text", organization]));
+        }
+        if !contact.street.is_empty() || contact.city.is_some() || contact.country.is_some() {
+            let street = if contact.street.len() == 1 { json!(contact.street[0]) } else { json!(contact.street) };
+            let adr = json!(["", "", street, contact.city.as_deref().unwrap_or_default(), "", "", contact.country.as_deref().unwrap_or_default()]);
+            properties.push(json!(["adr", {}, "text", adr]));
+        }
+        if let Some(email) = &contact.email {
+            properties.push(json!(["email", {}, "text", email]));
+        }
+        if let Some(phone) = &contact.phone {
+            properties.push(json!(["tel", { "type": "voice" }, "uri", format!("tel:{}", phone)]));
+        }
+        json!(["vcard", properties])
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RdapIpNetworkResponse {
     #[serde(rename = "objectClassName")]
     object_class_name: Option<String>,
     handle: Option<String>,
-    roles: Option<Vec<String>>,
-    #[serde(rename = "vcardArray")]
-    vcard_array: Option<serde_json::Value>,
+    #[serde(rename = "startAddress")]
+    start_address: Option<String>,
+    #[serde(rename = "endAddress")]
+    end_address: Option<String>,
+    name: Option<String>,
+    country: Option<String>,
+    events: Option<Vec<RdapEvent>>,
+    entities: Option<Vec<RdapEntity>>,
+    // RDAP CIDR extension (cidr0) - widely deployed by the RIRs
+    #[serde(rename = "cidr0_cidrs")]
+    cidrs: Option<Vec<RdapCidr>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RdapAutnumResponse {
+    #[serde(rename = "objectClassName")]
+    object_class_name: Option<String>,
+    handle: Option<String>,
+    name: Option<String>,
+    #[serde(rename = "startAutnum")]
+    start_autnum: Option<u32>,
+    #[serde(rename = "endAutnum")]
+    end_autnum: Option<u32>,
+    country: Option<String>,
+    events: Option<Vec<RdapEvent>>,
+    entities: Option<Vec<RdapEntity>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RdapCidr {
+    v4prefix: Option<String>,
+    v6prefix: Option<String>,
+    length: Option<u8>,
 }
 
 impl RdapService {
     pub async fn new(config: Arc<Config>) -> Result<Self, WhoisError> {
-        // Create HTTP client with appropriate timeouts and settings
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.whois_timeout_seconds))
-            .user_agent("whois-service/0.1.0 (RDAP client)")
-            .gzip(true)
-            .build()
-            .map_err(|e| WhoisError::HttpError(e))?;
+        let client = Self::http_client(&config)?.build().map_err(WhoisError::HttpError)?;
 
         let service = Self {
             client,
+            config: config.clone(),
             tld_servers: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-            bootstrap_cache: OnceCell::new(),
+            bootstrap_cache: BootstrapCache::new(config.rdap_bootstrap_urls.clone()),
+            ipv4_bootstrap_cache: BootstrapCache::new(vec![RDAP_IPV4_BOOTSTRAP_URL.to_string()]),
+            ipv6_bootstrap_cache: BootstrapCache::new(vec![RDAP_IPV6_BOOTSTRAP_URL.to_string()]),
+            asn_bootstrap_cache: BootstrapCache::new(vec![RDAP_ASN_BOOTSTRAP_URL.to_string()]),
+            bootstrap_ttl: (config.rdap_bootstrap_ttl_seconds > 0)
+                .then(|| Duration::from_secs(config.rdap_bootstrap_ttl_seconds)),
             query_semaphore: Arc::new(Semaphore::new(config.concurrent_whois_queries)),
             discovery_semaphore: Arc::new(Semaphore::new(config.concurrent_whois_queries * 2)),
+            max_related_hops: config.rdap_max_related_hops,
+            max_retries: config.rdap_max_retries,
+            max_retry_wait: Duration::from_secs(config.rdap_max_retry_wait_seconds),
+            backoff: Mutex::new(HashMap::new()),
+            capabilities: tokio::sync::RwLock::new(HashMap::new()),
+            fixtures: FixtureStore::new(&config),
         };
 
         info!("RdapService initialized with hybrid discovery (hardcoded + bootstrap)");
@@ -132,6 +501,7 @@ impl RdapService {
 
     /// Perform RDAP lookup for a domain
     /// Returns structured data that doesn't require parsing
+    #[instrument(name = "rdap_lookup", skip_all, fields(domain = %domain, tld = field::Empty, server = field::Empty))]
     pub async fn lookup(&self, domain: &str) -> Result<RdapResult, WhoisError> {
         let domain = domain.trim().to_lowercase();
         
@@ -144,22 +514,163 @@ impl RdapService {
         let tld = self.extract_tld(&domain)?;
         
         // Find appropriate RDAP server (hybrid: hardcoded + bootstrap discovery)
+        Span::current().record("tld", tld.as_str());
         let rdap_server = self.find_rdap_server(&tld).await?;
+        Span::current().record("server", rdap_server.as_str());
         
         // Perform RDAP query
-        let raw_data = self.query_rdap_server(&rdap_server, &domain).await?;
+        let mut redirects = Vec::new();
+        let raw_data = self.query_rdap_server(&rdap_server, &domain, &mut redirects).await?;
         
         // Parse RDAP JSON response into our standard format
-        let (parsed_data, parsing_analysis) = self.parse_rdap_response(&raw_data);
+        let (mut parsed_data, mut parsing_report, rdap_object) = self.parse_rdap_response(&raw_data);
+        parsing_report.notes.extend(redirects.iter().map(|url| format!("Followed RDAP redirect to {}", url)));
+
+        // Thin registries point at the registrar's RDAP server for contact data
+        if let (Some(ref object), Some(ref mut parsed)) = (&rdap_object, &mut parsed_data) {
+            let origin = Url::parse(redirects.last().unwrap_or(&rdap_server));
+            if let (true, Ok(origin)) = (self.max_related_hops > 0, origin) {
+                self.follow_related_links(&domain, &origin, object, parsed, &mut parsing_report).await;
+            }
+        }
         
         Ok(RdapResult {
             server: rdap_server,
             raw_data,
             parsed_data,
-            parsing_analysis,
+            parsing_report,
+            rdap_object,
+            redirects,
+        })
+    }
+
+    /// Check whether a domain is registered using the RDAP response status
+    ///
+    /// The authoritative server answers 404 for unregistered domains (RFC 7480 section 5.3).
+    /// Any other non-success status is reported as `Unknown`.
+    pub async fn check_availability(&self, domain: &str) -> Result<DomainAvailability, WhoisError> {
+        let domain = domain.trim().to_lowercase();
+        let tld = self.extract_tld(&domain)?;
+        let rdap_server = self.find_rdap_server(&tld).await?;
+
+        let _permit = self.query_semaphore.acquire().await
+            .map_err(|_| WhoisError::Internal("Semaphore acquisition failed".to_string()))?;
+
+        let url = Self::build_rdap_url(&rdap_server, &format!("domain/{}", domain))?;
+        debug!("Checking availability via RDAP: {}", url);
+
+        let response = self.get_following_redirects(&self.client, url, &mut Vec::new()).await?;
+
+        let status = response.status();
+        Ok(if status.is_success() {
+            DomainAvailability::Registered
+        } else if status == reqwest::StatusCode::NOT_FOUND {
+            DomainAvailability::Available
+        } else {
+            debug!("RDAP availability check for {} returned {}", domain, status);
+            DomainAvailability::Unknown
+        })
+    }
+
+    /// Perform RDAP lookup for an IP address
+    /// Routes to the RIR via the IANA ipv4/ipv6 bootstrap registries and parses the `ip network` object
+    pub async fn lookup_ip(&self, ip: IpAddr) -> Result<RdapIpResult, WhoisError> {
+        let rdap_server = self.find_rdap_server_for_ip(ip).await?;
+        let raw_data = self.query_rdap_path(&rdap_server, &format!("ip/{}", ip), &mut Vec::new()).await?;
+        let parsed_data = self.parse_rdap_ip_response(&raw_data);
+
+        Ok(RdapIpResult {
+            server: rdap_server,
+            raw_data,
+            parsed_data,
+        })
+    }
+
+    /// Perform RDAP lookup for an autonomous system number
+    /// Routes to the RIR via the IANA asn bootstrap registry and parses the `autnum` object
+    pub async fn lookup_asn(&self, asn: u32) -> Result<RdapAsnResult, WhoisError> {
+        let rdap_server = self.find_rdap_server_for_asn(asn).await?;
+        let raw_data = self.query_rdap_path(&rdap_server, &format!("autnum/{}", asn), &mut Vec::new()).await?;
+        let parsed_data = self.parse_rdap_autnum_response(&raw_data, asn);
+
+        Ok(RdapAsnResult {
+            server: rdap_server,
+            raw_data,
+            parsed_data,
         })
     }
 
+    /// Probe an RDAP server's `/help` endpoint for its conformance, notices and limits
+    ///
+    /// Results are cached per server for `rdap_bootstrap_ttl_seconds`. A server
+    /// refusing the help query with 401 or 403 is reported through
+    /// `requires_auth` rather than as an error.
+    pub async fn server_capabilities(&self, base_url: &str) -> Result<RdapCapabilities, WhoisError> {
+        let base_url = if base_url.ends_with('/') { base_url.to_string() } else { format!("{}/", base_url) };
+        let url = Self::build_rdap_url(&base_url, "help")?;
+        let key = url.to_string();
+
+        if let Some((fetched_at, capabilities)) = self.capabilities.read().await.get(&key) {
+            if self.bootstrap_ttl.is_none_or(|ttl| fetched_at.elapsed() < ttl) {
+                return Ok(capabilities.clone());
+            }
+        }
+
+        let capabilities = self.fetch_capabilities(base_url, url).await?;
+        self.capabilities.write().await.insert(key, (Instant::now(), capabilities.clone()));
+        Ok(capabilities)
+    }
+
+    async fn fetch_capabilities(&self, base_url: String, url: Url) -> Result<RdapCapabilities, WhoisError> {
+        let _permit = self.query_semaphore.acquire().await
+            .map_err(|_| WhoisError::Internal("Semaphore acquisition failed".to_string()))?;
+        debug!("Probing RDAP server capabilities: {}", url);
+
+        let server = url.host_str().unwrap_or_default().to_string();
+        let response = self.get_following_redirects(&self.client, url, &mut Vec::new()).await?;
+        let status = response.status();
+
+        let mut capabilities = RdapCapabilities {
+            base_url,
+            conformance: Vec::new(),
+            notices: Vec::new(),
+            requires_auth: status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN,
+            rate_limit_hints: Vec::new(),
+            fetched_at: chrono::Utc::now(),
+        };
+        for name in ["ratelimit-policy", "ratelimit-limit", "x-ratelimit-limit"] {
+            if let Some(value) = response.headers().get(name).and_then(|value| value.to_str().ok()) {
+                capabilities.rate_limit_hints.push(format!("{}: {}", name, value));
+            }
+        }
+
+        if capabilities.requires_auth {
+            return Ok(capabilities);
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(WhoisError::RateLimited { server, retry_after });
+        }
+        if !status.is_success() {
+            return Err(WhoisError::Internal(format!("RDAP help query failed with status: {}", status)));
+        }
+
+        let help: RdapHelp = response.json().await.map_err(WhoisError::HttpError)?;
+        capabilities.conformance = help.rdap_conformance.unwrap_or_default();
+        capabilities.notices = help.notices.unwrap_or_default();
+        for notice in &capabilities.notices {
+            let text = notice.title.iter().chain(notice.description.iter().flatten()).cloned().collect::<Vec<_>>().join(" ");
+            let lower = text.to_lowercase();
+            if lower.contains("rate limit") || lower.contains("query limit") || lower.contains("queries per") {
+                capabilities.rate_limit_hints.push(text);
+            }
+        }
+        Ok(capabilities)
+    }
+
     /// Extract TLD from domain using global PSL for accurate parsing
     fn extract_tld(&self, domain: &str) -> Result<String, WhoisError> {
         // Parse the domain using the global public suffix list
@@ -199,11 +710,25 @@ impl RdapService {
         }
     }
 
+    /// RDAP server for every TLD known without bootstrap discovery (generated, then discovered)
+    pub async fn known_servers(&self) -> HashMap<String, String> {
+        let mut servers: HashMap<String, String> = GENERATED_RDAP_SERVERS.iter()
+            .map(|(tld, server)| (tld.to_string(), server.to_string()))
+            .collect();
+        servers.extend(self.tld_servers.read().await.iter().map(|(tld, server)| (tld.clone(), server.clone())));
+        servers
+    }
+
+    // `tier` records which source answered: cached, generated or bootstrap
+    #[instrument(level = "debug", skip(self), fields(tier = field::Empty))]
     async fn find_rdap_server(&self, tld: &str) -> Result<String, WhoisError> {
+        let span = Span::current();
+
         // Check cache first
         {
             let servers = self.tld_servers.read().await;
             if let Some(server) = servers.get(tld) {
+                span.record("tier", "cached");
                 debug!("Using cached RDAP server for {}: {}", tld, server);
                 return Ok(server.clone());
             }
@@ -211,11 +736,13 @@ impl RdapService {
 
         // Check generated RDAP mappings first (instant lookup for popular TLDs)
         if let Some(server) = GENERATED_RDAP_SERVERS.get(tld) {
-            info!("Using generated RDAP server for {}: {}", tld, server);
+            span.record("tier", "generated");
+            debug!("Using generated RDAP server for {}: {}", tld, server);
             return Ok(server.to_string());
         }
 
         // Dynamic discovery using IANA bootstrap service
+        span.record("tier", "bootstrap");
         if let Some(server) = self.discover_rdap_server_bootstrap(tld).await {
             // Cache the discovered server
             {
@@ -231,27 +758,17 @@ impl RdapService {
     async fn discover_rdap_server_bootstrap(&self, tld: &str) -> Option<String> {
         debug!("Discovering RDAP server for TLD via bootstrap: {}", tld);
 
-        // Check if we have cached bootstrap data
-        let needs_refresh = {
-            self.bootstrap_cache.get().is_none()
-        };
-
-        // Fetch bootstrap data if needed
-        if needs_refresh {
-            if let Err(e) = self.fetch_bootstrap_data().await {
+        let bootstrap = match self.cached_bootstrap(&self.bootstrap_cache).await {
+            Ok(data) => data,
+            Err(e) => {
                 warn!("Failed to fetch RDAP bootstrap data: {}", e);
                 return None;
             }
-        }
+        };
 
         // Search bootstrap data for the TLD
-        let bootstrap = match self.bootstrap_cache.get() {
-            Some(data) => data,
-            None => return None,
-        };
-        
         for service in &bootstrap.services {
-            if service.tlds.contains(&tld.to_string()) {
+            if service.resources.contains(&tld.to_string()) {
                 if let Some(server) = service.servers.first() {
                     info!("Discovered RDAP server via bootstrap for {}: {}", tld, server);
                     return Some(server.clone());
@@ -263,17 +780,202 @@ impl RdapService {
         None
     }
 
-    async fn fetch_bootstrap_data(&self) -> Result<(), WhoisError> {
-        debug!("Fetching RDAP bootstrap data from IANA");
+    async fn find_rdap_server_for_ip(&self, ip: IpAddr) -> Result<String, WhoisError> {
+        let cache = match ip {
+            IpAddr::V4(_) => &self.ipv4_bootstrap_cache,
+            IpAddr::V6(_) => &self.ipv6_bootstrap_cache,
+        };
+        let bootstrap = self.cached_bootstrap(cache).await?;
+
+        // Longest-prefix match across all RIR entries
+        let mut best_match: Option<(u8, &String)> = None;
+        for service in &bootstrap.services {
+            let Some(server) = service.servers.first() else {
+                continue;
+            };
+            for prefix in &service.resources {
+                if let Some(length) = Self::prefix_match_length(ip, prefix) {
+                    if best_match.is_none_or(|(best, _)| length > best) {
+                        best_match = Some((length, server));
+                    }
+                }
+            }
+        }
+
+        match best_match {
+            Some((_, server)) => {
+                debug!("Using RDAP server for {}: {}", ip, server);
+                Ok(server.clone())
+            }
+            None => Err(WhoisError::Internal(format!("No RDAP server found for IP: {}", ip))),
+        }
+    }
+
+    async fn find_rdap_server_for_asn(&self, asn: u32) -> Result<String, WhoisError> {
+        let bootstrap = self.cached_bootstrap(&self.asn_bootstrap_cache).await?;
+
+        for service in &bootstrap.services {
+            let Some(server) = service.servers.first() else {
+                continue;
+            };
+            // Entries are either a single number ("1") or an inclusive range ("36864-37887")
+            let in_range = service.resources.iter().any(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                match (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                    (Ok(start), Ok(end)) => (start..=end).contains(&asn),
+                    _ => false,
+                }
+            });
+            if in_range {
+                debug!("Using RDAP server for AS{}: {}", asn, server);
+                return Ok(server.clone());
+            }
+        }
+
+        Err(WhoisError::Internal(format!("No RDAP server found for AS{}", asn)))
+    }
+
+    /// Return the prefix length if `ip` falls inside the CIDR `prefix`
+    fn prefix_match_length(ip: IpAddr, prefix: &str) -> Option<u8> {
+        let (network, length) = prefix.split_once('/')?;
+        let length: u8 = length.parse().ok()?;
+        let network: IpAddr = network.parse().ok()?;
+
+        let matches = match (ip, network) {
+            (IpAddr::V4(ip), IpAddr::V4(network)) if length <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - length as u32).unwrap_or(0);
+                u32::from(ip) & mask == u32::from(network) & mask
+            }
+            (IpAddr::V6(ip), IpAddr::V6(network)) if length <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - length as u32).unwrap_or(0);
+                u128::from(ip) & mask == u128::from(network) & mask
+            }
+            _ => false,
+        };
+
+        matches.then_some(length)
+    }
+
+    /// Bootstrap data from the cache, re-fetched once older than the TTL
+    ///
+    /// If the re-fetch fails the expired copy is kept rather than losing discovery.
+    async fn cached_bootstrap(&self, cache: &BootstrapCache) -> Result<Arc<RdapBootstrap>, WhoisError> {
+        let stale = {
+            let snapshot = cache.snapshot.read().await;
+            match snapshot.as_ref() {
+                Some(s) if self.bootstrap_ttl.is_none_or(|ttl| s.fetched_at.elapsed() < ttl) => {
+                    return Ok(s.data.clone());
+                }
+                Some(s) => Some(s.data.clone()),
+                None => None,
+            }
+        };
+
+        match self.refresh_cache(cache).await {
+            Ok(data) => Ok(data),
+            Err(e) => match stale {
+                Some(data) => {
+                    warn!("Bootstrap refresh from {} failed, keeping expired copy: {}", cache.name(), e);
+                    Ok(data)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Fetch a bootstrap registry and store it, unless it is older than what we hold
+    async fn refresh_cache(&self, cache: &BootstrapCache) -> Result<Arc<RdapBootstrap>, WhoisError> {
+        // Mirrors in configured order; the last error is reported if none answer
+        let mut last_error = WhoisError::Internal("No RDAP bootstrap URLs configured".to_string());
+        let mut fetched = None;
+        for url in &cache.urls {
+            match self.fetch_bootstrap(url).await {
+                Ok(data) => {
+                    fetched = Some((url.as_str(), data));
+                    break;
+                }
+                Err(e) => {
+                    debug!("Bootstrap fetch from {} failed: {}", url, e);
+                    last_error = e;
+                }
+            }
+        }
+        let Some((url, fetched)) = fetched else {
+            return Err(last_error);
+        };
+
+        let mut snapshot = cache.snapshot.write().await;
+        let data = match snapshot.as_ref() {
+            // A lagging mirror can serve an older publication; keep the newer copy
+            Some(current) if Self::publication_date(&current.data) > Self::publication_date(&fetched) => {
+                debug!("Ignoring older bootstrap publication from {}", url);
+                current.data.clone()
+            }
+            _ => Arc::new(fetched),
+        };
+        *snapshot = Some(BootstrapSnapshot { data: data.clone(), fetched_at: Instant::now() });
+
+        info!(
+            "Cached RDAP bootstrap data from {} (published {})",
+            url,
+            data.publication_date.as_deref().unwrap_or("unknown")
+        );
+        Ok(data)
+    }
+
+    fn publication_date(bootstrap: &RdapBootstrap) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        chrono::DateTime::parse_from_rfc3339(bootstrap.publication_date.as_deref()?).ok()
+    }
+
+    /// Re-fetch the IANA bootstrap registries now, ignoring the TTL
+    ///
+    /// The domain registry is always refreshed; IP and ASN registries only if
+    /// they have been loaded. Previously discovered TLD servers are forgotten so
+    /// the next lookup uses the new data.
+    pub async fn refresh_bootstrap(&self) -> Result<(), WhoisError> {
+        self.refresh_cache(&self.bootstrap_cache).await?;
+        self.tld_servers.write().await.clear();
+
+        for cache in [&self.ipv4_bootstrap_cache, &self.ipv6_bootstrap_cache, &self.asn_bootstrap_cache] {
+            if cache.snapshot.read().await.is_some() {
+                if let Err(e) = self.refresh_cache(cache).await {
+                    warn!("Failed to refresh bootstrap data from {}: {}", cache.name(), e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Refresh the bootstrap registries every TTL in the background
+    ///
+    /// The task ends once the service is dropped. Returns None when the TTL is
+    /// disabled (`rdap_bootstrap_ttl_seconds = 0`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_bootstrap_refresh(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.bootstrap_ttl?;
+        let service: std::sync::Weak<Self> = Arc::downgrade(self);
+
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                if let Err(e) = service.refresh_bootstrap().await {
+                    warn!("Background RDAP bootstrap refresh failed: {}", e);
+                }
+            }
+        }))
+    }
+
+    async fn fetch_bootstrap(&self, url: &str) -> Result<RdapBootstrap, WhoisError> {
+        debug!("Fetching RDAP bootstrap data from {}", url);
 
         let _permit = self.discovery_semaphore.acquire().await
             .map_err(|_| WhoisError::Internal("Semaphore acquisition failed".to_string()))?;
 
-        let response = self.client
-            .get(RDAP_BOOTSTRAP_URL)
-            .send()
-            .await
-            .map_err(|e| WhoisError::HttpError(e))?;
+        let url = Url::parse(url).map_err(|e| WhoisError::Internal(format!("Invalid bootstrap URL {}: {}", url, e)))?;
+        let response = self.get_following_redirects(&self.client, url, &mut Vec::new()).await?;
 
         if !response.status().is_success() {
             return Err(WhoisError::Internal(format!("Bootstrap fetch failed with status: {}", response.status())));
@@ -282,34 +984,165 @@ impl RdapService {
         let bootstrap_data: RdapBootstrap = response
             .json()
             .await
-            .map_err(|e| WhoisError::HttpError(e))?;
+            .map_err(WhoisError::HttpError)?;
 
-        // Cache the bootstrap data
-        self.bootstrap_cache.set(bootstrap_data).expect("Bootstrap cache should only be set once");
+        Ok(bootstrap_data)
+    }
 
-        info!("Successfully fetched and cached RDAP bootstrap data");
-        Ok(())
+    async fn query_rdap_server(&self, server: &str, domain: &str, redirects: &mut Vec<String>) -> Result<String, WhoisError> {
+        self.query_rdap_path(server, &format!("domain/{}", domain), redirects).await
+    }
+
+    async fn query_rdap_path(&self, server: &str, path: &str, redirects: &mut Vec<String>) -> Result<String, WhoisError> {
+        let url = Self::build_rdap_url(server, path)?;
+        self.fetch_rdap_url(&self.client, url, path.strip_prefix("domain/"), redirects).await
+    }
+
+    /// GET an RDAP URL; `domain` turns a 404 into `DomainNotFound` for domain queries
+    #[instrument(name = "rdap_query", level = "debug", skip_all, fields(url = %url))]
+    async fn fetch_rdap_url(&self, client: &reqwest::Client, url: Url, domain: Option<&str>, redirects: &mut Vec<String>) -> Result<String, WhoisError> {
+        let fixture = self.fixtures.rdap_path(&url);
+        let server = url.host_str().unwrap_or_default().to_string();
+        let started = Instant::now();
+        let result = self.fixtures.through(fixture, self.fetch_upstream(client, url, domain, redirects)).await;
+        telemetry::record_upstream_query("rdap", &server, started.elapsed(), &result);
+        result
+    }
+
+    /// Fetch with the 429 policy: wait out a Retry-After of at most
+    /// `max_retry_wait` up to `max_retries` times, otherwise surface
+    /// `RateLimited`. Until an advised delay has passed the server isn't
+    /// queried again, by this lookup or any other.
+    async fn fetch_upstream(&self, client: &reqwest::Client, url: Url, domain: Option<&str>, redirects: &mut Vec<String>) -> Result<String, WhoisError> {
+        let server = url.host_str().unwrap_or_default().to_string();
+        let mut retries = 0;
+        loop {
+            if let Some(remaining) = self.backoff_remaining(&server) {
+                debug!("RDAP server {} asked to back off for another {:?}", server, remaining);
+                return Err(WhoisError::RateLimited { server, retry_after: Some(remaining.as_secs_f64().ceil() as u64) });
+            }
+
+            match self.fetch_once(client, url.clone(), &server, domain, redirects).await {
+                Err(WhoisError::RateLimited { server, retry_after }) => {
+                    let delay = retry_after.map(Duration::from_secs);
+                    match delay {
+                        Some(delay) if retries < self.max_retries && delay <= self.max_retry_wait => {
+                            retries += 1;
+                            debug!("RDAP server {} rate limited, retrying in {:?}", server, delay);
+                            Self::sleep(delay).await;
+                        }
+                        _ => {
+                            if let Some(until) = delay.and_then(|delay| Instant::now().checked_add(delay)) {
+                                self.backoff.lock().unwrap_or_else(|e| e.into_inner()).insert(server.clone(), until);
+                            }
+                            return Err(WhoisError::RateLimited { server, retry_after });
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
     }
 
-    async fn query_rdap_server(&self, server: &str, domain: &str) -> Result<String, WhoisError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn http_client(config: &Config) -> Result<reqwest::ClientBuilder, WhoisError> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.whois_timeout_seconds))
+            .user_agent("whois-service/0.1.0 (RDAP client)")
+            .gzip(true)
+            // Redirects are followed by `get_following_redirects`, which vets and records each hop
+            .redirect(reqwest::redirect::Policy::none());
+
+        // reqwest handles http(s):// and socks5(h):// proxies natively
+        if let Some(proxy_url) = config.rdap_proxy() {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url).map_err(WhoisError::HttpError)?);
+            info!("Routing RDAP requests through configured proxy");
+        }
+        Ok(builder)
+    }
+
+    /// Client for a request from a public server to `url`, refused (`Err` says why)
+    /// when `url` is or resolves to a non-public address
+    ///
+    /// The host is resolved once and the client pinned to the addresses checked,
+    /// so the name can't be rebound to an internal address before the request.
+    /// Behind `rdap_proxy_url` the proxy resolves names where local DNS says
+    /// nothing about it, so only IP literals are checked.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn vetted_client(&self, url: &Url) -> Result<reqwest::Client, String> {
+        if self.config.rdap_proxy().is_some() && matches!(url.host(), Some(url::Host::Domain(_))) {
+            return Ok(self.client.clone());
+        }
+        let addresses = resolve_target(url).await.map_err(|e| format!("its addresses can't be checked: {}", e))?;
+        if let Some(ip) = addresses.private_address() {
+            return Err(format!("{} is not a public address", ip));
+        }
+        match (addresses, url.host_str()) {
+            (TargetAddresses::Resolved(addresses), Some(host)) => Self::http_client(&self.config)
+                .and_then(|builder| builder.resolve_to_addrs(host, &addresses).build().map_err(WhoisError::HttpError))
+                .map_err(|e| e.to_string()),
+            _ => Ok(self.client.clone()),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn vetted_client(&self, url: &Url) -> Result<reqwest::Client, String> {
+        match is_private_target(url).await {
+            true => Err("not a public address".to_string()),
+            false => Ok(self.client.clone()),
+        }
+    }
+
+    // The fetch backend has no timeout, compression, redirect or proxy settings:
+    // the browser or Workers runtime owns those
+    #[cfg(target_arch = "wasm32")]
+    fn http_client(_config: &Config) -> Result<reqwest::ClientBuilder, WhoisError> {
+        Ok(reqwest::Client::builder().user_agent("whois-service/0.1.0 (RDAP client)"))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn sleep(delay: Duration) {
+        tokio::time::sleep(delay).await;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn sleep(delay: Duration) {
+        gloo_timers::future::sleep(delay).await;
+    }
+
+    fn backoff_remaining(&self, server: &str) -> Option<Duration> {
+        let mut backoff = self.backoff.lock().unwrap_or_else(|e| e.into_inner());
+        let remaining = backoff.get(server)?.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            backoff.remove(server);
+            return None;
+        }
+        Some(remaining)
+    }
+
+    async fn fetch_once(&self, client: &reqwest::Client, url: Url, server: &str, domain: Option<&str>, redirects: &mut Vec<String>) -> Result<String, WhoisError> {
         let _permit = self.query_semaphore.acquire().await
             .map_err(|_| WhoisError::Internal("Semaphore acquisition failed".to_string()))?;
 
-        // Construct RDAP URL using proper URL parsing for security
-        let base_url = Url::parse(server)
-            .map_err(|e| WhoisError::Internal(format!("Invalid RDAP server URL '{}': {}", server, e)))?;
-        
-        let url = base_url.join(&format!("domain/{}", domain))
-            .map_err(|e| WhoisError::Internal(format!("Failed to construct RDAP URL: {}", e)))?;
-
         debug!("Querying RDAP server: {}", url);
 
-        let response = self.client
-            .get(url)
-            .header("Accept", "application/rdap+json, application/json")
-            .send()
-            .await
-            .map_err(|e| WhoisError::HttpError(e))?;
+        redirects.clear();
+        let response = self.get_following_redirects(client, url, redirects).await?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(WhoisError::RateLimited { server: server.to_string(), retry_after });
+        }
+
+        // 404 from the authoritative server means the domain isn't registered (RFC 7480 section 5.3)
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            if let Some(domain) = domain {
+                return Err(WhoisError::DomainNotFound(domain.to_string()));
+            }
+        }
 
         if !response.status().is_success() {
             return Err(WhoisError::Internal(format!("RDAP query failed with status: {}", response.status())));
@@ -318,35 +1151,218 @@ impl RdapService {
         let raw_data = response
             .text()
             .await
-            .map_err(|e| WhoisError::HttpError(e))?;
+            .map_err(WhoisError::HttpError)?;
 
         debug!("RDAP response length: {} bytes", raw_data.len());
         Ok(raw_data)
     }
 
-    fn parse_rdap_response(&self, raw_data: &str) -> (Option<ParsedWhoisData>, Vec<String>) {
-        let mut analysis = Vec::new();
-        analysis.push("=== RDAP PARSING ANALYSIS ===".to_string());
+    /// GET `url` with `client`, following redirects here so every hop can be vetted and recorded
+    ///
+    /// At most `MAX_RDAP_REDIRECTS` hops are followed. A hop must keep the
+    /// request's scheme (no https -> http downgrade) and may not lead from a
+    /// public server into private, loopback or link-local address space
+    /// (see `vetted_client`). Each target is appended to `redirects`.
+    async fn get_following_redirects(&self, client: &reqwest::Client, url: Url, redirects: &mut Vec<String>) -> Result<reqwest::Response, WhoisError> {
+        let mut origin_private = None;
+        let mut current = url.clone();
+        let mut client = client.clone();
+        loop {
+            let response = client
+                .get(current.clone())
+                .header("Accept", "application/rdap+json, application/json")
+                .send()
+                .await
+                .map_err(WhoisError::HttpError)?;
+
+            let location = response.headers().get(reqwest::header::LOCATION).and_then(|value| value.to_str().ok());
+            let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+                return Ok(response);
+            };
+
+            if redirects.len() >= MAX_RDAP_REDIRECTS {
+                return Err(WhoisError::Internal(format!("RDAP redirect limit ({}) exceeded at {}", MAX_RDAP_REDIRECTS, current)));
+            }
+            let target = current.join(location).map_err(|e| {
+                WhoisError::Internal(format!("Invalid RDAP redirect from {} to {}: {}", current, location, e))
+            })?;
+            if target.scheme() != url.scheme() {
+                return Err(WhoisError::Internal(format!("Refused RDAP redirect from {} to {}: scheme changed", current, target)));
+            }
+            if origin_private.is_none() {
+                origin_private = Some(is_private_target(&url).await);
+            }
+            client = match origin_private {
+                Some(false) => self.vetted_client(&target).await.map_err(|reason| {
+                    WhoisError::Internal(format!("Refused RDAP redirect from {} to {}: {}", current, target, reason))
+                })?,
+                _ => self.client.clone(),
+            };
+
+            debug!("Following RDAP redirect {} -> {}", current, target);
+            redirects.push(target.to_string());
+            current = target;
+        }
+    }
+
+    /// Follow `related` links from a registry response to the registrar's RDAP server
+    ///
+    /// Thin registries (.com, .net) only publish registry data; the registrar holds the
+    /// contacts. Registrar data fills fields the registry left empty. Failures are logged
+    /// and the registry data is kept.
+    ///
+    /// Links come from the upstream's answer, so they are vetted like redirects against
+    /// `origin`, the URL the registry object was served from: same scheme, and no way
+    /// from a public server into private address space.
+    #[instrument(level = "debug", skip_all, fields(domain = %domain))]
+    async fn follow_related_links(
+        &self,
+        domain: &str,
+        origin: &Url,
+        registry_object: &RdapDomain,
+        parsed: &mut ParsedWhoisData,
+        report: &mut ParsingReport,
+    ) {
+        let mut visited: Vec<String> = Vec::new();
+        let mut next = Self::related_domain_link(registry_object);
+        let mut origin_private = None;
+
+        while let Some(href) = next.take() {
+            if visited.len() >= self.max_related_hops || visited.contains(&href) {
+                break;
+            }
+            visited.push(href.clone());
+
+            let url = match Url::parse(&href) {
+                Ok(url) if url.scheme() == "https" || url.scheme() == "http" => url,
+                _ => {
+                    debug!("Ignoring invalid RDAP related link for {}: {}", domain, href);
+                    break;
+                }
+            };
+            if url.scheme() != origin.scheme() {
+                warn!("Refused RDAP related link from {} to {}: scheme changed", origin, url);
+                report.warnings.push(format!("Refused registrar RDAP link: {}", href));
+                break;
+            }
+            if origin_private.is_none() {
+                origin_private = Some(is_private_target(origin).await);
+            }
+            let client = match origin_private {
+                Some(false) => match self.vetted_client(&url).await {
+                    Ok(client) => client,
+                    Err(reason) => {
+                        warn!("Refused RDAP related link from {} to {}: {}", origin, url, reason);
+                        report.warnings.push(format!("Refused registrar RDAP link: {}", href));
+                        break;
+                    }
+                },
+                _ => self.client.clone(),
+            };
+
+            let raw_data = match self.fetch_rdap_url(&client, url, None, &mut Vec::new()).await {
+                Ok(raw_data) => raw_data,
+                Err(e) => {
+                    debug!("Failed to follow RDAP related link {} for {}: {}", href, domain, e);
+                    report.warnings.push(format!("Registrar RDAP link failed: {}", href));
+                    break;
+                }
+            };
+
+            let (registrar_data, _, registrar_object) = self.parse_rdap_response(&raw_data);
+            if let Some(registrar_data) = registrar_data {
+                Self::merge_registrar_data(parsed, registrar_data);
+                parsed.set_confidence();
+                report.notes.push(format!("Merged registrar RDAP data from {}", href));
+            }
+            next = registrar_object.as_ref().and_then(Self::related_domain_link);
+        }
+    }
+
+    /// First `rel: related` link that points at another RDAP domain object
+    fn related_domain_link(object: &RdapDomain) -> Option<String> {
+        object.links.iter().flatten().find_map(|link| {
+            let href = link.href.as_deref()?;
+            let is_rdap = link.media_type.as_deref().is_none_or(|t| t.contains("rdap") || t.contains("json"));
+            (link.rel.as_deref() == Some("related") && is_rdap && href.contains("/domain/"))
+                .then(|| href.to_string())
+        })
+    }
+
+    /// Fill gaps in the registry data with the registrar's answer
+    fn merge_registrar_data(parsed: &mut ParsedWhoisData, registrar: ParsedWhoisData) {
+        fn fill<T>(slot: &mut Option<T>, value: Option<T>) {
+            if slot.is_none() {
+                *slot = value;
+            }
+        }
+
+        fill(&mut parsed.registrar, registrar.registrar);
+        fill(&mut parsed.registrar_iana_id, registrar.registrar_iana_id);
+        fill(&mut parsed.registrar_url, registrar.registrar_url);
+        fill(&mut parsed.registrar_abuse_email, registrar.registrar_abuse_email);
+        fill(&mut parsed.registrar_abuse_phone, registrar.registrar_abuse_phone);
+        fill(&mut parsed.reseller, registrar.reseller);
+        fill(&mut parsed.registrant, registrar.registrant);
+        fill(&mut parsed.admin, registrar.admin);
+        fill(&mut parsed.tech, registrar.tech);
+        fill(&mut parsed.billing, registrar.billing);
+
+        // Contacts now come from the registrar, so its redaction verdict applies
+        if registrar.privacy_protected.is_some() {
+            parsed.privacy_protected = registrar.privacy_protected;
+        }
+    }
+
+    /// Construct RDAP URL using proper URL parsing for security
+    fn build_rdap_url(server: &str, path: &str) -> Result<Url, WhoisError> {
+        let base_url = Url::parse(server)
+            .map_err(|e| WhoisError::Internal(format!("Invalid RDAP server URL '{}': {}", server, e)))?;
+
+        base_url.join(path)
+            .map_err(|e| WhoisError::Internal(format!("Failed to construct RDAP URL: {}", e)))
+    }
+
+    fn parse_rdap_response(&self, raw_data: &str) -> (Option<ParsedWhoisData>, ParsingReport, Option<RdapDomain>) {
 
         // Parse JSON response
-        let rdap_response: Result<RdapDomainResponse, _> = serde_json::from_str(raw_data);
+        let rdap_response: Result<RdapDomain, _> = serde_json::from_str(raw_data);
         
         match rdap_response {
             Ok(rdap) => {
                 let mut parsed = ParsedWhoisData {
                     registrar: None,
+                    registrar_iana_id: None,
+                    registrar_url: None,
+                    registrar_abuse_email: None,
+                    registrar_abuse_phone: None,
+                    reseller: None,
+                    registry_domain_id: rdap.handle.clone(),
                     creation_date: None,
                     expiration_date: None,
                     updated_date: None,
+                    creation_date_raw: None,
+                    expiration_date_raw: None,
+                    updated_date_raw: None,
                     name_servers: Vec::new(),
+                    name_servers_unicode: Vec::new(),
+                    name_server_ips: HashMap::new(),
                     status: Vec::new(),
-                    registrant_name: None,
-                    registrant_email: None,
-                    admin_email: None,
-                    tech_email: None,
+                    status_codes: Vec::new(),
+                    dnssec: rdap.secure_dns.as_ref()
+                        .and_then(|dns| dns.delegation_signed)
+                        .map(|signed| if signed { DnssecStatus::Signed } else { DnssecStatus::Unsigned }),
+                    registrant: None,
+                    admin: None,
+                    tech: None,
+                    billing: None,
+                    privacy_protected: None,
                     created_ago: None,
                     updated_ago: None,
                     expires_in: None,
+                    confidence: 0.0,
+                    extra_fields: HashMap::new(),
+                    field_sources: HashMap::new(),
                 };
 
                 // Extract name servers
@@ -354,6 +1370,10 @@ impl RdapService {
                     for ns in nameservers {
                         if let Some(ref name) = ns.ldh_name {
                             parsed.name_servers.push(name.clone());
+                            if let Some(ref ips) = ns.ip_addresses {
+                                let glue = ips.v4.iter().chain(&ips.v6).cloned();
+                                parsed.name_server_ips.entry(name.clone()).or_default().extend(glue);
+                            }
                         }
                     }
                 }
@@ -361,6 +1381,7 @@ impl RdapService {
                 // Extract status information
                 if let Some(ref status) = rdap.status {
                     parsed.status = status.clone();
+                    parsed.status_codes = EppStatus::parse_all(status);
                 }
 
                 // Extract events (creation, expiration, last update)
@@ -370,10 +1391,8 @@ impl RdapService {
                             match action.as_str() {
                                 "registration" => parsed.creation_date = Some(date.clone()),
                                 "expiration" => parsed.expiration_date = Some(date.clone()),
-                                "last changed" | "last update of RDAP database" => {
-                                    if parsed.updated_date.is_none() {
-                                        parsed.updated_date = Some(date.clone());
-                                    }
+                                "last changed" | "last update of RDAP database" if parsed.updated_date.is_none() => {
+                                    parsed.updated_date = Some(date.clone());
                                 },
                                 _ => {}
                             }
@@ -392,15 +1411,23 @@ impl RdapService {
                                         parsed.registrar = Some(registrar_name);
                                     }
                                 }
+                                self.extract_registrar_details(entity, &mut parsed);
+                            }
+                            if roles.iter().any(|role| role == "reseller") && parsed.reseller.is_none() {
+                                parsed.reseller = entity.vcard_array.as_ref().and_then(|vcard| self.extract_registrar_from_vcard(vcard));
                             }
                             
-                            if roles.contains(&"registrant".to_string()) {
-                                if let Some(ref vcard) = entity.vcard_array {
-                                    if let Some(name) = self.extract_name_from_vcard(vcard) {
-                                        parsed.registrant_name = Some(name);
-                                    }
-                                    if let Some(email) = self.extract_email_from_vcard(vcard) {
-                                        parsed.registrant_email = Some(email);
+                            if let Some(ref vcard) = entity.vcard_array {
+                                for role in roles {
+                                    let contact = match role.as_str() {
+                                        "registrant" => &mut parsed.registrant,
+                                        "administrative" => &mut parsed.admin,
+                                        "technical" => &mut parsed.tech,
+                                        "billing" => &mut parsed.billing,
+                                        _ => continue,
+                                    };
+                                    if contact.is_none() {
+                                        *contact = Some(self.contact_from_vcard(vcard));
                                     }
                                 }
                             }
@@ -408,77 +1435,696 @@ impl RdapService {
                     }
                 }
 
-                // Calculate date-based fields using the same logic as WHOIS parser
-                self.calculate_date_fields(&mut parsed);
+                // An RFC 9537 redaction list means contact data was withheld even if the vCards look real
+                parsed.privacy_protected = if rdap.redacted.as_ref().is_some_and(|r| !r.is_empty()) {
+                    Some(true)
+                } else {
+                    WhoisParser::detect_privacy(&parsed)
+                };
+
+                parsed.normalize_name_servers();
+
+                // RFC 3339 dates with the whois parser as fallback for servers that stray from it
+                let parser = WhoisParser::new();
+                let tld = rdap.ldh_name.clone();
+                parsed.normalize_dates(|date| {
+                    chrono::DateTime::parse_from_rfc3339(date)
+                        .map(|dt| dt.with_timezone(&chrono::Utc))
+                        .ok()
+                        .or_else(|| parser.parse_date_for_tld(date, tld.as_deref()))
+                });
+                parsed.set_confidence();
 
-                analysis.push(format!("✓ RDAP JSON parsed successfully"));
-                analysis.push(format!("✓ Registrar: {}", parsed.registrar.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-                analysis.push(format!("✓ Creation Date: {}", parsed.creation_date.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-                analysis.push(format!("✓ Expiration Date: {}", parsed.expiration_date.as_ref().unwrap_or(&"NOT FOUND".to_string())));
-                analysis.push(format!("✓ Name Servers: {} found", parsed.name_servers.len()));
-                analysis.push(format!("✓ Status: {} found", parsed.status.len()));
+                let mut report = ParsingReport::for_parsed(&parsed);
+                report.notes.push("RDAP JSON parsed successfully".to_string());
 
-                (Some(parsed), analysis)
+                (Some(parsed), report, Some(rdap))
             }
             Err(e) => {
-                analysis.push(format!("❌ Failed to parse RDAP JSON: {}", e));
-                analysis.push("Raw response (first 500 chars):".to_string());
-                analysis.push(raw_data.chars().take(500).collect::<String>());
-                (None, analysis)
+                let mut report = ParsingReport::for_parsed(&ParsedWhoisData::default());
+                report.warnings.push(format!("Failed to parse RDAP JSON: {}", e));
+                report.warnings.push(format!(
+                    "Raw response (first 500 chars): {}",
+                    raw_data.chars().take(500).collect::<String>()
+                ));
+                (None, report, None)
             }
         }
     }
 
-    fn calculate_date_fields(&self, parsed: &mut ParsedWhoisData) {
-        let now = chrono::Utc::now();
-        
-        // Calculate created_ago (days since creation)
-        if let Some(ref creation_date) = parsed.creation_date {
-            if let Some(created_dt) = self.parse_iso_date(creation_date) {
-                let days_ago = (now - created_dt).num_days();
-                parsed.created_ago = Some(days_ago);
+    fn parse_rdap_ip_response(&self, raw_data: &str) -> Option<ParsedIpWhoisData> {
+        let network: RdapIpNetworkResponse = match serde_json::from_str(raw_data) {
+            Ok(network) => network,
+            Err(e) => {
+                warn!("Failed to parse RDAP ip network JSON: {}", e);
+                return None;
             }
+        };
+
+        if network.object_class_name.as_deref() != Some("ip network") {
+            debug!("Unexpected RDAP object class: {:?}", network.object_class_name);
         }
-        
-        // Calculate updated_ago (days since last update)
-        if let Some(ref updated_date) = parsed.updated_date {
-            if let Some(updated_dt) = self.parse_iso_date(updated_date) {
-                let days_ago = (now - updated_dt).num_days();
-                parsed.updated_ago = Some(days_ago);
+
+        let mut parsed = ParsedIpWhoisData {
+            netblock: None,
+            cidr: Vec::new(),
+            net_name: network.name.clone().or(network.handle.clone()),
+            organization: None,
+            country: network.country.clone(),
+            abuse_emails: Vec::new(),
+            abuse_phones: Vec::new(),
+            registration_date: None,
+            updated_date: None,
+        };
+
+        if let (Some(start), Some(end)) = (&network.start_address, &network.end_address) {
+            parsed.netblock = Some(format!("{} - {}", start, end));
+        }
+
+        for cidr in network.cidrs.iter().flatten() {
+            if let (Some(prefix), Some(length)) = (cidr.v4prefix.as_ref().or(cidr.v6prefix.as_ref()), cidr.length) {
+                parsed.cidr.push(format!("{}/{}", prefix, length));
             }
         }
-        
-        // Calculate expires_in (days until expiration, negative if expired)
-        if let Some(ref expiration_date) = parsed.expiration_date {
-            if let Some(expires_dt) = self.parse_iso_date(expiration_date) {
-                let days_until = (expires_dt - now).num_days();
-                parsed.expires_in = Some(days_until);
+
+        for event in network.events.iter().flatten() {
+            if let (Some(action), Some(date)) = (&event.event_action, &event.event_date) {
+                match action.as_str() {
+                    "registration" => parsed.registration_date = Some(date.clone()),
+                    "last changed" => parsed.updated_date = Some(date.clone()),
+                    _ => {}
+                }
+            }
+        }
+
+        // Abuse contacts are usually nested inside the registrant entity
+        let mut entities = Vec::new();
+        Self::collect_entities(network.entities.as_deref().unwrap_or_default(), &mut entities);
+
+        for entity in entities {
+            let (Some(roles), Some(vcard)) = (&entity.roles, &entity.vcard_array) else {
+                continue;
+            };
+
+            if roles.iter().any(|r| r == "registrant") && parsed.organization.is_none() {
+                parsed.organization = self.extract_name_from_vcard(vcard);
+            }
+
+            if roles.iter().any(|r| r == "abuse") {
+                if let Some(email) = self.extract_email_from_vcard(vcard) {
+                    if !parsed.abuse_emails.contains(&email) {
+                        parsed.abuse_emails.push(email);
+                    }
+                }
+                if let Some(phone) = Self::vcard_property(vcard, "tel") {
+                    if !parsed.abuse_phones.contains(&phone) {
+                        parsed.abuse_phones.push(phone);
+                    }
+                }
+            }
+        }
+
+        Some(parsed)
+    }
+
+    fn parse_rdap_autnum_response(&self, raw_data: &str, asn: u32) -> Option<ParsedAsnWhoisData> {
+        let autnum: RdapAutnumResponse = match serde_json::from_str(raw_data) {
+            Ok(autnum) => autnum,
+            Err(e) => {
+                warn!("Failed to parse RDAP autnum JSON: {}", e);
+                return None;
+            }
+        };
+
+        if autnum.object_class_name.as_deref() != Some("autnum") {
+            debug!("Unexpected RDAP object class: {:?}", autnum.object_class_name);
+        }
+
+        let mut parsed = ParsedAsnWhoisData {
+            asn,
+            handle: autnum.handle.clone(),
+            as_name: autnum.name.clone(),
+            start_autnum: autnum.start_autnum,
+            end_autnum: autnum.end_autnum,
+            holder: None,
+            country: autnum.country.clone(),
+            allocation_date: None,
+            contact_emails: Vec::new(),
+            abuse_emails: Vec::new(),
+        };
+
+        for event in autnum.events.iter().flatten() {
+            if let (Some("registration"), Some(date)) = (event.event_action.as_deref(), &event.event_date) {
+                parsed.allocation_date = Some(date.clone());
             }
         }
+
+        let mut entities = Vec::new();
+        Self::collect_entities(autnum.entities.as_deref().unwrap_or_default(), &mut entities);
+
+        for entity in entities {
+            let (Some(roles), Some(vcard)) = (&entity.roles, &entity.vcard_array) else {
+                continue;
+            };
+
+            if roles.iter().any(|r| r == "registrant") && parsed.holder.is_none() {
+                parsed.holder = self.extract_name_from_vcard(vcard);
+            }
+
+            if let Some(email) = self.extract_email_from_vcard(vcard) {
+                let target = if roles.iter().any(|r| r == "abuse") {
+                    &mut parsed.abuse_emails
+                } else {
+                    &mut parsed.contact_emails
+                };
+                if !target.contains(&email) {
+                    target.push(email);
+                }
+            }
+        }
+
+        Some(parsed)
     }
 
-    fn parse_iso_date(&self, date_str: &str) -> Option<chrono::DateTime<chrono::Utc>> {
-        // RDAP dates are typically ISO 8601 format
-        chrono::DateTime::parse_from_rfc3339(date_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .ok()
+    /// Flatten nested RDAP entities (depth-first)
+    fn collect_entities<'a>(entities: &'a [RdapEntity], out: &mut Vec<&'a RdapEntity>) {
+        for entity in entities {
+            out.push(entity);
+            if let Some(ref nested) = entity.entities {
+                Self::collect_entities(nested, out);
+            }
+        }
     }
 
-    fn extract_registrar_from_vcard(&self, _vcard: &serde_json::Value) -> Option<String> {
-        // vCard arrays in RDAP are complex - this is a simplified extraction
-        // TODO: Implement proper vCard parsing if needed
-        None
+    /// IANA ID, website and abuse contact of the registrar entity (ICANN RDAP profile)
+    fn extract_registrar_details(&self, registrar: &RdapEntity, parsed: &mut ParsedWhoisData) {
+        parsed.registrar_iana_id = registrar.public_ids.iter().flatten()
+            .find(|id| id.id_type.as_deref() == Some("IANA Registrar ID"))
+            .and_then(|id| id.identifier.clone());
+
+        parsed.registrar_url = registrar.vcard_array.as_ref()
+            .and_then(|vcard| Self::vcard_property(vcard, "url"))
+            .or_else(|| {
+                registrar.links.iter().flatten()
+                    .find(|link| link.rel.as_deref() == Some("about"))
+                    .and_then(|link| link.href.clone())
+            });
+
+        // The abuse contact is nested inside the registrar entity
+        let abuse = registrar.entities.iter().flatten()
+            .find(|entity| entity.roles.iter().flatten().any(|role| role == "abuse"));
+        if let Some(vcard) = abuse.and_then(|entity| entity.vcard_array.as_ref()) {
+            parsed.registrar_abuse_email = self.extract_email_from_vcard(vcard);
+            parsed.registrar_abuse_phone = Self::vcard_property(vcard, "tel")
+                .map(|tel| tel.trim_start_matches("tel:").to_string());
+        }
     }
 
-    fn extract_name_from_vcard(&self, _vcard: &serde_json::Value) -> Option<String> {
-        // vCard arrays in RDAP are complex - this is a simplified extraction
-        // TODO: Implement proper vCard parsing if needed
-        None
+    fn extract_registrar_from_vcard(&self, vcard: &serde_json::Value) -> Option<String> {
+        Self::vcard_property(vcard, "fn")
     }
 
-    fn extract_email_from_vcard(&self, _vcard: &serde_json::Value) -> Option<String> {
-        // vCard arrays in RDAP are complex - this is a simplified extraction
-        // TODO: Implement proper vCard parsing if needed
-        None
+    fn extract_name_from_vcard(&self, vcard: &serde_json::Value) -> Option<String> {
+        Self::vcard_property(vcard, "fn")
+    }
+
+    fn extract_email_from_vcard(&self, vcard: &serde_json::Value) -> Option<String> {
+        Self::vcard_property(vcard, "email")
+    }
+
+    /// Build a contact from a jCard's fn, org, adr, tel and email properties
+    fn contact_from_vcard(&self, vcard: &serde_json::Value) -> Contact {
+        let mut contact = Contact {
+            name: self.extract_name_from_vcard(vcard),
+            organization: Self::vcard_property(vcard, "org"),
+            phone: Self::vcard_property(vcard, "tel")
+                .map(|tel| tel.trim_start_matches("tel:").to_string()),
+            email: self.extract_email_from_vcard(vcard),
+            ..Contact::default()
+        };
+
+        // adr value: [pobox, ext, street, locality, region, code, country]; street may be a list
+        let adr = vcard.get(1).and_then(|props| props.as_array()).and_then(|props| {
+            props.iter().find(|entry| entry.get(0).and_then(|name| name.as_str()) == Some("adr"))
+        });
+        if let Some(adr) = adr {
+            let text = |value: &serde_json::Value| {
+                value.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+            };
+            if let Some(parts) = adr.get(3).and_then(|value| value.as_array()) {
+                contact.street = match parts.get(2) {
+                    Some(serde_json::Value::Array(lines)) => lines.iter().filter_map(text).collect(),
+                    Some(line) => text(line).into_iter().collect(),
+                    None => Vec::new(),
+                };
+                contact.city = parts.get(3).and_then(text);
+                contact.country = parts.get(6).and_then(text);
+            }
+            // Structured country code parameter takes precedence over the free-text name
+            if let Some(cc) = adr.get(1).and_then(|params| params.get("cc")).and_then(text) {
+                contact.country = Some(cc);
+            }
+        }
+
+        contact
+    }
+
+    /// Read a text property from a jCard (RFC 7095): ["vcard", [[name, params, type, value], ...]]
+    fn vcard_property(vcard: &serde_json::Value, property: &str) -> Option<String> {
+        vcard.get(1)?.as_array()?.iter().find_map(|entry| {
+            let entry = entry.as_array()?;
+            if entry.first()?.as_str()? != property {
+                return None;
+            }
+            let value = entry.get(3)?.as_str()?.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    }
+} 
+
+/// Whether a URL's host is, or resolves to, a non-public address
+pub(crate) async fn is_private_target(url: &Url) -> bool {
+    match url.host() {
+        Some(url::Host::Ipv4(ip)) => is_private_ip(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => is_private_ip(IpAddr::V6(ip)),
+        #[cfg(not(target_arch = "wasm32"))]
+        Some(url::Host::Domain(host)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            match tokio::net::lookup_host((host, port)).await {
+                Ok(mut addresses) => addresses.any(|address| is_private_ip(address.ip())),
+                // Unresolvable targets fail on their own when requested
+                Err(_) => false,
+            }
+        }
+        // No resolver in the browser; the fetch API keeps pages off private networks itself
+        #[cfg(target_arch = "wasm32")]
+        Some(url::Host::Domain(_)) => false,
+        None => true,
+    }
+}
+
+/// Where a request to a URL will connect: its IP literal, or what its hostname resolves to
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) enum TargetAddresses {
+    Literal(IpAddr),
+    /// Pin these on the request (`ClientBuilder::resolve_to_addrs`) so it can't be
+    /// rebound to other addresses between the check and the connection
+    Resolved(Vec<std::net::SocketAddr>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TargetAddresses {
+    /// The first non-public address, if any
+    pub(crate) fn private_address(&self) -> Option<IpAddr> {
+        match self {
+            Self::Literal(ip) => Some(*ip).filter(|ip| is_private_ip(*ip)),
+            Self::Resolved(addresses) => addresses.iter().map(|address| address.ip()).find(|ip| is_private_ip(*ip)),
+        }
+    }
+}
+
+/// Resolve a URL's host once, for a check whose addresses are then connected to
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn resolve_target(url: &Url) -> std::io::Result<TargetAddresses> {
+    match url.host() {
+        Some(url::Host::Ipv4(ip)) => Ok(TargetAddresses::Literal(IpAddr::V4(ip))),
+        Some(url::Host::Ipv6(ip)) => Ok(TargetAddresses::Literal(IpAddr::V6(ip))),
+        Some(url::Host::Domain(host)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            let addresses: Vec<_> = tokio::net::lookup_host((host, port)).await?.collect();
+            if addresses.is_empty() {
+                return Err(std::io::Error::other(format!("{} has no addresses", host)));
+            }
+            Ok(TargetAddresses::Resolved(addresses))
+        }
+        None => Err(std::io::Error::other(format!("{} has no host", url))),
     }
-} 
\ No newline at end of file
+}
+
+/// Whether an address is anything but public unicast: private, loopback, link-local,
+/// shared (CGNAT), "this network", multicast, reserved, benchmarking or documentation
+///
+/// The one check behind RDAP redirects and related links, whois referrals and webhooks.
+pub(crate) fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_multicast()
+                || a == 0 // "This network", 0.0.0.0/8
+                || a >= 240 // Reserved, 240.0.0.0/4, and broadcast
+                || (a == 100 && b & 0xc0 == 64) // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 192 && b == 0 && (c == 0 || c == 2)) // IETF protocol assignments, TEST-NET-1
+                || (a == 198 && b & 0xfe == 18) // Benchmarking, 198.18.0.0/15
+                || (a == 198 && b == 51 && c == 100) // TEST-NET-2
+                || (a == 203 && b == 0 && c == 113) // TEST-NET-3
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_private_ip(IpAddr::V4(mapped)),
+            None => {
+                let [first, second, ..] = ip.segments();
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast() // ff00::/8
+                    || first & 0xfe00 == 0xfc00 // Unique local, fc00::/7
+                    || first & 0xffc0 == 0xfe80 // Link-local, fe80::/10
+                    || (first == 0x2001 && second == 0x0db8) // Documentation, 2001:db8::/32
+            }
+        },
+    }
+}
+
+// Longest Retry-After honoured; larger values are clamped so they can't overflow an `Instant`
+const MAX_RETRY_AFTER_SECS: u64 = 24 * 60 * 60;
+
+/// Seconds to wait from a `Retry-After` value, in delta-seconds or HTTP-date form (RFC 9110 section 10.2.3),
+/// clamped to a day
+pub(crate) fn parse_retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds.min(MAX_RETRY_AFTER_SECS));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds().max(0) as u64;
+    Some(seconds.min(MAX_RETRY_AFTER_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// HTTP server answering each connection with the next canned (status line, extra header) pair
+    async fn scripted_server(responses: Vec<(&'static str, &'static str)>) -> (String, Arc<AtomicUsize>) {
+        scripted_server_with_body(r#"{"objectClassName": "domain", "ldhName": "example.test"}"#, responses).await
+    }
+
+    async fn scripted_server_with_body(
+        body: &'static str,
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            for (status, header) in responses {
+                let Ok((mut stream, _)) = listener.accept().await else { return };
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, header, body.len(), body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (format!("http://{}/", address), requests)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_queries_wait_or_back_off() {
+        let service = RdapService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+
+        // A short Retry-After is waited out and the query retried
+        let (base, requests) = scripted_server(vec![
+            ("429 Too Many Requests", "Retry-After: 0\r\n"),
+            ("200 OK", "Content-Type: application/rdap+json\r\n"),
+        ])
+        .await;
+        assert!(service.query_rdap_server(&base, "example.test", &mut Vec::new()).await.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // A long one is surfaced, and the server is left alone until it passes
+        let (base, requests) = scripted_server(vec![("429 Too Many Requests", "Retry-After: 120\r\n")]).await;
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::RateLimited { retry_after: Some(120), .. })));
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::RateLimited { retry_after: Some(119..=120), .. })));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A huge one is clamped rather than overflowing the backoff deadline
+        let service = RdapService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+        let (base, _) = scripted_server(vec![("429 Too Many Requests", "Retry-After: 18446744073709551615\r\n")]).await;
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::RateLimited { retry_after: Some(MAX_RETRY_AFTER_SECS), .. })));
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::RateLimited { retry_after: Some(secs), .. }) if secs > 0));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_recorded_and_vetted() {
+        let service = RdapService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+
+        let (base, _) = scripted_server(vec![
+            ("302 Found", "Location: /registrar/domain/example.test\r\n"),
+            ("200 OK", "Content-Type: application/rdap+json\r\n"),
+        ])
+        .await;
+        let mut redirects = Vec::new();
+        assert!(service.query_rdap_server(&base, "example.test", &mut redirects).await.is_ok());
+        assert_eq!(redirects, vec![format!("{}registrar/domain/example.test", base)]);
+
+        // No https -> http style scheme changes
+        let (base, _) = scripted_server(vec![("301 Moved Permanently", "Location: https://rdap.example/domain/example.test\r\n")]).await;
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::Internal(e)) if e.contains("scheme changed")));
+
+        let (base, _) = scripted_server(vec![("307 Temporary Redirect", "Location: /again\r\n"); MAX_RDAP_REDIRECTS + 1]).await;
+        let result = service.query_rdap_server(&base, "example.test", &mut Vec::new()).await;
+        assert!(matches!(result, Err(WhoisError::Internal(e)) if e.contains("redirect limit")));
+
+    }
+
+    #[test]
+    fn test_private_ip_ranges() {
+        let private = [
+            "0.1.2.3", "10.1.2.3", "100.100.0.1", "127.0.0.1", "169.254.169.254", "172.16.0.1", "192.0.0.8",
+            "192.0.2.1", "192.168.1.1", "198.18.0.1", "198.51.100.7", "203.0.113.9", "224.0.0.251", "240.0.0.1",
+            "255.255.255.255", "::", "::1", "::ffff:127.0.0.1", "fd00::1", "fe80::1", "ff02::1", "2001:db8::1",
+        ];
+        for ip in private {
+            assert!(is_private_ip(ip.parse().unwrap()), "{} is not public", ip);
+        }
+        for ip in ["192.0.32.10", "8.8.8.8", "100.128.0.1", "198.20.0.1", "2001:500:8f::53", "::ffff:8.8.8.8"] {
+            assert!(!is_private_ip(ip.parse().unwrap()), "{} is public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_related_links_are_vetted() {
+        let service = RdapService::new(Arc::new(Config::builder().build().unwrap())).await.unwrap();
+        let registrar = r#"{"objectClassName": "domain", "ldhName": "example.test", "entities": [{"roles": ["registrant"],
+            "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["email", {}, "text", "jane@example.test"]]]}]}"#;
+        let (base, requests) = scripted_server_with_body(registrar, vec![("200 OK", "Content-Type: application/rdap+json\r\n")]).await;
+        let registry: RdapDomain = serde_json::from_value(serde_json::json!({
+            "objectClassName": "domain",
+            "links": [{"rel": "related", "type": "application/rdap+json", "href": format!("{}domain/example.test", base)}],
+        }))
+        .unwrap();
+        let follow = |origin: &'static str| {
+            let (service, registry) = (&service, &registry);
+            async move {
+                let (mut parsed, mut report) = (ParsedWhoisData::default(), ParsingReport::default());
+                let origin = Url::parse(origin).unwrap();
+                service.follow_related_links("example.test", &origin, registry, &mut parsed, &mut report).await;
+                (parsed, report)
+            }
+        };
+
+        // A public registry can't send us into private address space, or from https to http
+        let (parsed, report) = follow("http://192.0.32.10/domain/example.test").await;
+        assert!(parsed.registrant.is_none());
+        assert!(report.warnings[0].starts_with("Refused registrar RDAP link"));
+        let (_, report) = follow("https://192.0.32.10/domain/example.test").await;
+        assert!(report.warnings[0].starts_with("Refused registrar RDAP link"));
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+        // A registry on a private network may link to its neighbours
+        let (parsed, _) = follow("http://127.0.0.1/domain/example.test").await;
+        assert_eq!(parsed.registrant_email(), Some("jane@example.test"));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Names are checked at the addresses they resolve to, and must resolve
+        let localhost = Url::parse(&base.replace("127.0.0.1", "localhost")).unwrap();
+        assert!(service.vetted_client(&localhost).await.unwrap_err().contains("not a public address"));
+        let unresolvable = Url::parse("https://rdap.nonexistent.invalid/").unwrap();
+        assert!(service.vetted_client(&unresolvable).await.unwrap_err().contains("can't be checked"));
+
+        // Behind a proxy local DNS proves nothing, so only literals are checked
+        let mut config = Config::builder().build().unwrap();
+        config.rdap_proxy_url = Some("http://127.0.0.1:3128".to_string());
+        let proxied = RdapService::new(Arc::new(config)).await.unwrap();
+        assert!(proxied.vetted_client(&localhost).await.is_ok());
+        assert!(proxied.vetted_client(&Url::parse("http://10.0.0.1/").unwrap()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_capabilities_are_probed_once() {
+        let service = RdapService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+
+        let help = r#"{"rdapConformance": ["rdap_level_0", "reverse_search"],
+            "notices": [{"title": "Rate Limits", "description": ["At most 10 queries per minute"]}]}"#;
+        let (base, requests) = scripted_server_with_body(help, vec![
+            ("200 OK", "Content-Type: application/rdap+json\r\nRateLimit-Policy: 10;w=60\r\n"),
+        ])
+        .await;
+        let capabilities = service.server_capabilities(base.trim_end_matches('/')).await.unwrap();
+        assert!(capabilities.supports("RDAP_LEVEL_0"));
+        assert!(capabilities.supports_search());
+        assert!(!capabilities.requires_auth);
+        assert_eq!(capabilities.rate_limit_hints, vec!["ratelimit-policy: 10;w=60", "Rate Limits At most 10 queries per minute"]);
+
+        assert_eq!(service.server_capabilities(&base).await.unwrap().conformance.len(), 2);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let (base, _) = scripted_server(vec![("401 Unauthorized", "")]).await;
+        assert!(service.server_capabilities(&base).await.unwrap().requires_auth);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 30 "), Some(30));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+        let soon = (chrono::Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        assert!(matches!(parse_retry_after(&soon), Some(88..=90)));
+        assert_eq!(parse_retry_after("soon"), None);
+        // Absurd values are clamped instead of overflowing the backoff deadline
+        assert_eq!(parse_retry_after("18446744073709551615"), Some(MAX_RETRY_AFTER_SECS));
+        assert_eq!(parse_retry_after("Fri, 31 Dec 9999 23:59:59 GMT"), Some(MAX_RETRY_AFTER_SECS));
+    }
+
+    /// One registration as a registry's RDAP server publishes it (the whois text form is in the
+    /// parser tests), with a jCard address using a street list and a `cc` parameter
+    const RDAP_RECORD: &str = r#"{
+        "objectClassName": "domain",
+        "handle": "2336799_DOMAIN_COM-VRSN",
+        "ldhName": "EXAMPLE.COM",
+        "status": ["client transfer prohibited"],
+        "events": [
+            {"eventAction": "registration", "eventDate": "1995-08-14T04:00:00Z"},
+            {"eventAction": "expiration", "eventDate": "2030-08-13T04:00:00Z"},
+            {"eventAction": "last changed", "eventDate": "2024-08-14T07:01:34Z"}
+        ],
+        "entities": [
+            {
+                "objectClassName": "entity",
+                "roles": ["registrar"],
+                "publicIds": [{"type": "IANA Registrar ID", "identifier": "292"}],
+                "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["fn", {}, "text", "Example Registrar, Inc."]]],
+                "links": [{"rel": "about", "href": "https://registrar.example"}],
+                "entities": [{
+                    "objectClassName": "entity",
+                    "roles": ["abuse"],
+                    "vcardArray": ["vcard", [
+                        ["version", {}, "text", "4.0"],
+                        ["fn", {}, "text", "Abuse Desk"],
+                        ["tel", {"type": "voice"}, "uri", "tel:+1.2083895740"],
+                        ["email", {}, "text", "abuse@registrar.example"]
+                    ]]
+                }]
+            },
+            {
+                "objectClassName": "entity",
+                "roles": ["reseller"],
+                "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["fn", {}, "text", "Example Reseller Ltd"]]]
+            },
+            {
+                "objectClassName": "entity",
+                "roles": ["registrant"],
+                "vcardArray": ["vcard", [
+                    ["version", {}, "text", "4.0"],
+                    ["fn", {}, "text", "Jane Doe"],
+                    ["org", {}, "text", "Example Org"],
+                    ["adr", {"cc": "US"}, "text", ["", "", ["1 Main St", "Suite 100"], "Springfield", "", "", "United States"]],
+                    ["tel", {"type": "voice"}, "uri", "tel:+1.5555550100"],
+                    ["email", {}, "text", "jane@example.com"]
+                ]]
+            },
+            {
+                "objectClassName": "entity",
+                "roles": ["technical"],
+                "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["email", {}, "text", "tech@example.com"]]]
+            }
+        ],
+        "nameservers": [
+            {"objectClassName": "nameserver", "ldhName": "NS1.EXAMPLE.COM"},
+            {"objectClassName": "nameserver", "ldhName": "NS2.EXAMPLE.COM"}
+        ],
+        "secureDNS": {"delegationSigned": false}
+    }"#;
+
+    async fn parse_rdap(json: &str) -> ParsedWhoisData {
+        let service = RdapService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+        let (parsed, _, _) = service.parse_rdap_response(json);
+        parsed.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_full_rdap_record() {
+        let parsed = parse_rdap(RDAP_RECORD).await;
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(parsed.registrar_iana_id.as_deref(), Some("292"));
+        assert_eq!(parsed.registrar_url.as_deref(), Some("https://registrar.example"));
+        assert_eq!(parsed.registrar_abuse_email.as_deref(), Some("abuse@registrar.example"));
+        assert_eq!(parsed.registrar_abuse_phone.as_deref(), Some("+1.2083895740"));
+        assert_eq!(parsed.registry_domain_id.as_deref(), Some("2336799_DOMAIN_COM-VRSN"));
+        assert_eq!(parsed.reseller.as_deref(), Some("Example Reseller Ltd"));
+
+        let registrant = parsed.registrant.as_ref().unwrap();
+        assert_eq!(registrant.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(registrant.organization.as_deref(), Some("Example Org"));
+        assert_eq!(registrant.street, vec!["1 Main St", "Suite 100"]);
+        assert_eq!(registrant.city.as_deref(), Some("Springfield"));
+        // The `cc` parameter wins over the free-text country name
+        assert_eq!(registrant.country.as_deref(), Some("US"));
+        assert_eq!(registrant.phone.as_deref(), Some("+1.5555550100"));
+        assert_eq!(parsed.registrant_name(), Some("Jane Doe"));
+        assert_eq!(parsed.registrant_email(), Some("jane@example.com"));
+        assert_eq!(parsed.tech_email(), Some("tech@example.com"));
+        assert_eq!(parsed.admin_email(), None);
+        assert!(parsed.admin.is_none() && parsed.billing.is_none());
+
+        // RDAP has no free-form key/value lines to keep
+        assert!(parsed.extra_fields.is_empty());
+        assert_eq!(parsed.privacy_protected, Some(false));
+        assert_eq!(parsed.confidence, 1.0);
+        assert!(!parsed.needs_review());
+    }
+
+    #[tokio::test]
+    async fn test_rdap_record_variants() {
+        // Redaction markers in the contact itself
+        let json = RDAP_RECORD.replace(r#"["fn", {}, "text", "Jane Doe"]"#, r#"["fn", {}, "text", "REDACTED FOR PRIVACY"]"#);
+        assert_eq!(parse_rdap(&json).await.privacy_protected, Some(true));
+
+        // An RFC 9537 `redacted` array is enough on its own
+        let mut json: serde_json::Value = serde_json::from_str(RDAP_RECORD).unwrap();
+        json["redacted"] = serde_json::json!([{"name": {"type": "Registrant Email"}, "method": "removal"}]);
+        assert_eq!(parse_rdap(&json.to_string()).await.privacy_protected, Some(true));
+
+        // An unreadable creation date, no expiry and no registrant cost 0.2 + 0.2 + 0.1
+        let mut json: serde_json::Value = serde_json::from_str(RDAP_RECORD).unwrap();
+        json["events"] = serde_json::json!([
+            {"eventAction": "registration", "eventDate": "sometime in 1995"},
+            {"eventAction": "last changed", "eventDate": "2024-08-14T07:01:34Z"}
+        ]);
+        json["entities"].as_array_mut().unwrap().retain(|entity| entity["roles"][0] != "registrant");
+        let parsed = parse_rdap(&json.to_string()).await;
+        assert_eq!(parsed.confidence, 0.5);
+        assert!(parsed.needs_review());
+        assert_eq!(parsed.creation_date_raw.as_deref(), Some("sometime in 1995"));
+
+        // Neither the handle nor a reseller is required
+        let mut json: serde_json::Value = serde_json::from_str(RDAP_RECORD).unwrap();
+        json.as_object_mut().unwrap().remove("handle");
+        json["entities"].as_array_mut().unwrap().retain(|entity| entity["roles"][0] != "reseller");
+        let parsed = parse_rdap(&json.to_string()).await;
+        assert_eq!(parsed.registry_domain_id, None);
+        assert_eq!(parsed.reseller, None);
+    }
+}
@@ -0,0 +1,92 @@
+//! RDAP gateway for the HTTP server
+//!
+//! `GET /rdap/domain/:domain` answers like an RFC 9083 RDAP server, so existing
+//! RDAP clients can point at this service. Results go through the normal
+//! cache-first lookup: an RDAP answer is passed through as the registry sent
+//! it, and data that came from port 43 is converted with
+//! `RdapDomain::from_parsed`. Errors use the RDAP error body
+//! (`errorCode`, `title`, `description`).
+
+use crate::{AppState, ValidatedDomain};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+use whois_service::{errors::WhoisError, RdapDomain, WhoisResponse};
+
+const RDAP_CONTENT_TYPE: &str = "application/rdap+json";
+
+/// `GET /rdap/domain/:domain`
+pub async fn domain(State(state): State<AppState>, Path(domain): Path<String>) -> Response {
+    match lookup(&state, domain).await {
+        Ok(document) => rdap_response(StatusCode::OK, &document),
+        Err(e) => rdap_error(e),
+    }
+}
+
+async fn lookup(state: &AppState, domain: String) -> Result<Value, WhoisError> {
+    let domain = ValidatedDomain::validate_domain(domain)?.0;
+    let response = crate::cached_lookup(state, domain, false).await?;
+
+    if let Some(document) = upstream_document(&response) {
+        return Ok(document);
+    }
+
+    let parsed = response
+        .parsed_data
+        .as_ref()
+        .ok_or_else(|| WhoisError::Internal(format!("No parsed registration data for {}", response.domain)))?;
+    let port43 = response.whois_server.strip_prefix("WHOIS: ");
+    let domain = RdapDomain::from_parsed(&response.domain, parsed, port43);
+    let mut document = serde_json::to_value(domain).map_err(|e| WhoisError::Internal(e.to_string()))?;
+    strip_nulls(&mut document);
+    Ok(document)
+}
+
+// The registry's own domain object; merged lookups append whois text after it
+fn upstream_document(response: &WhoisResponse) -> Option<Value> {
+    if !response.whois_server.starts_with("RDAP") {
+        return None;
+    }
+    let document: Value = serde_json::Deserializer::from_str(&response.raw_data).into_iter().next()?.ok()?;
+    (document.get("objectClassName")?.as_str()? == "domain").then_some(document)
+}
+
+// RDAP members are omitted rather than null
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(members) => {
+            members.retain(|_, member| !member.is_null());
+            members.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+fn rdap_response(status: StatusCode, document: &Value) -> Response {
+    let mut response = (status, document.to_string()).into_response();
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(RDAP_CONTENT_TYPE));
+    response
+}
+
+// Same status codes (and Retry-After) as the JSON API, in RFC 9083 section 6 form
+fn rdap_error(error: WhoisError) -> Response {
+    let description = error.to_string();
+    let mut response = error.into_response();
+    let status = response.status();
+    let description = if status.is_server_error() { "Internal server error".to_string() } else { description };
+
+    let document = json!({
+        "rdapConformance": ["rdap_level_0"],
+        "errorCode": status.as_u16(),
+        "title": status.canonical_reason().unwrap_or("Error"),
+        "description": [description],
+    });
+    *response.body_mut() = Body::from(document.to_string());
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(RDAP_CONTENT_TYPE));
+    response
+}
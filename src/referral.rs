@@ -0,0 +1,132 @@
+//! Validation of whois referral targets
+//!
+//! Registry and RIR responses name the server to ask next, so a hostile or
+//! broken upstream could point the service at arbitrary hosts. Before a
+//! referral is followed its target must be a syntactically valid hostname or
+//! IP literal on the whois port (43; any other port is refused, so an upstream
+//! can't aim the service at other services), must not match `referral_denylist`,
+//! and must match `referral_allowlist` when that is set. With
+//! `referral_deny_private_ips` the target's addresses - the literal, or what
+//! the hostname resolves to - must also be public, by the same check as RDAP
+//! redirects and webhooks (`rdap::is_private_ip`). The hostname is resolved
+//! once and the referral connects to exactly the addresses checked; a name that
+//! doesn't resolve is refused.
+//!
+//! List entries match a host and its subdomains ("example.net" covers
+//! "whois.example.net"); IP entries match that address. Servers the caller
+//! chose (configured root servers, TLD overrides, `LookupOptions::server`) are
+//! not referrals and aren't checked.
+
+use crate::{config::Config, rdap::is_private_ip};
+use std::net::{IpAddr, SocketAddr};
+
+// Longest hostname and label allowed by RFC 1035
+const MAX_HOSTNAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+/// Which referral targets may be connected to
+pub(crate) struct ReferralPolicy {
+    deny_private_ips: bool,
+    allowlist: Vec<String>,
+    denylist: Vec<String>,
+}
+
+impl ReferralPolicy {
+    pub(crate) fn new(config: &Config) -> Self {
+        let normalize = |entries: &[String]| entries.iter().map(|entry| normalize_host(entry)).collect();
+        Self {
+            deny_private_ips: config.referral_deny_private_ips,
+            allowlist: normalize(&config.referral_allowlist),
+            denylist: normalize(&config.referral_denylist),
+        }
+    }
+
+    /// Whether resolved addresses have to be checked with `check_addresses`
+    pub(crate) fn checks_addresses(&self) -> bool {
+        self.deny_private_ips
+    }
+
+    /// Check the host part of a referral target; `Err` says why it is refused
+    pub(crate) fn check(&self, host: &str) -> Result<(), String> {
+        let host = normalize_host(host);
+        let ip = host.parse::<IpAddr>().ok();
+        if ip.is_none() && !is_valid_hostname(&host) {
+            return Err(format!("'{}' is not a valid hostname", host));
+        }
+        if self.denylist.iter().any(|entry| matches(&host, entry)) {
+            return Err(format!("{} is on the referral denylist", host));
+        }
+        if !self.allowlist.is_empty() && !self.allowlist.iter().any(|entry| matches(&host, entry)) {
+            return Err(format!("{} is not on the referral allowlist", host));
+        }
+        match ip {
+            Some(ip) => self.check_addresses(&[SocketAddr::new(ip, 0)]),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuse targets resolving to non-public addresses when `referral_deny_private_ips` is set
+    pub(crate) fn check_addresses(&self, addresses: &[SocketAddr]) -> Result<(), String> {
+        if !self.deny_private_ips {
+            return Ok(());
+        }
+        match addresses.iter().find(|address| is_private_ip(address.ip())) {
+            Some(address) => Err(format!("{} is not a public address", address.ip())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Lowercase host without IPv6 brackets or a trailing dot
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_start_matches('[').trim_end_matches(']').trim_end_matches('.').to_lowercase()
+}
+
+fn matches(host: &str, entry: &str) -> bool {
+    host == entry || host.strip_suffix(entry).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// LDH hostname: dot-separated labels of letters, digits and inner hyphens
+fn is_valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= MAX_HOSTNAME_LEN
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_LABEL_LEN
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referral_targets_are_validated() {
+        let mut config = Config::builder().build().unwrap();
+        let policy = ReferralPolicy::new(&config);
+        assert!(policy.check("whois.markmonitor.com").is_ok());
+        assert!(policy.check("WHOIS.Example.NET.").is_ok());
+        assert!(policy.check("127.0.0.1").is_ok());
+        assert!(policy.check("whois example.com").is_err());
+        assert!(policy.check("-bad.example").is_err());
+        assert!(policy.check("").is_err());
+
+        config.referral_deny_private_ips = true;
+        config.referral_allowlist = vec!["example.net".to_string(), "192.0.32.10".to_string(), "10.1.2.3".to_string()];
+        config.referral_denylist = vec!["evil.example.net".to_string()];
+        let policy = ReferralPolicy::new(&config);
+        assert!(policy.check("whois.example.net").is_ok());
+        assert!(policy.check("192.0.32.10").is_ok());
+        assert!(policy.check("badexample.net").is_err());
+        assert!(policy.check("whois.evil.example.net").is_err());
+        assert!(policy.check("10.1.2.3").is_err());
+        assert!(policy.check_addresses(&["[fe80::1]:43".parse().unwrap()]).is_err());
+        assert!(policy.check_addresses(&["[::ffff:192.168.1.1]:43".parse().unwrap()]).is_err());
+        assert!(policy.check_addresses(&["100.72.0.1:43".parse().unwrap()]).is_err());
+        assert!(policy.check_addresses(&["224.0.0.1:43".parse().unwrap()]).is_err());
+        assert!(policy.check_addresses(&["[2001:500:8f::53]:43".parse().unwrap(), "192.0.32.10:43".parse().unwrap()]).is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+//! `X-Request-Id` handling for the HTTP server
+//!
+//! Every request gets an ID (the caller's `X-Request-Id` if it sent a usable
+//! one, a fresh UUID otherwise) that is echoed in the response header, recorded
+//! on the request span so every log line of the request carries it, and added to
+//! JSON error bodies so a user-reported failure can be matched to the server logs.
+//!
+//! A caller's ID is only kept when it is at most `MAX_REQUEST_ID_LEN` letters,
+//! digits, `-`, `_`, `.` or `:`, so it can't flood or forge log lines.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use tower_http::trace::MakeSpan;
+use tracing::Span;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Error bodies are a few hundred bytes; anything bigger (or of unknown size) is passed through untouched
+const MAX_ERROR_BODY: usize = 64 * 1024;
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+fn request_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(X_REQUEST_ID).and_then(|value| value.to_str().ok())
+}
+
+fn is_usable_id(id: &[u8]) -> bool {
+    (1..=MAX_REQUEST_ID_LEN).contains(&id.len())
+        && id.iter().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Drop an unusable caller `X-Request-Id`, so `SetRequestIdLayer` (next in line) issues a fresh one
+pub async fn drop_unusable_id(mut request: Request, next: Next) -> Response {
+    if request.headers().get(X_REQUEST_ID).is_some_and(|id| !is_usable_id(id.as_bytes())) {
+        request.headers_mut().remove(X_REQUEST_ID);
+    }
+    next.run(request).await
+}
+
+/// `TraceLayer` span carrying the request ID (and the remote trace parent with `otel`)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestSpan;
+
+impl<B> MakeSpan<B> for RequestSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        let span = tracing::info_span!(
+            "request",
+            request_id = request_id(request.headers()).unwrap_or_default(),
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+        );
+        #[cfg(feature = "otel")]
+        crate::otel::set_remote_parent(&span, request.headers());
+        span
+    }
+}
+
+/// Add `"request_id"` to JSON error bodies
+pub async fn tag_error_body(request: Request, next: Next) -> Response {
+    let id = request_id(request.headers()).map(str::to_owned);
+    let response = next.run(request).await;
+
+    let Some(id) = id else { return response };
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !(response.status().is_client_error() || response.status().is_server_error()) || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let size = body.size_hint().upper();
+    if size.is_none_or(|size| size > MAX_ERROR_BODY as u64) {
+        return Response::from_parts(parts, body);
+    }
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut fields)) => {
+            fields.insert("request_id".to_string(), Value::String(id));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(Value::Object(fields).to_string())
+        }
+        _ => Body::from(bytes),
+    };
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderValue, routing::get, Json, Router};
+    use tower::{ServiceBuilder, ServiceExt};
+    use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+
+    fn app() -> Router {
+        let error = || async { (StatusCode::NOT_FOUND, Json(serde_json::json!({"code": "DOMAIN_NOT_FOUND"}))) };
+        let huge = || async { (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"raw": "x".repeat(MAX_ERROR_BODY)}))) };
+        Router::new().route("/error", get(error)).route("/huge", get(huge)).layer(
+            ServiceBuilder::new()
+                .layer(axum::middleware::from_fn(drop_unusable_id))
+                .layer(SetRequestIdLayer::new(X_REQUEST_ID, MakeRequestUuid))
+                .layer(PropagateRequestIdLayer::new(X_REQUEST_ID))
+                .layer(axum::middleware::from_fn(tag_error_body)),
+        )
+    }
+
+    async fn get_with_id(path: &str, id: Option<&str>) -> (String, serde_json::Value) {
+        let mut request = Request::builder().uri(path);
+        if let Some(id) = id {
+            request = request.header(X_REQUEST_ID, HeaderValue::from_str(id).unwrap());
+        }
+        let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let id = response.headers()[X_REQUEST_ID].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (id, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_request_ids_are_echoed_and_tag_error_bodies() {
+        let (id, body) = get_with_id("/error", Some("req-42.a:b_c")).await;
+        assert_eq!(id, "req-42.a:b_c");
+        assert_eq!(body["request_id"], "req-42.a:b_c");
+        assert_eq!(body["code"], "DOMAIN_NOT_FOUND");
+
+        // Missing, overlong or odd IDs are replaced with a fresh one
+        for caller_id in [None, Some("x".repeat(MAX_REQUEST_ID_LEN + 1)), Some("forged\tlog line".to_string())] {
+            let (id, body) = get_with_id("/error", caller_id.as_deref()).await;
+            assert_eq!(id.len(), 36, "{:?}", caller_id);
+            assert_eq!(body["request_id"], id.as_str());
+        }
+
+        // Bodies too big to buffer pass through untouched
+        let (_, body) = get_with_id("/huge", Some("req-43")).await;
+        assert!(body.get("request_id").is_none());
+        assert_eq!(body["raw"].as_str().unwrap().len(), MAX_ERROR_BODY);
+    }
+}
@@ -0,0 +1,162 @@
+//! Incremental port-43 response reading
+//!
+//! Hands a whois answer out chunk by chunk instead of collecting the whole
+//! body first, so `max_response_size` is enforced as data arrives. With
+//! `stop_at_end_of_record` - used for `-k` persistent connections, the only
+//! mode in which servers keep the socket open - the answer ends at its
+//! end-of-record marker instead of EOF; plain queries always read to EOF.
+//!
+//! Chunks can be fed straight into a
+//! [`StreamingParser`](crate::parser::StreamingParser), as `WhoisService` does
+//! for domain lookups: each hop of a referral chain is parsed while it
+//! arrives, and the last hop's parse becomes the result. The text is still
+//! collected alongside, for `raw_data`, fixtures and finding referrals.
+
+use crate::buffer_pool::PooledBuffer;
+use crate::connection_pool::ConnectionPool;
+use crate::errors::WhoisError;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::time::timeout;
+
+// Longest end-of-record marker ("\r\n\r\n\r\n"); only this much history is kept
+const MARKER_WINDOW: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadState {
+    Reading,
+    Eof,
+    EndOfRecord,
+}
+
+pub struct ResponseReader<R> {
+    inner: R,
+    buffer: PooledBuffer,
+    read_timeout: Duration,
+    max_size: usize,
+    received: usize,
+    tail: Vec<u8>,
+    stop_at_end_of_record: bool,
+    state: ReadState,
+}
+
+impl<R: AsyncRead + Unpin> ResponseReader<R> {
+    /// Read from `inner` using a pooled buffer, failing once more than `max_size` bytes arrive
+    pub fn new(inner: R, buffer: PooledBuffer, read_timeout: Duration, max_size: usize) -> Self {
+        Self {
+            inner,
+            buffer,
+            read_timeout,
+            max_size,
+            received: 0,
+            tail: Vec::with_capacity(MARKER_WINDOW * 2),
+            stop_at_end_of_record: false,
+            state: ReadState::Reading,
+        }
+    }
+
+    /// End the answer at two blank lines instead of waiting for EOF (persistent servers)
+    pub fn stop_at_end_of_record(mut self) -> Self {
+        self.stop_at_end_of_record = true;
+        self
+    }
+
+    /// Next chunk of the answer, or `None` once it has ended
+    ///
+    /// Each read gets the full `read_timeout`, so a slow but steady server is
+    /// not cut off as long as data keeps arriving.
+    pub async fn next_chunk(&mut self) -> Result<Option<&[u8]>, WhoisError> {
+        if self.state != ReadState::Reading {
+            return Ok(None);
+        }
+
+        let buffer = self.buffer.as_mut_slice();
+        let n = timeout(self.read_timeout, self.inner.read(buffer)).await??;
+        if n == 0 {
+            self.state = ReadState::Eof;
+            return Ok(None);
+        }
+
+        self.received += n;
+        if self.received > self.max_size {
+            return Err(WhoisError::ResponseTooLarge);
+        }
+
+        if self.stop_at_end_of_record {
+            // The marker can straddle reads, so check it against the previous tail too
+            self.tail.extend_from_slice(&buffer[n.saturating_sub(MARKER_WINDOW)..n]);
+            let excess = self.tail.len().saturating_sub(MARKER_WINDOW);
+            self.tail.drain(..excess);
+            if ConnectionPool::is_response_complete(&self.tail) {
+                self.state = ReadState::EndOfRecord;
+            }
+        }
+
+        Ok(Some(&buffer[..n]))
+    }
+
+    /// Read the rest of the answer into memory
+    pub async fn read_to_end(&mut self) -> Result<Vec<u8>, WhoisError> {
+        let mut response = Vec::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            response.extend_from_slice(chunk);
+        }
+        Ok(response)
+    }
+
+    /// Whether the answer ended at its end-of-record marker with the connection still open
+    pub fn ended_at_marker(&self) -> bool {
+        self.state == ReadState::EndOfRecord
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer_pool::BufferPool;
+    use std::sync::Arc;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_stops_at_marker_and_enforces_size_progressively() {
+        let pool = Arc::new(BufferPool::new(2, 4));
+        let (mut server, client) = tokio::io::duplex(64);
+
+        // Server keeps the connection open and sends the last newline of the marker late
+        server.write_all(b"a: 1\n\n").await.unwrap();
+        let mut reader = ResponseReader::new(client, pool.get(), Duration::from_secs(1), 1024).stop_at_end_of_record();
+        let first = reader.next_chunk().await.unwrap().unwrap().to_vec();
+        assert_eq!(first, b"a: 1");
+        server.write_all(b"\n").await.unwrap();
+        let rest = reader.read_to_end().await.unwrap();
+        assert_eq!([first, rest].concat(), b"a: 1\n\n\n");
+        assert!(reader.ended_at_marker());
+
+        let (mut server, client) = tokio::io::duplex(64);
+        server.write_all(b"0123456789").await.unwrap();
+        let mut reader = ResponseReader::new(client, pool.get(), Duration::from_secs(1), 6);
+        assert!(reader.next_chunk().await.unwrap().is_some());
+        // Fails on the read that crosses the limit, without waiting for EOF
+        assert!(matches!(reader.next_chunk().await, Err(WhoisError::ResponseTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn test_chunks_feed_the_streaming_parser() {
+        // 4-byte buffers split every line across reads
+        let pool = Arc::new(BufferPool::new(1, 4));
+        let (mut server, client) = tokio::io::duplex(256);
+        let data = "Domain Name: EXAMPLE.COM\nRegistrar: Example Registrar\nName Server: NS1.EXAMPLE.COM\n\n\n";
+        server.write_all(data.as_bytes()).await.unwrap();
+
+        let mut reader = ResponseReader::new(client, pool.get(), Duration::from_secs(1), 1024).stop_at_end_of_record();
+        let mut parser = crate::parser::StreamingParser::new();
+        while let Some(chunk) = reader.next_chunk().await.unwrap() {
+            parser.feed(chunk);
+        }
+        assert!(reader.ended_at_marker());
+
+        let parsed = parser.finish().unwrap();
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar"));
+        assert_eq!(parsed.name_servers, crate::parser::WhoisParser::parse(data).name_servers);
+    }
+}
@@ -0,0 +1,93 @@
+//! Coalescing of identical concurrent calls
+//!
+//! The first caller for a key starts the work; callers arriving while it is in
+//! flight await the same future and get a clone of its output. The map only
+//! holds weak references, so when every caller has given up (timeout, dropped
+//! request) the work is cancelled as it would be without coalescing.
+
+use futures::future::{BoxFuture, FutureExt, Shared, WeakShared};
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+pub(crate) type Call<V> = Shared<BoxFuture<'static, V>>;
+
+pub(crate) struct InFlight<K, V> {
+    calls: Mutex<HashMap<K, WeakShared<BoxFuture<'static, V>>>>,
+}
+
+impl<K, V> InFlight<K, V>
+where
+    K: Eq + Hash + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self { calls: Mutex::new(HashMap::new()) })
+    }
+
+    /// Join the call in flight for `key`, or start `work` as that call
+    pub(crate) fn run<F>(self: &Arc<Self>, key: K, work: impl FnOnce() -> F) -> Call<V>
+    where
+        F: Future<Output = V> + Send + 'static,
+    {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(call) = calls.get(&key).and_then(WeakShared::upgrade) {
+            return call;
+        }
+        // Drop entries whose callers all went away before they finished
+        calls.retain(|_, call| call.upgrade().is_some());
+
+        let in_flight = Arc::downgrade(self);
+        let finished = key.clone();
+        let work = work();
+        let call = async move {
+            let output = work.await;
+            if let Some(in_flight) = in_flight.upgrade() {
+                in_flight.calls.lock().unwrap_or_else(|e| e.into_inner()).remove(&finished);
+            }
+            output
+        }
+        .boxed()
+        .shared();
+
+        if let Some(weak) = call.downgrade() {
+            calls.insert(key, weak);
+        }
+        call
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_execution() {
+        let in_flight: Arc<InFlight<&str, usize>> = InFlight::new();
+        let executions = Arc::new(AtomicUsize::new(0));
+        let call = || {
+            let executions = executions.clone();
+            in_flight.run("example.com", move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                executions.fetch_add(1, Ordering::SeqCst) + 1
+            })
+        };
+
+        let results = futures::future::join_all((0..20).map(|_| call())).await;
+        assert!(results.iter().all(|&result| result == 1));
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+
+        // Finished calls leave the map, so the next one runs again
+        assert_eq!(call().await, 2);
+
+        // A call nobody waits for anymore is dropped, not joined
+        assert!(tokio::time::timeout(Duration::from_millis(10), call()).await.is_err());
+        assert_eq!(call().await, 3);
+        assert!(in_flight.calls.lock().unwrap().is_empty());
+    }
+}
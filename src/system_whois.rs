@@ -0,0 +1,74 @@
+//! System `whois` command fallback
+//!
+//! Last-resort tier for TLDs where both RDAP and native port-43 lookups fail.
+//! Shells out to the `whois` binary on PATH with the configured timeout and
+//! response size limit. Enabled with the `system-whois` feature.
+
+use crate::{config::Config, errors::WhoisError, parser::WhoisParser, whois::WhoisResult};
+use std::{process::Stdio, sync::Arc, time::Duration};
+use tokio::{io::AsyncReadExt, process::Command};
+use tracing::debug;
+
+const SYSTEM_WHOIS_BINARY: &str = "whois";
+
+pub struct SystemWhois {
+    timeout: Duration,
+    max_response_size: usize,
+}
+
+impl SystemWhois {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.whois_timeout_seconds),
+            max_response_size: config.max_response_size,
+        }
+    }
+
+    /// Run `whois <domain>` and parse its output
+    pub async fn lookup(&self, domain: &str) -> Result<WhoisResult, WhoisError> {
+        debug!("Falling back to system {} command for {}", SYSTEM_WHOIS_BINARY, domain);
+
+        // kill_on_drop reaps the child if we time out or give up on oversized output
+        let mut child = Command::new(SYSTEM_WHOIS_BINARY)
+            .arg("--")
+            .arg(domain)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut stdout = child.stdout.take()
+            .ok_or_else(|| WhoisError::Internal("Failed to capture whois command output".to_string()))?;
+
+        let (output, status) = tokio::time::timeout(self.timeout, async {
+            // Read one byte past the limit so oversized output is detectable
+            let mut output = Vec::new();
+            (&mut stdout).take(self.max_response_size as u64 + 1).read_to_end(&mut output).await?;
+            if output.len() > self.max_response_size {
+                return Err(WhoisError::ResponseTooLarge);
+            }
+            let status = child.wait().await?;
+            Ok((output, status))
+        })
+        .await??;
+
+        let raw_data = String::from_utf8_lossy(&output).into_owned();
+        if raw_data.trim().is_empty() {
+            return Err(WhoisError::Internal(format!(
+                "System whois command returned no output for {} ({})",
+                domain, status
+            )));
+        }
+
+        let (parsed_data, parsing_report) = WhoisParser::parse_with_report_for(&raw_data, Some(domain));
+
+        Ok(WhoisResult {
+            server: SYSTEM_WHOIS_BINARY.to_string(),
+            raw_data,
+            parsed_data: Some(parsed_data),
+            parsing_report,
+            referral_chain: Vec::new(),
+        })
+    }
+}
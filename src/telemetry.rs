@@ -0,0 +1,71 @@
+//! Library metrics (feature `metrics`)
+//!
+//! Emits through the `metrics` facade, so any recorder the embedding
+//! application installs (Prometheus, StatsD, ...) picks them up. Without the
+//! feature every function here is a no-op.
+//!
+//! - `whois_upstream_queries_total{protocol, server, outcome}`
+//! - `whois_upstream_query_duration_seconds{protocol, server}`
+//! - `whois_referrals_total{server}` - referrals followed, by target server
+//! - `whois_cache_hits_total` / `whois_cache_misses_total`
+//! - `whois_buffer_pool_*` - network buffer pool utilization
+
+#[cfg(feature = "whois-tcp")]
+use crate::BufferPoolStats;
+use crate::WhoisError;
+use std::time::Duration;
+
+/// Count and time one query to an upstream whois or RDAP server
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_upstream_query<T>(protocol: &'static str, server: &str, elapsed: Duration, result: &Result<T, WhoisError>) {
+    #[cfg(feature = "metrics")]
+    {
+        let outcome = match result {
+            Ok(_) => "success",
+            Err(WhoisError::DomainNotFound(_)) => "not_found",
+            Err(WhoisError::Timeout) => "timeout",
+            Err(WhoisError::RateLimited { .. }) => "rate_limited",
+            Err(_) => "error",
+        };
+        let server = server.to_lowercase();
+        metrics::counter!(
+            "whois_upstream_queries_total",
+            "protocol" => protocol,
+            "server" => server.clone(),
+            "outcome" => outcome
+        )
+        .increment(1);
+        metrics::histogram!("whois_upstream_query_duration_seconds", "protocol" => protocol, "server" => server)
+            .record(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(feature = "whois-tcp")]
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_referral(server: &str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("whois_referrals_total", "server" => server.to_lowercase()).increment(1);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_cache_lookup(hit: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let name = if hit { "whois_cache_hits_total" } else { "whois_cache_misses_total" };
+        metrics::counter!(name).increment(1);
+    }
+}
+
+#[cfg(feature = "whois-tcp")]
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_buffer_pool(stats: &BufferPoolStats) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::gauge!("whois_buffer_pool_idle").set(stats.idle as f64);
+        metrics::gauge!("whois_buffer_pool_in_use").set(stats.in_use as f64);
+        metrics::counter!("whois_buffer_pool_hits_total").absolute(stats.hits);
+        metrics::counter!("whois_buffer_pool_misses_total").absolute(stats.misses);
+        metrics::counter!("whois_buffer_pool_discarded_total").absolute(stats.discarded);
+    }
+}
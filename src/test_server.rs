@@ -0,0 +1,250 @@
+//! Local whois and RDAP servers for offline tests (feature `test-util`)
+//!
+//! `MockWhoisServer` speaks port-43 whois on a loopback TCP listener and
+//! `MockRdapServer` serves RDAP domain objects plus a matching bootstrap
+//! registry over HTTP. Both answer from fixtures, either passed in directly or
+//! loaded from a directory:
+//!
+//! - whois: `<query>.txt` holds the raw answer for that query
+//! - RDAP: `<domain>.json` holds the domain object for that domain
+//!
+//! Point a `Config` at them with `tld_server_overrides` (whois) and
+//! `rdap_bootstrap_urls` (RDAP). Fixture domains should use TLDs without a
+//! built-in RDAP mapping (e.g. `.test`) so the bootstrap is consulted.
+//! `with_delay` holds every answer back to stand in for a slow upstream.
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    task::JoinHandle,
+};
+use tracing::debug;
+
+/// Read every `*.<extension>` file in `dir`, keyed by lowercased file stem
+async fn load_fixtures(dir: &Path, extension: &str) -> io::Result<HashMap<String, String>> {
+    let mut fixtures = HashMap::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(extension) {
+            continue;
+        }
+        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            fixtures.insert(stem.to_lowercase(), tokio::fs::read_to_string(&path).await?);
+        }
+    }
+    Ok(fixtures)
+}
+
+/// Port-43 whois server answering from canned responses
+///
+/// Unknown queries get a Verisign-style "No match" answer. The server stops
+/// when dropped.
+pub struct MockWhoisServer {
+    addr: SocketAddr,
+    delay_ms: Arc<AtomicU64>,
+    task: JoinHandle<()>,
+}
+
+impl MockWhoisServer {
+    /// Serve `responses` (query -> raw answer) on an ephemeral loopback port
+    pub async fn start(responses: HashMap<String, String>) -> io::Result<Self> {
+        let responses: Arc<HashMap<String, String>> = Arc::new(
+            responses.into_iter().map(|(query, answer)| (query.to_lowercase(), answer)).collect(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let delay_ms = Arc::new(AtomicU64::new(0));
+
+        let delay = delay_ms.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let responses = responses.clone();
+                let delay = Duration::from_millis(delay.load(Ordering::Relaxed));
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    if let Err(e) = Self::answer(stream, &responses).await {
+                        debug!("Mock whois connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, delay_ms, task })
+    }
+
+    /// Wait `delay` before answering each query
+    pub fn with_delay(self, delay: Duration) -> Self {
+        self.delay_ms.store(delay.as_millis() as u64, Ordering::Relaxed);
+        self
+    }
+
+    /// Serve the `<query>.txt` fixtures in `dir`
+    pub async fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        Self::start(load_fixtures(dir.as_ref(), "txt").await?).await
+    }
+
+    async fn answer(stream: tokio::net::TcpStream, responses: &HashMap<String, String>) -> io::Result<()> {
+        let mut stream = BufReader::new(stream);
+        let mut line = String::new();
+        stream.read_line(&mut line).await?;
+
+        let query = line.trim().to_lowercase();
+        let answer = match responses.get(&query) {
+            Some(answer) => answer.clone(),
+            None => format!("No match for \"{}\".\r\n", query.to_uppercase()),
+        };
+
+        let stream = stream.get_mut();
+        stream.write_all(answer.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Address usable as a whois server, e.g. in `tld_server_overrides`
+    pub fn address(&self) -> String {
+        self.addr.to_string()
+    }
+}
+
+impl Drop for MockWhoisServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// HTTP server answering RDAP domain queries and serving a bootstrap registry
+///
+/// `GET /domain/<name>` returns the fixture for that domain (404 if unknown) and
+/// `GET /bootstrap/dns.json` maps every fixture TLD to this server. The server
+/// stops when dropped.
+pub struct MockRdapServer {
+    addr: SocketAddr,
+    state: Arc<RdapState>,
+    task: JoinHandle<()>,
+}
+
+struct RdapState {
+    domains: HashMap<String, String>,
+    bootstrap: String,
+    delay_ms: AtomicU64,
+}
+
+impl MockRdapServer {
+    /// Serve `domains` (domain -> RDAP JSON) on an ephemeral loopback port
+    pub async fn start(domains: HashMap<String, String>) -> io::Result<Self> {
+        let domains: HashMap<String, String> =
+            domains.into_iter().map(|(domain, json)| (domain.to_lowercase(), json)).collect();
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let mut tlds: Vec<&str> = domains.keys().filter_map(|d| d.rsplit('.').next()).collect();
+        tlds.sort_unstable();
+        tlds.dedup();
+        let bootstrap = serde_json::json!({
+            "version": "1.0",
+            "publication": "2024-01-01T00:00:00Z",
+            "services": [[tlds, [format!("http://{}/", addr)]]],
+        })
+        .to_string();
+
+        let state = Arc::new(RdapState { domains, bootstrap, delay_ms: AtomicU64::new(0) });
+        let app = Router::new()
+            .route("/domain/:name", get(Self::domain))
+            .route("/bootstrap/dns.json", get(Self::bootstrap))
+            .with_state(state.clone());
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                debug!("Mock RDAP server stopped: {}", e);
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Wait `delay` before answering each domain query (the bootstrap registry is served at once)
+    pub fn with_delay(self, delay: Duration) -> Self {
+        self.state.delay_ms.store(delay.as_millis() as u64, Ordering::Relaxed);
+        self
+    }
+
+    /// Serve the `<domain>.json` fixtures in `dir`
+    pub async fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        Self::start(load_fixtures(dir.as_ref(), "json").await?).await
+    }
+
+    async fn domain(State(state): State<Arc<RdapState>>, UrlPath(name): UrlPath<String>) -> Response {
+        tokio::time::sleep(Duration::from_millis(state.delay_ms.load(Ordering::Relaxed))).await;
+        match state.domains.get(&name.to_lowercase()) {
+            Some(json) => ([(header::CONTENT_TYPE, "application/rdap+json")], json.clone()).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    async fn bootstrap(State(state): State<Arc<RdapState>>) -> Response {
+        ([(header::CONTENT_TYPE, "application/json")], state.bootstrap.clone()).into_response()
+    }
+
+    /// Base URL of the RDAP service, e.g. "http://127.0.0.1:4321/"
+    pub fn base_url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// URL of the bootstrap registry, for `rdap_bootstrap_urls`
+    pub fn bootstrap_url(&self) -> String {
+        format!("http://{}/bootstrap/dns.json", self.addr)
+    }
+}
+
+impl Drop for MockRdapServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(all(test, feature = "whois-tcp"))]
+mod tests {
+    use super::*;
+    use crate::{Config, ProtocolPreference, WhoisClient};
+
+    #[tokio::test]
+    async fn test_client_resolves_against_fixture_servers() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let whois = MockWhoisServer::from_dir(fixtures.join("whois")).await.unwrap();
+        let rdap = MockRdapServer::from_dir(fixtures.join("rdap")).await.unwrap();
+
+        let base = Config::builder()
+            .tld_server_override("test", whois.address())
+            .rdap_bootstrap_urls(vec![rdap.bootstrap_url()]);
+
+        let whois_only = base.clone().protocol_preference(ProtocolPreference::WhoisOnly);
+        let client = WhoisClient::builder().config(whois_only).without_cache().build().await.unwrap();
+        let response = client.lookup("example.test").await.unwrap();
+        assert_eq!(response.parsed_data.unwrap().registrar.as_deref(), Some("Fixture Registrar, Inc."));
+        assert!(client.lookup("missing.test").await.is_err());
+
+        let rdap_only = base.protocol_preference(ProtocolPreference::RdapOnly);
+        let client = WhoisClient::builder().config(rdap_only).without_cache().build().await.unwrap();
+        let response = client.lookup("example.test").await.unwrap();
+        assert!(response.whois_server.contains(&rdap.base_url()));
+        assert_eq!(response.parsed_data.unwrap().registrar.as_deref(), Some("Fixture Registrar, Inc."));
+    }
+}
@@ -0,0 +1,262 @@
+//! STIX 2.1 and MISP export (feature `threat-intel`)
+//!
+//! `WhoisResponse::to_stix` builds a STIX 2.1 bundle holding a `domain-name`
+//! observable and an `x-whois` object with the registration details, linked
+//! through `domain_ref`. The `domain-name` id is the deterministic UUIDv5 the
+//! specification prescribes (section 2.9), so repeated exports of a domain
+//! deduplicate in a TIP. `WhoisResponse::to_misp` builds a MISP object that
+//! follows the `whois` object template.
+//!
+//! Dates are normalized to RFC 3339 when they parse; anything else is passed
+//! through as the registry wrote it.
+
+use crate::{parser::WhoisParser, Contact, ParsedWhoisData, WhoisResponse};
+use chrono::{DateTime, SecondsFormat, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const STIX_SPEC_VERSION: &str = "2.1";
+// STIX 2.1 section 2.9: namespace for deterministic SCO identifiers
+const STIX_SCO_NAMESPACE: Uuid = Uuid::from_u128(0x00abedb4_aa42_466c_9c01_fed23315a9b7);
+
+/// A STIX 2.1 bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StixBundle {
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub id: String,
+    pub objects: Vec<StixObject>,
+}
+
+/// Objects carried in a whois bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum StixObject {
+    #[serde(rename = "domain-name")]
+    DomainName(StixDomainName),
+    #[serde(rename = "x-whois")]
+    Whois(Box<StixWhois>),
+}
+
+/// `domain-name` cyber observable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StixDomainName {
+    pub spec_version: String,
+    pub id: String,
+    pub value: String,
+}
+
+/// Custom `x-whois` object with the registration details of `domain_ref`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StixWhois {
+    pub spec_version: String,
+    pub id: String,
+    pub created: String,
+    pub modified: String,
+    pub domain_ref: String,
+    pub whois_server: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrar: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrar_iana_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub name_servers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrant_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrant_organization: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrant_email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrant_country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy_protected: Option<bool>,
+}
+
+/// A MISP object (`whois` template) ready for the events API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MispObject {
+    pub name: String,
+    #[serde(rename = "meta-category")]
+    pub meta_category: String,
+    pub description: String,
+    #[serde(rename = "Attribute")]
+    pub attributes: Vec<MispAttribute>,
+}
+
+/// One attribute of a MISP object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MispAttribute {
+    #[serde(rename = "type")]
+    pub attribute_type: String,
+    pub category: String,
+    pub object_relation: String,
+    pub value: String,
+    pub to_ids: bool,
+}
+
+impl WhoisResponse {
+    /// STIX 2.1 bundle with a `domain-name` observable and its `x-whois` details
+    pub fn to_stix(&self) -> StixBundle {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let domain = StixDomainName {
+            spec_version: STIX_SPEC_VERSION.to_string(),
+            id: domain_name_id(&self.domain),
+            value: self.domain.clone(),
+        };
+
+        let parsed = self.parsed_data.clone().unwrap_or_default();
+        let dates = Dates::new(&parsed);
+        let registrant = parsed.registrant.unwrap_or_default();
+        let whois = StixWhois {
+            spec_version: STIX_SPEC_VERSION.to_string(),
+            id: format!("x-whois--{}", Uuid::new_v4()),
+            created: now.clone(),
+            modified: now,
+            domain_ref: domain.id.clone(),
+            whois_server: self.whois_server.clone(),
+            registrar: parsed.registrar,
+            registrar_iana_id: parsed.registrar_iana_id,
+            creation_date: dates.created,
+            expiration_date: dates.expires,
+            updated_date: dates.updated,
+            name_servers: parsed.name_servers,
+            status: parsed.status,
+            registrant_name: registrant.name,
+            registrant_organization: registrant.organization,
+            registrant_email: registrant.email,
+            registrant_country: registrant.country,
+            privacy_protected: parsed.privacy_protected,
+        };
+
+        StixBundle {
+            object_type: "bundle".to_string(),
+            id: format!("bundle--{}", Uuid::new_v4()),
+            objects: vec![StixObject::DomainName(domain), StixObject::Whois(Box::new(whois))],
+        }
+    }
+
+    /// MISP `whois` object; the raw reply goes in the `text` attribute
+    pub fn to_misp(&self) -> MispObject {
+        let parsed = self.parsed_data.clone().unwrap_or_default();
+        let dates = Dates::new(&parsed);
+        let mut attributes = vec![misp("domain", "Network activity", "domain", &self.domain)];
+
+        let mut push = |attribute_type, category, relation, value: Option<String>| {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                attributes.push(misp(attribute_type, category, relation, &value));
+            }
+        };
+        push("whois-registrar", "Attribution", "registrar", parsed.registrar);
+        let Contact { name, organization, email, phone, .. } = parsed.registrant.unwrap_or_default();
+        push("whois-registrant-name", "Attribution", "registrant-name", name);
+        push("whois-registrant-org", "Attribution", "registrant-org", organization);
+        push("whois-registrant-email", "Attribution", "registrant-email", email);
+        push("whois-registrant-phone", "Attribution", "registrant-phone", phone);
+        push("datetime", "Other", "creation-date", dates.created);
+        push("datetime", "Other", "expiration-date", dates.expires);
+        push("datetime", "Other", "modification-date", dates.updated);
+        for name_server in parsed.name_servers {
+            push("hostname", "Network activity", "nameserver", Some(name_server.to_lowercase()));
+        }
+        push("text", "Other", "text", Some(self.raw_data.clone()));
+
+        MispObject {
+            name: "whois".to_string(),
+            meta_category: "network".to_string(),
+            description: format!("Whois record for {} from {}", self.domain, self.whois_server),
+            attributes,
+        }
+    }
+}
+
+fn misp(attribute_type: &str, category: &str, relation: &str, value: &str) -> MispAttribute {
+    MispAttribute {
+        attribute_type: attribute_type.to_string(),
+        category: category.to_string(),
+        object_relation: relation.to_string(),
+        value: value.to_string(),
+        to_ids: false,
+    }
+}
+
+struct Dates {
+    created: Option<String>,
+    expires: Option<String>,
+    updated: Option<String>,
+}
+
+impl Dates {
+    fn new(parsed: &ParsedWhoisData) -> Self {
+        let parser = WhoisParser::new();
+        let normalize = |date: &Option<String>| {
+            let date = date.as_deref()?;
+            Some(match parser.parse_date(date) {
+                Some(parsed) => rfc3339(parsed),
+                None => date.to_string(),
+            })
+        };
+        Self {
+            created: normalize(&parsed.creation_date),
+            expires: normalize(&parsed.expiration_date),
+            updated: normalize(&parsed.updated_date),
+        }
+    }
+}
+
+fn rfc3339(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// UUIDv5 over the canonical JSON of the id-contributing property (`value`)
+fn domain_name_id(domain: &str) -> String {
+    let name = serde_json::json!({ "value": domain }).to_string();
+    let mut context = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    context.update(STIX_SCO_NAMESPACE.as_bytes());
+    context.update(name.as_bytes());
+    let hash = context.finish();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash.as_ref()[..16]);
+    format!("domain-name--{}", uuid::Builder::from_sha1_bytes(bytes).into_uuid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stix_and_misp_export() {
+        let response = WhoisResponse {
+            parsed_data: Some(ParsedWhoisData {
+                registrar: Some("RESERVED-Internet Assigned Numbers Authority".to_string()),
+                creation_date: Some("1995-08-14T04:00:00Z".to_string()),
+                name_servers: vec!["A.IANA-SERVERS.NET".to_string()],
+                ..ParsedWhoisData::default()
+            }),
+            ..crate::test_response("example.com")
+        };
+
+        // Matches the id the reference python-stix2 library generates
+        let bundle = serde_json::to_value(response.to_stix()).unwrap();
+        assert_eq!(bundle["objects"][0]["id"], "domain-name--bedb4899-d24b-5401-bc86-8f6b4cc18ec7");
+        assert_eq!(bundle["objects"][1]["type"], "x-whois");
+        assert_eq!(bundle["objects"][1]["domain_ref"], bundle["objects"][0]["id"]);
+        assert_eq!(bundle["objects"][1]["creation_date"], "1995-08-14T04:00:00Z");
+        assert!(bundle["objects"][1].get("registrant_email").is_none());
+
+        let misp = response.to_misp();
+        let relations: Vec<_> = misp.attributes.iter().map(|a| a.object_relation.as_str()).collect();
+        assert_eq!(relations, ["domain", "registrar", "creation-date", "nameserver", "text"]);
+        assert_eq!(misp.attributes[3].value, "a.iana-servers.net");
+    }
+}
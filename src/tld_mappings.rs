@@ -34,9 +34,6 @@ pub static HARDCODED_TLD_SERVERS: Lazy<HashMap<&'static str, &'static str>> = La
     
     // Top country code TLDs (ccTLDs) - covers major markets
     map.insert("uk", "whois.nic.uk");
-    map.insert("co.uk", "whois.nic.uk");
-    map.insert("org.uk", "whois.nic.uk");
-    map.insert("me.uk", "whois.nic.uk");
     map.insert("de", "whois.denic.de");
     map.insert("fr", "whois.afnic.fr");
     map.insert("it", "whois.nic.it");
@@ -63,10 +60,8 @@ pub static HARDCODED_TLD_SERVERS: Lazy<HashMap<&'static str, &'static str>> = La
     
     // Major Asia-Pacific ccTLDs
     map.insert("jp", "whois.jprs.jp");
-    map.insert("co.jp", "whois.jprs.jp");
     map.insert("kr", "whois.kr");
     map.insert("cn", "whois.cnnic.cn");
-    map.insert("com.cn", "whois.cnnic.cn");
     map.insert("hk", "whois.hkirc.hk");
     map.insert("tw", "whois.twnic.net.tw");
     map.insert("sg", "whois.sgnic.sg");
@@ -76,18 +71,14 @@ pub static HARDCODED_TLD_SERVERS: Lazy<HashMap<&'static str, &'static str>> = La
     map.insert("ph", "whois.dot.ph");
     map.insert("vn", "whois.vnnic.vn");
     map.insert("in", "whois.registry.in");
-    map.insert("co.in", "whois.registry.in");
     map.insert("au", "whois.auda.org.au");
-    map.insert("com.au", "whois.auda.org.au");
     map.insert("nz", "whois.srs.net.nz");
-    map.insert("co.nz", "whois.srs.net.nz");
     
     // Americas ccTLDs
     map.insert("ca", "whois.cira.ca");
     map.insert("us", "whois.nic.us");
     map.insert("mx", "whois.mx");
     map.insert("br", "whois.registro.br");
-    map.insert("com.br", "whois.registro.br");
     map.insert("ar", "whois.nic.ar");
     map.insert("cl", "whois.nic.cl");
     map.insert("co", "whois.nic.co");
@@ -108,8 +99,28 @@ pub static HARDCODED_TLD_SERVERS: Lazy<HashMap<&'static str, &'static str>> = La
     map.insert("ae", "whois.aeda.net.ae");
     map.insert("sa", "whois.nic.net.sa");
     map.insert("za", "whois.registry.net.za");
-    map.insert("co.za", "whois.registry.net.za");
     map.insert("eg", "whois.ripe.net");
     
     map
-}); 
\ No newline at end of file
+}); 
+
+// Thin registries only hold the sponsoring registrar; contacts live at the registrar's whois server
+pub const THIN_REGISTRY_TLDS: &[&str] = &["com", "net", "cc", "tv"];
+
+// Registry behaviour worth knowing before bulk runs, beyond rate limits and timeouts
+pub const TLD_QUIRKS: &[(&str, &str)] = &[
+    ("de", "DENIC only returns the domain status unless queried as `-T dn,ace <domain>`"),
+    ("jp", "JPRS answers in Japanese unless the query ends with `/e`"),
+    ("dk", "Contact handles are only shown when queried with `--show-handles`"),
+    ("uk", "Nominet blocks source addresses that exceed its query limits"),
+    ("eu", "EURid withholds registrant contacts from port-43 whois"),
+    ("es", "Port-43 whois is restricted to authorized IP addresses"),
+];
+
+// Whois servers and registry operators for every root-zone TLD, generated by build.rs
+// from the vendored IANA snapshot (data/iana-whois.json, refreshed with
+// `cargo run --example refresh_iana_whois`), and the servers
+// of multi-label public suffixes derived from them and the vendored PSL. Whois
+// servers are consulted after the hardcoded map; suffixes missing from the
+// generated table fall back to their TLD's server at lookup time.
+include!(concat!(env!("OUT_DIR"), "/whois_mappings.rs"));
@@ -0,0 +1,68 @@
+//! HTTPS termination for the server (feature `tls`)
+//!
+//! Serves the API over rustls using the PEM files at `tls_cert_path` and
+//! `tls_key_path`. Every `tls_reload_seconds` the files' modification times are
+//! checked and renewed certificates are loaded without dropping connections;
+//! a renewal that fails to parse keeps the previous certificate in service.
+
+use axum_server::tls_rustls::RustlsConfig;
+use std::{path::PathBuf, time::Duration, time::SystemTime};
+use tracing::{info, warn};
+use whois_service::config::Config;
+
+pub struct TlsFiles {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+impl TlsFiles {
+    /// Certificate and key paths, or `None` when TLS isn't configured
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Some(Self {
+            cert: config.tls_cert_path.as_ref()?.into(),
+            key: config.tls_key_path.as_ref()?.into(),
+        })
+    }
+
+    /// Load the certificate, reloading it in the background every `reload_interval` if set
+    pub async fn load(self, reload_interval: Option<Duration>) -> std::io::Result<RustlsConfig> {
+        // A process-wide provider must exist before the first ServerConfig is built
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let config = RustlsConfig::from_pem_file(&self.cert, &self.key).await?;
+        info!("TLS enabled with certificate {}", self.cert.display());
+
+        if let Some(interval) = reload_interval {
+            tokio::spawn(self.watch(config.clone(), interval));
+        }
+        Ok(config)
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Some((modified(&self.cert)?, modified(&self.key)?))
+    }
+
+    async fn watch(self, config: RustlsConfig, interval: Duration) {
+        let mut last_seen = self.modified();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let current = self.modified();
+            if current.is_none() || current == last_seen {
+                continue;
+            }
+
+            match config.reload_from_pem_file(&self.cert, &self.key).await {
+                Ok(()) => {
+                    info!("Reloaded TLS certificate from {}", self.cert.display());
+                    last_seen = current;
+                }
+                // Files may be mid-rotation; retry on the next tick
+                Err(e) => warn!("Failed to reload TLS certificate, keeping the current one: {}", e),
+            }
+        }
+    }
+}
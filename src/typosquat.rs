@@ -0,0 +1,222 @@
+//! Typosquat candidate generation and checking
+//!
+//! `generate` permutes the registrable label of a seed domain the way
+//! typosquatters do: dropping a character (omission), swapping neighbours
+//! (transposition), substituting lookalikes (homoglyph, including Cyrillic
+//! letters as punycode) and registering the same label under other TLDs (TLD
+//! swap). `WhoisClient::check_typosquats` runs every candidate through
+//! `is_available` and looks up the registered ones for their creation date.
+
+use crate::{errors::WhoisError, DomainAvailability, WhoisClient};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// TLDs tried by the TLD swap permutation when none are given
+pub const DEFAULT_SWAP_TLDS: &[&str] = &["com", "net", "org", "co", "io", "info", "biz", "app", "xyz", "online"];
+
+// ASCII lookalikes, applied to every occurrence one at a time
+const ASCII_HOMOGLYPHS: &[(&str, &str)] = &[
+    ("o", "0"),
+    ("0", "o"),
+    ("l", "1"),
+    ("1", "l"),
+    ("i", "1"),
+    ("i", "l"),
+    ("l", "i"),
+    ("m", "rn"),
+    ("rn", "m"),
+    ("w", "vv"),
+    ("vv", "w"),
+    ("e", "3"),
+    ("a", "4"),
+    ("s", "5"),
+    ("g", "q"),
+    ("cl", "d"),
+];
+
+// Cyrillic letters rendered identically to Latin ones in most fonts
+const UNICODE_HOMOGLYPHS: &[(char, char)] = &[
+    ('a', 'а'),
+    ('c', 'с'),
+    ('e', 'е'),
+    ('o', 'о'),
+    ('p', 'р'),
+    ('x', 'х'),
+    ('y', 'у'),
+];
+
+/// How a candidate was derived from the seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permutation {
+    Omission,
+    Transposition,
+    Homoglyph,
+    TldSwap,
+}
+
+/// A lookalike domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candidate {
+    /// ASCII (punycode) form, ready for lookups
+    pub domain: String,
+    pub permutation: Permutation,
+}
+
+/// Registration state of a candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TyposquatResult {
+    #[serde(flatten)]
+    pub candidate: Candidate,
+    pub availability: DomainAvailability,
+    /// Creation date of a registered candidate, as published by the registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registrar: Option<String>,
+    /// Why the candidate couldn't be classified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Candidates for `seed` with TLD swaps across `DEFAULT_SWAP_TLDS`
+pub fn generate(seed: &str) -> Result<Vec<Candidate>, WhoisError> {
+    generate_with_tlds(seed, DEFAULT_SWAP_TLDS)
+}
+
+/// Candidates for `seed`, swapping its suffix for each of `tlds`
+///
+/// Subdomains of the seed are ignored, the seed itself is never a candidate and
+/// each domain appears once, under the first permutation that produced it.
+pub fn generate_with_tlds(seed: &str, tlds: &[&str]) -> Result<Vec<Candidate>, WhoisError> {
+    let seed = seed.trim().trim_end_matches('.').to_lowercase();
+    let seed = idna::domain_to_ascii(&seed).map_err(|_| WhoisError::InvalidDomain(format!("Invalid domain: {}", seed)))?;
+    let (label, suffix) = split(&seed)?;
+
+    let mut seen = HashSet::from([format!("{}.{}", label, suffix)]);
+    let mut candidates = Vec::new();
+    let mut push = |label: String, suffix: &str, permutation| {
+        if !valid_label(&label) {
+            return;
+        }
+        let Ok(domain) = idna::domain_to_ascii(&format!("{}.{}", label, suffix)) else {
+            return;
+        };
+        if seen.insert(domain.clone()) {
+            candidates.push(Candidate { domain, permutation });
+        }
+    };
+
+    let chars: Vec<char> = label.chars().collect();
+    for i in 0..chars.len() {
+        let mut omitted = chars.clone();
+        omitted.remove(i);
+        push(omitted.into_iter().collect(), &suffix, Permutation::Omission);
+    }
+    for i in 1..chars.len() {
+        let mut swapped = chars.clone();
+        swapped.swap(i - 1, i);
+        push(swapped.into_iter().collect(), &suffix, Permutation::Transposition);
+    }
+    for (from, to) in ASCII_HOMOGLYPHS {
+        for (index, _) in label.match_indices(from) {
+            push(format!("{}{}{}", &label[..index], to, &label[index + from.len()..]), &suffix, Permutation::Homoglyph);
+        }
+    }
+    for (latin, cyrillic) in UNICODE_HOMOGLYPHS {
+        for (index, _) in label.match_indices(*latin) {
+            push(format!("{}{}{}", &label[..index], cyrillic, &label[index + 1..]), &suffix, Permutation::Homoglyph);
+        }
+    }
+    for tld in tlds {
+        let tld = tld.trim().trim_start_matches('.').to_lowercase();
+        if !tld.is_empty() && tld != suffix {
+            push(label.clone(), &tld, Permutation::TldSwap);
+        }
+    }
+
+    Ok(candidates)
+}
+
+// Registrable label and public suffix ("example", "co.uk")
+fn split(domain: &str) -> Result<(String, String), WhoisError> {
+    let invalid = || WhoisError::InvalidDomain(format!("No registrable domain in {}", domain));
+    let registrable = crate::domain::registrable_domain(domain).ok_or_else(invalid)?;
+    let suffix = crate::domain::effective_tld(domain).ok_or_else(invalid)?;
+    let label = registrable.strip_suffix(suffix.as_str()).and_then(|label| label.strip_suffix('.')).ok_or_else(invalid)?;
+    Ok((label.to_string(), suffix))
+}
+
+// Labels still go through IDNA, which rejects the non-ASCII cases
+fn valid_label(label: &str) -> bool {
+    !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-') && !label.contains('.')
+}
+
+impl WhoisClient {
+    /// Generate typosquat candidates for `seed` and check which are registered
+    ///
+    /// Candidates are checked `concurrent_whois_queries` at a time; registered
+    /// ones are looked up (through the cache) for their creation date and
+    /// registrar. Results keep the order of `typosquat::generate`.
+    pub async fn check_typosquats(&self, seed: &str) -> Result<Vec<TyposquatResult>, WhoisError> {
+        let candidates = generate(seed)?;
+        Ok(self.check_candidates(candidates).await)
+    }
+
+    /// Check the registration state of already generated candidates
+    pub async fn check_candidates(&self, candidates: Vec<Candidate>) -> Vec<TyposquatResult> {
+        stream::iter(candidates)
+            .map(|candidate| async move {
+                let mut result = TyposquatResult {
+                    candidate,
+                    availability: DomainAvailability::Unknown,
+                    created: None,
+                    registrar: None,
+                    error: None,
+                };
+                match self.is_available(&result.candidate.domain).await {
+                    Ok(availability) => result.availability = availability,
+                    Err(e) => result.error = Some(e.to_string()),
+                }
+                if result.availability == DomainAvailability::Registered {
+                    match self.lookup(&result.candidate.domain).await {
+                        Ok(response) => {
+                            let parsed = response.parsed_data.unwrap_or_default();
+                            result.created = parsed.creation_date;
+                            result.registrar = parsed.registrar;
+                        }
+                        Err(e) => result.error = Some(e.to_string()),
+                    }
+                }
+                result
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_permutations() {
+        let candidates = generate_with_tlds("www.Google.com", &["net", "com"]).unwrap();
+        let of = |permutation| -> Vec<&str> {
+            candidates.iter().filter(|c| c.permutation == permutation).map(|c| c.domain.as_str()).collect()
+        };
+
+        assert!(of(Permutation::Omission).contains(&"gogle.com"));
+        assert!(of(Permutation::Transposition).contains(&"googel.com"));
+        assert!(of(Permutation::Homoglyph).contains(&"g0ogle.com"));
+        // Cyrillic 'о' in place of the first 'o'
+        assert!(of(Permutation::Homoglyph).contains(&"xn--gogle-jye.com"));
+        assert_eq!(of(Permutation::TldSwap), ["google.net"]);
+        assert!(candidates.iter().all(|c| c.domain != "google.com"));
+
+        let unique: HashSet<_> = candidates.iter().map(|c| &c.domain).collect();
+        assert_eq!(unique.len(), candidates.len());
+    }
+}
@@ -0,0 +1,343 @@
+//! Domain monitoring
+//!
+//! A `Watcher` re-queries registered domains on their own polling interval
+//! (always fresh, bypassing the cache), compares the registrar, nameservers,
+//! status codes and expiration date with the previous snapshot, and publishes
+//! a `DomainChangeEvent` whenever something moved. The first successful lookup
+//! of a domain is its baseline and emits nothing; failed lookups are logged and
+//! keep the previous snapshot.
+//!
+//! Expiry alerting: with `with_expiry_thresholds([60, 30, 7])` an
+//! `ExpiryThreshold` change fires when a domain's `expires_in` drops to or below
+//! a threshold (only the tightest one when several are crossed at once, and on
+//! the baseline lookup if the domain is already inside one). An expiration date
+//! moving forward is reported as `Renewed`, which re-arms the thresholds.
+//!
+//! Events go to every `subscribe()` receiver and `on_change` callback, and with
+//! the `webhooks` feature can be POSTed as `domain.changed` (or, for expiry
+//! alerts, `domain.expiring`) webhook events.
+
+use crate::{errors::WhoisError, parser::WhoisParser, EppStatus, LookupOptions, ParsedWhoisData, WhoisClient, WhoisResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::broadcast, task::AbortHandle};
+use tracing::{debug, info, warn, Instrument};
+
+// Events buffered per subscriber before slow receivers start missing them
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// One field that differs between two snapshots of a domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum DomainChange {
+    Registrar { old: Option<String>, new: Option<String> },
+    NameServers { added: Vec<String>, removed: Vec<String> },
+    Status { added: Vec<String>, removed: Vec<String> },
+    ExpirationDate { old: Option<String>, new: Option<String> },
+    /// Expiration date moved later
+    Renewed { old: Option<String>, new: Option<String> },
+    /// `expires_in` dropped to or below an alert threshold (days)
+    ExpiryThreshold { days: u32, expires_in: i64 },
+}
+
+/// Changes detected for a watched domain by one poll
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainChangeEvent {
+    pub domain: String,
+    pub detected_at: DateTime<Utc>,
+    pub changes: Vec<DomainChange>,
+    /// The lookup that revealed the changes
+    pub response: WhoisResponse,
+}
+
+impl DomainChangeEvent {
+    /// Webhook event name: `domain.expiring` for expiry alerts, else `domain.changed`
+    pub fn webhook_event(&self) -> &'static str {
+        if self.changes.iter().any(|change| matches!(change, DomainChange::ExpiryThreshold { .. })) {
+            "domain.expiring"
+        } else {
+            "domain.changed"
+        }
+    }
+}
+
+/// Compare the monitored fields of two parsed snapshots
+///
+/// Nameservers compare case-insensitively and statuses by EPP code, so
+/// formatting differences between protocols don't count as changes.
+pub fn changes_between(old: &ParsedWhoisData, new: &ParsedWhoisData) -> Vec<DomainChange> {
+    let mut changes = Vec::new();
+
+    if old.registrar != new.registrar {
+        changes.push(DomainChange::Registrar { old: old.registrar.clone(), new: new.registrar.clone() });
+    }
+
+    let name_servers = |parsed: &ParsedWhoisData| -> BTreeSet<String> {
+        parsed.name_servers.iter().map(|ns| ns.trim_end_matches('.').to_lowercase()).collect()
+    };
+    let (added, removed) = set_difference(name_servers(old), name_servers(new));
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(DomainChange::NameServers { added, removed });
+    }
+
+    let statuses = |parsed: &ParsedWhoisData| -> BTreeSet<String> {
+        EppStatus::parse_all(&parsed.status).iter().map(|status| status.as_str().to_string()).collect()
+    };
+    let (added, removed) = set_difference(statuses(old), statuses(new));
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(DomainChange::Status { added, removed });
+    }
+
+    if old.expiration_date != new.expiration_date {
+        let parser = WhoisParser::new();
+        let date = |value: &Option<String>| value.as_deref().and_then(|date| parser.parse_date(date));
+        let (old, new) = (old.expiration_date.clone(), new.expiration_date.clone());
+        changes.push(match (date(&old), date(&new)) {
+            (Some(before), Some(after)) if after > before => DomainChange::Renewed { old, new },
+            _ => DomainChange::ExpirationDate { old, new },
+        });
+    }
+
+    changes
+}
+
+/// Tightest threshold crossed going from `previous` to `current` days until expiry
+///
+/// With no previous value (the baseline), any threshold already reached counts.
+fn crossed_threshold(thresholds: &[u32], previous: Option<i64>, current: Option<i64>) -> Option<u32> {
+    let current = current?;
+    thresholds
+        .iter()
+        .copied()
+        .filter(|&days| current <= i64::from(days) && previous.is_none_or(|previous| previous > i64::from(days)))
+        .min()
+}
+
+fn set_difference(old: BTreeSet<String>, new: BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+    let added = new.difference(&old).cloned().collect();
+    let removed = old.difference(&new).cloned().collect();
+    (added, removed)
+}
+
+/// Periodically re-queries domains and reports changes
+///
+/// Dropping the watcher stops all polling.
+pub struct Watcher {
+    client: WhoisClient,
+    events: broadcast::Sender<DomainChangeEvent>,
+    tasks: Mutex<HashMap<String, AbortHandle>>,
+    expiry_thresholds: Arc<[u32]>,
+}
+
+impl Watcher {
+    pub fn new(client: WhoisClient) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { client, events, tasks: Mutex::new(HashMap::new()), expiry_thresholds: Arc::from([]) }
+    }
+
+    /// Alert when a domain gets within any of these many days of expiry
+    ///
+    /// Applies to domains watched after this call.
+    pub fn with_expiry_thresholds(mut self, days: impl IntoIterator<Item = u32>) -> Self {
+        self.expiry_thresholds = days.into_iter().collect();
+        self
+    }
+
+    /// Start polling `domain` every `interval`, replacing any existing watch on it
+    ///
+    /// The first lookup happens immediately and sets the baseline. A zero
+    /// `interval` is rejected, since it would re-query the domain in a tight loop.
+    pub fn watch(&self, domain: &str, interval: Duration) -> Result<(), WhoisError> {
+        if interval.is_zero() {
+            return Err(WhoisError::InvalidRequest("Watch interval must be greater than zero".to_string()));
+        }
+        let domain = domain.trim().to_lowercase();
+        let client = self.client.clone();
+        let events = self.events.clone();
+        let thresholds = self.expiry_thresholds.clone();
+        let span = tracing::info_span!("watch", domain = %domain);
+        let task = tokio::spawn(poll(client, domain.clone(), interval, thresholds, events).instrument(span));
+
+        if let Some(previous) = self.tasks.lock().unwrap_or_else(|e| e.into_inner()).insert(domain, task.abort_handle()) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Stop polling `domain`; returns whether it was watched
+    pub fn unwatch(&self, domain: &str) -> bool {
+        match self.tasks.lock().unwrap_or_else(|e| e.into_inner()).remove(&domain.trim().to_lowercase()) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Domains currently being watched
+    pub fn watched(&self) -> Vec<String> {
+        let mut domains: Vec<String> = self.tasks.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+        domains.sort();
+        domains
+    }
+
+    /// Receive every change event from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainChangeEvent> {
+        self.events.subscribe()
+    }
+
+    /// Call `callback` for every change event (on a background task)
+    pub fn on_change<F>(&self, callback: F)
+    where
+        F: Fn(DomainChangeEvent) + Send + 'static,
+    {
+        let mut events = self.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => callback(event),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Watch callback fell behind, {} events dropped", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// POST every change event to `target` (see `DomainChangeEvent::webhook_event`)
+    #[cfg(feature = "webhooks")]
+    pub fn with_webhook(self, sender: crate::webhook::WebhookSender, target: crate::webhook::WebhookTarget) -> Self {
+        let mut events = self.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!(url = %target.url, "Watch webhook fell behind, {} events dropped", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = sender.send(&target, event.webhook_event(), &event).await {
+                    warn!(domain = %event.domain, url = %target.url, "Watch webhook delivery failed: {}", e);
+                }
+            }
+        });
+        self
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        for task in self.tasks.lock().unwrap_or_else(|e| e.into_inner()).values() {
+            task.abort();
+        }
+    }
+}
+
+async fn poll(
+    client: WhoisClient,
+    domain: String,
+    interval: Duration,
+    thresholds: Arc<[u32]>,
+    events: broadcast::Sender<DomainChangeEvent>,
+) {
+    let options = LookupOptions { fresh: true, ..LookupOptions::default() };
+    let mut previous: Option<ParsedWhoisData> = None;
+    let mut previous_expires_in: Option<i64> = None;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        let response = match client.lookup_opts(&domain, &options).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Watch lookup failed, keeping previous snapshot: {}", e);
+                continue;
+            }
+        };
+        let Some(current) = response.parsed_data.clone() else {
+            debug!("Watch lookup returned no parsed data");
+            continue;
+        };
+
+        let mut changes = match previous.replace(current.clone()) {
+            Some(previous) => changes_between(&previous, &current),
+            None => Vec::new(),
+        };
+        if let Some(days) = crossed_threshold(&thresholds, previous_expires_in, current.expires_in) {
+            let expires_in = current.expires_in.unwrap_or_default();
+            info!(days, expires_in, "Watched domain reached expiry threshold");
+            changes.push(DomainChange::ExpiryThreshold { days, expires_in });
+        }
+        if current.expires_in.is_some() {
+            previous_expires_in = current.expires_in;
+        }
+
+        if !changes.is_empty() {
+            info!(changes = changes.len(), "Watched domain changed");
+            // No receivers is fine; events are simply dropped
+            let _ = events.send(DomainChangeEvent { domain: domain.clone(), detected_at: Utc::now(), changes, response });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_zero_interval_is_rejected() {
+        let watcher = Watcher::new(WhoisClient::new_without_cache().await.unwrap());
+        let error = watcher.watch("example.com", Duration::ZERO).unwrap_err();
+        assert_eq!(error.code(), "INVALID_REQUEST");
+        assert!(watcher.watched().is_empty());
+    }
+
+    #[test]
+    fn test_changes_between_snapshots() {
+        let old = ParsedWhoisData {
+            registrar: Some("Old Registrar".to_string()),
+            name_servers: vec!["NS1.EXAMPLE.NET".to_string(), "ns2.example.net".to_string()],
+            status: vec!["clientTransferProhibited https://icann.org/epp#clientTransferProhibited".to_string()],
+            expiration_date: Some("2025-01-01T00:00:00Z".to_string()),
+            ..ParsedWhoisData::default()
+        };
+        let mut new = old.clone();
+        new.name_servers = vec!["ns1.example.net".to_string(), "ns3.example.net".to_string()];
+        new.status = vec!["client transfer prohibited".to_string()];
+        assert_eq!(
+            changes_between(&old, &new),
+            vec![DomainChange::NameServers {
+                added: vec!["ns3.example.net".to_string()],
+                removed: vec!["ns2.example.net".to_string()],
+            }]
+        );
+
+        new.registrar = Some("New Registrar".to_string());
+        new.expiration_date = Some("2026-01-01T00:00:00Z".to_string());
+        let changes = changes_between(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(changes[2], DomainChange::Renewed { .. }));
+    }
+
+    #[test]
+    fn test_crossed_threshold() {
+        let thresholds = [60, 30, 7];
+        assert_eq!(crossed_threshold(&thresholds, None, Some(90)), None);
+        assert_eq!(crossed_threshold(&thresholds, None, Some(20)), Some(30));
+        assert_eq!(crossed_threshold(&thresholds, Some(61), Some(60)), Some(60));
+        assert_eq!(crossed_threshold(&thresholds, Some(45), Some(40)), None);
+        assert_eq!(crossed_threshold(&thresholds, Some(40), Some(5)), Some(7));
+        // Renewed: back above every threshold, so they fire again later
+        assert_eq!(crossed_threshold(&thresholds, Some(365), Some(59)), Some(60));
+    }
+}
@@ -0,0 +1,244 @@
+//! Signed webhook delivery (feature `webhooks`)
+//!
+//! Events are POSTed as JSON envelopes:
+//!
+//! ```json
+//! {"event": "job.completed", "timestamp": "2024-01-01T00:00:00Z", "data": {...}}
+//! ```
+//!
+//! When the target has a secret, `X-Whois-Signature: sha256=<hex>` carries the
+//! HMAC-SHA256 of the exact body bytes, so receivers can verify the sender.
+//! Network errors, 429s and 5xx responses are retried with exponential backoff
+//! (1s, 2s, 4s, ...) up to `webhook_max_retries` times; other 4xx responses are
+//! treated as permanent, and so are redirects, which are never followed.
+//!
+//! Targets supplied by API clients (a bulk job's own `webhook`) are checked with
+//! `validate_public`, which refuses hosts that are or resolve to loopback,
+//! private, link-local or other non-public addresses, and are delivered with
+//! `send_public`. That repeats the check on every attempt and connects only to
+//! the addresses it approved, so a name re-pointed at an internal address after
+//! the job was created (DNS rebinding) is refused. The configured `webhook_url`
+//! is the operator's choice and may point anywhere.
+
+use crate::{config::Config, errors::WhoisError};
+use ring::hmac;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+pub const EVENT_HEADER: &str = "x-whois-event";
+pub const SIGNATURE_HEADER: &str = "x-whois-signature";
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Where to deliver events, and the secret to sign them with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct WebhookTarget {
+    pub url: String,
+    #[serde(default, skip_serializing)]
+    pub secret: Option<String>,
+}
+
+impl WebhookTarget {
+    pub fn new(url: impl Into<String>, secret: Option<String>) -> Self {
+        Self { url: url.into(), secret }
+    }
+
+    /// The target configured through `webhook_url` / `webhook_secret`, if any
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Some(Self::new(config.webhook_url.clone()?, config.webhook_secret.clone()))
+    }
+
+    /// Reject anything but absolute http(s) URLs
+    pub fn validate(&self) -> Result<(), WhoisError> {
+        self.parse().map(|_| ())
+    }
+
+    /// `validate`, and reject hosts that are or resolve to non-public addresses
+    pub async fn validate_public(&self) -> Result<(), WhoisError> {
+        let url = self.parse()?;
+        if crate::rdap::is_private_target(&url).await {
+            return Err(WhoisError::InvalidRequest(format!("Webhook URL {} points to a non-public address", self.url)));
+        }
+        Ok(())
+    }
+
+    fn parse(&self) -> Result<url::Url, WhoisError> {
+        match url::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => Ok(url),
+            _ => Err(WhoisError::InvalidRequest(format!("Invalid webhook URL: {}", self.url))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    event: &'a str,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    data: &'a T,
+}
+
+/// Delivers signed events to webhook targets, retrying transient failures
+#[derive(Clone)]
+pub struct WebhookSender {
+    client: reqwest::Client,
+    max_retries: u32,
+}
+
+impl WebhookSender {
+    pub fn new(max_retries: u32) -> Self {
+        let client = Self::client_builder().build().unwrap_or_default();
+        Self { client, max_retries }
+    }
+
+    fn client_builder() -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .user_agent(concat!("whois-service/", env!("CARGO_PKG_VERSION"), " (webhooks)"))
+            // A redirect could lead a vetted target to an internal address
+            .redirect(reqwest::redirect::Policy::none())
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.webhook_max_retries)
+    }
+
+    /// The shared client, or for `public_only` a client pinned to the target's
+    /// freshly checked addresses
+    async fn client_for(&self, target: &WebhookTarget, public_only: bool) -> Result<reqwest::Client, WhoisError> {
+        if !public_only {
+            return Ok(self.client.clone());
+        }
+        let url = target.parse()?;
+        let addresses = crate::rdap::resolve_target(&url).await?;
+        if let Some(ip) = addresses.private_address() {
+            return Err(WhoisError::InvalidRequest(format!("Webhook URL {} points to non-public address {}", target.url, ip)));
+        }
+        match (addresses, url.host_str()) {
+            (crate::rdap::TargetAddresses::Resolved(addresses), Some(host)) => Self::client_builder()
+                // A proxy would resolve the name again on its side
+                .no_proxy()
+                .resolve_to_addrs(host, &addresses)
+                .build()
+                .map_err(WhoisError::HttpError),
+            _ => Ok(self.client.clone()),
+        }
+    }
+
+    /// POST `data` as `event` to `target`, retrying transient failures
+    pub async fn send<T: Serialize>(&self, target: &WebhookTarget, event: &str, data: &T) -> Result<(), WhoisError> {
+        self.deliver(target, event, data, false).await
+    }
+
+    /// `send` to a client-supplied target, refusing it on any attempt where it
+    /// doesn't resolve to public addresses only
+    pub async fn send_public<T: Serialize>(&self, target: &WebhookTarget, event: &str, data: &T) -> Result<(), WhoisError> {
+        self.deliver(target, event, data, true).await
+    }
+
+    async fn deliver<T: Serialize>(&self, target: &WebhookTarget, event: &str, data: &T, public_only: bool) -> Result<(), WhoisError> {
+        let envelope = Envelope { event, timestamp: chrono::Utc::now(), data };
+        let body = serde_json::to_vec(&envelope).map_err(|e| WhoisError::Internal(e.to_string()))?;
+        let signature = target.secret.as_deref().map(|secret| sign(secret, &body));
+
+        let mut attempt = 0;
+        loop {
+            let error = match self.client_for(target, public_only).await {
+                Ok(client) => {
+                    let mut request = client
+                        .post(&target.url)
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .header(EVENT_HEADER, event)
+                        .body(body.clone());
+                    if let Some(signature) = &signature {
+                        request = request.header(SIGNATURE_HEADER, signature);
+                    }
+
+                    match request.send().await {
+                        Ok(response) if response.status().is_success() => {
+                            debug!(url = %target.url, event, "Webhook delivered");
+                            return Ok(());
+                        }
+                        Ok(response) => {
+                            let status = response.status();
+                            let error = WhoisError::Internal(format!("Webhook {} answered {}", target.url, status));
+                            if status.is_redirection() || (status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS) {
+                                return Err(error);
+                            }
+                            error
+                        }
+                        Err(e) => WhoisError::HttpError(e),
+                    }
+                }
+                // A target that now points at a non-public address stays refused
+                Err(e @ WhoisError::InvalidRequest(_)) => return Err(e),
+                Err(e) => e,
+            };
+
+            if attempt >= self.max_retries {
+                return Err(error);
+            }
+            let backoff = Duration::from_secs((1u64 << attempt.min(6)).min(MAX_BACKOFF_SECS));
+            warn!(url = %target.url, event, attempt = attempt + 1, "Webhook delivery failed, retrying in {:?}: {}", backoff, error);
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// `sha256=<hex>` HMAC of `body` under `secret`, as sent in `X-Whois-Signature`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    let hex: String = tag.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_client_webhooks_must_be_public() {
+        assert!(WebhookTarget::new("https://93.184.215.14/hook", None).validate_public().await.is_ok());
+        for url in ["http://127.0.0.1:8080/hook", "http://[::1]/hook", "https://10.0.0.5/hook", "http://169.254.169.254/latest"] {
+            let error = WebhookTarget::new(url, None).validate_public().await.unwrap_err();
+            assert_eq!(error.code(), "INVALID_REQUEST", "{}", url);
+        }
+        assert_eq!(WebhookTarget::new("not a url", None).validate_public().await.unwrap_err().code(), "INVALID_REQUEST");
+    }
+
+    #[tokio::test]
+    async fn test_public_delivery_rechecks_the_resolved_address() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.read(&mut [0u8; 4096]).await;
+            stream.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        });
+
+        // A name that resolves to loopback by delivery time is refused without connecting...
+        let sender = WebhookSender::new(0);
+        let target = WebhookTarget::new(format!("http://localhost:{}/hook", port), None);
+        let error = sender.send_public(&target, "job.completed", &"done").await.unwrap_err();
+        assert_eq!(error.code(), "INVALID_REQUEST");
+        assert!(!server.is_finished());
+
+        // ...while the operator's own target may point anywhere
+        sender.send(&target, "job.completed", &"done").await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_sign_matches_rfc4231_vector() {
+        // RFC 4231 test case 2
+        let signature = sign("Jefe", b"what do ya want for nothing?");
+        assert_eq!(signature, "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        assert!(WebhookTarget::new("https://hooks.example/whois", None).validate().is_ok());
+        assert!(WebhookTarget::new("ftp://hooks.example", None).validate().is_err());
+    }
+}
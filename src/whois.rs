@@ -1,153 +1,729 @@
+//! Port-43 whois lookups (feature `whois-tcp`) and the result types shared by every tier
+
+use crate::{parser::ParsingReport, ParsedWhoisData, ParsedIpWhoisData, ParsedAsnWhoisData};
+#[cfg(feature = "whois-tcp")]
 use crate::{
-    config::Config, 
+    config::{Config, FixtureMode}, 
     errors::WhoisError, 
-    ParsedWhoisData,
-    tld_mappings::HARDCODED_TLD_SERVERS,
-    buffer_pool::{BufferPool, PooledBuffer},
-    parser::WhoisParser,
+    DomainAvailability,
+    LookupOptions,
+    TldInfo,
+    tld_mappings::{GENERATED_SUFFIX_SERVERS, GENERATED_WHOIS_SERVERS, HARDCODED_TLD_SERVERS},
+    buffer_pool::{BufferPool, BufferPoolStats},
+    parser::{StreamingParser, WhoisParser},
+    proxy::WhoisProxy,
+    connection_pool::ConnectionPool,
+    response_reader::ResponseReader,
+    fixtures::FixtureStore,
+    health::{HealthTracker, ServerHealth},
+    referral::ReferralPolicy,
+    telemetry,
+    connector::Connector,
+    dns::DnsResolver,
+    rate_limiter::RateLimiter,
 };
-use once_cell::sync::Lazy;
-use publicsuffix::{List, Psl};
+#[cfg(feature = "whois-tcp")]
+use futures::stream::{self, StreamExt};
+#[cfg(feature = "whois-tcp")]
 use std::{
-    collections::HashMap,
-    sync::Arc,
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
     time::Duration,
 };
+#[cfg(feature = "whois-tcp")]
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::AsyncWriteExt,
     net::TcpStream,
     sync::Semaphore,
     time::timeout,
 };
-use tracing::{debug, info, warn};
+#[cfg(feature = "whois-tcp")]
+use tracing::{debug, field, info, instrument, warn, Instrument, Span};
+
 
-// Global PSL instance - shared across all service instances
-static PSL: Lazy<List> = Lazy::new(|| List::new());
+// How often discovered TLD mappings are written to `tld_mappings_path`
+#[cfg(feature = "whois-tcp")]
+const MAPPING_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
 
 // Standard whois protocol port
+#[cfg(feature = "whois-tcp")]
 const WHOIS_PORT: u16 = 43;
 
+// ARIN refers queries for address space it doesn't manage to the owning RIR,
+// which makes it the best starting point when the root servers are unreachable
+#[cfg(feature = "whois-tcp")]
+const FALLBACK_RIR_SERVER: &str = "whois.arin.net";
+
+#[cfg(feature = "whois-tcp")]
 pub struct WhoisService {
     config: Arc<Config>,
     tld_servers: Arc<tokio::sync::RwLock<HashMap<String, String>>>,
+    mappings_dirty: AtomicBool, // Discovered mappings not yet flushed to disk
     domain_query_semaphore: Arc<Semaphore>,  // For actual domain lookups
     discovery_semaphore: Arc<Semaphore>,     // For TLD discovery (higher limit)
-    buffer_pool: BufferPool,  // Reusable buffers for network I/O
+    buffer_pool: Arc<BufferPool>, // Reusable buffers for network I/O
     parser: WhoisParser,      // Whois data parser
+    proxy: Option<WhoisProxy>, // Optional SOCKS5/HTTP proxy for port-43 traffic
+    connector: Connector,      // Direct connections (address-family aware)
+    connection_pool: ConnectionPool, // Idle persistent connections for keep-alive capable servers
+    rate_limiter: RateLimiter, // Per-server token buckets
+    fixtures: FixtureStore,    // Record/replay of raw answers (pass-through unless fixture_mode is set)
+    health: HealthTracker,     // Outcome and latency per server, for avoiding degraded ones
+    referrals: ReferralPolicy, // Which referral targets may be connected to
 }
 
+#[derive(Clone)]
 pub struct WhoisResult {
     pub server: String,
     pub raw_data: String,
     pub parsed_data: Option<ParsedWhoisData>,
-    pub parsing_analysis: Vec<String>,
+    pub parsing_report: ParsingReport,
+    /// Every port-43 answer, registry first, once a referral was followed
+    pub referral_chain: Vec<ReferralHop>,
+}
+
+/// One server queried while following whois referrals
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReferralHop {
+    pub server: String,
+    /// Size of the raw response
+    pub bytes: usize,
+    pub elapsed_ms: u64,
+    pub raw_data: String,
+}
+
+impl ReferralHop {
+    #[cfg(feature = "whois-tcp")]
+    fn new(server: &str, raw_data: String, started: std::time::Instant) -> Self {
+        Self {
+            server: server.to_string(),
+            bytes: raw_data.len(),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            raw_data,
+        }
+    }
 }
 
+pub struct IpWhoisResult {
+    pub server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedIpWhoisData>,
+}
+
+pub struct AsnWhoisResult {
+    pub server: String,
+    pub raw_data: String,
+    pub parsed_data: Option<ParsedAsnWhoisData>,
+}
+
+/// Number resource queried against the Regional Internet Registries
+#[cfg(feature = "whois-tcp")]
+#[derive(Debug, Clone, Copy)]
+enum RirQuery {
+    Ip(IpAddr),
+    Asn(u32),
+}
+
+#[cfg(feature = "whois-tcp")]
+impl RirQuery {
+    /// Format the query line for a specific server
+    fn format_for(&self, server: &str) -> String {
+        // ARIN needs a record-type flag, otherwise it returns every object type matching the query
+        let is_arin = server.eq_ignore_ascii_case(FALLBACK_RIR_SERVER);
+        match (self, is_arin) {
+            (RirQuery::Ip(ip), true) => format!("n + {}", ip),
+            (RirQuery::Ip(ip), false) => ip.to_string(),
+            (RirQuery::Asn(asn), true) => format!("a + {}", asn),
+            (RirQuery::Asn(asn), false) => format!("AS{}", asn),
+        }
+    }
+}
+
+/// Split an optional ":port" suffix off a server name (relays may listen on a non-standard port)
+#[cfg(feature = "whois-tcp")]
+fn split_host_port(server: &str) -> (&str, u16) {
+    match server.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host, port),
+            Err(_) => (server, WHOIS_PORT),
+        },
+        _ => (server, WHOIS_PORT),
+    }
+}
+
+#[cfg(feature = "whois-tcp")]
 impl WhoisService {
     pub async fn new(config: Arc<Config>) -> Result<Self, WhoisError> {
+        let proxy = config.whois_proxy().map(WhoisProxy::parse).transpose()?;
+
         let service = Self {
             config: config.clone(),
             tld_servers: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            mappings_dirty: AtomicBool::new(false),
             domain_query_semaphore: Arc::new(Semaphore::new(config.concurrent_whois_queries)),
             discovery_semaphore: Arc::new(Semaphore::new(config.concurrent_whois_queries * 2)),
-            buffer_pool: Arc::new(tokio::sync::Mutex::new(Vec::with_capacity(config.buffer_pool_size))),
+            buffer_pool: Arc::new(BufferPool::new(config.buffer_pool_size, config.buffer_size)),
             parser: WhoisParser::new(),
+            proxy,
+            connector: Connector::new(config.address_family, DnsResolver::new(&config)?),
+            connection_pool: ConnectionPool::new(config.whois_pool_size, &config.whois_keepalive_servers),
+            rate_limiter: RateLimiter::new(config.whois_default_qps, &config.whois_rate_limits),
+            fixtures: FixtureStore::new(&config),
+            health: HealthTracker::new(&config),
+            referrals: ReferralPolicy::new(&config),
         };
 
         info!("WhoisService initialized with hybrid TLD discovery (hardcoded + dynamic)");
         info!("Buffer pool: {} buffers of {} bytes each", config.buffer_pool_size, config.buffer_size);
         info!("Hardcoded TLD mappings: {} entries", HARDCODED_TLD_SERVERS.len());
+        info!("Generated TLD mappings: {} entries", GENERATED_WHOIS_SERVERS.len());
+        if let Some(ref proxy) = service.proxy {
+            info!("Routing whois connections through proxy {}", proxy.address());
+        }
+
+        if let Some(ref path) = config.tld_mappings_path {
+            match Self::load_tld_mappings(path).await {
+                Ok(mappings) => {
+                    info!("Loaded {} persisted TLD mappings from {}", mappings.len(), path);
+                    service.tld_servers.write().await.extend(mappings);
+                }
+                Err(e) => warn!("Failed to load TLD mappings from {}: {}", path, e),
+            }
+        }
         
         Ok(service)
     }
 
+    async fn load_tld_mappings(path: &str) -> Result<HashMap<String, String>, WhoisError> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| WhoisError::Internal(format!("Invalid TLD mappings file: {}", e))),
+            // First run - nothing persisted yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write discovered TLD mappings to `tld_mappings_path` (no-op when unset)
+    pub async fn save_tld_mappings(&self) -> Result<(), WhoisError> {
+        let Some(ref path) = self.config.tld_mappings_path else {
+            return Ok(());
+        };
+
+        // Sorted for stable, diffable output
+        let mappings: BTreeMap<String, String> = self.tld_servers.read().await
+            .iter()
+            .map(|(tld, server)| (tld.clone(), server.clone()))
+            .collect();
+        let json = serde_json::to_vec_pretty(&mappings)
+            .map_err(|e| WhoisError::Internal(format!("Failed to serialize TLD mappings: {}", e)))?;
+
+        // Write then rename so a crash never leaves a truncated file
+        let tmp_path = format!("{}.tmp", path);
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        debug!("Saved {} TLD mappings to {}", mappings.len(), path);
+        Ok(())
+    }
+
+    /// Flush changed TLD mappings to disk every minute in the background
+    ///
+    /// The task ends once the service is dropped. Returns None without `tld_mappings_path`.
+    pub fn spawn_mapping_flush(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        self.config.tld_mappings_path.as_ref()?;
+        let service: Weak<Self> = Arc::downgrade(self);
+
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(MAPPING_FLUSH_INTERVAL).await;
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                if service.mappings_dirty.swap(false, Ordering::AcqRel) {
+                    if let Err(e) = service.save_tld_mappings().await {
+                        warn!("Failed to persist TLD mappings: {}", e);
+                        service.mappings_dirty.store(true, Ordering::Release);
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Network buffer pool utilization
+    pub fn buffer_pool_stats(&self) -> BufferPoolStats {
+        self.buffer_pool.stats()
+    }
+
+    /// Success rate, latency and degraded state of every whois server queried so far
+    pub fn server_health(&self) -> Vec<ServerHealth> {
+        self.health.snapshot()
+    }
+
     /// Perform whois lookup for a domain
     /// Assumes domain is already validated and properly formatted (e.g., "example.com")
     pub async fn lookup(&self, domain: &str) -> Result<WhoisResult, WhoisError> {
+        self.lookup_with_timeout(domain, None, None).await
+    }
+
+    /// Perform whois lookup against a specific server, bypassing TLD discovery
+    /// Referrals from that server are still followed
+    pub async fn lookup_with_server(&self, domain: &str, server: &str) -> Result<WhoisResult, WhoisError> {
+        self.lookup_with_timeout(domain, Some(server), None).await
+    }
+
+    /// Perform whois lookup, optionally against a specific server and with a per-query timeout
+    ///
+    /// `query_timeout` replaces the configured (per-TLD) `whois_timeout_seconds` and adaptive timeouts
+    /// for every connect and read of this lookup, including referrals.
+    pub async fn lookup_with_timeout(
+        &self,
+        domain: &str,
+        server: Option<&str>,
+        query_timeout: Option<Duration>,
+    ) -> Result<WhoisResult, WhoisError> {
+        let mut options = LookupOptions::new();
+        options.server = server.map(str::to_string);
+        options.timeout = query_timeout;
+        self.lookup_opts(domain, &options).await
+    }
+
+    /// Perform whois lookup honouring the port-43 parts of `options`:
+    /// `server`, `timeout` (per query, as in `lookup_with_timeout`) and `follow_referrals`
+    #[instrument(name = "whois_lookup", skip_all, fields(domain = %domain, tld = field::Empty, server = field::Empty))]
+    pub async fn lookup_opts(&self, domain: &str, options: &LookupOptions) -> Result<WhoisResult, WhoisError> {
         let domain = domain.trim().to_lowercase();
         
         // Basic validation - assume domain is pre-parsed and valid
         if domain.is_empty() || !domain.contains('.') {
             return Err(WhoisError::InvalidDomain(domain));
         }
-        
-        // Extract TLD from the domain using global PSL
-        let tld = self.extract_tld(&domain)?;
-        
-        // Find appropriate whois server (hybrid: hardcoded + dynamic discovery)
-        let whois_server = self.find_whois_server(&tld).await?;
-        
+
+        let whois_server = match options.server.as_deref() {
+            Some(server) => {
+                let server = server.trim().to_lowercase();
+                if server.is_empty() {
+                    return Err(WhoisError::InvalidRequest("Empty whois server override".to_string()));
+                }
+                server
+            }
+            None => {
+                // Extract TLD from the domain using global PSL
+                let tld = self.extract_tld(&domain)?;
+                Span::current().record("tld", tld.as_str());
+
+                // Find appropriate whois server (hybrid: hardcoded + dynamic discovery)
+                let server = self.find_whois_server(&tld).await?;
+                if self.health.is_degraded(&server) {
+                    self.healthy_alternative(&tld, &server).await?
+                } else {
+                    server
+                }
+            }
+        };
+
+        Span::current().record("server", whois_server.as_str());
+        self.query_and_parse(&whois_server, &domain, options.timeout, options.follow_referrals).await
+    }
+
+    async fn query_and_parse(
+        &self,
+        whois_server: &str,
+        domain: &str,
+        query_timeout: Option<Duration>,
+        follow_referrals: bool,
+    ) -> Result<WhoisResult, WhoisError> {
+        // Slow registries may get a longer budget and different referral depth
+        let tld = self.extract_tld(domain).unwrap_or_default();
+        let max_referrals = if follow_referrals { self.config.max_referrals_for(&tld) } else { 0 };
+
         // Perform whois query
-        let raw_data = self.raw_whois_query(&whois_server, &domain).await?;
-        
+        let started = std::time::Instant::now();
+        let mut record = None;
+        let raw_data = self
+            .raw_whois_query(whois_server, domain, self.query_timeout(whois_server, &tld, query_timeout), None, Some(&mut record))
+            .await?;
+        let registry_hop = ReferralHop::new(whois_server, raw_data, started);
+
         // Check for referrals and follow them
-        let (final_server, final_data) = self.follow_referrals(&whois_server, &raw_data, &domain).await?;
+        let mut referral_chain = self
+            .follow_referrals(registry_hop, &mut record, domain, &tld, query_timeout, max_referrals)
+            .await;
+        let (final_server, final_data) = if referral_chain.len() == 1 {
+            // A chain without referrals would only repeat `raw_data`
+            let hop = referral_chain.remove(0);
+            (hop.server, hop.raw_data)
+        } else {
+            let hop = &referral_chain[referral_chain.len() - 1];
+            (hop.server.clone(), hop.raw_data.clone())
+        };
+
+        if self.parser.detect_availability(&final_data) == DomainAvailability::Available {
+            return Err(WhoisError::DomainNotFound(domain.to_string()));
+        }
         
-        // Parse the whois data with detailed analysis
-        let (parsed_data, parsing_analysis) = self.parser.parse_whois_data_with_analysis(&final_data);
+        // The final answer was parsed as it arrived; finish it with a report of what was found
+        let (parsed_data, parsing_report) = match record {
+            Some(record) => record.finish_with_report(Some(&tld)),
+            None => WhoisParser::parse_with_report_for(&final_data, Some(&tld)),
+        };
         
         Ok(WhoisResult {
             server: final_server,
             raw_data: final_data,
+            parsed_data: Some(parsed_data),
+            parsing_report,
+            referral_chain,
+        })
+    }
+
+    /// Perform whois lookup for an IP address
+    /// Starts at the root servers and follows referrals to the RIR holding the allocation
+    pub async fn lookup_ip(&self, ip: IpAddr) -> Result<IpWhoisResult, WhoisError> {
+        let (server, raw_data) = self.rir_whois_lookup(RirQuery::Ip(ip)).await?;
+        let parsed_data = self.parser.parse_ip_whois_data(&raw_data);
+
+        Ok(IpWhoisResult {
+            server,
+            raw_data,
             parsed_data,
-            parsing_analysis,
         })
     }
 
-    /// Extract TLD from domain using global PSL for accurate parsing
-    fn extract_tld(&self, domain: &str) -> Result<String, WhoisError> {
-        // Parse the domain using the global public suffix list
-        match PSL.domain(domain.as_bytes()) {
-            Some(parsed_domain) => {
-                // Get the public suffix (effective TLD)
-                let suffix = parsed_domain.suffix();
-                match std::str::from_utf8(suffix.as_bytes()) {
-                    Ok(tld) => Ok(tld.to_string()),
-                    Err(_) => Err(WhoisError::InvalidDomain(format!("Invalid UTF-8 in TLD for domain: {}", domain)))
+    /// Perform whois lookup for an autonomous system number
+    /// Starts at the root servers and follows referrals to the RIR holding the allocation
+    pub async fn lookup_asn(&self, asn: u32) -> Result<AsnWhoisResult, WhoisError> {
+        let (server, raw_data) = self.rir_whois_lookup(RirQuery::Asn(asn)).await?;
+        let parsed_data = self.parser.parse_asn_whois_data(&raw_data, asn);
+
+        Ok(AsnWhoisResult {
+            server,
+            raw_data,
+            parsed_data,
+        })
+    }
+
+    async fn rir_whois_lookup(&self, query: RirQuery) -> Result<(String, String), WhoisError> {
+        // Root servers know which RIR each number block was delegated to
+        let mut initial = None;
+        for root_server in self.get_root_servers() {
+            match self.raw_whois_query(&root_server, &query.format_for(&root_server), self.default_timeout(), None, None).await {
+                Ok(data) => {
+                    initial = Some((root_server, data));
+                    break;
                 }
-            },
+                Err(e) => {
+                    debug!("Failed to query root server {} for {:?}: {}", root_server, query, e);
+                }
+            }
+        }
+
+        let (mut current_server, mut current_data) = match initial {
+            Some(initial) => initial,
+            None => {
+                warn!("Root servers unavailable for {:?}, starting at {}", query, FALLBACK_RIR_SERVER);
+                let server = FALLBACK_RIR_SERVER.to_string();
+                let data = self.raw_whois_query(&server, &query.format_for(&server), self.default_timeout(), None, None).await?;
+                (server, data)
+            }
+        };
+
+        // Follow referrals between registries (IANA -> RIR, ARIN -> RIPE, ...)
+        let mut visited = vec![current_server.clone()];
+        while visited.len() <= self.config.max_referrals {
+            let referral_server = match self.extract_rir_referral(&current_data) {
+                Some(server) if !visited.contains(&server) => server,
+                _ => break,
+            };
+            let Some(addresses) = self.vet_referral(&referral_server).await else {
+                break;
+            };
+
+            debug!("Following RIR referral from {} to {}", current_server, referral_server);
+            let referral_query = query.format_for(&referral_server);
+            match self.raw_whois_query(&referral_server, &referral_query, self.default_timeout(), addresses.as_deref(), None).await {
+                Ok(new_data) => {
+                    visited.push(referral_server.clone());
+                    current_server = referral_server;
+                    current_data = new_data;
+                }
+                Err(e) => {
+                    warn!("Failed to query RIR referral server {}: {}", referral_server, e);
+                    break;
+                }
+            }
+        }
+
+        Ok((current_server, current_data))
+    }
+
+    /// Extract a port-43 referral from root ("refer:") or ARIN ("ReferralServer:") responses
+    fn extract_rir_referral(&self, data: &str) -> Option<String> {
+        for line in data.lines() {
+            if let Some((key, value)) = line.trim().split_once(':') {
+                let key = key.trim().to_lowercase();
+                if key != "refer" && key != "referralserver" {
+                    continue;
+                }
+
+                let value = value.trim().to_lowercase();
+                // rwhois:// and http(s):// referrals speak other protocols
+                let server = match value.strip_prefix("whois://") {
+                    Some(server) => server,
+                    None if !value.contains("://") => value.as_str(),
+                    None => continue,
+                };
+
+                let server = server.split(['/', ':']).next().unwrap_or("").trim();
+                if !server.is_empty() {
+                    return Some(server.to_string());
+                }
+            }
+        }
+        None
+    }
+
+    /// Extract the effective TLD ("co.uk" for "foo.co.uk") using the public suffix list
+    fn extract_tld(&self, domain: &str) -> Result<String, WhoisError> {
+        match crate::domain::effective_tld(domain) {
+            Some(tld) => Ok(tld),
             None => {
                 // Fallback to simple extraction if PSL parsing fails
                 warn!("Public suffix parsing failed for {}, using fallback", domain);
-                let parts: Vec<&str> = domain.split('.').collect();
-                if parts.is_empty() {
-                    Err(WhoisError::InvalidDomain(format!("No TLD found in domain: {}", domain)))
-                } else {
-                    Ok(parts[parts.len() - 1].to_string())
+                match domain.trim_end_matches('.').rsplit('.').next() {
+                    Some(tld) if !tld.is_empty() => Ok(tld.to_lowercase()),
+                    _ => Err(WhoisError::InvalidDomain(format!("No TLD found in domain: {}", domain))),
                 }
             }
         }
     }
 
+    // `tier` records which source answered: override, cached, hardcoded, generated or discovered
+    #[instrument(level = "debug", skip(self), fields(tier = field::Empty))]
     async fn find_whois_server(&self, tld: &str) -> Result<String, WhoisError> {
+        // A multi-label suffix ("com.tr") uses its own server when one is known, else its TLD's
+        let top_level = tld.rsplit('.').next().unwrap_or(tld);
+        if let Some(server) = self.known_whois_server(tld).await {
+            return Ok(server);
+        }
+        if top_level != tld {
+            if let Some(server) = self.known_whois_server(top_level).await {
+                return Ok(server);
+            }
+        }
+
+        // Dynamic discovery for uncommon/new TLDs
+        Span::current().record("tier", "discovered");
+        if let Some(server) = self.discover_whois_server_dynamic(top_level).await {
+            // Cache the discovered server
+            {
+                let mut servers = self.tld_servers.write().await;
+                servers.insert(top_level.to_string(), server.clone());
+            }
+            self.mappings_dirty.store(true, Ordering::Release);
+            return Ok(server);
+        }
+
+        Err(WhoisError::UnsupportedTld(tld.to_string()))
+    }
+
+    /// The `percentile` of recent latency of the server `domain` would be queried at
+    ///
+    /// `None` until that server has answered enough queries, or when it is
+    /// only reachable through discovery.
+    pub(crate) async fn expected_latency(&self, domain: &str, percentile: f64) -> Option<Duration> {
+        let tld = self.extract_tld(domain).ok()?;
+        let top_level = tld.rsplit('.').next().unwrap_or(&tld);
+        let server = match self.known_whois_server(&tld).await {
+            Some(server) => server,
+            None => self.known_whois_server(top_level).await?,
+        };
+        self.health.latency_percentile(&server, percentile)
+    }
+
+    // Rediscover a TLD's server when the known one is degraded, failing fast if discovery finds no other.
+    // An empty discovery is remembered for the cooldown, so later lookups skip straight to the fallback.
+    async fn healthy_alternative(&self, tld: &str, degraded: &str) -> Result<String, WhoisError> {
+        let unavailable = || WhoisError::Internal(format!("Whois server {} is degraded", degraded));
+        if self.health.lacks_alternative(degraded) {
+            return Err(unavailable());
+        }
+
+        let top_level = tld.rsplit('.').next().unwrap_or(tld);
+        match self.discover_whois_server_dynamic(top_level).await {
+            Some(server) if server != degraded && !self.health.is_degraded(&server) => {
+                info!("Whois server {} is degraded, using {} for {}", degraded, server, tld);
+                Ok(server)
+            }
+            _ => {
+                self.health.record_no_alternative(degraded);
+                Err(unavailable())
+            }
+        }
+    }
+
+    // Server for a suffix from overrides, discovered mappings, hardcoded or generated tables
+    async fn known_whois_server(&self, tld: &str) -> Option<String> {
+        let span = Span::current();
+
+        // Operator overrides win over everything else
+        if let Some(server) = self.config.tld_server_overrides.get(tld) {
+            span.record("tier", "override");
+            debug!("Using configured whois server override for {}: {}", tld, server);
+            return Some(server.clone());
+        }
+
         // Check cache first
         {
             let servers = self.tld_servers.read().await;
             if let Some(server) = servers.get(tld) {
+                span.record("tier", "cached");
                 debug!("Using cached whois server for {}: {}", tld, server);
-                return Ok(server.clone());
+                return Some(server.clone());
             }
         }
 
         // Check hardcoded TLD mappings first (instant lookup for popular TLDs)
         if let Some(server) = HARDCODED_TLD_SERVERS.get(tld) {
-            info!("Using hardcoded whois server for {}: {}", tld, server);
-            return Ok(server.to_string());
+            span.record("tier", "hardcoded");
+            debug!("Using hardcoded whois server for {}: {}", tld, server);
+            return Some(server.to_string());
         }
 
-        // Dynamic discovery for uncommon/new TLDs
-        if let Some(server) = self.discover_whois_server_dynamic(tld).await {
-            // Cache the discovered server
-            {
-                let mut servers = self.tld_servers.write().await;
-                servers.insert(tld.to_string(), server.clone());
+        // Then the build-time IANA snapshot, which covers uncommon TLDs and
+        // multi-label public suffixes without discovery
+        if let Some(server) = GENERATED_WHOIS_SERVERS.get(tld).or_else(|| GENERATED_SUFFIX_SERVERS.get(tld)) {
+            span.record("tier", "generated");
+            debug!("Using generated whois server for {}: {}", tld, server);
+            return Some(server.to_string());
+        }
+
+        None
+    }
+
+    /// Whois server for every TLD known without discovery, in lookup precedence order
+    pub async fn known_servers(&self) -> HashMap<String, String> {
+        let mut servers: HashMap<String, String> = GENERATED_SUFFIX_SERVERS.iter()
+            .chain(GENERATED_WHOIS_SERVERS.iter())
+            .chain(HARDCODED_TLD_SERVERS.iter())
+            .map(|(tld, server)| (tld.to_string(), server.to_string()))
+            .collect();
+        servers.extend(self.tld_servers.read().await.iter().map(|(tld, server)| (tld.clone(), server.clone())));
+        servers.extend(self.config.tld_server_overrides.iter().map(|(tld, server)| (tld.clone(), server.clone())));
+        servers
+    }
+
+    /// Fill in the port-43 side of `WhoisClient::tld_info`
+    ///
+    /// Uses the known server for the suffix (or its TLD) and asks the IANA
+    /// root zone database for whatever build-time data didn't cover.
+    pub(crate) async fn fill_tld_info(&self, info: &mut TldInfo) {
+        let top_level = info.tld.rsplit('.').next().unwrap_or(&info.tld).to_string();
+        info.whois_server = match self.known_whois_server(&info.tld).await {
+            Some(server) => Some(server),
+            None => self.known_whois_server(&top_level).await,
+        };
+
+        if info.whois_server.is_none() || info.registry_operator.is_none() {
+            for root_server in self.get_root_servers() {
+                match self.discovery_whois_query(&root_server, &top_level).await {
+                    Ok(response) => {
+                        if info.whois_server.is_none() {
+                            info.whois_server = self.parse_root_server_response(&response);
+                        }
+                        if info.registry_operator.is_none() {
+                            // The first `organisation:` is the sponsor; admin and tech contacts follow it
+                            info.registry_operator = response.lines().find_map(|line| {
+                                let (key, value) = line.split_once(':')?;
+                                let value = value.trim();
+                                (key.trim().eq_ignore_ascii_case("organisation") && !value.is_empty()).then(|| value.to_string())
+                            });
+                        }
+                        break;
+                    }
+                    Err(e) => debug!("Failed to query root server {} for {}: {}", root_server, top_level, e),
+                }
             }
-            return Ok(server);
         }
 
-        Err(WhoisError::UnsupportedTld(tld.to_string()))
+        if let Some(ref server) = info.whois_server {
+            info.whois_qps = Some(self.rate_limiter.limit_for(server));
+            info.whois_timeout_seconds = Some(self.config.whois_timeout_for(&info.tld));
+        }
+    }
+
+    /// Re-query IANA for every known TLD and update the server map
+    ///
+    /// Covers the hardcoded TLDs and any discovered since startup, so registry
+    /// migrations are picked up without a redeploy. TLDs IANA doesn't answer
+    /// for keep their current server. Returns the number of servers that changed.
+    pub async fn refresh_tld_mappings(&self) -> usize {
+        let mut tlds: Vec<String> = HARDCODED_TLD_SERVERS.keys().map(|tld| tld.to_string()).collect();
+        for tld in self.tld_servers.read().await.keys() {
+            if !HARDCODED_TLD_SERVERS.contains_key(tld.as_str()) {
+                tlds.push(tld.clone());
+            }
+        }
+
+        let results: Vec<(String, Option<String>)> = stream::iter(tlds)
+            .map(|tld| async move {
+                let server = self.query_root_servers_for_tld(&tld).await;
+                (tld, server)
+            })
+            .buffer_unordered(self.config.concurrent_whois_queries.max(1))
+            .collect()
+            .await;
+
+        let mut servers = self.tld_servers.write().await;
+        let mut changed = 0;
+        for (tld, server) in results {
+            let Some(server) = server else {
+                continue;
+            };
+            let current = servers.get(&tld).cloned()
+                .or_else(|| HARDCODED_TLD_SERVERS.get(tld.as_str()).map(|s| s.to_string()))
+                .or_else(|| GENERATED_WHOIS_SERVERS.get(tld.as_str()).map(|s| s.to_string()));
+            if current.as_deref() != Some(server.as_str()) {
+                info!("Whois server for {} changed: {} -> {}", tld, current.as_deref().unwrap_or("none"), server);
+                changed += 1;
+            }
+            servers.insert(tld, server);
+        }
+
+        if changed > 0 {
+            self.mappings_dirty.store(true, Ordering::Release);
+        }
+        info!("Refreshed whois TLD mappings: {} changed", changed);
+        changed
     }
 
+    /// Run `refresh_tld_mappings` every `whois_tld_refresh_seconds` in the background
+    ///
+    /// The task ends once the service is dropped. Returns None when disabled (0).
+    pub fn spawn_tld_refresh(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        if self.config.whois_tld_refresh_seconds == 0 {
+            return None;
+        }
+        let interval = Duration::from_secs(self.config.whois_tld_refresh_seconds);
+        let service: Weak<Self> = Arc::downgrade(self);
+
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                service.refresh_tld_mappings().await;
+            }
+        }))
+    }
+
+    #[instrument(name = "whois_discovery", skip(self))]
     async fn discover_whois_server_dynamic(&self, tld: &str) -> Option<String> {
         debug!("Dynamically discovering whois server for TLD: {}", tld);
 
@@ -280,16 +856,14 @@ impl WhoisService {
     }
 
     fn get_root_servers(&self) -> Vec<String> {
-        // Root whois servers - IANA is the authoritative source
-        vec![
-            "whois.iana.org".to_string(),
-        ]
+        // Root whois servers tried in order - IANA unless operators point discovery at a relay
+        self.config.root_whois_servers.clone()
     }
 
     async fn test_whois_server(&self, server: &str) -> bool {
         match timeout(
             Duration::from_secs(self.config.discovery_timeout_seconds.min(10)), 
-            TcpStream::connect((server, WHOIS_PORT))
+            self.open_connection(server, None)
         ).await {
             Ok(Ok(_)) => {
                 debug!("Successfully connected to whois server: {}", server);
@@ -306,37 +880,170 @@ impl WhoisService {
         }
     }
 
-    async fn raw_whois_query(&self, server: &str, query: &str) -> Result<String, WhoisError> {
-        self.whois_query_with_semaphore(server, query, &self.domain_query_semaphore, "Semaphore error").await
+    /// Global whois timeout, used where no TLD applies (discovery, RIR lookups)
+    fn default_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.whois_timeout_seconds)
+    }
+
+    // A caller's timeout wins; otherwise the server's adaptive timeout under the (per-TLD) configured one
+    fn query_timeout(&self, server: &str, tld: &str, requested: Option<Duration>) -> Duration {
+        requested.unwrap_or_else(|| {
+            let configured = Duration::from_secs(self.config.whois_timeout_for(tld));
+            self.health.timeout_for(server, configured)
+        })
+    }
+
+    /// Query `server`; with `addresses`, a new connection goes to one of those instead of resolving the host again
+    ///
+    /// With `record`, the answer is also fed to a domain `StreamingParser` as it
+    /// arrives, which is left in `record`.
+    async fn raw_whois_query(
+        &self,
+        server: &str,
+        query: &str,
+        query_timeout: Duration,
+        addresses: Option<&[SocketAddr]>,
+        record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<String, WhoisError> {
+        self.whois_query_with_semaphore(server, query, query_timeout, addresses, record, &self.domain_query_semaphore, "Semaphore error")
+            .await
     }
 
     async fn discovery_whois_query(&self, server: &str, query: &str) -> Result<String, WhoisError> {
-        self.whois_query_with_semaphore(server, query, &self.discovery_semaphore, "Discovery semaphore error").await
+        self.whois_query_with_semaphore(server, query, self.default_timeout(), None, None, &self.discovery_semaphore, "Discovery semaphore error").await
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn whois_query_with_semaphore(
         &self, 
         server: &str, 
         query: &str, 
+        query_timeout: Duration,
+        addresses: Option<&[SocketAddr]>,
+        record: Option<&mut Option<StreamingParser>>,
         semaphore: &Semaphore, 
         error_msg: &str
     ) -> Result<String, WhoisError> {
         // Acquire semaphore permit to limit concurrent queries
         let _permit = semaphore.acquire().await.map_err(|_| WhoisError::Internal(error_msg.to_string()))?;
         
-        self.execute_whois_query(server, query).await
+        self.execute_whois_query(server, query, query_timeout, addresses, record).await
     }
 
-    async fn execute_whois_query(&self, server: &str, query: &str) -> Result<String, WhoisError> {
-        let mut stream = self.connect_to_whois_server(server).await?;
+    #[instrument(name = "whois_query", level = "debug", skip(self, query_timeout, addresses, record))]
+    async fn execute_whois_query(
+        &self,
+        server: &str,
+        query: &str,
+        query_timeout: Duration,
+        addresses: Option<&[SocketAddr]>,
+        mut record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<String, WhoisError> {
+        let fixture = self.fixtures.whois_path(server, query);
+        let started = std::time::Instant::now();
+        if let Some(record) = record.as_deref_mut() {
+            *record = None;
+        }
+        // Boxed to keep the instrumented lookup future shallow enough for rustc's query depth limit
+        let upstream = Box::pin(self.query_upstream(server, query, query_timeout, addresses, record.as_deref_mut()));
+        let result = match self.fixtures.through(fixture, upstream).await {
+            Ok(response) if self.parser.detect_rate_limit(&response) => {
+                Err(WhoisError::RateLimited { server: server.to_string(), retry_after: None })
+            }
+            result => result,
+        };
+        // A replayed fixture never went through the reader
+        if let (Ok(response), Some(record @ None)) = (&result, record) {
+            let mut parser = StreamingParser::new();
+            parser.feed(response.as_bytes());
+            *record = Some(parser);
+        }
+        telemetry::record_upstream_query("whois", server, started.elapsed(), &result);
+        match result {
+            Ok(_) => self.health.record_success(server, started.elapsed()),
+            Err(WhoisError::RateLimited { .. }) => self.health.record_failure(server, "Rate limited", None),
+            Err(WhoisError::Timeout) => self.health.record_failure(server, "Network timeout", Some(query_timeout)),
+            Err(ref e) => self.health.record_failure(server, &e.to_string(), None),
+        }
+        telemetry::record_buffer_pool(&self.buffer_pool.stats());
+        result
+    }
+
+    async fn query_upstream(
+        &self,
+        server: &str,
+        query: &str,
+        query_timeout: Duration,
+        addresses: Option<&[SocketAddr]>,
+        record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<String, WhoisError> {
+        // Respect registry rate limits before opening the connection
+        self.rate_limiter.acquire(server).await;
+
+        if self.connection_pool.supports(server) {
+            return self.execute_pooled_query(server, query, query_timeout, addresses, record).await;
+        }
+
+        let mut stream = self.connect_to_whois_server(server, query_timeout, addresses).await?;
         self.send_query(&mut stream, query).await?;
-        self.read_whois_response(&mut stream).await
+        self.read_whois_response(&mut stream, query_timeout, record).await
+    }
+
+    /// Query over a persistent connection, reusing an idle one when available
+    async fn execute_pooled_query(
+        &self,
+        server: &str,
+        query: &str,
+        query_timeout: Duration,
+        addresses: Option<&[SocketAddr]>,
+        mut record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<String, WhoisError> {
+        if let Some(mut stream) = self.connection_pool.take(server).await {
+            match self.persistent_round_trip(&mut stream, query, query_timeout, record.as_deref_mut()).await {
+                Ok((response, open)) => {
+                    if open {
+                        self.connection_pool.put(server, stream).await;
+                    }
+                    return Ok(response);
+                }
+                // The server may have dropped the idle connection; retry on a fresh one
+                Err(e) => debug!("Pooled connection to {} failed, reconnecting: {}", server, e),
+            }
+        }
+
+        // "-k" switches a new connection into persistent mode
+        let mut stream = self.connect_to_whois_server(server, query_timeout, addresses).await?;
+        let (response, open) = self
+            .persistent_round_trip(&mut stream, &format!("-k {}", query), query_timeout, record)
+            .await?;
+        if open {
+            self.connection_pool.put(server, stream).await;
+        }
+        Ok(response)
+    }
+
+    async fn persistent_round_trip(
+        &self,
+        stream: &mut TcpStream,
+        query: &str,
+        query_timeout: Duration,
+        record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<(String, bool), WhoisError> {
+        self.send_query(stream, query).await?;
+        let (response, open) = self.read_response(stream, query_timeout, true, record).await?;
+        if response.is_empty() && !open {
+            return Err(WhoisError::IoError(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "connection closed before answering",
+            )));
+        }
+        Ok((response, open))
     }
 
-    async fn connect_to_whois_server(&self, server: &str) -> Result<TcpStream, WhoisError> {
+    async fn connect_to_whois_server(&self, server: &str, query_timeout: Duration, addresses: Option<&[SocketAddr]>) -> Result<TcpStream, WhoisError> {
         let stream = timeout(
-            Duration::from_secs(self.config.whois_timeout_seconds),
-            TcpStream::connect((server, WHOIS_PORT))
+            query_timeout,
+            self.open_connection(server, addresses)
         ).await??;
 
         // Optimize TCP performance
@@ -347,62 +1054,101 @@ impl WhoisService {
         Ok(stream)
     }
 
+    /// Open a TCP connection to the whois server, through the proxy if configured
+    ///
+    /// `addresses` (already resolved and checked) are connected to instead of resolving the host.
+    async fn open_connection(&self, server: &str, addresses: Option<&[SocketAddr]>) -> Result<TcpStream, WhoisError> {
+        let (host, port) = split_host_port(server);
+        match (&self.proxy, addresses) {
+            (Some(proxy), _) => proxy.connect(host, port).await,
+            (None, Some(addresses)) => self.connector.connect_to(host, addresses.to_vec()).await,
+            (None, None) => self.connector.connect(host, port).await,
+        }
+    }
+
     async fn send_query(&self, stream: &mut TcpStream, query: &str) -> Result<(), WhoisError> {
         let query_line = format!("{}\r\n", query);
         stream.write_all(query_line.as_bytes()).await?;
         Ok(())
     }
 
-    async fn read_whois_response(&self, stream: &mut TcpStream) -> Result<String, WhoisError> {
-        // Get RAII buffer from pool - automatically returns on drop
-        let mut pooled_buffer = PooledBuffer::new(
-            self.buffer_pool.clone(), 
-            self.config.buffer_size, 
-            self.config.buffer_pool_size
-        );
-        let buffer = pooled_buffer.as_mut();
+    async fn read_whois_response(
+        &self,
+        stream: &mut TcpStream,
+        query_timeout: Duration,
+        record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<String, WhoisError> {
+        let (response, _) = self.read_response(stream, query_timeout, false, record).await?;
+        Ok(response)
+    }
 
-        // Read response
+    /// Read an answer; returns whether the connection is still open for another query
+    ///
+    /// Plain whois answers end at EOF. In `persistent` mode the answer ends with
+    /// two blank lines and the server keeps the connection open. The whole text
+    /// is still kept, as `raw_data`, for fixtures and for finding referrals. With
+    /// `record`, each chunk is also fed to a fresh `StreamingParser` as it
+    /// arrives, which is left in `record` once the answer is complete.
+    async fn read_response(
+        &self,
+        stream: &mut TcpStream,
+        query_timeout: Duration,
+        persistent: bool,
+        record: Option<&mut Option<StreamingParser>>,
+    ) -> Result<(String, bool), WhoisError> {
+        // Buffer returns to the pool when the reader is dropped
+        let mut reader = ResponseReader::new(stream, self.buffer_pool.get(), query_timeout, self.config.max_response_size);
+        if persistent {
+            reader = reader.stop_at_end_of_record();
+        }
+        let mut parser = record.is_some().then(StreamingParser::new);
         let mut response = Vec::new();
-        
-        loop {
-            match timeout(
-                Duration::from_secs(self.config.whois_timeout_seconds),
-                stream.read(buffer)
-            ).await? {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    response.extend_from_slice(&buffer[..n]);
-                    if response.len() > self.config.max_response_size {
-                        return Err(WhoisError::ResponseTooLarge);
-                    }
-                }
-                Err(e) => {
-                    return Err(WhoisError::IoError(e));
-                }
+        while let Some(chunk) = reader.next_chunk().await? {
+            response.extend_from_slice(chunk);
+            if let Some(parser) = parser.as_mut() {
+                parser.feed(chunk);
             }
         }
+        let open = reader.ended_at_marker();
 
-        // Buffer automatically returns to pool when pooled_buffer goes out of scope
-        String::from_utf8(response).map_err(|_| WhoisError::InvalidUtf8)
+        let response = String::from_utf8(response).map_err(|_| WhoisError::InvalidUtf8)?;
+        if let Some(record) = record {
+            *record = parser;
+        }
+        Ok((response, open))
     }
 
-    async fn follow_referrals(&self, initial_server: &str, initial_data: &str, domain: &str) -> Result<(String, String), WhoisError> {
-        let mut current_server = initial_server.to_string();
-        let mut current_data = initial_data.to_string();
-        let mut referral_count = 0;
-        let max_referrals = self.config.max_referrals;
+    /// Follow referrals from the registry's answer; returns every hop, the registry first
+    #[instrument(level = "debug", skip(self, registry_hop, record, tld, query_timeout), fields(server = %registry_hop.server))]
+    async fn follow_referrals(
+        &self,
+        registry_hop: ReferralHop,
+        record: &mut Option<StreamingParser>,
+        domain: &str,
+        tld: &str,
+        query_timeout: Option<Duration>,
+        max_referrals: usize,
+    ) -> Vec<ReferralHop> {
+        let mut chain = vec![registry_hop];
 
-        while referral_count < max_referrals {
-            if let Some(referral_server) = self.extract_whois_server(&current_data) {
-                if referral_server != current_server {
-                    debug!("Following referral from {} to {}", current_server, referral_server);
+        while chain.len() <= max_referrals {
+            let current = &chain[chain.len() - 1];
+            if let Some(referral_server) = self.extract_whois_server(&current.raw_data) {
+                if referral_server != current.server && self.health.is_degraded(&referral_server) {
+                    debug!("Skipping referral to degraded server {}", referral_server);
+                } else if let Some(addresses) = self.vet_referral_from(&current.server, &referral_server).await {
+                    debug!("Following referral from {} to {}", current.server, referral_server);
+                    telemetry::record_referral(&referral_server);
                     
-                    match self.raw_whois_query(&referral_server, domain).await {
+                    let hop = tracing::debug_span!("whois_referral", attempt = chain.len(), server = %referral_server);
+                    let hop_timeout = self.query_timeout(&referral_server, tld, query_timeout);
+                    let started = std::time::Instant::now();
+                    // A failed hop leaves the previous answer, and its parse, as the final one
+                    let mut hop_record = None;
+                    match self.raw_whois_query(&referral_server, domain, hop_timeout, addresses.as_deref(), Some(&mut hop_record)).instrument(hop).await {
                         Ok(new_data) => {
-                            current_server = referral_server;
-                            current_data = new_data;
-                            referral_count += 1;
+                            *record = hop_record;
+                            chain.push(ReferralHop::new(&referral_server, new_data, started));
                             continue;
                         }
                         Err(e) => {
@@ -415,7 +1161,49 @@ impl WhoisService {
             break;
         }
 
-        Ok((current_server, current_data))
+        chain
+    }
+
+    /// `vet_referral` for a referral found in `current`'s answer; a server referring to itself isn't followed
+    async fn vet_referral_from(&self, current: &str, server: &str) -> Option<Option<Vec<SocketAddr>>> {
+        if server == current {
+            return None;
+        }
+        self.vet_referral(server).await
+    }
+
+    /// Check a referral to `server` against the referral policy (see referral.rs)
+    ///
+    /// `None` refuses it. Otherwise it carries the addresses that were checked, when
+    /// there were any to check: the referral must connect to exactly those, so the
+    /// name can't be re-pointed between the check and the connection.
+    /// Referrals always go to the whois port; only configured servers may name another one.
+    async fn vet_referral(&self, server: &str) -> Option<Option<Vec<SocketAddr>>> {
+        let (host, port) = split_host_port(server);
+        let mut verdict = match port {
+            WHOIS_PORT => self.referrals.check(host).map(|()| None),
+            port => Err(format!("referrals must use port {}, not {}", WHOIS_PORT, port)),
+        };
+
+        // Proxies resolve the target themselves, and replayed lookups stay offline
+        let resolve = self.referrals.checks_addresses()
+            && self.proxy.is_none()
+            && self.config.fixture_mode != FixtureMode::Replay
+            && host.parse::<IpAddr>().is_err();
+        if verdict.is_ok() && resolve {
+            verdict = match self.connector.resolve(host, port).await {
+                Ok(addresses) => self.referrals.check_addresses(&addresses).map(|()| Some(addresses)),
+                Err(e) => Err(format!("its addresses can't be checked: {}", e)),
+            };
+        }
+
+        match verdict {
+            Ok(addresses) => Some(addresses),
+            Err(reason) => {
+                warn!("Refusing referral to {}: {}", server, reason);
+                None
+            }
+        }
     }
 
     fn extract_whois_server(&self, data: &str) -> Option<String> {
@@ -425,11 +1213,196 @@ impl WhoisService {
                 let key = key.trim().to_lowercase();
                 let value = value.trim();
                 
-                if (key.contains("whois") && key.contains("server")) || key == "refer" {
+                // Thin registries print the field empty when there's no registrar server
+                if ((key.contains("whois") && key.contains("server")) || key == "refer") && !value.is_empty() {
                     return Some(value.to_string());
                 }
             }
         }
         None
     }
-} 
\ No newline at end of file
+} 
+#[cfg(all(test, feature = "whois-tcp"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tld_mappings_persist_across_restarts() {
+        let path = std::env::temp_dir().join(format!("whois-tld-mappings-{}.json", std::process::id()));
+        let mut config = Config::load().unwrap();
+        config.tld_mappings_path = Some(path.to_string_lossy().into_owned());
+        let config = Arc::new(config);
+
+        let service = WhoisService::new(config.clone()).await.unwrap();
+        service.tld_servers.write().await.insert("example".to_string(), "whois.nic.example".to_string());
+        service.save_tld_mappings().await.unwrap();
+
+        let restarted = WhoisService::new(config).await.unwrap();
+        assert_eq!(restarted.find_whois_server("example").await.unwrap(), "whois.nic.example");
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_multi_label_suffixes_resolve_without_discovery() {
+        let service = WhoisService::new(Arc::new(Config::load().unwrap())).await.unwrap();
+        // A registry of its own, from the build-time suffix table
+        assert_eq!(service.find_whois_server("ac.uk").await.unwrap(), "whois.ja.net");
+        // Generated from the TLD's server in the vendored snapshot, offline builds included
+        assert_eq!(GENERATED_SUFFIX_SERVERS.get("com.tr"), Some(&"whois.nic.tr"));
+        assert_eq!(GENERATED_SUFFIX_SERVERS.get("net.au"), Some(&"whois.auda.org.au"));
+        assert_eq!(service.find_whois_server("com.tr").await.unwrap(), "whois.nic.tr");
+        assert_eq!(service.find_whois_server("co.uk").await.unwrap(), "whois.nic.uk");
+    }
+
+    #[tokio::test]
+    async fn test_per_call_timeout_and_cancellation_release_permits() {
+        let config = Arc::new(Config::load().unwrap());
+        let service = WhoisService::new(config.clone()).await.unwrap();
+
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+
+        let started = std::time::Instant::now();
+        let result = service.lookup_with_timeout("example.com", Some(&server), Some(Duration::from_millis(100))).await;
+        assert!(matches!(result, Err(WhoisError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(config.whois_timeout_seconds));
+
+        tokio::select! {
+            _ = service.lookup_with_server("example.com", &server) => panic!("silent server answered"),
+            _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+        }
+        assert_eq!(service.domain_query_semaphore.available_permits(), config.concurrent_whois_queries);
+    }
+
+    #[tokio::test]
+    async fn test_referral_chain_keeps_every_answer() {
+        // Replayed answers, so referral targets can sit on the whois port
+        let dir = std::env::temp_dir().join(format!("whois-referrals-{}", std::process::id()));
+        let mut config = Config::builder().build().unwrap();
+        config.fixture_mode = FixtureMode::Replay;
+        config.fixture_dir = Some(dir.display().to_string());
+        let fixtures = FixtureStore::new(&config);
+        let answer = |server: &str, referral: &str| {
+            let path = fixtures.whois_path(server, "example.com");
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let data = format!(
+                "Domain Name: EXAMPLE.COM\r\nRegistrar WHOIS Server: {}\r\nCreation Date: 1995-08-14T04:00:00Z\r\n",
+                referral
+            );
+            std::fs::write(path, data).unwrap();
+        };
+        answer("whois.registry.test", "whois.registrar.test");
+        answer("whois.ported.test", "whois.registrar.test:6379");
+        answer("whois.private.test", "127.0.0.1");
+        // Every target has an answer, so only the referral policy can stop a hop
+        for registrar in ["whois.registrar.test", "whois.registrar.test:6379", "127.0.0.1"] {
+            let path = fixtures.whois_path(registrar, "example.com");
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "Domain Name: example.com\r\nRegistrar: Example Registrar\r\n").unwrap();
+        }
+
+        let service = WhoisService::new(Arc::new(config.clone())).await.unwrap();
+        let result = service.lookup_with_server("example.com", "whois.registry.test").await.unwrap();
+        assert_eq!(result.server, "whois.registrar.test");
+        let servers: Vec<_> = result.referral_chain.iter().map(|hop| hop.server.as_str()).collect();
+        assert_eq!(servers, ["whois.registry.test", "whois.registrar.test"]);
+        assert!(result.referral_chain[0].raw_data.contains("Creation Date"));
+        assert_eq!(result.referral_chain[1].bytes, result.raw_data.len());
+
+        let options = LookupOptions::new().server("whois.registry.test".to_string()).follow_referrals(false);
+        let result = service.lookup_opts("example.com", &options).await.unwrap();
+        assert_eq!(result.server, "whois.registry.test");
+        assert!(result.referral_chain.is_empty());
+
+        // Only configured servers may name a port; a referral can't move the query off port 43
+        let result = service.lookup_with_server("example.com", "whois.ported.test").await.unwrap();
+        assert_eq!(result.server, "whois.ported.test");
+        assert!(result.referral_chain.is_empty());
+
+        // A registrar on a private address is refused under referral_deny_private_ips
+        assert_eq!(service.lookup_with_server("example.com", "whois.private.test").await.unwrap().referral_chain.len(), 2);
+        config.referral_deny_private_ips = true;
+        let service = WhoisService::new(Arc::new(config)).await.unwrap();
+        let result = service.lookup_with_server("example.com", "whois.private.test").await.unwrap();
+        assert_eq!(result.server, "whois.private.test");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_answers_fail_once() {
+        let dir = std::env::temp_dir().join(format!("whois-rate-limited-{}", std::process::id()));
+        let mut config = Config::builder().build().unwrap();
+        config.fixture_mode = FixtureMode::Replay;
+        config.fixture_dir = Some(dir.display().to_string());
+        let path = FixtureStore::new(&config).whois_path("whois.limited.test", "example.com");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "Your access is too fast,please try again later.\r\n").unwrap();
+
+        let service = WhoisService::new(Arc::new(config)).await.unwrap();
+        let Err(error) = service.lookup_with_server("example.com", "whois.limited.test").await else {
+            panic!("rate-limited answer was accepted");
+        };
+        assert!(matches!(error, WhoisError::RateLimited { ref server, retry_after: None } if server == "whois.limited.test"), "{:?}", error);
+        let health = service.server_health().into_iter().find(|h| h.server == "whois.limited.test").unwrap();
+        assert_eq!((health.successes, health.failures), (0, 1));
+        assert_eq!(health.last_error.as_deref(), Some("Rate limited"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_answers_are_parsed_as_they_arrive() {
+        let data = "Domain Name: EXAMPLE.COM\r\nRegistrar: Example Registrar\r\nName Server: NS1.EXAMPLE.COM\r\nCreation Date: 1995-08-14T04:00:00Z\r\n";
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = tokio::io::AsyncReadExt::read(&mut stream, &mut [0u8; 256]).await;
+            // Small writes, so lines arrive split across reads
+            for piece in data.as_bytes().chunks(7) {
+                stream.write_all(piece).await.unwrap();
+                stream.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+        });
+
+        let service = WhoisService::new(Arc::new(Config::builder().build().unwrap())).await.unwrap();
+        let result = service.lookup_with_server("example.com", &server).await.unwrap();
+        assert_eq!(result.raw_data, data);
+        let parsed = result.parsed_data.unwrap();
+        assert_eq!(parsed.registrar.as_deref(), Some("Example Registrar"));
+
+        let (whole, report) = WhoisParser::parse_with_report_for(data, Some("com"));
+        assert_eq!(parsed.creation_date, whole.creation_date);
+        assert_eq!(result.parsing_report.matched_lines, report.matched_lines);
+    }
+
+    #[tokio::test]
+    async fn test_referrals_connect_to_the_addresses_they_were_checked_at() {
+        let mut config = Config::builder().build().unwrap();
+        config.referral_deny_private_ips = true;
+        let service = WhoisService::new(Arc::new(config.clone())).await.unwrap();
+        // A name resolving to loopback is refused, and so is one whose addresses can't be checked
+        assert_eq!(service.vet_referral("localhost").await, None);
+        assert_eq!(service.vet_referral("whois.nonexistent.invalid").await, None);
+        assert_eq!(service.vet_referral("192.0.32.10").await, Some(None));
+
+        // Without the address check an unresolvable target is left to fail at connect time
+        config.referral_deny_private_ips = false;
+        let service = WhoisService::new(Arc::new(config)).await.unwrap();
+        assert_eq!(service.vet_referral("whois.nonexistent.invalid").await, Some(None));
+
+        // Checked addresses are connected to as they are, without resolving the name again
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        assert!(service.open_connection("whois.nonexistent.invalid", Some(&[address])).await.is_ok());
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("whois.iana.org"), ("whois.iana.org", WHOIS_PORT));
+        assert_eq!(split_host_port("relay.internal:4343"), ("relay.internal", 4343));
+        assert_eq!(split_host_port("2001:db8::1"), ("2001:db8::1", WHOIS_PORT));
+    }
+}
@@ -0,0 +1,165 @@
+//! Classic port-43 whois listener (`whois_proxy_port`)
+//!
+//! Speaks RFC 3912: the client sends one query line, the server answers with
+//! text and closes the connection. Queries run through the same cache-first
+//! lookup as the HTTP API, so `whois -h <host> -p <port> example.com` benefits
+//! from caching and server discovery. RDAP results are answered with whois
+//! text rebuilt from the parsed fields.
+//!
+//! It reaches the same upstream registries as the HTTP API, so the same limits
+//! apply: each peer IP gets the `client_rate_limit_qps` / `client_rate_limit_burst`
+//! token bucket (answered with "% Error: Too many requests" when exceeded), and
+//! at most `MAX_CONNECTIONS` connections are served at once; further ones wait
+//! in the accept backlog.
+
+use crate::{format, AppState, ValidatedDomain};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+use tracing::{debug, info, warn, Instrument};
+use whois_service::{
+    errors::WhoisError,
+    rate_limiter::{KeyedRateLimiter, RateLimitDecision},
+};
+
+// A domain query fits easily; anything longer is not a whois client
+const MAX_QUERY_BYTES: u64 = 1024;
+const READ_TIMEOUT_SECS: u64 = 10;
+// Connections served at once
+const MAX_CONNECTIONS: usize = 128;
+
+/// Bind `addr` and answer whois queries in the background; returns the bound address
+pub async fn spawn(state: AppState, addr: SocketAddr) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let addr = listener.local_addr()?;
+    info!("Whois proxy listening on {}", addr);
+
+    let limiter = (state.config.client_rate_limit_qps > 0.0).then(|| {
+        Arc::new(KeyedRateLimiter::new(state.config.client_rate_limit_qps, state.config.client_rate_limit_burst))
+    });
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    tokio::spawn(async move {
+        loop {
+            let Ok(permit) = connections.clone().acquire_owned().await else {
+                return;
+            };
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Whois proxy accept failed: {}", e);
+                    continue;
+                }
+            };
+            let state = state.clone();
+            let limiter = limiter.clone();
+            let span = tracing::info_span!("whois_proxy", peer = %peer);
+            tokio::spawn(
+                async move {
+                    let _permit = permit;
+                    if let Err(e) = handle(state, limiter.as_deref(), stream, peer).await {
+                        debug!("Whois proxy connection error: {}", e);
+                    }
+                }
+                .instrument(span),
+            );
+        }
+    });
+    Ok(addr)
+}
+
+async fn handle(state: AppState, limiter: Option<&KeyedRateLimiter>, stream: TcpStream, peer: SocketAddr) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    let mut reader = BufReader::new(reader.take(MAX_QUERY_BYTES));
+    let read = reader.read_line(&mut line);
+    match tokio::time::timeout(Duration::from_secs(READ_TIMEOUT_SECS), read).await {
+        Ok(result) => result?,
+        Err(_) => return Ok(()),
+    };
+
+    let decision = limiter.map(|limiter| limiter.check(&peer.ip().to_string()));
+    let reply = match decision {
+        Some(RateLimitDecision::Limited { retry_after }) => {
+            crate::metrics::increment_errors("client_rate_limited");
+            let retry_after = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            format!("% Error: Too many requests, try again in {} seconds\r\n", retry_after)
+        }
+        _ => reply(&line, answer(&state, &line).await),
+    };
+
+    writer.write_all(reply.as_bytes()).await?;
+    writer.shutdown().await
+}
+
+/// The text sent back for a query line and its lookup result
+fn reply(line: &str, result: Result<String, WhoisError>) -> String {
+    match result {
+        Ok(text) => text,
+        Err(WhoisError::DomainNotFound(_)) => format!("No match for \"{}\".\r\n", query(line).to_uppercase()),
+        Err(e) => {
+            let message = match e {
+                WhoisError::InvalidDomain(_) | WhoisError::UnsupportedTld(_) | WhoisError::RateLimited { .. } => e.to_string(),
+                _ => "Internal server error".to_string(),
+            };
+            format!("% Error: {}\r\n", message)
+        }
+    }
+}
+
+async fn answer(state: &AppState, line: &str) -> Result<String, WhoisError> {
+    let domain = ValidatedDomain::validate_domain(query(line).to_string())?.0;
+    info!(domain, "Whois proxy query");
+    let response = crate::cached_lookup(state, domain, false).await?;
+    Ok(format::classic_text(&response))
+}
+
+// The domain from a query line; tolerates server flags ("-k example.com") and
+// Verisign's exact-match prefix ("=example.com")
+fn query(line: &str) -> &str {
+    line.split_whitespace().last().unwrap_or_default().trim_start_matches('=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use whois_service::config::Config;
+
+    async fn ask(addr: SocketAddr, line: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(line.as_bytes()).await.unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        reply
+    }
+
+    #[test]
+    fn test_query_lines_and_replies() {
+        assert_eq!(query("example.com\r\n"), "example.com");
+        assert_eq!(query("-k =example.com\r\n"), "example.com");
+        assert_eq!(query("\r\n"), "");
+
+        let not_found = reply("=example.com\r\n", Err(WhoisError::DomainNotFound("example.com".to_string())));
+        assert_eq!(not_found, "No match for \"EXAMPLE.COM\".\r\n");
+        let internal = reply("example.com\r\n", Err(WhoisError::Internal("upstream secrets".to_string())));
+        assert_eq!(internal, "% Error: Internal server error\r\n");
+        assert_eq!(reply("example.com\r\n", Ok("Domain Name: EXAMPLE.COM\r\n".to_string())), "Domain Name: EXAMPLE.COM\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_loopback_queries_are_answered_and_rate_limited() {
+        let mut config = Config::builder().build().unwrap();
+        config.client_rate_limit_qps = 0.01;
+        config.client_rate_limit_burst = 2;
+        let state = AppState::for_tests(config).await;
+        let addr = spawn(state, "127.0.0.1:0".parse().unwrap()).await.unwrap();
+
+        // Invalid queries are refused before any lookup
+        assert!(ask(addr, "not a domain!\r\n").await.starts_with("% Error: Invalid domain"));
+        assert!(ask(addr, "-k\r\n").await.starts_with("% Error: "));
+        // The third query from this peer is over the burst
+        assert!(ask(addr, "example.com\r\n").await.starts_with("% Error: Too many requests"));
+    }
+}